@@ -1,4 +1,4 @@
-use std::{cell::OnceCell, time::Duration};
+use std::{cell::OnceCell, sync::Arc, time::Duration};
 
 use chrono::Utc;
 use oauth2::{
@@ -14,8 +14,9 @@ use oauth2::{
 use crate::{
     constants,
     models::{
-        FinishedAuthorization, MinecraftAccessToken, MinecraftLoginWithXboxRequest, MinecraftLoginWithXboxResponse,
-        MinecraftProfileResponse, MsaTokens, PendingAuthorization, TokenWithExpiry, XboxLiveAuthenticateRequest,
+        DeviceCodeErrorResponse, DeviceCodeResponse, DeviceCodeTokenResponse, FinishedAuthorization,
+        MinecraftAccessToken, MinecraftLoginWithXboxRequest, MinecraftLoginWithXboxResponse, MinecraftProfileResponse,
+        MsaTokens, PendingAuthorization, PendingDeviceCode, TokenWithExpiry, XboxLiveAuthenticateRequest,
         XboxLiveAuthenticateRequestProperties, XboxLiveAuthenticateResponse, XboxLiveSecurityTokenRequest,
         XboxLiveSecurityTokenRequestProperties, XboxLiveSecurityTokenResponse, XstsToken,
     },
@@ -99,6 +100,33 @@ impl From<RequestTokenError<HttpClientError<reqwest::Error>, StandardErrorRespon
     }
 }
 
+#[derive(thiserror::Error, Debug)]
+pub enum DeviceCodeError {
+    #[error("Connection error: {0}")]
+    ConnectionError(#[from] reqwest::Error),
+    #[error("Serialization error")]
+    SerializationError,
+    #[error("Non-OK Http Status: {0}")]
+    NonOkHttpStatus(reqwest::StatusCode),
+    #[error("The code expired before the login was completed")]
+    Expired,
+    #[error("The login was declined")]
+    Declined,
+    #[error("The device code wasn't recognized")]
+    BadVerificationCode,
+    #[error("External error: {0}")]
+    ExternalError(Arc<str>),
+}
+
+impl DeviceCodeError {
+    pub fn is_connection_error(&self) -> bool {
+        match self {
+            Self::ConnectionError(_) => true,
+            _ => false,
+        }
+    }
+}
+
 #[derive(thiserror::Error, Debug)]
 pub enum XboxAuthenticateError {
     #[error("Connection error: {0}")]
@@ -183,6 +211,85 @@ impl Authenticator {
         })
     }
 
+    /// Starts a device code login, the user then enters the code at the verification uri on any device
+    pub async fn start_device_code(&mut self) -> Result<PendingDeviceCode, DeviceCodeError> {
+        let response = self
+            .client
+            .post(constants::DEVICE_CODE_URL)
+            .form(&[("client_id", constants::CLIENT_ID), ("scope", constants::MSA_SCOPES)])
+            .send()
+            .await?;
+
+        if response.status() != reqwest::StatusCode::OK {
+            return Err(DeviceCodeError::NonOkHttpStatus(response.status()));
+        }
+
+        let bytes = response.bytes().await?;
+
+        let response: DeviceCodeResponse =
+            serde_json::from_slice(&bytes).map_err(|_| DeviceCodeError::SerializationError)?;
+
+        Ok(PendingDeviceCode {
+            device_code: response.device_code,
+            user_code: response.user_code,
+            verification_uri: response.verification_uri,
+            interval: Duration::from_secs(response.interval.max(1)),
+            expiry: Utc::now() + Duration::from_secs(response.expires_in),
+        })
+    }
+
+    /// Polls until the user has finished the device code login, or the code expires
+    pub async fn poll_device_code(&mut self, pending: &PendingDeviceCode) -> Result<MsaTokens, DeviceCodeError> {
+        let mut interval = pending.interval;
+        loop {
+            tokio::time::sleep(interval).await;
+
+            if Utc::now() >= pending.expiry {
+                return Err(DeviceCodeError::Expired);
+            }
+
+            let response = self
+                .client
+                .post(constants::TOKEN_URL)
+                .form(&[
+                    ("grant_type", constants::DEVICE_CODE_GRANT_TYPE),
+                    ("client_id", constants::CLIENT_ID),
+                    ("device_code", &pending.device_code),
+                ])
+                .send()
+                .await?;
+
+            let status = response.status();
+            let bytes = response.bytes().await?;
+
+            if status == reqwest::StatusCode::OK {
+                let response: DeviceCodeTokenResponse =
+                    serde_json::from_slice(&bytes).map_err(|_| DeviceCodeError::SerializationError)?;
+
+                return Ok(MsaTokens {
+                    access: TokenWithExpiry {
+                        token: response.access_token,
+                        expiry: Utc::now() + Duration::from_secs(response.expires_in),
+                    },
+                    refresh: response.refresh_token,
+                });
+            }
+
+            let Ok(response) = serde_json::from_slice::<DeviceCodeErrorResponse>(&bytes) else {
+                return Err(DeviceCodeError::NonOkHttpStatus(status));
+            };
+
+            match &*response.error {
+                "authorization_pending" => {},
+                "slow_down" => interval += Duration::from_secs(5),
+                "authorization_declined" => return Err(DeviceCodeError::Declined),
+                "expired_token" | "code_expired" => return Err(DeviceCodeError::Expired),
+                "bad_verification_code" => return Err(DeviceCodeError::BadVerificationCode),
+                _ => return Err(DeviceCodeError::ExternalError(response.error_description.unwrap_or(response.error))),
+            }
+        }
+    }
+
     pub async fn refresh_msa(&mut self, refresh: &str) -> Result<Option<MsaTokens>, MsaAuthorizationError> {
         let token_response = self
             .oauth2_client()
@@ -1,6 +1,9 @@
 pub const CLIENT_ID: &str = "e5226706-5096-431d-9516-ae48fe263401";
 pub const AUTH_URL: &str = "https://login.microsoftonline.com/consumers/oauth2/v2.0/authorize";
 pub const TOKEN_URL: &str = "https://login.microsoftonline.com/consumers/oauth2/v2.0/token";
+pub const DEVICE_CODE_URL: &str = "https://login.microsoftonline.com/consumers/oauth2/v2.0/devicecode";
+pub const DEVICE_CODE_GRANT_TYPE: &str = "urn:ietf:params:oauth:grant-type:device_code";
+pub const MSA_SCOPES: &str = "XboxLive.signin XboxLive.offline_access";
 pub const REDIRECT_URL_BASE: &str = "http://localhost:3160";
 pub const REDIRECT_URL: &str = "http://localhost:3160/auth";
 pub const SERVER_ADDRESS: &str = "127.0.0.1:3160";
@@ -8,3 +11,6 @@ pub const XBOX_AUTHENTICATE_URL: &str = "https://user.auth.xboxlive.com/user/aut
 pub const XSTS_AUTHORIZE_URL: &str = "https://xsts.auth.xboxlive.com/xsts/authorize";
 pub const MINECRAFT_LOGIN_WITH_XBOX_URL: &str = "https://api.minecraftservices.com/authentication/login_with_xbox";
 pub const MINECRAFT_PROFILE_URL: &str = "https://api.minecraftservices.com/minecraft/profile";
+pub const MINECRAFT_SKINS_URL: &str = "https://api.minecraftservices.com/minecraft/profile/skins";
+pub const MINECRAFT_ACTIVE_SKIN_URL: &str = "https://api.minecraftservices.com/minecraft/profile/skins/active";
+pub const MINECRAFT_ACTIVE_CAPE_URL: &str = "https://api.minecraftservices.com/minecraft/profile/capes/active";
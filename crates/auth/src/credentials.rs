@@ -1,4 +1,4 @@
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
 use chrono::Utc;
 use serde::{Deserialize, Serialize};
@@ -52,6 +52,29 @@ impl AuthStageWithData {
 }
 
 impl AccountCredentials {
+    /// Whether the Minecraft access token is missing or expires within `margin`
+    pub fn needs_refresh(&self, margin: Duration) -> bool {
+        let deadline = Utc::now() + margin;
+        self.access_token.as_ref().is_none_or(|access_token| access_token.expiry < deadline)
+    }
+
+    /// Drops every token that expires within `margin`, so that the next login refreshes them ahead of time
+    pub fn expire_within(&mut self, margin: Duration) {
+        let deadline = Utc::now() + margin;
+        if self.access_token.as_ref().is_some_and(|token| token.expiry < deadline) {
+            self.access_token = None;
+        }
+        if self.xsts.as_ref().is_some_and(|token| token.expiry < deadline) {
+            self.xsts = None;
+        }
+        if self.xbl.as_ref().is_some_and(|token| token.expiry < deadline) {
+            self.xbl = None;
+        }
+        if self.msa_access.as_ref().is_some_and(|token| token.expiry < deadline) {
+            self.msa_access = None;
+        }
+    }
+
     pub fn stage(&mut self) -> AuthStageWithData {
         let now = Utc::now();
 
@@ -91,3 +114,74 @@ impl AccountCredentials {
         AuthStageWithData::Initial
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use chrono::DateTime;
+
+    use super::*;
+
+    const MARGIN: Duration = Duration::from_secs(60 * 60);
+
+    fn token(expiry: DateTime<Utc>) -> TokenWithExpiry {
+        TokenWithExpiry {
+            token: "token".into(),
+            expiry,
+        }
+    }
+
+    fn credentials(expiry: DateTime<Utc>) -> AccountCredentials {
+        AccountCredentials {
+            msa_refresh: Some("refresh".into()),
+            msa_access: Some(token(expiry)),
+            xbl: Some(token(expiry)),
+            xsts: Some(XstsToken {
+                token: "xsts".into(),
+                expiry,
+                userhash: "userhash".into(),
+            }),
+            access_token: Some(token(expiry)),
+        }
+    }
+
+    #[test]
+    fn test_needs_refresh_without_access_token() {
+        assert!(AccountCredentials::default().needs_refresh(MARGIN));
+    }
+
+    #[test]
+    fn test_needs_refresh_within_margin() {
+        let expiring = credentials(Utc::now() + chrono::Duration::minutes(30));
+        assert!(expiring.needs_refresh(MARGIN));
+        assert!(!expiring.needs_refresh(Duration::from_secs(10 * 60)));
+
+        let valid = credentials(Utc::now() + chrono::Duration::hours(2));
+        assert!(!valid.needs_refresh(MARGIN));
+    }
+
+    #[test]
+    fn test_expire_within_drops_expiring_tokens() {
+        let mut expiring = credentials(Utc::now() + chrono::Duration::minutes(30));
+        expiring.expire_within(MARGIN);
+
+        assert!(expiring.access_token.is_none());
+        assert!(expiring.xsts.is_none());
+        assert!(expiring.xbl.is_none());
+        assert!(expiring.msa_access.is_none());
+        // The refresh token doesn't expire, so the next login can use it without interaction
+        assert!(expiring.msa_refresh.is_some());
+        assert!(matches!(expiring.stage(), AuthStageWithData::MsaRefresh(_)));
+    }
+
+    #[test]
+    fn test_expire_within_keeps_valid_tokens() {
+        let mut valid = credentials(Utc::now() + chrono::Duration::hours(2));
+        valid.expire_within(MARGIN);
+
+        assert!(valid.access_token.is_some());
+        assert!(valid.xsts.is_some());
+        assert!(valid.xbl.is_some());
+        assert!(valid.msa_access.is_some());
+        assert!(matches!(valid.stage(), AuthStageWithData::AccessToken(_)));
+    }
+}
@@ -2,5 +2,10 @@ pub mod authenticator;
 pub mod constants;
 pub mod credentials;
 pub mod models;
+pub mod profile;
 pub mod secret;
+pub mod secret_file;
 pub mod serve_redirect;
+pub mod yggdrasil;
+#[cfg(any(test, feature = "test-util"))]
+pub mod test_util;
@@ -1,4 +1,4 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{collections::HashMap, sync::Arc, time::Duration};
 
 use chrono::{DateTime, Utc};
 use oauth2::{CsrfToken, PkceCodeVerifier};
@@ -38,6 +38,41 @@ pub struct FinishedAuthorization {
     pub code: String,
 }
 
+#[derive(Deserialize)]
+pub struct DeviceCodeResponse {
+    pub device_code: Arc<str>,
+    pub user_code: Arc<str>,
+    pub verification_uri: Arc<str>,
+    pub expires_in: u64,
+    #[serde(default = "default_device_code_interval")]
+    pub interval: u64,
+}
+
+fn default_device_code_interval() -> u64 {
+    5
+}
+
+pub struct PendingDeviceCode {
+    pub device_code: Arc<str>,
+    pub user_code: Arc<str>,
+    pub verification_uri: Arc<str>,
+    pub interval: Duration,
+    pub expiry: DateTime<Utc>,
+}
+
+#[derive(Deserialize)]
+pub struct DeviceCodeTokenResponse {
+    pub access_token: Arc<str>,
+    pub refresh_token: Option<Arc<str>>,
+    pub expires_in: u64,
+}
+
+#[derive(Deserialize)]
+pub struct DeviceCodeErrorResponse {
+    pub error: Arc<str>,
+    pub error_description: Option<Arc<str>>,
+}
+
 pub struct MsaTokens {
     pub access: TokenWithExpiry,
     pub refresh: Option<Arc<str>>,
@@ -115,6 +150,8 @@ pub struct MinecraftProfileResponse {
     pub id: Uuid,
     pub name: Arc<str>,
     pub skins: Vec<MinecraftProfileSkin>,
+    #[serde(default)]
+    pub capes: Vec<MinecraftProfileCape>,
 }
 
 #[derive(Clone, Deserialize)]
@@ -124,6 +161,14 @@ pub struct MinecraftProfileSkin {
     pub variant: SkinVariant,
 }
 
+#[derive(Clone, Deserialize)]
+pub struct MinecraftProfileCape {
+    pub id: Arc<str>,
+    pub state: SkinState,
+    pub url: Arc<str>,
+    pub alias: Arc<str>,
+}
+
 #[derive(Clone, Copy, Eq, PartialEq, Deserialize)]
 #[serde(rename_all = "UPPERCASE")]
 pub enum SkinState {
@@ -0,0 +1,107 @@
+use std::sync::Arc;
+
+use serde::Serialize;
+
+use crate::{
+    constants,
+    models::{MinecraftAccessToken, MinecraftProfileResponse},
+};
+
+#[derive(thiserror::Error, Debug)]
+pub enum ProfileError {
+    #[error("Connection error: {0}")]
+    ConnectionError(#[from] reqwest::Error),
+    #[error("Serialization error")]
+    SerializationError,
+    #[error("Non-OK Http Status: {0}")]
+    NonOkHttpStatus(reqwest::StatusCode),
+    #[error("Minecraft services rejected the request: {0}")]
+    Rejected(Arc<str>),
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SetCapeRequest<'a> {
+    cape_id: &'a str,
+}
+
+/// Uploads a skin PNG and makes it the active skin, returning the updated profile
+pub async fn upload_skin(
+    client: &reqwest::Client,
+    access_token: &MinecraftAccessToken,
+    slim: bool,
+    png: &[u8],
+) -> Result<MinecraftProfileResponse, ProfileError> {
+    let boundary = format!("----PandoraSkin{:032x}", rand::random::<u128>());
+    let variant = if slim { "slim" } else { "classic" };
+
+    let mut body = Vec::with_capacity(png.len() + 512);
+    body.extend_from_slice(format!(
+        "--{boundary}\r\nContent-Disposition: form-data; name=\"variant\"\r\n\r\n{variant}\r\n"
+    ).as_bytes());
+    body.extend_from_slice(format!(
+        "--{boundary}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"skin.png\"\r\nContent-Type: image/png\r\n\r\n"
+    ).as_bytes());
+    body.extend_from_slice(png);
+    body.extend_from_slice(format!("\r\n--{boundary}--\r\n").as_bytes());
+
+    let response = client
+        .post(constants::MINECRAFT_SKINS_URL)
+        .bearer_auth(access_token.secret())
+        .header(reqwest::header::CONTENT_TYPE, format!("multipart/form-data; boundary={boundary}"))
+        .body(body)
+        .send()
+        .await?;
+
+    read_profile(response).await
+}
+
+/// Resets the skin back to the default for the account
+pub async fn reset_skin(
+    client: &reqwest::Client,
+    access_token: &MinecraftAccessToken,
+) -> Result<MinecraftProfileResponse, ProfileError> {
+    let response = client
+        .delete(constants::MINECRAFT_ACTIVE_SKIN_URL)
+        .bearer_auth(access_token.secret())
+        .send()
+        .await?;
+
+    read_profile(response).await
+}
+
+/// Shows one of the capes owned by the account, or hides the cape if `cape_id` is None
+pub async fn set_active_cape(
+    client: &reqwest::Client,
+    access_token: &MinecraftAccessToken,
+    cape_id: Option<&str>,
+) -> Result<MinecraftProfileResponse, ProfileError> {
+    let request = match cape_id {
+        Some(cape_id) => client.put(constants::MINECRAFT_ACTIVE_CAPE_URL).json(&SetCapeRequest { cape_id }),
+        None => client.delete(constants::MINECRAFT_ACTIVE_CAPE_URL),
+    };
+
+    let response = request.bearer_auth(access_token.secret()).send().await?;
+
+    read_profile(response).await
+}
+
+async fn read_profile(response: reqwest::Response) -> Result<MinecraftProfileResponse, ProfileError> {
+    let status = response.status();
+    let bytes = response.bytes().await?;
+
+    if status != reqwest::StatusCode::OK {
+        #[derive(serde::Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct ErrorResponse {
+            error_message: Option<Arc<str>>,
+        }
+
+        return match serde_json::from_slice::<ErrorResponse>(&bytes) {
+            Ok(ErrorResponse { error_message: Some(message) }) => Err(ProfileError::Rejected(message)),
+            _ => Err(ProfileError::NonOkHttpStatus(status)),
+        };
+    }
+
+    serde_json::from_slice(&bytes).map_err(|_| ProfileError::SerializationError)
+}
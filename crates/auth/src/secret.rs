@@ -1,9 +1,19 @@
 pub use inner::*;
 
+use uuid::Uuid;
+
+use crate::{credentials::AccountCredentials, secret_file::FileSecretStorage};
+
 #[derive(thiserror::Error, Debug)]
 pub enum SecretStorageError {
     #[error("Access to the secret storage was denied")]
     AccessDenied,
+    #[error("The secret file needs a passphrase, enter it in the settings")]
+    MissingPassphrase,
+    #[error("The secret file is encrypted with a key file, but a passphrase was given")]
+    UnexpectedPassphrase,
+    #[error("The secret file couldn't be decrypted, the passphrase or key file is wrong")]
+    InvalidKey,
     #[error("Serialization error")]
     SerializationError,
     #[error("I/O error")]
@@ -20,6 +30,56 @@ pub enum SecretStorageError {
     SecurityFrameworkError(#[from] security_framework::base::Error),
 }
 
+/// Either the platform keyring or the encrypted file fallback
+pub enum SecretStorage {
+    Platform(PlatformSecretStorage),
+    File(FileSecretStorage),
+}
+
+impl SecretStorage {
+    pub async fn read_credentials(&self, uuid: Uuid) -> Result<Option<AccountCredentials>, SecretStorageError> {
+        match self {
+            Self::Platform(storage) => storage.read_credentials(uuid).await,
+            Self::File(storage) => storage.read_credentials(uuid).await,
+        }
+    }
+
+    pub async fn write_credentials(&self, uuid: Uuid, credentials: &AccountCredentials) -> Result<(), SecretStorageError> {
+        match self {
+            Self::Platform(storage) => storage.write_credentials(uuid, credentials).await,
+            Self::File(storage) => storage.write_credentials(uuid, credentials).await,
+        }
+    }
+
+    pub async fn delete_credentials(&self, uuid: Uuid) -> Result<(), SecretStorageError> {
+        match self {
+            Self::Platform(storage) => storage.delete_credentials(uuid).await,
+            Self::File(storage) => storage.delete_credentials(uuid).await,
+        }
+    }
+
+    pub async fn read_secret(&self, key: &str) -> Result<Option<String>, SecretStorageError> {
+        match self {
+            Self::Platform(storage) => storage.read_secret(key).await,
+            Self::File(storage) => storage.read_secret(key).await,
+        }
+    }
+
+    pub async fn write_secret(&self, key: &str, value: &str) -> Result<(), SecretStorageError> {
+        match self {
+            Self::Platform(storage) => storage.write_secret(key, value).await,
+            Self::File(storage) => storage.write_secret(key, value).await,
+        }
+    }
+
+    pub async fn delete_secret(&self, key: &str) -> Result<(), SecretStorageError> {
+        match self {
+            Self::Platform(storage) => storage.delete_secret(key).await,
+            Self::File(storage) => storage.delete_secret(key).await,
+        }
+    }
+}
+
 #[cfg(target_os = "linux")]
 mod inner {
     use uuid::Uuid;
@@ -63,6 +123,14 @@ mod inner {
             })
         }
 
+        /// Whether a Secret Service is reachable, which often isn't the case on headless systems
+        pub async fn is_available(&self) -> bool {
+            match &self.keyring {
+                Ok(keyring) => keyring.unlock().await.is_ok(),
+                Err(_) => false,
+            }
+        }
+
         pub async fn read_credentials(&self, uuid: Uuid) -> Result<Option<AccountCredentials>, SecretStorageError> {
             let keyring = self.keyring.as_ref()?;
             keyring.unlock().await?;
@@ -109,6 +177,44 @@ mod inner {
             keyring.delete(&attributes).await?;
             Ok(())
         }
+
+        pub async fn read_secret(&self, key: &str) -> Result<Option<String>, SecretStorageError> {
+            let keyring = self.keyring.as_ref()?;
+            keyring.unlock().await?;
+
+            let attributes = vec![("service", "pandora-launcher"), ("secret", key)];
+
+            let items = keyring.search_items(&attributes).await?;
+
+            if items.is_empty() {
+                Ok(None)
+            } else if items.len() > 1 {
+                Err(SecretStorageError::NotUnique)
+            } else {
+                let raw = items[0].secret().await?;
+                Ok(Some(String::from_utf8(raw.to_vec()).map_err(|_| SecretStorageError::SerializationError)?))
+            }
+        }
+
+        pub async fn write_secret(&self, key: &str, value: &str) -> Result<(), SecretStorageError> {
+            let keyring = self.keyring.as_ref()?;
+            keyring.unlock().await?;
+
+            let attributes = vec![("service", "pandora-launcher"), ("secret", key)];
+
+            keyring.create_item("Pandora Launcher Secret", &attributes, value.as_bytes().to_vec(), true).await?;
+            Ok(())
+        }
+
+        pub async fn delete_secret(&self, key: &str) -> Result<(), SecretStorageError> {
+            let keyring = self.keyring.as_ref()?;
+            keyring.unlock().await?;
+
+            let attributes = vec![("service", "pandora-launcher"), ("secret", key)];
+
+            keyring.delete(&attributes).await?;
+            Ok(())
+        }
     }
 }
 
@@ -127,6 +233,10 @@ mod inner {
             Ok(Self)
         }
 
+        pub async fn is_available(&self) -> bool {
+            true
+        }
+
         pub async fn read_credentials(&self, uuid: Uuid) -> Result<Option<AccountCredentials>, SecretStorageError> {
             let target_name = format!("PandoraLauncher_MinecraftAccount_{}", uuid.as_hyphenated());
 
@@ -241,6 +351,73 @@ mod inner {
 
             Ok(())
         }
+
+        pub async fn read_secret(&self, key: &str) -> Result<Option<String>, SecretStorageError> {
+            let mut target_name: Vec<u16> = format!("PandoraLauncher_Secret_{}", key).encode_utf16().chain(std::iter::once(0)).collect();
+
+            let mut credentials: *mut CREDENTIALW = std::ptr::null_mut();
+
+            unsafe {
+                let result = CredReadW(
+                    windows::core::PWSTR::from_raw(target_name.as_mut_ptr()),
+                    CRED_TYPE_GENERIC,
+                    None,
+                    &mut credentials,
+                );
+
+                if let Err(error) = result {
+                    const ERROR_NOT_FOUND: windows::core::HRESULT =
+                        windows::core::HRESULT::from_win32(windows::Win32::Foundation::ERROR_NOT_FOUND.0);
+                    if error.code() == ERROR_NOT_FOUND {
+                        return Ok(None);
+                    }
+                    return Err(error.into());
+                }
+
+                let Some(credentials) = credentials.as_mut() else {
+                    return Ok(None);
+                };
+
+                let raw =
+                    std::slice::from_raw_parts(credentials.CredentialBlob, credentials.CredentialBlobSize as usize);
+                Ok(Some(String::from_utf8(raw.to_vec()).map_err(|_| SecretStorageError::SerializationError)?))
+            }
+        }
+
+        pub async fn write_secret(&self, key: &str, value: &str) -> Result<(), SecretStorageError> {
+            let mut target_name: Vec<u16> = format!("PandoraLauncher_Secret_{}", key).encode_utf16().chain(std::iter::once(0)).collect();
+            let mut bytes = value.as_bytes().to_vec();
+
+            let credentials = CREDENTIALW {
+                Flags: CRED_FLAGS(0),
+                Type: CRED_TYPE_GENERIC,
+                TargetName: windows::core::PWSTR::from_raw(target_name.as_mut_ptr()),
+                CredentialBlobSize: bytes.len() as u32,
+                CredentialBlob: bytes.as_mut_ptr(),
+                Persist: CRED_PERSIST_LOCAL_MACHINE,
+                ..CREDENTIALW::default()
+            };
+
+            unsafe { Ok(CredWriteW(&credentials, 0)?) }
+        }
+
+        pub async fn delete_secret(&self, key: &str) -> Result<(), SecretStorageError> {
+            let mut target_name: Vec<u16> = format!("PandoraLauncher_Secret_{}", key).encode_utf16().chain(std::iter::once(0)).collect();
+
+            let result = unsafe {
+                CredDeleteW(windows::core::PWSTR::from_raw(target_name.as_mut_ptr()), CRED_TYPE_GENERIC, None)
+            };
+
+            if let Err(error) = result {
+                const ERROR_NOT_FOUND: windows::core::HRESULT =
+                    windows::core::HRESULT::from_win32(windows::Win32::Foundation::ERROR_NOT_FOUND.0);
+                if error.code() != ERROR_NOT_FOUND {
+                    return Err(error.into());
+                }
+            }
+
+            Ok(())
+        }
     }
 }
 
@@ -262,6 +439,10 @@ mod inner {
             })
         }
 
+        pub async fn is_available(&self) -> bool {
+            true
+        }
+
         pub async fn read_credentials(&self, uuid: Uuid) -> Result<Option<AccountCredentials>, SecretStorageError> {
             let uuid_str = uuid.as_hyphenated().to_string();
             let data = match self.keychain.find_generic_password("com.moulberry.pandoralauncher", uuid_str.as_str()) {
@@ -305,5 +486,38 @@ mod inner {
             item.delete();
             Ok(())
         }
+
+        pub async fn read_secret(&self, key: &str) -> Result<Option<String>, SecretStorageError> {
+            let data = match self.keychain.find_generic_password("com.moulberry.pandoralauncher.secret", key) {
+                Ok((data, _)) => data,
+                Err(error) if error.code() == security_framework_sys::base::errSecItemNotFound => {
+                    return Ok(None);
+                },
+                Err(error) => {
+                    return Err(error.into());
+                }
+            };
+            Ok(Some(String::from_utf8(data.as_ref().to_vec()).map_err(|_| SecretStorageError::SerializationError)?))
+        }
+
+        pub async fn write_secret(&self, key: &str, value: &str) -> Result<(), SecretStorageError> {
+            self.keychain.set_generic_password("com.moulberry.pandoralauncher.secret", key, value.as_bytes())?;
+            Ok(())
+        }
+
+        pub async fn delete_secret(&self, key: &str) -> Result<(), SecretStorageError> {
+            let item = match self.keychain.find_generic_password("com.moulberry.pandoralauncher.secret", key) {
+                Ok((_, item)) => item,
+                Err(error) if error.code() == security_framework_sys::base::errSecItemNotFound => {
+                    return Ok(());
+                },
+                Err(error) => {
+                    return Err(error.into());
+                }
+            };
+
+            item.delete();
+            Ok(())
+        }
     }
 }
@@ -0,0 +1,398 @@
+use std::{collections::BTreeMap, path::PathBuf};
+
+use aes::Aes256;
+use base64::{Engine, engine::general_purpose};
+use cbc::cipher::{BlockDecryptMut, BlockEncryptMut, KeyIvInit, block_padding::Pkcs7};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use uuid::Uuid;
+
+use crate::{credentials::AccountCredentials, secret::SecretStorageError};
+
+type Aes256CbcEnc = cbc::Encryptor<Aes256>;
+type Aes256CbcDec = cbc::Decryptor<Aes256>;
+type HmacSha256 = Hmac<Sha256>;
+
+const PBKDF2_ROUNDS: u32 = 600_000;
+const FILE_VERSION: u32 = 1;
+
+/// What the encryption key of the secret file is derived from
+pub enum SecretKeySource {
+    Passphrase(String),
+    /// Random key stored in a separate file, which is created if it doesn't exist yet. This only protects
+    /// the secrets if the key file is kept somewhere else than the secret file, e.g. a mounted secret
+    KeyFile(PathBuf),
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+enum KeySourceKind {
+    Passphrase,
+    KeyFile,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SecretFile {
+    version: u32,
+    key_source: KeySourceKind,
+    salt: String,
+    entries: BTreeMap<String, EncryptedEntry>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct EncryptedEntry {
+    iv: String,
+    data: String,
+    mac: String,
+}
+
+/// Stores secrets in a file encrypted with AES-256-CBC and authenticated with HMAC-SHA256, for systems
+/// without a keyring such as headless Linux or containers
+pub struct FileSecretStorage {
+    path: PathBuf,
+    state: tokio::sync::Mutex<OpenSecretFile>,
+}
+
+/// The keys are kept behind the same lock as the entries, since changing the key re-encrypts every entry
+struct OpenSecretFile {
+    file: SecretFile,
+    keys: SecretKeys,
+}
+
+struct SecretKeys {
+    encryption_key: [u8; 32],
+    mac_key: [u8; 32],
+}
+
+impl FileSecretStorage {
+    pub async fn open(path: PathBuf, key_source: SecretKeySource) -> Result<Self, SecretStorageError> {
+        let kind = match key_source {
+            SecretKeySource::Passphrase(_) => KeySourceKind::Passphrase,
+            SecretKeySource::KeyFile(_) => KeySourceKind::KeyFile,
+        };
+
+        let file = match tokio::fs::read(&path).await {
+            Ok(bytes) => {
+                let file: SecretFile = serde_json::from_slice(&bytes).map_err(|_| SecretStorageError::SerializationError)?;
+                if file.version > FILE_VERSION {
+                    return Err(SecretStorageError::SerializationError);
+                }
+                if file.key_source != kind {
+                    return Err(match file.key_source {
+                        KeySourceKind::Passphrase => SecretStorageError::MissingPassphrase,
+                        KeySourceKind::KeyFile => SecretStorageError::UnexpectedPassphrase,
+                    });
+                }
+                file
+            },
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => SecretFile {
+                version: FILE_VERSION,
+                key_source: kind,
+                salt: general_purpose::STANDARD.encode(rand::random::<[u8; 16]>()),
+                entries: BTreeMap::new(),
+            },
+            Err(_) => return Err(SecretStorageError::IoError),
+        };
+
+        let key_material = match key_source {
+            SecretKeySource::Passphrase(passphrase) => passphrase.into_bytes(),
+            SecretKeySource::KeyFile(key_path) => read_or_create_key_file(key_path, file.entries.is_empty()).await?,
+        };
+
+        let salt = general_purpose::STANDARD.decode(&file.salt).map_err(|_| SecretStorageError::SerializationError)?;
+        let keys = SecretKeys::derive(key_material, salt).await?;
+
+        // Check the key against an existing entry so a wrong passphrase is reported straight away
+        if let Some((name, entry)) = file.entries.iter().next() {
+            keys.decrypt(name, entry).map_err(|_| SecretStorageError::InvalidKey)?;
+        }
+
+        Ok(Self {
+            path,
+            state: tokio::sync::Mutex::new(OpenSecretFile { file, keys }),
+        })
+    }
+
+    pub fn path(&self) -> &PathBuf {
+        &self.path
+    }
+
+    /// Whether the file is encrypted with a passphrase rather than a key file
+    pub async fn uses_passphrase(&self) -> bool {
+        self.state.lock().await.file.key_source == KeySourceKind::Passphrase
+    }
+
+    /// Re-encrypts every entry with a key derived from `key_source` and a new salt, e.g. to change the passphrase
+    pub async fn rekey(&self, key_source: SecretKeySource) -> Result<(), SecretStorageError> {
+        let mut state = self.state.lock().await;
+
+        let mut entries = Vec::with_capacity(state.file.entries.len());
+        for (name, entry) in &state.file.entries {
+            entries.push((name.clone(), state.keys.decrypt(name, entry)?));
+        }
+
+        let (kind, key_material) = match key_source {
+            SecretKeySource::Passphrase(passphrase) => (KeySourceKind::Passphrase, passphrase.into_bytes()),
+            SecretKeySource::KeyFile(key_path) => (KeySourceKind::KeyFile, read_or_create_key_file(key_path, true).await?),
+        };
+
+        let salt = rand::random::<[u8; 16]>();
+        let keys = SecretKeys::derive(key_material, salt.to_vec()).await?;
+
+        let mut file = SecretFile {
+            version: FILE_VERSION,
+            key_source: kind,
+            salt: general_purpose::STANDARD.encode(salt),
+            entries: BTreeMap::new(),
+        };
+        for (name, plaintext) in entries {
+            let entry = keys.encrypt(&name, &plaintext);
+            file.entries.insert(name, entry);
+        }
+
+        // Only switch over once the file has been written, so a failure leaves everything readable with the old key
+        self.save(&file).await?;
+        *state = OpenSecretFile { file, keys };
+        Ok(())
+    }
+
+    pub async fn read_credentials(&self, uuid: Uuid) -> Result<Option<AccountCredentials>, SecretStorageError> {
+        let Some(bytes) = self.read(&credentials_entry_name(uuid)).await? else {
+            return Ok(None);
+        };
+        Ok(Some(serde_json::from_slice(&bytes).map_err(|_| SecretStorageError::SerializationError)?))
+    }
+
+    pub async fn write_credentials(
+        &self,
+        uuid: Uuid,
+        credentials: &AccountCredentials,
+    ) -> Result<(), SecretStorageError> {
+        let bytes = serde_json::to_vec(credentials).map_err(|_| SecretStorageError::SerializationError)?;
+        self.write(credentials_entry_name(uuid), &bytes).await
+    }
+
+    pub async fn delete_credentials(&self, uuid: Uuid) -> Result<(), SecretStorageError> {
+        self.delete(&credentials_entry_name(uuid)).await
+    }
+
+    pub async fn read_secret(&self, key: &str) -> Result<Option<String>, SecretStorageError> {
+        let Some(bytes) = self.read(&secret_entry_name(key)).await? else {
+            return Ok(None);
+        };
+        Ok(Some(String::from_utf8(bytes).map_err(|_| SecretStorageError::SerializationError)?))
+    }
+
+    pub async fn write_secret(&self, key: &str, value: &str) -> Result<(), SecretStorageError> {
+        self.write(secret_entry_name(key), value.as_bytes()).await
+    }
+
+    pub async fn delete_secret(&self, key: &str) -> Result<(), SecretStorageError> {
+        self.delete(&secret_entry_name(key)).await
+    }
+
+    async fn read(&self, name: &str) -> Result<Option<Vec<u8>>, SecretStorageError> {
+        let state = self.state.lock().await;
+        let Some(entry) = state.file.entries.get(name) else {
+            return Ok(None);
+        };
+        Ok(Some(state.keys.decrypt(name, entry)?))
+    }
+
+    async fn write(&self, name: String, bytes: &[u8]) -> Result<(), SecretStorageError> {
+        let mut state = self.state.lock().await;
+        let entry = state.keys.encrypt(&name, bytes);
+        state.file.entries.insert(name, entry);
+        self.save(&state.file).await
+    }
+
+    async fn delete(&self, name: &str) -> Result<(), SecretStorageError> {
+        let mut state = self.state.lock().await;
+        if state.file.entries.remove(name).is_some() {
+            self.save(&state.file).await?;
+        }
+        Ok(())
+    }
+
+    async fn save(&self, file: &SecretFile) -> Result<(), SecretStorageError> {
+        let bytes = serde_json::to_vec_pretty(file).map_err(|_| SecretStorageError::SerializationError)?;
+
+        if let Some(parent) = self.path.parent() {
+            tokio::fs::create_dir_all(parent).await.map_err(|_| SecretStorageError::IoError)?;
+        }
+
+        let temp_path = self.path.with_extension("tmp");
+        write_private(&temp_path, &bytes).await?;
+        tokio::fs::rename(&temp_path, &self.path).await.map_err(|_| SecretStorageError::IoError)
+    }
+}
+
+impl SecretKeys {
+    async fn derive(key_material: Vec<u8>, salt: Vec<u8>) -> Result<Self, SecretStorageError> {
+        let derived = tokio::task::spawn_blocking(move || {
+            let mut derived = [0u8; 64];
+            pbkdf2::pbkdf2_hmac::<Sha256>(&key_material, &salt, PBKDF2_ROUNDS, &mut derived);
+            derived
+        }).await.map_err(|_| SecretStorageError::UnknownError)?;
+
+        Ok(Self {
+            encryption_key: derived[..32].try_into().unwrap(),
+            mac_key: derived[32..].try_into().unwrap(),
+        })
+    }
+
+    fn encrypt(&self, name: &str, plaintext: &[u8]) -> EncryptedEntry {
+        let iv: [u8; 16] = rand::random();
+        let data = Aes256CbcEnc::new(&self.encryption_key.into(), &iv.into()).encrypt_padded_vec_mut::<Pkcs7>(plaintext);
+        let mac = self.mac(name, &iv, &data).finalize().into_bytes();
+
+        EncryptedEntry {
+            iv: general_purpose::STANDARD.encode(iv),
+            data: general_purpose::STANDARD.encode(data),
+            mac: general_purpose::STANDARD.encode(mac),
+        }
+    }
+
+    fn decrypt(&self, name: &str, entry: &EncryptedEntry) -> Result<Vec<u8>, SecretStorageError> {
+        let decode = |value: &str| general_purpose::STANDARD.decode(value).map_err(|_| SecretStorageError::SerializationError);
+        let iv = decode(&entry.iv)?;
+        let data = decode(&entry.data)?;
+        let mac = decode(&entry.mac)?;
+
+        let iv: [u8; 16] = iv.try_into().map_err(|_| SecretStorageError::SerializationError)?;
+        self.mac(name, &iv, &data).verify_slice(&mac).map_err(|_| SecretStorageError::InvalidKey)?;
+
+        Aes256CbcDec::new(&self.encryption_key.into(), &iv.into())
+            .decrypt_padded_vec_mut::<Pkcs7>(&data)
+            .map_err(|_| SecretStorageError::InvalidKey)
+    }
+
+    /// The entry name is included so entries can't be swapped around in the file
+    fn mac(&self, name: &str, iv: &[u8], data: &[u8]) -> HmacSha256 {
+        let mut mac = <HmacSha256 as Mac>::new_from_slice(&self.mac_key).unwrap();
+        mac.update(name.as_bytes());
+        mac.update(&[0]);
+        mac.update(iv);
+        mac.update(data);
+        mac
+    }
+}
+
+fn credentials_entry_name(uuid: Uuid) -> String {
+    format!("credentials_{}", uuid.as_hyphenated())
+}
+
+fn secret_entry_name(key: &str) -> String {
+    format!("secret_{key}")
+}
+
+async fn read_or_create_key_file(path: PathBuf, allow_create: bool) -> Result<Vec<u8>, SecretStorageError> {
+    match tokio::fs::read(&path).await {
+        Ok(key) if key.len() >= 32 => Ok(key),
+        Ok(_) => Err(SecretStorageError::InvalidKey),
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => {
+            // A new key would never be able to decrypt the existing entries
+            if !allow_create {
+                return Err(SecretStorageError::InvalidKey);
+            }
+            if let Some(parent) = path.parent() {
+                tokio::fs::create_dir_all(parent).await.map_err(|_| SecretStorageError::IoError)?;
+            }
+            let key = rand::random::<[u8; 32]>().to_vec();
+            write_private(&path, &key).await?;
+            Ok(key)
+        },
+        Err(_) => Err(SecretStorageError::IoError),
+    }
+}
+
+/// Writes a file that is only readable by the current user where the platform supports it
+async fn write_private(path: &std::path::Path, bytes: &[u8]) -> Result<(), SecretStorageError> {
+    let mut options = tokio::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+
+    let mut file = options.open(path).await.map_err(|_| SecretStorageError::IoError)?;
+    tokio::io::AsyncWriteExt::write_all(&mut file, bytes).await.map_err(|_| SecretStorageError::IoError)?;
+    tokio::io::AsyncWriteExt::flush(&mut file).await.map_err(|_| SecretStorageError::IoError)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    #[tokio::test]
+    async fn test_passphrase_roundtrip() {
+        let dir = TempDir::new("secret-file-passphrase");
+        let path = dir.join("secrets.json");
+
+        let storage = FileSecretStorage::open(path.clone(), SecretKeySource::Passphrase("hunter2".into())).await.unwrap();
+        storage.write_secret("token", "abc").await.unwrap();
+        drop(storage);
+
+        let storage = FileSecretStorage::open(path.clone(), SecretKeySource::Passphrase("hunter2".into())).await.unwrap();
+        assert_eq!(storage.read_secret("token").await.unwrap().as_deref(), Some("abc"));
+        storage.delete_secret("token").await.unwrap();
+        assert_eq!(storage.read_secret("token").await.unwrap(), None);
+        storage.write_secret("token", "def").await.unwrap();
+        drop(storage);
+
+        let wrong = FileSecretStorage::open(path.clone(), SecretKeySource::Passphrase("wrong".into())).await;
+        assert!(matches!(wrong, Err(SecretStorageError::InvalidKey)));
+
+        let key_file = FileSecretStorage::open(path, SecretKeySource::KeyFile(dir.join("secrets.key"))).await;
+        assert!(matches!(key_file, Err(SecretStorageError::MissingPassphrase)));
+    }
+
+    #[tokio::test]
+    async fn test_rekey_from_key_file_to_passphrase() {
+        let dir = TempDir::new("secret-file-rekey");
+        let path = dir.join("secrets.json");
+        let key_path = dir.join("secrets.key");
+
+        let storage = FileSecretStorage::open(path.clone(), SecretKeySource::KeyFile(key_path.clone())).await.unwrap();
+        storage.write_secret("token", "abc").await.unwrap();
+        assert!(!storage.uses_passphrase().await);
+
+        storage.rekey(SecretKeySource::Passphrase("hunter2".into())).await.unwrap();
+        assert!(storage.uses_passphrase().await);
+        assert_eq!(storage.read_secret("token").await.unwrap().as_deref(), Some("abc"));
+        drop(storage);
+
+        let key_file = FileSecretStorage::open(path.clone(), SecretKeySource::KeyFile(key_path)).await;
+        assert!(matches!(key_file, Err(SecretStorageError::MissingPassphrase)));
+
+        let storage = FileSecretStorage::open(path.clone(), SecretKeySource::Passphrase("hunter2".into())).await.unwrap();
+        storage.rekey(SecretKeySource::Passphrase("correct horse".into())).await.unwrap();
+        drop(storage);
+
+        let old = FileSecretStorage::open(path.clone(), SecretKeySource::Passphrase("hunter2".into())).await;
+        assert!(matches!(old, Err(SecretStorageError::InvalidKey)));
+        let storage = FileSecretStorage::open(path, SecretKeySource::Passphrase("correct horse".into())).await.unwrap();
+        assert_eq!(storage.read_secret("token").await.unwrap().as_deref(), Some("abc"));
+    }
+
+    #[tokio::test]
+    async fn test_key_file_credentials() {
+        let dir = TempDir::new("secret-file-keyfile");
+        let path = dir.join("secrets.json");
+        let key_path = dir.join("secrets.key");
+        let uuid = Uuid::new_v4();
+
+        let storage = FileSecretStorage::open(path.clone(), SecretKeySource::KeyFile(key_path.clone())).await.unwrap();
+        storage.write_credentials(uuid, &AccountCredentials::default()).await.unwrap();
+        drop(storage);
+
+        let storage = FileSecretStorage::open(path.clone(), SecretKeySource::KeyFile(key_path.clone())).await.unwrap();
+        assert!(storage.read_credentials(uuid).await.unwrap().is_some());
+        assert!(storage.read_credentials(Uuid::new_v4()).await.unwrap().is_none());
+        drop(storage);
+
+        std::fs::remove_file(&key_path).unwrap();
+        let missing_key = FileSecretStorage::open(path, SecretKeySource::KeyFile(key_path)).await;
+        assert!(matches!(missing_key, Err(SecretStorageError::InvalidKey)));
+    }
+}
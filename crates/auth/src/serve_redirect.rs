@@ -31,7 +31,8 @@ pub async fn start_server(
 ) -> Result<FinishedAuthorization, ProcessAuthorizationError> {
     log::info!("Starting auth redirect server on {}", constants::SERVER_ADDRESS);
 
-    let listener = tokio::net::TcpListener::bind(constants::SERVER_ADDRESS).await?;
+    let listener = tokio::net::TcpListener::bind(constants::SERVER_ADDRESS).await
+        .map_err(|e| ProcessAuthorizationError::StartServer(Box::new(e)))?;
 
     log::info!("Successfully started listening on {}", constants::SERVER_ADDRESS);
 
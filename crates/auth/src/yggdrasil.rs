@@ -0,0 +1,331 @@
+use std::sync::Arc;
+
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use url::Url;
+use uuid::Uuid;
+
+use crate::models::MinecraftAccessToken;
+
+/// Header used by authlib-injector compatible servers to point from their website to the api root
+const API_LOCATION_HEADER: &str = "x-authlib-injector-api-location";
+
+#[derive(thiserror::Error, Debug)]
+pub enum YggdrasilError {
+    #[error("Connection error: {0}")]
+    ConnectionError(#[from] reqwest::Error),
+    #[error("Serialization error")]
+    SerializationError,
+    #[error("Non-OK Http Status: {0}")]
+    NonOkHttpStatus(reqwest::StatusCode),
+    #[error("Invalid auth server url")]
+    InvalidUrl,
+    #[error("Auth server rejected the request: {0}")]
+    Rejected(Arc<str>),
+    #[error("The account doesn't have a profile selected")]
+    MissingProfile,
+}
+
+impl YggdrasilError {
+    pub fn is_connection_error(&self) -> bool {
+        match self {
+            Self::ConnectionError(_) => true,
+            _ => false,
+        }
+    }
+}
+
+/// Tokens for an authlib-injector account, the password itself is never stored
+#[derive(Clone, Deserialize, Serialize)]
+pub struct YggdrasilCredentials {
+    pub access_token: Arc<str>,
+    pub client_token: Arc<str>,
+}
+
+impl YggdrasilCredentials {
+    pub fn minecraft_access_token(&self) -> MinecraftAccessToken {
+        MinecraftAccessToken(Arc::clone(&self.access_token))
+    }
+}
+
+#[derive(Clone, Deserialize)]
+pub struct YggdrasilProfile {
+    pub id: Uuid,
+    pub name: Arc<str>,
+}
+
+pub struct YggdrasilSession {
+    pub credentials: YggdrasilCredentials,
+    pub profile: YggdrasilProfile,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AuthenticateRequest<'a> {
+    agent: Agent,
+    username: &'a str,
+    password: &'a str,
+    client_token: &'a str,
+    request_user: bool,
+}
+
+#[derive(Serialize)]
+struct Agent {
+    name: &'static str,
+    version: u32,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TokenRequest<'a> {
+    access_token: &'a str,
+    client_token: &'a str,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SessionResponse {
+    access_token: Arc<str>,
+    client_token: Arc<str>,
+    #[serde(default)]
+    available_profiles: Vec<YggdrasilProfile>,
+    selected_profile: Option<YggdrasilProfile>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ErrorResponse {
+    error: Arc<str>,
+    error_message: Option<Arc<str>>,
+}
+
+/// Client for a Yggdrasil auth server, such as the ones used by authlib-injector
+#[derive(Clone)]
+pub struct YggdrasilClient {
+    client: reqwest::Client,
+    api_root: Arc<str>,
+}
+
+impl YggdrasilClient {
+    pub fn new(client: reqwest::Client, api_root: Arc<str>) -> Self {
+        Self { client, api_root }
+    }
+
+    /// Finds the api root for a url entered by the user, following the API Location Indication header if present
+    pub async fn resolve(client: reqwest::Client, url: &str) -> Result<Self, YggdrasilError> {
+        let url = url.trim();
+        let url = if url.starts_with("http://") || url.starts_with("https://") {
+            Url::parse(url)
+        } else {
+            Url::parse(&format!("https://{url}"))
+        };
+        let url = url.map_err(|_| YggdrasilError::InvalidUrl)?;
+
+        let response = client.get(url).send().await?;
+
+        let api_root = match response.headers().get(API_LOCATION_HEADER).and_then(|value| value.to_str().ok()) {
+            Some(location) => response.url().join(location).map_err(|_| YggdrasilError::InvalidUrl)?,
+            None => response.url().clone(),
+        };
+
+        Ok(Self::new(client, api_root.as_str().trim_end_matches('/').into()))
+    }
+
+    pub fn api_root(&self) -> &Arc<str> {
+        &self.api_root
+    }
+
+    /// Fetches the server metadata, encoded for authlib-injector's `-Dauthlibinjector.yggdrasil.prefetched`
+    pub async fn fetch_prefetched_metadata(&self) -> Result<Arc<str>, YggdrasilError> {
+        let response = self.client.get(&*self.api_root).send().await?;
+
+        if response.status() != reqwest::StatusCode::OK {
+            return Err(YggdrasilError::NonOkHttpStatus(response.status()));
+        }
+
+        let bytes = response.bytes().await?;
+
+        // Make sure this is actually a Yggdrasil server before handing it to the game
+        serde_json::from_slice::<serde_json::Value>(&bytes).map_err(|_| YggdrasilError::SerializationError)?;
+
+        Ok(base64::engine::general_purpose::STANDARD.encode(&bytes).into())
+    }
+
+    pub async fn authenticate(&self, username: &str, password: &str) -> Result<YggdrasilSession, YggdrasilError> {
+        let client_token = Uuid::from_u128(rand::random()).simple().to_string();
+
+        let request = AuthenticateRequest {
+            agent: Agent {
+                name: "Minecraft",
+                version: 1,
+            },
+            username,
+            password,
+            client_token: &client_token,
+            request_user: false,
+        };
+
+        let response = self.client.post(self.endpoint("authserver/authenticate")).json(&request).send().await?;
+        let response: SessionResponse = Self::read_json(response).await?;
+
+        // Servers pick the profile themselves when the account only has one
+        let profile = match response.selected_profile {
+            Some(profile) => profile,
+            None => response.available_profiles.into_iter().next().ok_or(YggdrasilError::MissingProfile)?,
+        };
+
+        Ok(YggdrasilSession {
+            credentials: YggdrasilCredentials {
+                access_token: response.access_token,
+                client_token: response.client_token,
+            },
+            profile,
+        })
+    }
+
+    pub async fn refresh(&self, credentials: &YggdrasilCredentials) -> Result<YggdrasilSession, YggdrasilError> {
+        let request = TokenRequest {
+            access_token: &credentials.access_token,
+            client_token: &credentials.client_token,
+        };
+
+        let response = self.client.post(self.endpoint("authserver/refresh")).json(&request).send().await?;
+        let response: SessionResponse = Self::read_json(response).await?;
+
+        Ok(YggdrasilSession {
+            credentials: YggdrasilCredentials {
+                access_token: response.access_token,
+                client_token: response.client_token,
+            },
+            profile: response.selected_profile.ok_or(YggdrasilError::MissingProfile)?,
+        })
+    }
+
+    /// Returns whether the access token can still be used to join servers
+    pub async fn validate(&self, credentials: &YggdrasilCredentials) -> Result<bool, YggdrasilError> {
+        let request = TokenRequest {
+            access_token: &credentials.access_token,
+            client_token: &credentials.client_token,
+        };
+
+        let response = self.client.post(self.endpoint("authserver/validate")).json(&request).send().await?;
+
+        match response.status() {
+            reqwest::StatusCode::NO_CONTENT | reqwest::StatusCode::OK => Ok(true),
+            reqwest::StatusCode::FORBIDDEN | reqwest::StatusCode::UNAUTHORIZED => Ok(false),
+            status => Err(YggdrasilError::NonOkHttpStatus(status)),
+        }
+    }
+
+    pub async fn invalidate(&self, credentials: &YggdrasilCredentials) -> Result<(), YggdrasilError> {
+        let request = TokenRequest {
+            access_token: &credentials.access_token,
+            client_token: &credentials.client_token,
+        };
+
+        let response = self.client.post(self.endpoint("authserver/invalidate")).json(&request).send().await?;
+
+        if !response.status().is_success() {
+            return Err(YggdrasilError::NonOkHttpStatus(response.status()));
+        }
+
+        Ok(())
+    }
+
+    fn endpoint(&self, path: &str) -> String {
+        format!("{}/{}", self.api_root, path)
+    }
+
+    async fn read_json<T: serde::de::DeserializeOwned>(response: reqwest::Response) -> Result<T, YggdrasilError> {
+        let status = response.status();
+        let bytes = response.bytes().await?;
+
+        if status != reqwest::StatusCode::OK {
+            return match serde_json::from_slice::<ErrorResponse>(&bytes) {
+                Ok(error) => Err(YggdrasilError::Rejected(error.error_message.unwrap_or(error.error))),
+                Err(_) => Err(YggdrasilError::NonOkHttpStatus(status)),
+            };
+        }
+
+        serde_json::from_slice(&bytes).map_err(|_| YggdrasilError::SerializationError)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use base64::Engine;
+
+    use super::{YggdrasilClient, YggdrasilCredentials, YggdrasilError};
+    use crate::test_util::{self, MockResponse};
+
+    const PROFILE_ID: &str = "0123456789abcdef0123456789abcdef";
+
+    /// Minimal auth server accepting the password "hunter2" and the access token "token-2"
+    async fn start_mock_server() -> String {
+        test_util::start_mock_server(|request| {
+            let body = request.json();
+            let profile = serde_json::json!({ "id": PROFILE_ID, "name": "Steve" });
+
+            match request.path.as_str() {
+                "/" => MockResponse::json("200 OK", serde_json::json!({ "meta": { "serverName": "Mock" } })),
+                "/authserver/authenticate" if body["password"] == "hunter2" => MockResponse::json("200 OK", serde_json::json!({
+                    "accessToken": "token-1",
+                    "clientToken": body["clientToken"],
+                    "availableProfiles": [profile],
+                })),
+                "/authserver/refresh" if body["accessToken"] == "token-1" => MockResponse::json("200 OK", serde_json::json!({
+                    "accessToken": "token-2",
+                    "clientToken": body["clientToken"],
+                    "selectedProfile": profile,
+                })),
+                "/authserver/validate" if body["accessToken"] == "token-2" => MockResponse::new("204 No Content"),
+                "/authserver/invalidate" => MockResponse::new("204 No Content"),
+                _ => MockResponse::json("403 Forbidden", serde_json::json!({
+                    "error": "ForbiddenOperationException",
+                    "errorMessage": "Invalid credentials. Invalid username or password."
+                })),
+            }
+        }).await
+    }
+
+    #[tokio::test]
+    async fn test_session_lifecycle() {
+        let api_root = start_mock_server().await;
+        let client = YggdrasilClient::new(reqwest::Client::new(), api_root.into());
+
+        let error = client.authenticate("steve@example.com", "wrong").await.err().unwrap();
+        assert!(matches!(error, YggdrasilError::Rejected(_)));
+
+        let session = client.authenticate("steve@example.com", "hunter2").await.unwrap();
+        assert_eq!(&*session.profile.name, "Steve");
+        assert_eq!(session.profile.id.simple().to_string(), PROFILE_ID);
+        assert_eq!(&*session.credentials.access_token, "token-1");
+        assert!(!client.validate(&session.credentials).await.unwrap());
+
+        let session = client.refresh(&session.credentials).await.unwrap();
+        assert_eq!(&*session.credentials.access_token, "token-2");
+        assert!(client.validate(&session.credentials).await.unwrap());
+
+        client.invalidate(&session.credentials).await.unwrap();
+
+        let expired = YggdrasilCredentials {
+            access_token: Arc::from("token-0"),
+            client_token: session.credentials.client_token.clone(),
+        };
+        assert!(matches!(client.refresh(&expired).await, Err(YggdrasilError::Rejected(_))));
+    }
+
+    #[tokio::test]
+    async fn test_prefetched_metadata() {
+        let api_root = start_mock_server().await;
+        let client = YggdrasilClient::resolve(reqwest::Client::new(), &api_root).await.unwrap();
+        assert_eq!(&**client.api_root(), api_root);
+
+        let prefetched = client.fetch_prefetched_metadata().await.unwrap();
+        let decoded = base64::engine::general_purpose::STANDARD.decode(&*prefetched).unwrap();
+        let metadata: serde_json::Value = serde_json::from_slice(&decoded).unwrap();
+        assert_eq!(metadata["meta"]["serverName"], "Mock");
+    }
+}
@@ -1,6 +1,7 @@
-use std::sync::Arc;
+use std::{path::PathBuf, sync::Arc};
 
 use auth::models::{MinecraftAccessToken, MinecraftProfileResponse};
+use md5::{Digest, Md5};
 use bridge::{account::Account, message::MessageToFrontend};
 use rustc_hash::FxHashMap;
 use serde::{Deserialize, Serialize};
@@ -9,7 +10,25 @@ use uuid::Uuid;
 pub struct MinecraftLoginInfo {
     pub uuid: Uuid,
     pub username: Arc<str>,
+    pub kind: AccountKind,
+    /// None for offline accounts and for online accounts launched without a connection
     pub access_token: Option<MinecraftAccessToken>,
+    pub authlib_injector: Option<AuthlibInjectorLaunch>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccountKind {
+    Microsoft,
+    Yggdrasil,
+    Offline,
+}
+
+/// Everything needed to point the game at a third-party Yggdrasil auth server through authlib-injector
+pub struct AuthlibInjectorLaunch {
+    pub agent_path: PathBuf,
+    pub api_root: Arc<str>,
+    /// Base64 encoded server metadata, saves the agent from fetching it again while the game starts
+    pub prefetched: Arc<str>,
 }
 
 #[derive(Default, Debug, Serialize, Deserialize)]
@@ -26,6 +45,7 @@ impl BackendAccountInfo {
                 uuid: *uuid,
                 username: account.username.clone(),
                 head: account.head.clone(),
+                needs_relogin: account.needs_relogin,
             });
         }
         accounts.sort_by(|a, b| lexical_sort::natural_lexical_cmp(&a.username, &b.username));
@@ -42,14 +62,54 @@ pub struct BackendAccount {
     #[serde(default)]
     pub offline: bool,
     pub head: Option<Arc<[u8]>>,
+    /// Api root of the authlib-injector compatible auth server for third-party accounts
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub yggdrasil_api_root: Option<Arc<str>>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub needs_relogin: bool,
+    /// Id of the skin library entry used by an offline account
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub offline_skin: Option<Arc<str>>,
 }
 
 impl BackendAccount {
+    pub fn kind(&self) -> AccountKind {
+        if self.offline {
+            AccountKind::Offline
+        } else if self.yggdrasil_api_root.is_some() {
+            AccountKind::Yggdrasil
+        } else {
+            AccountKind::Microsoft
+        }
+    }
+
     pub fn new_from_profile(profile: &MinecraftProfileResponse) -> Self {
         Self {
             username: profile.name.clone(),
             offline: false,
             head: None,
+            yggdrasil_api_root: None,
+            needs_relogin: false,
+            offline_skin: None,
         }
     }
 }
+
+/// The UUID vanilla servers give a player in offline mode, so worlds and servers recognise the same player
+pub fn offline_uuid(username: &str) -> Uuid {
+    let hash = Md5::digest(format!("OfflinePlayer:{username}").as_bytes());
+    uuid::Builder::from_md5_bytes(hash.into()).into_uuid()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_offline_uuid_matches_vanilla() {
+        // UUID.nameUUIDFromBytes("OfflinePlayer:<name>"), as used by vanilla servers in offline mode
+        assert_eq!(offline_uuid("Notch"), Uuid::parse_str("b50ad385-829d-3141-a216-7e7d7539ba7f").unwrap());
+        assert_eq!(offline_uuid("Steve"), Uuid::parse_str("5627dd98-e6be-3c21-b8a8-e92344183641").unwrap());
+        assert_eq!(offline_uuid("Notch").get_version_num(), 3);
+    }
+}
@@ -0,0 +1,170 @@
+use std::{sync::Arc, time::{Duration, Instant}};
+
+use auth::yggdrasil::YggdrasilClient;
+use bridge::modal_action::{ModalAction, ProgressTracker};
+use uuid::Uuid;
+
+use crate::BackendState;
+
+const INITIAL_DELAY: Duration = Duration::from_secs(15);
+const CHECK_INTERVAL: Duration = Duration::from_secs(10 * 60);
+/// Credentials expiring within this window are refreshed ahead of time, so launching doesn't have to wait
+const REFRESH_MARGIN: Duration = Duration::from_secs(60 * 60);
+
+pub struct AccountRefreshState {
+    next_check: Instant,
+    running: bool,
+}
+
+impl Default for AccountRefreshState {
+    fn default() -> Self {
+        Self {
+            next_check: Instant::now() + INITIAL_DELAY,
+            running: false,
+        }
+    }
+}
+
+impl BackendState {
+    /// Starts refreshing the credentials of every online account in the background once the check interval has passed
+    pub fn refresh_accounts_if_due(&self) {
+        {
+            let mut state = self.account_refresh.lock();
+            let now = Instant::now();
+            if state.running || now < state.next_check || self.meta.connectivity.is_offline() {
+                return;
+            }
+            state.running = true;
+            state.next_check = now + CHECK_INTERVAL;
+        }
+
+        let backend = self.clone();
+        tokio::task::spawn(async move {
+            backend.refresh_accounts().await;
+            backend.account_refresh.lock().running = false;
+        });
+    }
+
+    async fn refresh_accounts(&self) {
+        let accounts: Vec<(Uuid, Arc<str>, Option<Arc<str>>)> = {
+            let mut account_info = self.account_info.write();
+            account_info.get().accounts.iter()
+                .filter(|(_, account)| !account.offline)
+                .map(|(uuid, account)| (*uuid, account.username.clone(), account.yggdrasil_api_root.clone()))
+                .collect()
+        };
+
+        for (uuid, username, yggdrasil_api_root) in accounts {
+            // Logging in and changing skins also refresh the credentials, so they mustn't run at the same time
+            let account_lock = self.account_lock(uuid);
+            let _account_guard = account_lock.lock().await;
+
+            if !self.account_info.write().get().accounts.contains_key(&uuid) {
+                continue;
+            }
+
+            match yggdrasil_api_root {
+                Some(api_root) => self.refresh_yggdrasil_account(uuid, &username, api_root).await,
+                None => self.refresh_microsoft_account(uuid, &username).await,
+            }
+        }
+    }
+
+    async fn refresh_microsoft_account(&self, uuid: Uuid, username: &str) {
+        let secret_storage = match self.get_secret_storage().await {
+            Ok(secret_storage) => secret_storage,
+            Err(error) => {
+                log::warn!("Unable to check {username}, error initializing secret storage: {error}");
+                return;
+            },
+        };
+
+        let mut credentials = match secret_storage.read_credentials(uuid).await {
+            Ok(Some(credentials)) => credentials,
+            Ok(None) => {
+                self.notify_needs_relogin(uuid, username);
+                return;
+            },
+            Err(error) => {
+                log::warn!("Unable to read credentials for {username}: {error}");
+                return;
+            },
+        };
+
+        if !credentials.needs_refresh(REFRESH_MARGIN) {
+            return;
+        }
+
+        log::info!("Refreshing credentials for {username} in the background");
+        credentials.expire_within(REFRESH_MARGIN);
+
+        let modal_action = ModalAction::default();
+        let login_tracker = ProgressTracker::new(Arc::from("Refreshing account"), self.send.clone());
+
+        match self.login(&mut credentials, &login_tracker, &modal_action, false).await {
+            Ok((profile, _)) => {
+                if let Err(error) = secret_storage.write_credentials(profile.id, &credentials).await {
+                    log::warn!("Unable to write refreshed credentials for {username}: {error}");
+                }
+                self.set_needs_relogin(uuid, false);
+            },
+            Err(error) if error.requires_relogin() => {
+                log::warn!("Credentials for {username} can no longer be refreshed: {error}");
+                self.notify_needs_relogin(uuid, username);
+            },
+            Err(error) => {
+                log::warn!("Unable to refresh credentials for {username}, trying again later: {error}");
+            },
+        }
+    }
+
+    async fn refresh_yggdrasil_account(&self, uuid: Uuid, username: &str, api_root: Arc<str>) {
+        let client = YggdrasilClient::new(self.redirecting_http_client.clone(), api_root);
+
+        match self.refresh_yggdrasil_session(&client, uuid).await {
+            Ok((_, refreshed_username)) => {
+                if refreshed_username.is_some() {
+                    log::info!("Refreshed the session for {username} in the background");
+                }
+                self.set_needs_relogin(uuid, false);
+            },
+            Err(error) if error.requires_relogin() => {
+                log::warn!("Session for {username} can no longer be refreshed: {error}");
+                self.notify_needs_relogin(uuid, username);
+            },
+            Err(error) => {
+                log::warn!("Unable to refresh the session for {username}, trying again later: {error}");
+            },
+        }
+    }
+
+    /// Lock held while an account's credentials are being refreshed or replaced
+    pub fn account_lock(&self, uuid: Uuid) -> Arc<tokio::sync::Mutex<()>> {
+        self.account_locks.lock().entry(uuid).or_default().clone()
+    }
+
+    fn notify_needs_relogin(&self, uuid: Uuid, username: &str) {
+        if self.set_needs_relogin(uuid, true) {
+            self.send.send_warning(format!("The session for {username} has expired, log in again before playing"));
+        }
+    }
+
+    /// Returns whether the account's state changed
+    pub fn set_needs_relogin(&self, uuid: Uuid, needs_relogin: bool) -> bool {
+        let mut account_info = self.account_info.write();
+
+        let Some(account) = account_info.get().accounts.get(&uuid) else {
+            return false;
+        };
+        if account.needs_relogin == needs_relogin {
+            return false;
+        }
+
+        account_info.modify(|info| {
+            if let Some(account) = info.accounts.get_mut(&uuid) {
+                account.needs_relogin = needs_relogin;
+            }
+        });
+        true
+    }
+}
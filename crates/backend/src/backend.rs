@@ -3,27 +3,28 @@ use std::{
 };
 
 use auth::{
-    authenticator::{Authenticator, MsaAuthorizationError, XboxAuthenticateError},
+    authenticator::{Authenticator, DeviceCodeError, MsaAuthorizationError, XboxAuthenticateError},
     credentials::{AccountCredentials, AUTH_STAGE_COUNT},
-    models::{MinecraftAccessToken, MinecraftProfileResponse, SkinState},
-    secret::{PlatformSecretStorage, SecretStorageError},
+    models::{MinecraftAccessToken, MinecraftProfileResponse, MsaTokens, SkinState},
+    secret::SecretStorage,
     serve_redirect::{self, ProcessAuthorizationError},
 };
 use bridge::{
     handle::{BackendHandle, BackendReceiver, FrontendHandle}, install::{ContentDownload, ContentInstall, ContentInstallFile, ContentInstallPath}, instance::{InstanceID, InstanceContentSummary, InstanceServerSummary, InstanceWorldSummary, ContentType}, message::MessageToFrontend, modal_action::{ModalAction, ModalActionVisitUrl, ProgressTracker, ProgressTrackerFinishType}, safe_path::SafePath
 };
 use indexmap::IndexSet;
-use parking_lot::RwLock;
+use parking_lot::{Mutex, RwLock};
 use reqwest::{StatusCode, redirect::Policy};
 use rustc_hash::{FxHashMap, FxHashSet};
-use schema::{backend_config::BackendConfig, instance::InstanceConfiguration, loader::Loader, modrinth::ModrinthSideRequirement};
+use schema::{backend_config::BackendConfig, instance::{InstanceConfiguration, InstanceKind, ReleaseChannel}, loader::Loader, server_properties::ServerProperties};
 use sha1::{Digest, Sha1};
-use tokio::sync::{mpsc::Receiver, OnceCell};
+use tokio::sync::mpsc::Receiver;
+use tokio_util::sync::CancellationToken;
 use ustr::Ustr;
 use uuid::Uuid;
 
 use crate::{
-    account::{BackendAccountInfo, MinecraftLoginInfo}, directories::LauncherDirectories, id_slab::IdSlab, instance::{Instance, ContentFolder}, launch::Launcher, metadata::{items::MinecraftVersionManifestMetadataItem, manager::MetadataManager}, mod_metadata::ModMetadataManager, persistent::Persistent
+    account::{AccountKind, BackendAccountInfo, MinecraftLoginInfo}, account_refresh::AccountRefreshState, directories::LauncherDirectories, download_manager::DownloadManager, endpoints::Endpoints, id_slab::IdSlab, instance::{Instance, ContentFolder}, launch::Launcher, metadata::{items::MinecraftVersionManifestMetadataItem, manager::MetadataManager}, mod_metadata::ModMetadataManager, persistent::Persistent, proxy::SharedProxy, skins::SkinLibrary
 };
 
 pub fn start(launcher_dir: PathBuf, send: FrontendHandle, self_handle: BackendHandle, recv: BackendReceiver) {
@@ -33,7 +34,15 @@ pub fn start(launcher_dir: PathBuf, send: FrontendHandle, self_handle: BackendHa
         .build()
         .expect("Failed to initialize Tokio runtime");
 
-    let http_client = reqwest::ClientBuilder::new()
+    let directories = Arc::new(LauncherDirectories::new(launcher_dir));
+
+    // Load config
+    let config: Arc<RwLock<Persistent<BackendConfig>>> = Arc::new(RwLock::new(Persistent::load(directories.config_json.clone())));
+    let root_certificates = load_root_certificates(config.write().get(), &send);
+    // Applied once the password has been read from the secret storage
+    let proxy = Arc::new(SharedProxy::default());
+
+    let http_client = configure_network(reqwest::ClientBuilder::new(), &proxy, &root_certificates)
         .connect_timeout(Duration::from_secs(15))
         .read_timeout(Duration::from_secs(15))
         .redirect(Policy::none())
@@ -42,17 +51,21 @@ pub fn start(launcher_dir: PathBuf, send: FrontendHandle, self_handle: BackendHa
         .build()
         .unwrap();
 
-    let redirecting_http_client = reqwest::ClientBuilder::new()
+    let redirecting_http_client = configure_network(reqwest::ClientBuilder::new(), &proxy, &root_certificates)
         .use_rustls_tls()
         .user_agent("PandoraLauncher/0.1.0 (https://github.com/Moulberry/PandoraLauncher)")
         .build()
         .unwrap();
 
-    let directories = Arc::new(LauncherDirectories::new(launcher_dir));
+    let endpoints = Arc::new(Endpoints::new(Arc::clone(&config)));
+
+    let downloads = Arc::new(DownloadManager::new(redirecting_http_client.clone(), Arc::clone(&endpoints), Arc::clone(&config)));
 
     let meta = Arc::new(MetadataManager::new(
         http_client.clone(),
         directories.metadata_dir.clone(),
+        send.clone(),
+        endpoints,
     ));
 
     let (watcher_tx, watcher_rx) = tokio::sync::mpsc::channel::<notify_debouncer_full::DebounceEventResult>(64);
@@ -82,9 +95,7 @@ pub fn start(launcher_dir: PathBuf, send: FrontendHandle, self_handle: BackendHa
 
     // Load accounts
     let account_info = Persistent::load(directories.accounts_json.clone());
-
-    // Load config
-    let config = Persistent::load(directories.config_json.clone());
+    let skin_library = Persistent::load(directories.skins_json.clone());
 
     let mut state = BackendState {
         self_handle,
@@ -92,20 +103,30 @@ pub fn start(launcher_dir: PathBuf, send: FrontendHandle, self_handle: BackendHa
         http_client,
         redirecting_http_client,
         meta: Arc::clone(&meta),
+        downloads: Arc::clone(&downloads),
         instance_state: Arc::new(RwLock::new(state_instances)),
         file_watching: Arc::new(RwLock::new(state_file_watching)),
         directories: Arc::clone(&directories),
-        launcher: Launcher::new(meta, directories, send),
+        launcher: Launcher::new(meta, downloads, directories, send),
         mod_metadata_manager: Arc::new(mod_metadata_manager),
         account_info: Arc::new(RwLock::new(account_info)),
-        config: Arc::new(RwLock::new(config)),
-        secret_storage: Arc::new(OnceCell::new()),
+        skin_library: Arc::new(RwLock::new(skin_library)),
+        config,
+        secret_storage: Default::default(),
+        secret_passphrase: Default::default(),
+        curseforge_api_key: Default::default(),
         head_cache: Default::default(),
+        account_refresh: Default::default(),
+        account_locks: Default::default(),
+        offline_session: Default::default(),
+        proxy,
+        background_preparations: Default::default(),
     };
 
     log::debug!("Doing initial backend load");
 
     runtime.block_on(async {
+        state.reload_proxy(true).await;
         state.send.send(state.account_info.write().get().create_update_message());
         state.load_all_instances().await;
     });
@@ -115,6 +136,35 @@ pub fn start(launcher_dir: PathBuf, send: FrontendHandle, self_handle: BackendHa
     std::mem::forget(runtime);
 }
 
+/// Reads the extra root certificates from the config, reporting any that are invalid
+fn load_root_certificates(config: &BackendConfig, send: &FrontendHandle) -> Vec<reqwest::Certificate> {
+    let mut root_certificates = Vec::new();
+    for path in &config.extra_root_certificates {
+        let result = std::fs::read(path).map_err(|error| error.to_string()).and_then(|bytes| {
+            reqwest::Certificate::from_pem_bundle(&bytes).map_err(|error| error.to_string())
+        });
+        match result {
+            Ok(certificates) => root_certificates.extend(certificates),
+            Err(error) => {
+                log::error!("Unable to load certificates from {:?}: {error}", path);
+                send.send_error(format!("Unable to load certificates from {:?}:\n{error}", path));
+            },
+        }
+    }
+    root_certificates
+}
+
+fn configure_network(mut builder: reqwest::ClientBuilder, proxy: &Arc<SharedProxy>, root_certificates: &[reqwest::Certificate]) -> reqwest::ClientBuilder {
+    builder = builder.proxy(proxy.reqwest_proxy());
+    for proxy in crate::proxy::environment_proxies() {
+        builder = builder.proxy(proxy);
+    }
+    for certificate in root_certificates {
+        builder = builder.add_root_certificate(certificate.clone());
+    }
+    builder
+}
+
 #[derive(Debug, Clone, Copy)]
 pub enum WatchTarget {
     RootDir,
@@ -149,15 +199,28 @@ pub struct BackendState {
     pub http_client: reqwest::Client,
     pub redirecting_http_client: reqwest::Client,
     pub meta: Arc<MetadataManager>,
+    pub downloads: Arc<DownloadManager>,
     pub instance_state: Arc<RwLock<BackendStateInstances>>,
     pub file_watching: Arc<RwLock<BackendStateFileWatching>>,
     pub directories: Arc<LauncherDirectories>,
     pub launcher: Launcher,
     pub mod_metadata_manager: Arc<ModMetadataManager>,
     pub account_info: Arc<RwLock<Persistent<BackendAccountInfo>>>,
+    pub skin_library: Arc<RwLock<Persistent<SkinLibrary>>>,
     pub config: Arc<RwLock<Persistent<BackendConfig>>>,
-    pub secret_storage: Arc<OnceCell<Result<PlatformSecretStorage, SecretStorageError>>>,
-    pub head_cache: Arc<RwLock<FxHashMap<Arc<str>, HeadCacheEntry>>>
+    pub secret_storage: Arc<tokio::sync::Mutex<Option<Arc<SecretStorage>>>>,
+    /// Passphrase of the encrypted secret file entered in the settings, only kept in memory
+    pub secret_passphrase: Arc<Mutex<Option<Arc<str>>>>,
+    /// CurseForge api key from the secret storage, None until it's been read
+    pub curseforge_api_key: Arc<tokio::sync::Mutex<Option<Option<Arc<str>>>>>,
+    pub head_cache: Arc<RwLock<FxHashMap<Arc<str>, HeadCacheEntry>>>,
+    pub account_refresh: Arc<Mutex<AccountRefreshState>>,
+    pub account_locks: Arc<Mutex<HashMap<Uuid, Arc<tokio::sync::Mutex<()>>>>>,
+    /// Api root of the local session server serving offline skins, started on the first launch that needs it
+    pub offline_session: Arc<tokio::sync::OnceCell<Arc<str>>>,
+    pub proxy: Arc<SharedProxy>,
+    /// Cancels the background preparation running for each .minecraft folder
+    pub background_preparations: Arc<Mutex<HashMap<Arc<Path>, CancellationToken>>>,
 }
 
 pub enum HeadCacheEntry {
@@ -176,6 +239,7 @@ impl BackendState {
 
         // Pre-fetch version manifest
         self.meta.load(&MinecraftVersionManifestMetadataItem).await;
+        self.meta.evict_offline_cache().await;
 
         self.handle(recv, watcher_rx).await;
     }
@@ -346,14 +410,20 @@ impl BackendState {
 
     async fn handle_tick(&mut self) {
         self.meta.expire().await;
+        self.refresh_accounts_if_due();
 
         let mut instance_state = self.instance_state.write();
         for instance in instance_state.instances.iter_mut() {
-            if let Some(child) = &mut instance.child
-                && !matches!(child.try_wait(), Ok(None))
-            {
+            let count = instance.sessions.len();
+            instance.sessions.retain_mut(|session| {
+                if session.is_alive() {
+                    return true;
+                }
                 log::debug!("Child process is no longer alive");
-                instance.child = None;
+                session.cleanup();
+                false
+            });
+            if instance.sessions.len() != count {
                 self.send.send(instance.create_modify_message());
             }
         }
@@ -364,6 +434,7 @@ impl BackendState {
         credentials: &mut AccountCredentials,
         login_tracker: &ProgressTracker,
         modal_action: &ModalAction,
+        interactive: bool,
     ) -> Result<(MinecraftProfileResponse, MinecraftAccessToken), LoginError> {
         log::info!("Starting login");
 
@@ -405,29 +476,46 @@ impl BackendState {
                 auth::credentials::AuthStageWithData::Initial => {
                     log::debug!("Auth Flow: Initial");
 
-                    let pending = authenticator.create_authorization();
-                    modal_action.set_visit_url(ModalActionVisitUrl {
-                        message: "Login with Microsoft".into(),
-                        url: pending.url.as_str().into(),
-                        prevent_auto_finish: false,
-                    });
-                    self.send.send(MessageToFrontend::Refresh);
+                    if !interactive {
+                        return Err(LoginError::InteractionRequired);
+                    }
 
-                    log::debug!("Starting serve_redirect server");
-                    let finished = tokio::select! {
-                        finished = serve_redirect::start_server(pending) => finished?,
-                        _ = modal_action.request_cancel.cancelled() => {
-                            return Err(LoginError::CancelledByUser);
-                        }
-                    };
+                    let msa_tokens = if self.config.write().get().device_code_login {
+                        self.login_with_device_code(&mut authenticator, modal_action).await?
+                    } else {
+                        let pending = authenticator.create_authorization();
+                        modal_action.set_visit_url(ModalActionVisitUrl {
+                            message: "Login with Microsoft".into(),
+                            url: pending.url.as_str().into(),
+                            prevent_auto_finish: false,
+                        });
+                        self.send.send(MessageToFrontend::Refresh);
+
+                        log::debug!("Starting serve_redirect server");
+                        let result = tokio::select! {
+                            result = serve_redirect::start_server(pending) => result,
+                            _ = modal_action.request_cancel.cancelled() => {
+                                return Err(LoginError::CancelledByUser);
+                            }
+                        };
 
-                    log::debug!("serve_redirect handled successfully");
+                        modal_action.unset_visit_url();
+                        self.send.send(MessageToFrontend::Refresh);
 
-                    modal_action.unset_visit_url();
-                    self.send.send(MessageToFrontend::Refresh);
+                        match result {
+                            Ok(finished) => {
+                                log::debug!("serve_redirect handled successfully");
 
-                    log::debug!("Finishing authorization, getting msa tokens");
-                    let msa_tokens = authenticator.finish_authorization(finished).await?;
+                                log::debug!("Finishing authorization, getting msa tokens");
+                                authenticator.finish_authorization(finished).await?
+                            },
+                            Err(ProcessAuthorizationError::StartServer(error)) => {
+                                log::warn!("Unable to start serve_redirect server, falling back to device code: {error}");
+                                self.login_with_device_code(&mut authenticator, modal_action).await?
+                            },
+                            Err(error) => return Err(error.into()),
+                        }
+                    };
 
                     credentials.msa_access = Some(msa_tokens.access);
                     credentials.msa_refresh = msa_tokens.refresh;
@@ -536,6 +624,40 @@ impl BackendState {
         }
     }
 
+    async fn login_with_device_code(
+        &self,
+        authenticator: &mut Authenticator,
+        modal_action: &ModalAction,
+    ) -> Result<MsaTokens, LoginError> {
+        log::debug!("Requesting device code");
+        let pending = tokio::select! {
+            pending = authenticator.start_device_code() => pending?,
+            _ = modal_action.request_cancel.cancelled() => {
+                return Err(LoginError::CancelledByUser);
+            }
+        };
+
+        modal_action.set_visit_url(ModalActionVisitUrl {
+            message: format!("Enter the code {} at {} to login with Microsoft", pending.user_code, pending.verification_uri).into(),
+            url: pending.verification_uri.clone(),
+            prevent_auto_finish: false,
+        });
+        self.send.send(MessageToFrontend::Refresh);
+
+        log::debug!("Polling for device code authorization");
+        let result = tokio::select! {
+            result = authenticator.poll_device_code(&pending) => result,
+            _ = modal_action.request_cancel.cancelled() => {
+                return Err(LoginError::CancelledByUser);
+            }
+        };
+
+        modal_action.unset_visit_url();
+        self.send.send(MessageToFrontend::Refresh);
+
+        Ok(result?)
+    }
+
     pub fn update_profile_head(&self, profile: &MinecraftProfileResponse) {
         log::info!("Updating profile head for {}", profile.id);
 
@@ -570,10 +692,11 @@ impl BackendState {
         let skin_url = skin.url;
 
         let http_client = self.http_client.clone();
+        let endpoints = Arc::clone(&self.meta.endpoints);
 
         tokio::task::spawn(async move {
             log::info!("Downloading skin from {}", skin_url);
-            let Ok(response) = http_client.get(&*skin_url).send().await else {
+            let Ok(response) = endpoints.send(http_client.get(&*skin_url)).await else {
                 log::warn!("Http error while requesting skin from {}", skin_url);
                 head_cache.write().insert(skin_url.clone(), HeadCacheEntry::Failed);
                 return;
@@ -647,9 +770,9 @@ impl BackendState {
     }
 
     pub async fn prelaunch_apply_modpacks(&self, id: InstanceID, modal_action: &ModalAction) -> Vec<PathBuf> {
-        let (loader, minecraft_version, mod_dir) = if let Some(instance) = self.instance_state.write().instances.get_mut(id) {
+        let (loader, minecraft_version, kind, mod_dir) = if let Some(instance) = self.instance_state.write().instances.get_mut(id) {
             let configuration = instance.configuration.get();
-            (configuration.loader, configuration.minecraft_version, instance.content_state[ContentFolder::Mods].path.clone())
+            (configuration.loader, configuration.minecraft_version, configuration.kind, instance.content_state[ContentFolder::Mods].path.clone())
         } else {
             return Vec::new();
         };
@@ -700,7 +823,7 @@ impl BackendState {
 
                 let filtered_downloads = downloads.iter().filter(|dl| {
                     if let Some(env) = dl.env {
-                        if env.client == ModrinthSideRequirement::Unsupported {
+                        if !kind.supports(env.client, env.server) {
                             return false;
                         }
                     }
@@ -956,10 +1079,10 @@ impl BackendState {
             }
         }
 
-        return self.create_instance(&name, version, loader).await;
+        return self.create_instance(&name, version, loader, InstanceKind::Client).await.map(|(instance_dir, _)| instance_dir);
     }
 
-    pub async fn create_instance(&self, name: &str, version: &str, loader: Loader) -> Option<PathBuf> {
+    pub async fn create_instance(&self, name: &str, version: &str, loader: Loader, kind: InstanceKind) -> Option<(PathBuf, InstanceConfiguration)> {
         log::info!("Creating instance {name}");
         if loader == Loader::Unknown {
             self.send.send_warning(format!("Unable to create instance, unknown loader"));
@@ -985,18 +1108,29 @@ impl BackendState {
         let _ = tokio::fs::create_dir_all(&instance_dir).await;
 
         let instance_info = InstanceConfiguration {
+            kind,
             minecraft_version: Ustr::from(version),
             loader,
             preferred_loader_version: None,
             memory: None,
             jvm_flags: None,
             jvm_binary: None,
+            release_channel: ReleaseChannel::default(),
+            account: None,
         };
 
         let info_path = instance_dir.join("info_v1.json");
         crate::write_safe(&info_path, serde_json::to_string(&instance_info).unwrap().as_bytes()).unwrap();
 
-        Some(instance_dir.clone())
+        // Servers are only created once the EULA has been accepted in the frontend
+        if kind == InstanceKind::Server {
+            let eula_path = instance_dir.join(".minecraft").join("eula.txt");
+            if let Err(error) = crate::write_safe(&eula_path, b"eula=true\n") {
+                self.send.send_warning(format!("Unable to accept the EULA for the server: {error}"));
+            }
+        }
+
+        Some((instance_dir, instance_info))
     }
 
     pub async fn rename_instance(&self, id: InstanceID, name: &str) {
@@ -1023,38 +1157,78 @@ impl BackendState {
         }
     }
 
-    pub async fn get_login_info(&self, modal_action: &ModalAction) -> Option<MinecraftLoginInfo> {
-        let selected_account = {
+    /// Reads the server.properties of a server instance, which is empty until the server has been started once
+    pub fn load_server_properties(&self, id: InstanceID) -> std::io::Result<(PathBuf, ServerProperties)> {
+        let Some(dot_minecraft_path) = self.instance_state.read().instances.get(id).map(|instance| instance.dot_minecraft_path.clone()) else {
+            return Err(std::io::Error::other("unknown instance"));
+        };
+
+        let path = dot_minecraft_path.join("server.properties");
+        match std::fs::read_to_string(&path) {
+            Ok(text) => Ok((path, ServerProperties::parse(&text))),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok((path, ServerProperties::default())),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Logs in with `bound_account` if given, otherwise with the selected account
+    pub async fn get_login_info(&self, modal_action: &ModalAction, bound_account: Option<Uuid>) -> Option<MinecraftLoginInfo> {
+        let (selected_account, yggdrasil_api_root, offline_login) = {
             let mut account_info = self.account_info.write();
             let account_info = account_info.get();
 
-            let mut selected_account = account_info.selected_account;
+            let mut selected_account = bound_account.or(account_info.selected_account);
+            let mut yggdrasil_api_root = None;
+            let mut offline_login = None;
 
             if let Some(uuid) = selected_account {
                 if let Some(account) = account_info.accounts.get(&uuid) {
-                    if account.offline {
-                        return Some(MinecraftLoginInfo {
+                    // Without a connection the session can't be refreshed, so launch without one
+                    if account.offline || self.meta.connectivity.is_offline() {
+                        let login_info = MinecraftLoginInfo {
                             uuid,
                             username: account.username.clone(),
-                            access_token: None
-                        })
+                            kind: account.kind(),
+                            access_token: None,
+                            authlib_injector: None,
+                        };
+                        if !account.offline || account.offline_skin.is_none() {
+                            return Some(login_info);
+                        }
+                        offline_login = Some(login_info);
                     }
+                    yggdrasil_api_root = account.yggdrasil_api_root.clone();
+                } else if bound_account.is_some() {
+                    modal_action.set_error_message(format!("The account bound to this instance ({uuid}) no longer exists, choose another account in the instance settings").into());
+                    modal_action.set_finished();
+                    return None;
                 } else {
                     selected_account = None;
                 }
             }
 
-            selected_account
+            (selected_account, yggdrasil_api_root, offline_login)
         };
 
-        let Some((profile, access_token)) = self.login_flow(modal_action, selected_account).await else {
+        if let Some(mut login_info) = offline_login {
+            login_info.authlib_injector = self.offline_skin_launch(modal_action).await;
+            return Some(login_info);
+        }
+
+        if let Some(uuid) = selected_account && let Some(api_root) = yggdrasil_api_root {
+            return self.yggdrasil_login_flow(modal_action, uuid, api_root).await;
+        }
+
+        let Some((profile, access_token)) = self.login_flow(modal_action, selected_account, bound_account).await else {
             return None;
         };
 
         Some(MinecraftLoginInfo {
             uuid: profile.id,
             username: profile.name.clone(),
+            kind: AccountKind::Microsoft,
             access_token: Some(access_token),
+            authlib_injector: None,
         })
     }
 }
@@ -1149,6 +1323,17 @@ pub enum LoginError {
     MsaAuthorizationError(#[from] MsaAuthorizationError),
     #[error("XboxLive authentication error: {0}")]
     XboxAuthenticateError(#[from] XboxAuthenticateError),
+    #[error("Device code error: {0}")]
+    DeviceCodeError(#[from] DeviceCodeError),
+    #[error("The account needs to log in again")]
+    InteractionRequired,
     #[error("Cancelled by user")]
     CancelledByUser,
 }
+
+impl LoginError {
+    /// Whether the stored credentials are unusable, rather than the login failing temporarily
+    pub fn requires_relogin(&self) -> bool {
+        matches!(self, Self::InteractionRequired | Self::MsaAuthorizationError(MsaAuthorizationError::InvalidGrant))
+    }
+}
@@ -308,6 +308,9 @@ impl BackendState {
                     self.load_all_instances().await;
                 } else if file_name == "config.json" {
                     self.config.write().mark_changed(&path);
+                    self.meta.endpoints.reload();
+                    self.downloads.reload_config();
+                    self.reload_proxy(false).await;
                 } else if file_name == "accounts.json" {
                     let mut account_info = self.account_info.write();
                     account_info.mark_changed(&path);
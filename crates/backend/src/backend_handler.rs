@@ -1,19 +1,19 @@
 use std::{io::{BufRead, Read, Seek, SeekFrom, Write}, path::Path, sync::{atomic::Ordering, Arc}, time::{Duration, SystemTime}};
 
-use auth::{credentials::AccountCredentials, models::{MinecraftAccessToken, MinecraftProfileResponse}, secret::PlatformSecretStorage};
+use auth::{credentials::AccountCredentials, models::{MinecraftAccessToken, MinecraftProfileResponse}};
 use bridge::{
-    install::{ContentDownload, ContentInstall, ContentInstallFile, InstallTarget}, instance::{InstanceStatus, ContentType, ContentSummary}, message::{LogFiles, MessageToBackend, MessageToFrontend}, meta::MetadataResult, modal_action::{ModalAction, ModalActionVisitUrl, ProgressTracker, ProgressTrackerFinishType}, serial::AtomicOptionSerial
+    install::{ContentInstall, InstallTarget}, instance::{InstanceStatus, ContentType, ContentSummary}, message::{LogFiles, MessageToBackend, MessageToFrontend}, meta::MetadataResult, modal_action::{ModalAction, ModalActionVisitUrl, ProgressTracker, ProgressTrackerFinishType}, serial::AtomicOptionSerial
 };
 use futures::TryFutureExt;
 use rustc_hash::{FxHashMap, FxHashSet};
-use schema::{content::ContentSource, modrinth::ModrinthLoader, version::{LaunchArgument, LaunchArgumentValue}};
+use schema::{content::{ContentProviderKind, ContentSource}, instance::InstanceKind, loader::Loader, modrinth::ModrinthLoader, version::{LaunchArgument, LaunchArgumentValue}};
 use serde::Deserialize;
 use strum::IntoEnumIterator;
 use tokio::{io::AsyncBufReadExt, sync::Semaphore};
 use ustr::Ustr;
 
 use crate::{
-    BackendState, LoginError, account::{BackendAccount, MinecraftLoginInfo}, arcfactory::ArcStrFactory, instance::ContentFolder, launch::{ArgumentExpansionKey, LaunchError}, log_reader, metadata::{items::{AssetsIndexMetadataItem, FabricLoaderManifestMetadataItem, ForgeInstallerMavenMetadataItem, MinecraftVersionManifestMetadataItem, MinecraftVersionMetadataItem, ModrinthProjectVersionsMetadataItem, ModrinthSearchMetadataItem, ModrinthV3VersionUpdateMetadataItem, ModrinthVersionUpdateMetadataItem, MojangJavaRuntimeComponentMetadataItem, MojangJavaRuntimesMetadataItem, NeoforgeInstallerMavenMetadataItem, VersionUpdateParameters, VersionV3LoaderFields, VersionV3UpdateParameters}, manager::MetaLoadError}, mod_metadata::ModUpdateAction
+    BackendState, LoginError, account::{BackendAccount, MinecraftLoginInfo}, arcfactory::ArcStrFactory, content_provider::{ContentProvider, CurseForgeProvider, ModrinthProvider}, endpoints::Endpoints, instance::ContentFolder, launch::{ArgumentExpansionKey, LaunchError}, log_reader, metadata::{items::{AssetsIndexMetadataItem, CurseForgeModFilesMetadataItem, FabricLoaderManifestMetadataItem, ForgeInstallerMavenMetadataItem, MinecraftVersionManifestMetadataItem, MinecraftVersionMetadataItem, ModrinthProjectMetadataItem, ModrinthProjectVersionsMetadataItem, ModrinthSearchMetadataItem, ModrinthV3VersionUpdateMetadataItem, ModrinthVersionUpdateMetadataItem, MojangJavaRuntimeComponentMetadataItem, MojangJavaRuntimesMetadataItem, NeoforgeInstallerMavenMetadataItem, VersionUpdateParameters, VersionV3LoaderFields, VersionV3UpdateParameters}, manager::MetaLoadError}, mod_metadata::ModUpdateAction, session::{self, InstanceSession}
 };
 
 impl BackendState {
@@ -22,6 +22,7 @@ impl BackendState {
             MessageToBackend::RequestMetadata { request, force_reload } => {
                 let meta = self.meta.clone();
                 let send = self.send.clone();
+                let backend = self.clone();
                 tokio::task::spawn(async move {
                     let (result, keep_alive_handle) = match request {
                         bridge::meta::MetadataRequest::MinecraftVersionManifest => {
@@ -48,6 +49,31 @@ impl BackendState {
                             let (result, handle) = meta.fetch_with_keepalive(&ModrinthProjectVersionsMetadataItem(project_versions), force_reload).await;
                             (result.map(MetadataResult::ModrinthProjectVersionsResult), handle)
                         },
+                        bridge::meta::MetadataRequest::ModrinthProject(ref project_id) => {
+                            let (result, handle) = meta.fetch_with_keepalive(&ModrinthProjectMetadataItem(project_id.clone()), force_reload).await;
+                            (result.map(MetadataResult::ModrinthProject), handle)
+                        },
+                        bridge::meta::MetadataRequest::CurseForgeModFiles(ref mod_files) => {
+                            if let Some(api) = &backend.curseforge_api().await {
+                                let (result, handle) = meta.fetch_with_keepalive(&CurseForgeModFilesMetadataItem { api, request: mod_files }, force_reload).await;
+                                (result.map(MetadataResult::CurseForgeModFilesResult), handle)
+                            } else {
+                                (Err(MetaLoadError::Error("CurseForge isn't configured, an api key or api url must be set".into())), None)
+                            }
+                        },
+                        bridge::meta::MetadataRequest::ContentSearch(ref search) => {
+                            let (result, handle) = match search.provider {
+                                ContentProviderKind::Modrinth => {
+                                    ModrinthProvider { meta: &meta }.search(search, force_reload).await
+                                },
+                                ContentProviderKind::CurseForge => if let Some(api) = backend.curseforge_api().await {
+                                    CurseForgeProvider { meta: &meta, api }.search(search, force_reload).await
+                                } else {
+                                    (Err(MetaLoadError::Error("CurseForge isn't configured, an api key or api url must be set".into())), None)
+                                },
+                            };
+                            (result.map(|result| MetadataResult::ContentSearchResult(Arc::new(result))), handle)
+                        },
                     };
                     let result = result.map_err(|err| format!("{}", err).into());
                     send.send(MessageToFrontend::MetadataResult {
@@ -69,8 +95,10 @@ impl BackendState {
             MessageToBackend::RequestLoadResourcePacks { id } => {
                 tokio::task::spawn(self.clone().load_instance_content(id, ContentFolder::ResourcePacks));
             },
-            MessageToBackend::CreateInstance { name, version, loader } => {
-                self.create_instance(&name, &version, loader).await;
+            MessageToBackend::CreateInstance { name, version, loader, kind } => {
+                if let Some((instance_dir, configuration)) = self.create_instance(&name, &version, loader, kind).await {
+                    self.prepare_in_background(instance_dir.join(".minecraft").into(), configuration);
+                }
             },
             MessageToBackend::DeleteInstance { id } => {
                 if let Some(instance) = self.instance_state.write().instances.get_mut(id) {
@@ -89,6 +117,7 @@ impl BackendState {
                         configuration.minecraft_version = version;
                     });
                 }
+                self.prepare_instance_in_background(id);
             },
             MessageToBackend::SetInstanceLoader { id, loader } => {
                 if let Some(instance) = self.instance_state.write().instances.get_mut(id) {
@@ -97,6 +126,7 @@ impl BackendState {
                         configuration.preferred_loader_version = None;
                     });
                 }
+                self.prepare_instance_in_background(id);
             },
             MessageToBackend::SetInstancePreferredLoaderVersion { id, loader_version } => {
                 if let Some(instance) = self.instance_state.write().instances.get_mut(id) {
@@ -104,6 +134,7 @@ impl BackendState {
                         configuration.preferred_loader_version = loader_version.map(Ustr::from);
                     });
                 }
+                self.prepare_instance_in_background(id);
             }
             MessageToBackend::SetInstanceMemory { id, memory } => {
                 if let Some(instance) = self.instance_state.write().instances.get_mut(id) {
@@ -126,19 +157,38 @@ impl BackendState {
                     });
                 }
             },
-            MessageToBackend::KillInstance { id } => {
+            MessageToBackend::SetInstanceReleaseChannel { id, release_channel } => {
                 if let Some(instance) = self.instance_state.write().instances.get_mut(id) {
-                    if let Some(mut child) = instance.child.take() {
-                        let result = child.kill();
-                        if result.is_err() {
+                    instance.configuration.modify(|configuration| {
+                        configuration.release_channel = release_channel;
+                    });
+                }
+            },
+            MessageToBackend::SetInstanceAccount { id, account } => {
+                if let Some(instance) = self.instance_state.write().instances.get_mut(id) {
+                    instance.configuration.modify(|configuration| {
+                        configuration.account = account;
+                    });
+                }
+            },
+            MessageToBackend::KillInstance { id, session } => {
+                if let Some(instance) = self.instance_state.write().instances.get_mut(id) {
+                    let mut killed = false;
+                    for instance_session in instance.sessions.iter_mut() {
+                        if session.is_some_and(|session| session != instance_session.id) {
+                            continue;
+                        }
+                        killed = true;
+                        if let Err(error) = instance_session.child.kill() {
                             self.send.send_error("Failed to kill instance");
-                            log::error!("Failed to kill instance: {:?}", result.unwrap_err());
+                            log::error!("Failed to kill instance: {:?}", error);
                         }
+                    }
 
-                        self.send.send(instance.create_modify_message());
-                    } else {
+                    if !killed {
                         self.send.send_error("Can't kill instance, instance wasn't running");
                     }
+                    // Sessions are removed once the process has exited, see handle_tick
                     return;
                 }
 
@@ -147,30 +197,50 @@ impl BackendState {
             MessageToBackend::StartInstance {
                 id,
                 quick_play,
+                account,
                 modal_action,
             } => {
-                let Some(login_info) = self.get_login_info(&modal_action).await else {
-                    return;
+                let (bound_account, kind) = {
+                    let mut instance_state = self.instance_state.write();
+                    instance_state.instances.get_mut(id)
+                        .map(|instance| {
+                            let configuration = instance.configuration.get();
+                            (configuration.account, configuration.kind)
+                        })
+                        .unwrap_or_default()
                 };
+                let account = account.or(bound_account);
 
-                let add_mods = tokio::select! {
-                    add_mods = self.prelaunch(id, &modal_action) => add_mods,
-                    _ = modal_action.request_cancel.cancelled() => {
-                        self.send.send(MessageToFrontend::CloseModal);
+                // Dedicated servers don't log in, players authenticate with the server themselves
+                let login_info = if kind == InstanceKind::Server {
+                    None
+                } else {
+                    let Some(login_info) = self.get_login_info(&modal_action, account).await else {
                         return;
-                    }
+                    };
+                    Some(login_info)
+                };
+                let (account, username) = match &login_info {
+                    Some(login_info) => (login_info.uuid, login_info.username.clone()),
+                    None => (uuid::Uuid::nil(), Arc::from("Server")),
                 };
 
-                if modal_action.error.read().unwrap().is_some() {
-                    modal_action.set_finished();
-                    self.send.send(MessageToFrontend::Refresh);
-                    return;
-                }
-
-                let (dot_minecraft, configuration) = if let Some(instance) = self.instance_state.write().instances.get_mut(id) {
-                    if instance.child.is_some() {
-                        self.send.send_warning("Can't launch instance, already running");
-                        modal_action.set_error_message("Can't launch instance, already running".into());
+                let session_id = InstanceSession::next_id();
+                let (dot_minecraft, running_add_mods) = if let Some(instance) = self.instance_state.write().instances.get_mut(id) {
+                    let error = if kind == InstanceKind::Server && (instance.launching || !instance.sessions.is_empty()) {
+                        Some("Can't start the server, it's already running".to_string())
+                    } else if let Some(session) = instance.sessions.iter().find(|session| session.account == account) {
+                        Some(format!("Can't launch instance, already running as {}. Choose another account to start another session", session.username))
+                    } else if instance.launching_accounts.contains(&account) {
+                        Some(format!("Can't launch instance, it's already launching as {username}. Choose another account to start another session"))
+                    } else if instance.launching && instance.sessions.is_empty() {
+                        Some("Can't launch another session until the instance has finished launching".to_string())
+                    } else {
+                        None
+                    };
+                    if let Some(error) = error {
+                        self.send.send_warning(error.clone());
+                        modal_action.set_error_message(error.into());
                         modal_action.set_finished();
                         return;
                     }
@@ -178,24 +248,90 @@ impl BackendState {
                     self.send.send(MessageToFrontend::MoveInstanceToTop {
                         id
                     });
+                    instance.launching = true;
+                    instance.launching_accounts.push(account);
                     self.send.send(instance.create_modify_message_with_status(InstanceStatus::Launching));
 
-                    (instance.dot_minecraft_path.clone(), instance.configuration.get().clone())
+                    (instance.dot_minecraft_path.clone(), instance.sessions.first().map(|session| session.add_mods.clone()))
                 } else {
                     self.send.send_error("Can't launch instance, unknown id");
                     modal_action.set_error_message("Can't launch instance, unknown id".into());
                     modal_action.set_finished();
                     return;
                 };
+                let already_running = running_add_mods.is_some();
+
+                // The running session is using the instance's files, so syncing and modpack files aren't touched
+                let add_mods = match running_add_mods {
+                    Some(add_mods) => add_mods,
+                    None => tokio::select! {
+                        add_mods = self.prelaunch(id, &modal_action) => add_mods,
+                        _ = modal_action.request_cancel.cancelled() => {
+                            self.send.send(MessageToFrontend::CloseModal);
+                            if let Some(instance) = self.instance_state.write().instances.get_mut(id) {
+                                instance.finish_launching(account);
+                                self.send.send(instance.create_modify_message());
+                            }
+                            return;
+                        }
+                    },
+                };
+
+                if modal_action.error.read().unwrap().is_some() {
+                    modal_action.set_finished();
+                    if let Some(instance) = self.instance_state.write().instances.get_mut(id) {
+                        instance.finish_launching(account);
+                        self.send.send(instance.create_modify_message());
+                    }
+                    self.send.send(MessageToFrontend::Refresh);
+                    return;
+                }
+
+                let Some(configuration) = self.instance_state.read().instances.get(id).map(|instance| instance.configuration.get().clone()) else {
+                    modal_action.set_error_message("Can't launch instance, unknown id".into());
+                    modal_action.set_finished();
+                    return;
+                };
+
+                // Another session is using the instance's own files, so this one gets a directory of its own
+                let session_dir = if already_running {
+                    match session::create_session_dir(&self.directories.temp_sessions_dir, session_id, &dot_minecraft) {
+                        Ok(session_dir) => Some(session_dir),
+                        Err(error) => {
+                            modal_action.set_error_message(format!("Unable to create a directory for the new session: {error}").into());
+                            modal_action.set_finished();
+                            if let Some(instance) = self.instance_state.write().instances.get_mut(id) {
+                                instance.finish_launching(account);
+                                self.send.send(instance.create_modify_message());
+                            }
+                            return;
+                        },
+                    }
+                } else {
+                    None
+                };
+                let game_dir = session_dir.as_deref().map(Arc::from).unwrap_or(dot_minecraft);
 
                 let launch_tracker = ProgressTracker::new(Arc::from("Launching"), self.send.clone());
                 modal_action.trackers.push(launch_tracker.clone());
 
-                let result = self.launcher.launch(&self.redirecting_http_client, dot_minecraft, configuration, quick_play, login_info, add_mods, &launch_tracker, &modal_action).await;
+                let result = match login_info {
+                    Some(login_info) => {
+                        self.launcher.launch(&self.redirecting_http_client, game_dir, configuration, quick_play, login_info, add_mods.clone(), &launch_tracker, &modal_action).await
+                    },
+                    None => {
+                        self.launcher.launch_server(&self.redirecting_http_client, game_dir, configuration, add_mods.clone(), &launch_tracker, &modal_action).await
+                    },
+                };
+
+                if result.is_err() && let Some(session_dir) = &session_dir {
+                    session::remove_session_dir(session_dir);
+                }
 
                 if matches!(result, Err(LaunchError::CancelledByUser)) {
                     self.send.send(MessageToFrontend::CloseModal);
                     if let Some(instance) = self.instance_state.write().instances.get_mut(id) {
+                        instance.finish_launching(account);
                         self.send.send(instance.create_modify_message());
                     }
                     return;
@@ -204,13 +340,24 @@ impl BackendState {
                 let is_err = result.is_err();
                 match result {
                     Ok(mut child) => {
-                        if self.config.write().get().open_game_output_when_launching {
+                        let mut game_output = None;
+                        // Servers are controlled through their console, so it's always opened
+                        if kind == InstanceKind::Server || self.config.write().get().open_game_output_when_launching {
                             if let Some(stdout) = child.stdout.take() {
-                                log_reader::start_game_output(stdout, child.stderr.take(), self.send.clone());
+                                // Only servers keep stdin open, the game's is used up by the launch wrapper
+                                game_output = Some(log_reader::start_game_output(stdout, child.stderr.take(), child.stdin.take(), self.send.clone()));
                             }
                         }
                         if let Some(instance) = self.instance_state.write().instances.get_mut(id) {
-                            instance.child = Some(child);
+                            instance.sessions.push(InstanceSession {
+                                id: session_id,
+                                child,
+                                account,
+                                username,
+                                game_output,
+                                add_mods,
+                                session_dir,
+                            });
                         }
                     },
                     Err(ref err) => {
@@ -219,6 +366,7 @@ impl BackendState {
                 }
 
                 if let Some(instance) = self.instance_state.write().instances.get_mut(id) {
+                    instance.finish_launching(account);
                     self.send.send(instance.create_modify_message());
                 }
 
@@ -277,6 +425,44 @@ impl BackendState {
                     }
                 }
             },
+            MessageToBackend::SetContentPinned { id, content_ids, pinned } => {
+                let mut instance_state = self.instance_state.write();
+                let Some(instance) = instance_state.instances.get_mut(id) else {
+                    return;
+                };
+
+                let mut reload = FxHashSet::default();
+
+                for content_id in content_ids {
+                    let Some((instance_mod, folder)) = instance.try_get_content(content_id) else {
+                        continue;
+                    };
+                    if instance_mod.pinned == pinned {
+                        continue;
+                    }
+                    let Some(pinned_state_path) = crate::pinned_state_path(&instance_mod.path) else {
+                        continue;
+                    };
+
+                    let result = if pinned {
+                        std::fs::write(&pinned_state_path, b"")
+                    } else {
+                        std::fs::remove_file(&pinned_state_path)
+                    };
+
+                    match result {
+                        Ok(_) => {
+                            reload.insert((id, folder));
+                        },
+                        Err(error) => {
+                            let error = format!("Error occured while updating pinned state: {error}");
+                            self.send.send_error(error);
+                        },
+                    }
+                }
+
+                instance_state.reload_immediately.extend(reload);
+            },
             MessageToBackend::DownloadAllMetadata => {
                 self.download_all_metadata().await;
             },
@@ -306,10 +492,19 @@ impl BackendState {
 
                 instance_state.reload_immediately.extend(reload);
             },
+            MessageToBackend::SetOfflineMode { offline } => {
+                self.meta.connectivity.set_offline(offline);
+            },
             MessageToBackend::UpdateCheck { instance: id, modal_action } => {
-                let (loader, version) = if let Some(instance) = self.instance_state.write().instances.get_mut(id) {
+                if self.meta.connectivity.is_offline() {
+                    modal_action.set_error_message("Can't check for updates while offline".into());
+                    modal_action.set_finished();
+                    return;
+                }
+
+                let (loader, version, release_channel) = if let Some(instance) = self.instance_state.write().instances.get_mut(id) {
                     let configuration = instance.configuration.get();
-                    (configuration.loader, configuration.minecraft_version)
+                    (configuration.loader, configuration.minecraft_version, configuration.release_channel)
                 } else {
                     self.send.send_error("Can't update instance, unknown id");
                     modal_action.set_error_message("Can't update instance, unknown id".into());
@@ -342,26 +537,31 @@ impl BackendState {
                 let mod_params = &VersionUpdateParameters {
                     loaders: [modrinth_loader].into(),
                     game_versions: [version].into(),
+                    version_types: release_channel.version_types(),
                 };
 
                 let fabric_mod_params = &VersionUpdateParameters {
                     loaders: [ModrinthLoader::Fabric].into(),
                     game_versions: [version].into(),
+                    version_types: release_channel.version_types(),
                 };
 
                 let forge_mod_params = &VersionUpdateParameters {
                     loaders: [ModrinthLoader::Forge].into(),
                     game_versions: [version].into(),
+                    version_types: release_channel.version_types(),
                 };
 
                 let neoforge_mod_params = &VersionUpdateParameters {
                     loaders: [ModrinthLoader::NeoForge].into(),
                     game_versions: [version].into(),
+                    version_types: release_channel.version_types(),
                 };
 
                 let resourcepack_params = &VersionUpdateParameters {
                     loaders: [ModrinthLoader::Minecraft].into(),
                     game_versions: [version].into(),
+                    version_types: release_channel.version_types(),
                 };
 
                 let modrinth_modpack_params = &VersionV3UpdateParameters {
@@ -370,9 +570,28 @@ impl BackendState {
                         mrpack_loaders: [modrinth_loader].into(),
                         game_versions: [version].into(),
                     },
+                    version_types: release_channel.version_types(),
                 };
 
                 let meta = self.meta.clone();
+                let curseforge_api = self.curseforge_api().await;
+
+                // Manually installed files may have come from CurseForge, identify them all with one request
+                if let Some(api) = &curseforge_api {
+                    let manual: Vec<([u8; 20], Arc<Path>)> = {
+                        let sources = self.mod_metadata_manager.read_content_sources();
+                        content.iter()
+                            .filter(|summary| !summary.pinned && sources.get(&summary.content_summary.hash).unwrap_or(ContentSource::Manual) == ContentSource::Manual)
+                            .map(|summary| (summary.content_summary.hash, summary.path.clone()))
+                            .collect()
+                    };
+
+                    if !manual.is_empty() {
+                        let identified = self.identify_curseforge_files(api, manual).await;
+                        self.mod_metadata_manager.set_content_sources(identified.into_iter()
+                            .map(|(hash, project)| (hash, ContentSource::CurseForgeProject { project })));
+                    }
+                }
 
                 let mut futures = Vec::new();
 
@@ -384,10 +603,18 @@ impl BackendState {
                 { // Scope is needed so await doesn't complain about the non-send RwLockReadGuard
                     let sources = self.mod_metadata_manager.read_content_sources();
                     for summary in content.iter() {
+                        // Pinned content keeps its current version, so there's nothing to check
+                        if summary.pinned {
+                            tracker.add_count(1);
+                            tracker.notify();
+                            continue;
+                        }
+
                         let source = sources.get(&summary.content_summary.hash).unwrap_or(ContentSource::Manual);
                         let semaphore = &semaphore;
                         let meta = &meta;
                         let tracker = &tracker;
+                        let curseforge_api = curseforge_api.as_ref();
                         futures.push(async move {
                             match source {
                                 ContentSource::Manual => {
@@ -395,6 +622,29 @@ impl BackendState {
                                     tracker.notify();
                                     Ok(ModUpdateAction::ManualInstall)
                                 },
+                                ContentSource::CurseForgeProject { project } => {
+                                    let Some(api) = curseforge_api else {
+                                        tracker.add_count(1);
+                                        tracker.notify();
+                                        return Ok(ModUpdateAction::ErrorNotFound);
+                                    };
+
+                                    // Resource packs aren't tied to a loader
+                                    let content_loader = match summary.content_summary.extra {
+                                        ContentType::ResourcePack => Loader::Vanilla,
+                                        _ => loader,
+                                    };
+
+                                    let permit = semaphore.acquire().await.unwrap();
+                                    let result = self.check_curseforge_update(api, project, summary.content_summary.hash,
+                                        content_loader, version.as_str().into(), release_channel).await;
+                                    drop(permit);
+
+                                    tracker.add_count(1);
+                                    tracker.notify();
+
+                                    result
+                                },
                                 ContentSource::ModrinthUnknown | ContentSource::ModrinthProject { .. } => {
                                     let permit = semaphore.acquire().await.unwrap();
                                     let result = match summary.content_summary.extra {
@@ -454,6 +704,11 @@ impl BackendState {
                                         }
                                     }
 
+                                    // Cached results may have been fetched with a less strict release channel
+                                    if !release_channel.allows(result.0.version_type) {
+                                        return Ok(ModUpdateAction::NoVersionInChannel);
+                                    }
+
                                     let install_file = result
                                         .0
                                         .files
@@ -472,6 +727,7 @@ impl BackendState {
                                         Ok(ModUpdateAction::Modrinth {
                                             file: install_file.clone(),
                                             project_id: result.0.project_id.clone(),
+                                            version_id: result.0.id.clone(),
                                         })
                                     }
                                 },
@@ -509,61 +765,19 @@ impl BackendState {
                 let content_install = if let Some(instance) = self.instance_state.write().instances.get_mut(id) {
                     let configuration = instance.configuration.get();
                     let (loader, minecraft_version) = (configuration.loader, configuration.minecraft_version);
-                    let Some((mod_summary, _)) = instance.try_get_content(mod_id) else {
-                        self.send.send_error("Can't update mod in instance, unknown mod id");
-                        modal_action.set_finished();
-                        return;
-                    };
 
-                    let Some(update_info) = self.mod_metadata_manager.updates.read().get(&mod_summary.content_summary.hash).cloned() else {
-                        self.send.send_error("Can't update mod in instance, missing update action");
-                        modal_action.set_finished();
-                        return;
-                    };
-
-                    match update_info {
-                        ModUpdateAction::ErrorNotFound => {
-                            self.send.send_error("Can't update mod in instance, 404 not found");
-                            modal_action.set_finished();
-                            return;
+                    match self.create_content_update_file(instance, mod_id) {
+                        Ok(update) => ContentInstall {
+                            target: InstallTarget::Instance(id),
+                            loader_hint: loader,
+                            version_hint: Some(minecraft_version.into()),
+                            files: [update.file].into(),
                         },
-                        ModUpdateAction::ErrorInvalidHash => {
-                            self.send.send_error("Can't update mod in instance, returned invalid hash");
-                            modal_action.set_finished();
-                            return;
-                        },
-                        ModUpdateAction::AlreadyUpToDate => {
-                            self.send.send_error("Can't update mod in instance, already up-to-date");
-                            modal_action.set_finished();
-                            return;
-                        },
-                        ModUpdateAction::ManualInstall => {
-                            self.send.send_error("Can't update mod in instance, mod was manually installed");
+                        Err(error) => {
+                            self.send.send_error(error);
                             modal_action.set_finished();
                             return;
                         },
-                        ModUpdateAction::Modrinth { file, project_id } => {
-                            let mut path = mod_summary.path.with_file_name(&*file.filename);
-                            if !mod_summary.enabled {
-                                path.add_extension("disabled");
-                            }
-                            debug_assert!(path.is_absolute());
-                            ContentInstall {
-                                target: InstallTarget::Instance(id),
-                                loader_hint: loader,
-                                version_hint: Some(minecraft_version.into()),
-                                files: [ContentInstallFile {
-                                    replace_old: Some(mod_summary.path.clone()),
-                                    path: bridge::install::ContentInstallPath::Raw(path.into()),
-                                    download: ContentDownload::Url {
-                                        url: file.url.clone(),
-                                        sha1: file.hashes.sha1.clone(),
-                                        size: file.size,
-                                    },
-                                    content_source: ContentSource::ModrinthProject { project: project_id },
-                                }].into(),
-                            }
-                        },
                     }
                 } else {
                     self.send.send_error("Can't update mod in instance, unknown instance id");
@@ -575,6 +789,114 @@ impl BackendState {
                 modal_action.set_finished();
                 self.send.send(MessageToFrontend::Refresh);
             },
+            MessageToBackend::GetContentUpdates { id, release_channel, channel } => {
+                let backend = self.clone();
+                tokio::task::spawn(async move {
+                    let result = backend.get_content_updates(id, release_channel).await;
+                    let _ = channel.send(result);
+                });
+            },
+            MessageToBackend::UpdateContents { instance: id, content_ids, release_channel, modal_action } => {
+                self.update_contents(id, content_ids, release_channel, &modal_action).await;
+                modal_action.set_finished();
+                self.send.send(MessageToFrontend::Refresh);
+            },
+            MessageToBackend::DownloadImage { url, max_width, channel } => {
+                let client = self.redirecting_http_client.clone();
+                let endpoints = Arc::clone(&self.meta.endpoints);
+                tokio::task::spawn(async move {
+                    let result = download_image_as_png(&client, &endpoints, &url, max_width).await;
+                    let _ = channel.send(result.map_err(|error| {
+                        log::warn!("Unable to download image {url}: {error}");
+                        Arc::from(format!("Unable to download image: {error}").as_str())
+                    }));
+                });
+            },
+            MessageToBackend::ImportCurseForgeModpack { path, target, modal_action, manual_downloads } => {
+                match self.import_curseforge_modpack(path, target, &modal_action).await {
+                    Ok(downloads) => {
+                        let _ = manual_downloads.send(downloads);
+                    },
+                    Err(error) => {
+                        modal_action.set_error_message(Arc::from(format!("{}", error).as_str()));
+                    },
+                }
+                modal_action.set_finished();
+                self.send.send(MessageToFrontend::Refresh);
+            },
+            MessageToBackend::SetModrinthToken { token } => {
+                let removed = token.is_none();
+                match self.set_modrinth_token(token).await {
+                    Ok(()) if removed => self.send.send_info("Removed Modrinth token"),
+                    Ok(()) => self.send.send_success("Saved Modrinth token"),
+                    Err(error) => self.send.send_error(format!("Unable to update Modrinth token: {error}")),
+                }
+            },
+            MessageToBackend::SetProxy { proxy, password } => {
+                let removed = proxy.is_none();
+                match self.set_proxy(proxy, password).await {
+                    Ok(()) if removed => self.send.send_info("Removed proxy"),
+                    Ok(()) => self.send.send_success("Saved proxy"),
+                    Err(error) => self.send.send_error(format!("Unable to update proxy: {error}")),
+                }
+            },
+            MessageToBackend::SetCurseForgeApiKey { api_key } => {
+                let removed = api_key.is_none();
+                match self.set_curseforge_api_key(api_key).await {
+                    Ok(()) if removed => self.send.send_info("Removed CurseForge api key"),
+                    Ok(()) => self.send.send_success("Saved CurseForge api key"),
+                    Err(error) => self.send.send_error(format!("Unable to update CurseForge api key: {error}")),
+                }
+            },
+            MessageToBackend::ManageSkins { action, channel } => {
+                let backend = self.clone();
+                tokio::task::spawn(async move {
+                    let result = backend.manage_skins(action).await;
+                    let _ = channel.send(result.map_err(|error| Arc::from(format!("{error}").as_str())));
+                });
+            },
+            MessageToBackend::GetModrinthProjectList { source, channel } => {
+                let backend = self.clone();
+                tokio::task::spawn(async move {
+                    let result = backend.get_modrinth_project_list(source).await;
+                    let _ = channel.send(result.map_err(|error| Arc::from(format!("{error}").as_str())));
+                });
+            },
+            MessageToBackend::InstallModrinthProjects { instance, project_ids, modal_action, skipped } => {
+                let result = self.install_modrinth_projects(instance, &project_ids, &modal_action).await;
+                let _ = skipped.send(result);
+                modal_action.set_finished();
+                self.send.send(MessageToFrontend::Refresh);
+            },
+            MessageToBackend::GetServerProperties { id, channel } => {
+                let result = self.load_server_properties(id).map(|(_, properties)| {
+                    properties.properties().map(|(key, value)| (key.clone(), value.clone())).collect()
+                });
+                let _ = channel.send(result.map_err(|error| Arc::from(format!("{error}").as_str())));
+            },
+            MessageToBackend::SetServerProperties { id, properties } => {
+                let result = self.load_server_properties(id).and_then(|(path, mut server_properties)| {
+                    for (key, value) in properties.iter() {
+                        server_properties.set(key, value);
+                    }
+                    crate::write_safe(&path, server_properties.write().as_bytes())
+                });
+                match result {
+                    Ok(()) => self.send.send_success("Saved server.properties"),
+                    Err(error) => self.send.send_error(format!("Unable to save server.properties: {error}")),
+                }
+            },
+            MessageToBackend::SyncServerMods { client, target, modal_action, result } => {
+                match self.sync_server_mods(client, target, &modal_action).await {
+                    Ok(sync_result) => {
+                        let _ = result.send(sync_result);
+                    },
+                    Err(error) => {
+                        modal_action.set_error_message(Arc::from(format!("{error}").as_str()));
+                    },
+                }
+                modal_action.set_finished();
+            },
             MessageToBackend::Sleep5s => {
                 tokio::time::sleep(Duration::from_secs(5)).await;
             },
@@ -787,6 +1109,24 @@ impl BackendState {
                 let configuration = self.config.write().get().clone();
                 _ = channel.send(configuration);
             },
+            MessageToBackend::GetSecretStorageBackend { channel } => {
+                let backend = self.clone();
+                tokio::task::spawn(async move {
+                    let result = backend.secret_storage_backend().await;
+                    _ = channel.send(result.map_err(|error| Arc::from(format!("{error}").as_str())));
+                });
+            },
+            MessageToBackend::SetSecretPassphrase { passphrase, channel } => {
+                let backend = self.clone();
+                tokio::task::spawn(async move {
+                    let result = backend.set_secret_passphrase(passphrase).await;
+                    _ = channel.send(result.map_err(|error| Arc::from(format!("{error}").as_str())));
+                });
+            },
+            MessageToBackend::SetSecretStorageBackend { backend, modal_action } => {
+                self.set_secret_storage_backend(backend, &modal_action).await;
+                modal_action.set_finished();
+            },
             MessageToBackend::CleanupOldLogFiles { instance: id } => {
                 let mut deleted = 0;
 
@@ -953,15 +1293,23 @@ impl BackendState {
                 tracker.notify();
             },
             MessageToBackend::AddNewAccount { modal_action } => {
-                self.login_flow(&modal_action, None).await;
+                self.login_flow(&modal_action, None, None).await;
+            },
+            MessageToBackend::AddYggdrasilAccount { server_url, username, password, modal_action } => {
+                self.add_yggdrasil_account(server_url, username, password, &modal_action).await;
+                modal_action.set_finished();
             },
             MessageToBackend::AddOfflineAccount { name, uuid } => {
+                let uuid = uuid.unwrap_or_else(|| crate::account::offline_uuid(&name));
                 let mut account_info = self.account_info.write();
                 account_info.modify(|account_info| {
                     account_info.accounts.insert(uuid, BackendAccount {
                         username: name,
                         offline: true,
-                        head: None
+                        head: None,
+                        yggdrasil_api_root: None,
+                        needs_relogin: false,
+                        offline_skin: None,
                     });
                     account_info.selected_account = Some(uuid);
                 });
@@ -979,20 +1327,37 @@ impl BackendState {
                 });
             },
             MessageToBackend::DeleteAccount { uuid } => {
-                let mut account_info = self.account_info.write();
-
-                account_info.modify(|account_info| {
-                    account_info.accounts.remove(&uuid);
+                let mut removed = None;
+                self.account_info.write().modify(|account_info| {
+                    removed = account_info.accounts.remove(&uuid);
                     if account_info.selected_account == Some(uuid) {
                         account_info.selected_account = None;
                     }
                 });
+
+                if let Some(api_root) = removed.and_then(|account| account.yggdrasil_api_root) {
+                    let backend = self.clone();
+                    tokio::task::spawn(async move {
+                        backend.remove_yggdrasil_credentials(uuid, api_root).await;
+                    });
+                }
             },
             MessageToBackend::SetOpenGameOutputAfterLaunching { value } => {
                 self.config.write().modify(|config| {
                     config.open_game_output_when_launching = value;
                 });
             },
+            MessageToBackend::SetDownloadSpeedLimit { kib_per_second } => {
+                self.config.write().modify(|config| {
+                    config.download_speed_limit_kib = kib_per_second;
+                });
+                self.downloads.reload_config();
+            },
+            MessageToBackend::SetDeviceCodeLogin { value } => {
+                self.config.write().modify(|config| {
+                    config.device_code_login = value;
+                });
+            },
             MessageToBackend::CreateInstanceShortcut { id, path } => {
                 if let Some(instance) = self.instance_state.write().instances.get_mut(id) {
                     let Ok(current_exe) = std::env::current_exe() else {
@@ -1006,12 +1371,41 @@ impl BackendState {
                     crate::shortcut::create_shortcut(path, &format!("Launch {}", instance.name), &current_exe, args);
                 }
             },
+            MessageToBackend::GetUnusedFilesSummary { channel } => {
+                let backend = self.clone();
+                tokio::task::spawn(async move {
+                    let result = backend.find_unused_files().await
+                        .map(|unused| crate::cleanup::create_summary(&unused))
+                        .map_err(|error| format!("{}", error).into());
+                    let _ = channel.send(result);
+                });
+            },
+            MessageToBackend::CleanupUnusedFiles { paths, modal_action } => {
+                self.cleanup_unused_files(&paths, &modal_action).await;
+                modal_action.set_finished();
+                self.send.send(MessageToFrontend::Refresh);
+            },
+            MessageToBackend::VerifyInstance { id, modal_action } => {
+                self.verify_instance(id, &modal_action).await;
+                modal_action.set_finished();
+            },
+            MessageToBackend::PrepareInstance { id, modal_action } => {
+                self.prepare_instance(id, &modal_action).await;
+                modal_action.set_finished();
+            },
         }
     }
 
-    pub async fn login_flow(&self, modal_action: &ModalAction, selected_account: Option<uuid::Uuid>) -> Option<(MinecraftProfileResponse, MinecraftAccessToken)> {
+    /// Logs in interactively, when `bound_account` is given the login has to end up as that account, otherwise the account is selected
+    pub async fn login_flow(&self, modal_action: &ModalAction, selected_account: Option<uuid::Uuid>, bound_account: Option<uuid::Uuid>) -> Option<(MinecraftProfileResponse, MinecraftAccessToken)> {
+        let account_lock = selected_account.map(|uuid| self.account_lock(uuid));
+        let _account_guard = match &account_lock {
+            Some(account_lock) => Some(account_lock.lock().await),
+            None => None,
+        };
+
         let mut credentials = if let Some(selected_account) = selected_account {
-            let secret_storage = match self.secret_storage.get_or_init(PlatformSecretStorage::new).await {
+            let secret_storage = match self.get_secret_storage().await {
                 Ok(secret_storage) => secret_storage,
                 Err(error) => {
                     modal_action.set_error_message(format!("Error initializing secret storage: {error}").into());
@@ -1037,14 +1431,14 @@ impl BackendState {
         let login_tracker = ProgressTracker::new(Arc::from("Logging in"), self.send.clone());
         modal_action.trackers.push(login_tracker.clone());
 
-        let login_result = self.login(&mut credentials, &login_tracker, &modal_action).await;
+        let login_result = self.login(&mut credentials, &login_tracker, &modal_action, true).await;
 
         if matches!(login_result, Err(LoginError::CancelledByUser)) {
             self.send.send(MessageToFrontend::CloseModal);
             return None;
         }
 
-        let secret_storage = match self.secret_storage.get_or_init(PlatformSecretStorage::new).await {
+        let secret_storage = match self.get_secret_storage().await {
             Ok(secret_storage) => secret_storage,
             Err(error) => {
                 modal_action.set_error_message(format!("Error initializing secret storage: {error}").into());
@@ -1072,13 +1466,21 @@ impl BackendState {
             },
         };
 
+        // Checked before touching any stored credentials, the bound account's credentials must survive logging in as someone else
+        if let Some(bound_account) = bound_account && profile.id != bound_account {
+            modal_action.set_error_message(format!("Logged in as {}, which isn't the account bound to this instance", profile.name).into());
+            modal_action.set_finished();
+            return None;
+        }
+
         if let Some(selected_account) = selected_account
             && profile.id != selected_account
         {
             let _ = secret_storage.delete_credentials(selected_account).await;
         }
 
-        self.update_account_info_with_profile(&profile);
+        self.update_account_info_with_profile(&profile, bound_account.is_none());
+        self.set_needs_relogin(profile.id, false);
 
         if let Err(error) = secret_storage.write_credentials(profile.id, &credentials).await {
             log::warn!("Unable to write credentials to keychain: {error}");
@@ -1088,11 +1490,11 @@ impl BackendState {
         Some((profile, access_token))
     }
 
-    pub fn update_account_info_with_profile(&self, profile: &MinecraftProfileResponse) {
+    pub fn update_account_info_with_profile(&self, profile: &MinecraftProfileResponse, select: bool) {
         let mut account_info = self.account_info.write();
 
         let info = account_info.get();
-        if info.accounts.contains_key(&profile.id) && info.selected_account == Some(profile.id) {
+        if info.accounts.contains_key(&profile.id) && (!select || info.selected_account == Some(profile.id)) {
             drop(account_info);
             self.update_profile_head(&profile);
             return;
@@ -1104,7 +1506,9 @@ impl BackendState {
                 info.accounts.insert(profile.id, account);
             }
 
-            info.selected_account = Some(profile.id);
+            if select {
+                info.selected_account = Some(profile.id);
+            }
         });
 
         drop(account_info);
@@ -1258,3 +1662,20 @@ fn check_argument_expansions(argument: &str) {
         }
     }
 }
+
+async fn download_image_as_png(client: &reqwest::Client, endpoints: &Endpoints, url: &str, max_width: u32) -> anyhow::Result<Arc<[u8]>> {
+    let response = endpoints.send(client.get(url)).await?.error_for_status()?;
+    let bytes = response.bytes().await?;
+
+    tokio::task::spawn_blocking(move || {
+        let mut image = image::load_from_memory(&bytes)?;
+        if image.width() > max_width {
+            let height = (image.height() as u64 * max_width as u64 / image.width() as u64).max(1) as u32;
+            image = image.resize_exact(max_width, height, image::imageops::FilterType::Triangle);
+        }
+
+        let mut png = Vec::new();
+        image.write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)?;
+        Ok::<Arc<[u8]>, anyhow::Error>(png.into())
+    }).await?
+}
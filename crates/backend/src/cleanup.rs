@@ -0,0 +1,395 @@
+use std::{path::{Path, PathBuf}, sync::Arc};
+
+use bridge::{
+    instance::InstanceStatus, message::{UnusedFilesCategory, UnusedFilesSummary}, modal_action::{ModalAction, ProgressTracker, ProgressTrackerFinishType}, safe_path::SafePath
+};
+use rustc_hash::FxHashSet;
+use schema::instance::InstanceConfiguration;
+use ustr::Ustr;
+
+use crate::{directories::LauncherDirectories, launch::LaunchError, BackendState};
+
+/// Files in the shared launcher directories that are still needed by at least one instance
+#[derive(Default, Debug)]
+pub struct ReferencedFiles {
+    pub libraries: FxHashSet<PathBuf>,
+    pub asset_indexes: FxHashSet<Ustr>,
+    pub asset_objects: FxHashSet<Ustr>,
+    pub runtime_components: FxHashSet<Ustr>,
+    pub natives_dirs: FxHashSet<String>,
+}
+
+impl ReferencedFiles {
+    pub fn add_library(&mut self, libraries_dir: &Path, path: &str) {
+        if let Some(path) = SafePath::new(path) {
+            self.libraries.insert(path.to_path(libraries_dir));
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct UnusedFile {
+    pub path: PathBuf,
+    pub category: UnusedFilesCategory,
+    pub files: usize,
+    pub bytes: u64,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum CleanupError {
+    #[error("Can't clean up while an instance is launching or running")]
+    InstanceActive,
+    #[error("Unable to determine which files are used by instance '{0}':\n{1}")]
+    ReferencedFiles(Ustr, LaunchError),
+}
+
+pub fn create_summary(unused: &[UnusedFile]) -> UnusedFilesSummary {
+    let mut summary = UnusedFilesSummary::default();
+    for file in unused {
+        summary.files[file.category] += file.files;
+        summary.bytes[file.category] += file.bytes;
+    }
+    summary.paths = unused.iter().map(|file| Arc::from(file.path.as_path())).collect();
+    summary
+}
+
+impl BackendState {
+    fn is_any_instance_active(&self) -> bool {
+        self.instance_state.read().instances.iter().any(|instance| instance.status() != InstanceStatus::NotRunning)
+    }
+
+    pub async fn find_unused_files(&self) -> Result<Vec<UnusedFile>, CleanupError> {
+        if self.is_any_instance_active() {
+            return Err(CleanupError::InstanceActive);
+        }
+
+        let configurations: Vec<(Ustr, InstanceConfiguration)> = self.instance_state.write().instances.iter_mut()
+            .map(|instance| (instance.name, instance.configuration.get().clone()))
+            .collect();
+
+        let mut referenced = ReferencedFiles::default();
+        for (name, configuration) in configurations {
+            self.launcher.collect_referenced_files(&self.redirecting_http_client, &configuration, &mut referenced).await
+                .map_err(|error| CleanupError::ReferencedFiles(name, error))?;
+        }
+
+        let directories = self.directories.clone();
+        Ok(tokio::task::spawn_blocking(move || find_unreferenced(&directories, &referenced)).await.unwrap())
+    }
+
+    /// Deletes the confirmed paths that are still unused
+    pub async fn cleanup_unused_files(&self, confirmed: &[Arc<Path>], modal_action: &ModalAction) {
+        let find_tracker = ProgressTracker::new("Finding unused files".into(), self.send.clone());
+        modal_action.trackers.push(find_tracker.clone());
+        find_tracker.set_total(1);
+        find_tracker.notify();
+
+        let unused = self.find_unused_files().await;
+
+        find_tracker.set_count(1);
+        find_tracker.set_finished(ProgressTrackerFinishType::from_err(unused.is_err()));
+        find_tracker.notify();
+
+        let confirmed: FxHashSet<&Path> = confirmed.iter().map(|path| &**path).collect();
+        let unused = match unused {
+            Ok(unused) => unused.into_iter().filter(|file| confirmed.contains(file.path.as_path())).collect::<Vec<_>>(),
+            Err(error) => {
+                modal_action.set_error_message(format!("{}", error).into());
+                return;
+            },
+        };
+
+        // An instance might have been started while the referenced files were being collected
+        if self.is_any_instance_active() {
+            modal_action.set_error_message(format!("{}", CleanupError::InstanceActive).into());
+            return;
+        }
+
+        let delete_tracker = ProgressTracker::new("Deleting unused files".into(), self.send.clone());
+        modal_action.trackers.push(delete_tracker.clone());
+        delete_tracker.set_total(unused.len());
+        delete_tracker.notify();
+
+        let directories = self.directories.clone();
+        let (deleted_files, deleted_bytes) = {
+            let delete_tracker = delete_tracker.clone();
+            tokio::task::spawn_blocking(move || {
+                delete_unused(&directories, &unused, || {
+                    delete_tracker.add_count(1);
+                    delete_tracker.notify();
+                })
+            }).await.unwrap()
+        };
+
+        delete_tracker.set_finished(ProgressTrackerFinishType::Normal);
+        delete_tracker.notify();
+
+        log::info!("Deleted {} unused files ({} bytes)", deleted_files, deleted_bytes);
+        self.send.send_success(format!("Deleted {} unused files, freed {:.1}MB", deleted_files, deleted_bytes as f64 / 1000.0 / 1000.0));
+    }
+}
+
+fn delete_unused(directories: &LauncherDirectories, unused: &[UnusedFile], mut on_deleted: impl FnMut()) -> (usize, u64) {
+    let mut deleted_files = 0;
+    let mut deleted_bytes = 0;
+
+    for file in unused {
+        let result = if file.path.is_dir() {
+            std::fs::remove_dir_all(&file.path)
+        } else {
+            std::fs::remove_file(&file.path)
+        };
+
+        match result {
+            Ok(_) => {
+                deleted_files += file.files;
+                deleted_bytes += file.bytes;
+            },
+            Err(error) => {
+                log::warn!("Unable to delete unused file {:?}: {}", file.path, error);
+            },
+        }
+
+        (on_deleted)();
+    }
+
+    remove_empty_dirs(&directories.libraries_dir);
+    remove_empty_dirs(&directories.assets_objects_dir);
+
+    (deleted_files, deleted_bytes)
+}
+
+fn find_unreferenced(directories: &LauncherDirectories, referenced: &ReferencedFiles) -> Vec<UnusedFile> {
+    let mut unused = Vec::new();
+
+    walk_files(&directories.libraries_dir, &mut |path, bytes| {
+        if !referenced.libraries.contains(path) {
+            unused.push(UnusedFile {
+                path: path.to_path_buf(),
+                category: UnusedFilesCategory::Libraries,
+                files: 1,
+                bytes,
+            });
+        }
+    });
+
+    for (path, name, is_dir) in read_dir_entries(&directories.assets_index_dir) {
+        if is_dir {
+            continue;
+        }
+        let Some(id) = name.strip_suffix(".json") else {
+            continue;
+        };
+        if !is_referenced(&referenced.asset_indexes, id) {
+            let bytes = path.metadata().map(|m| m.len()).unwrap_or(0);
+            unused.push(UnusedFile {
+                path,
+                category: UnusedFilesCategory::AssetIndexes,
+                files: 1,
+                bytes,
+            });
+        }
+    }
+
+    for (hash_folder, _, is_dir) in read_dir_entries(&directories.assets_objects_dir) {
+        if !is_dir {
+            continue;
+        }
+        for (path, hash, is_dir) in read_dir_entries(&hash_folder) {
+            if is_dir || is_referenced(&referenced.asset_objects, &hash) {
+                continue;
+            }
+            let bytes = path.metadata().map(|m| m.len()).unwrap_or(0);
+            unused.push(UnusedFile {
+                path,
+                category: UnusedFilesCategory::AssetObjects,
+                files: 1,
+                bytes,
+            });
+        }
+    }
+
+    for (path, component, is_dir) in read_dir_entries(&directories.runtime_base_dir) {
+        if is_dir && !is_referenced(&referenced.runtime_components, &component) {
+            let (files, bytes) = dir_size(&path);
+            unused.push(UnusedFile {
+                path,
+                category: UnusedFilesCategory::JavaRuntimes,
+                files,
+                bytes,
+            });
+        }
+    }
+
+    for (path, name, is_dir) in read_dir_entries(&directories.temp_natives_base_dir) {
+        if is_dir && !referenced.natives_dirs.contains(&name) {
+            let (files, bytes) = dir_size(&path);
+            unused.push(UnusedFile {
+                path,
+                category: UnusedFilesCategory::Natives,
+                files,
+                bytes,
+            });
+        }
+    }
+
+    unused
+}
+
+fn is_referenced(set: &FxHashSet<Ustr>, value: &str) -> bool {
+    // Avoid interning every filename on disk
+    Ustr::from_existing(value).map(|value| set.contains(&value)).unwrap_or(false)
+}
+
+fn read_dir_entries(dir: &Path) -> Vec<(PathBuf, String, bool)> {
+    let Ok(read_dir) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    read_dir.filter_map(|entry| {
+        let entry = entry.ok()?;
+        let name = entry.file_name().into_string().ok()?;
+        let is_dir = entry.file_type().ok()?.is_dir();
+        Some((entry.path(), name, is_dir))
+    }).collect()
+}
+
+fn walk_files(dir: &Path, handler: &mut impl FnMut(&Path, u64)) {
+    let Ok(read_dir) = std::fs::read_dir(dir) else {
+        return;
+    };
+
+    for entry in read_dir {
+        let Ok(entry) = entry else {
+            continue;
+        };
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+
+        let path = entry.path();
+        if file_type.is_dir() {
+            walk_files(&path, handler);
+        } else {
+            let bytes = entry.metadata().map(|m| m.len()).unwrap_or(0);
+            (handler)(&path, bytes);
+        }
+    }
+}
+
+fn dir_size(dir: &Path) -> (usize, u64) {
+    let mut files = 0;
+    let mut total = 0;
+    walk_files(dir, &mut |_, bytes| {
+        files += 1;
+        total += bytes;
+    });
+    (files, total)
+}
+
+fn remove_empty_dirs(dir: &Path) -> bool {
+    let Ok(read_dir) = std::fs::read_dir(dir) else {
+        return false;
+    };
+
+    let mut empty = true;
+    for entry in read_dir {
+        let Ok(entry) = entry else {
+            empty = false;
+            continue;
+        };
+        let is_dir = entry.file_type().map(|t| t.is_dir()).unwrap_or(false);
+        if !is_dir || !remove_empty_dirs(&entry.path()) {
+            empty = false;
+        }
+    }
+
+    empty && std::fs::remove_dir(dir).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use auth::test_util::TempDir;
+
+    use super::*;
+
+    fn test_directories(name: &str) -> (LauncherDirectories, TempDir) {
+        let dir = TempDir::new(&format!("cleanup-{name}"));
+        (LauncherDirectories::new(dir.to_path_buf()), dir)
+    }
+
+    fn write_file(path: &Path) {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, b"data").unwrap();
+    }
+
+    fn unused_paths(unused: &[UnusedFile]) -> FxHashSet<PathBuf> {
+        unused.iter().map(|file| file.path.clone()).collect()
+    }
+
+    #[test]
+    fn test_find_unreferenced() {
+        let (directories, _dir) = test_directories("find");
+
+        let used_library = directories.libraries_dir.join("com/example/used/1.0/used-1.0.jar");
+        let unused_library = directories.libraries_dir.join("com/example/unused/1.0/unused-1.0.jar");
+        // Older versions kept authlib-injector in the libraries, it's stored in its own folder now
+        let old_authlib_injector = directories.libraries_dir.join("moe/yushi/authlib-injector/1.2.5/authlib-injector-1.2.5.jar");
+        let used_index = directories.assets_index_dir.join("pandora-cleanup-used-index.json");
+        let unused_index = directories.assets_index_dir.join("pandora-cleanup-unused-index.json");
+        let unused_object = directories.assets_objects_dir.join("ab/ab0000000000000000000000000000pandora");
+        let unused_runtime = directories.runtime_base_dir.join("pandora-cleanup-unused-runtime");
+        let used_natives = directories.temp_natives_base_dir.join("used-natives");
+
+        for path in [&used_library, &unused_library, &old_authlib_injector, &used_index, &unused_index, &unused_object] {
+            write_file(path);
+        }
+        write_file(&unused_runtime.join("bin/java"));
+        write_file(&used_natives.join("lwjgl.so"));
+
+        let mut referenced = ReferencedFiles::default();
+        referenced.add_library(&directories.libraries_dir, "com/example/used/1.0/used-1.0.jar");
+        referenced.add_library(&directories.libraries_dir, "../outside.jar");
+        referenced.asset_indexes.insert(Ustr::from("pandora-cleanup-used-index"));
+        referenced.natives_dirs.insert("used-natives".into());
+
+        assert_eq!(referenced.libraries.len(), 1);
+
+        let unused = find_unreferenced(&directories, &referenced);
+        let expected: FxHashSet<PathBuf> = [unused_library, old_authlib_injector, unused_index, unused_object, unused_runtime.clone()].into_iter().collect();
+        assert_eq!(unused_paths(&unused), expected);
+
+        let runtime = unused.iter().find(|file| file.path == unused_runtime).unwrap();
+        assert_eq!(runtime.category, UnusedFilesCategory::JavaRuntimes);
+        assert_eq!((runtime.files, runtime.bytes), (1, 4));
+    }
+
+    #[test]
+    fn test_delete_unused() {
+        let (directories, _dir) = test_directories("delete");
+
+        let used_library = directories.libraries_dir.join("com/example/used/1.0/used-1.0.jar");
+        let unused_library = directories.libraries_dir.join("com/example/unused/1.0/unused-1.0.jar");
+        let unused_runtime = directories.runtime_base_dir.join("pandora-cleanup-unused-runtime");
+        write_file(&used_library);
+        write_file(&unused_library);
+        write_file(&unused_runtime.join("bin/java"));
+        write_file(&unused_runtime.join("lib/modules"));
+
+        let mut referenced = ReferencedFiles::default();
+        referenced.add_library(&directories.libraries_dir, "com/example/used/1.0/used-1.0.jar");
+
+        let unused = find_unreferenced(&directories, &referenced);
+        let mut deleted = 0;
+        let (files, bytes) = delete_unused(&directories, &unused, || deleted += 1);
+
+        assert_eq!(deleted, 2);
+        assert_eq!((files, bytes), (3, 12));
+        assert!(used_library.exists());
+        assert!(!unused_library.exists());
+        assert!(!unused_runtime.exists());
+        // Empty folders left behind by deleted libraries are removed as well
+        assert!(!directories.libraries_dir.join("com/example/unused").exists());
+        assert!(find_unreferenced(&directories, &referenced).is_empty());
+    }
+}
@@ -0,0 +1,407 @@
+use std::sync::Arc;
+
+use bridge::keep_alive::KeepAliveHandle;
+use schema::{
+    content::{ContentSearchHit, ContentSearchRequest, ContentSearchResult, ContentSearchType},
+    curseforge::{CurseForgeClass, CurseForgeModLoaderType, CurseForgeSearchRequest, CurseForgeSortField},
+    instance::ReleaseChannel,
+    loader::Loader,
+    modrinth::{ModrinthFacets, ModrinthLoader, ModrinthProjectVersionsRequest, ModrinthSearchIndex, ModrinthSearchRequest},
+};
+
+use crate::{
+    curseforge::CurseForgeApi,
+    install_content::ContentInstallError,
+    metadata::{items::{CurseForgeSearchMetadataItem, ModrinthProjectVersionsMetadataItem, ModrinthSearchMetadataItem, ModrinthVersionMetadataItem}, manager::{MetaLoadError, MetadataManager}},
+};
+
+/// A downloadable file resolved by a content provider
+#[derive(Debug, Clone)]
+pub struct ProviderFile {
+    pub filename: Arc<str>,
+    pub url: Arc<str>,
+    pub sha1: Arc<str>,
+    pub size: usize,
+    /// Folder to install into if the type can't be determined from the file itself
+    pub install_directory: Option<&'static str>,
+}
+
+pub trait ContentProvider {
+    type ProjectId;
+    type FileId;
+
+    fn search(&self, request: &ContentSearchRequest, force_reload: bool)
+        -> impl Future<Output = (Result<ContentSearchResult, MetaLoadError>, Option<KeepAliveHandle>)> + Send;
+
+    /// Finds the file to install, either the given file or the newest one matching the loader,
+    /// game version and release channel
+    fn find_file(
+        &self,
+        project_id: &Self::ProjectId,
+        file_id: Option<&Self::FileId>,
+        loader: Loader,
+        game_version: Option<Arc<str>>,
+        release_channel: ReleaseChannel,
+    ) -> impl Future<Output = Result<Option<ProviderFile>, ContentInstallError>> + Send;
+}
+
+pub struct ModrinthProvider<'a> {
+    pub meta: &'a MetadataManager,
+}
+
+impl ModrinthProvider<'_> {
+    fn search_request(request: &ContentSearchRequest) -> ModrinthSearchRequest {
+        let project_type = match request.content_type {
+            ContentSearchType::Mod => "mod",
+            ContentSearchType::Modpack => "modpack",
+            ContentSearchType::ResourcePack => "resourcepack",
+            ContentSearchType::Shader => "shader",
+        };
+
+        let mut facets = ModrinthFacets::default();
+        facets.require("project_type", project_type);
+        if let Some(loader) = request.loader
+            && matches!(request.content_type, ContentSearchType::Mod | ContentSearchType::Modpack)
+        {
+            let loader = loader.as_modrinth_loader();
+            if loader != ModrinthLoader::Unknown {
+                facets.require("categories", loader.id());
+            }
+        }
+        if let Some(game_version) = &request.game_version {
+            facets.require("versions", game_version);
+        }
+
+        ModrinthSearchRequest {
+            query: request.query.clone(),
+            facets: facets.build(),
+            index: ModrinthSearchIndex::Relevance,
+            offset: request.offset,
+            limit: request.limit,
+        }
+    }
+}
+
+impl ContentProvider for ModrinthProvider<'_> {
+    type ProjectId = Arc<str>;
+    type FileId = Arc<str>;
+
+    async fn search(&self, request: &ContentSearchRequest, force_reload: bool) -> (Result<ContentSearchResult, MetaLoadError>, Option<KeepAliveHandle>) {
+        let search = Self::search_request(request);
+        let (result, handle) = self.meta.fetch_with_keepalive(&ModrinthSearchMetadataItem(&search), force_reload).await;
+
+        let result = result.map(|result| ContentSearchResult {
+            hits: result.hits.iter().map(|hit| ContentSearchHit {
+                project_id: hit.project_id.clone(),
+                name: hit.title.clone().unwrap_or_else(|| hit.project_id.clone()),
+                summary: hit.description.clone(),
+                author: Some(hit.author.clone()),
+                downloads: hit.downloads as u64,
+                icon_url: hit.icon_url.clone(),
+            }).collect(),
+            total_hits: result.total_hits,
+        });
+        (result, handle)
+    }
+
+    async fn find_file(
+        &self,
+        project_id: &Arc<str>,
+        version_id: Option<&Arc<str>>,
+        loader: Loader,
+        game_version: Option<Arc<str>>,
+        release_channel: ReleaseChannel,
+    ) -> Result<Option<ProviderFile>, ContentInstallError> {
+        let version = if let Some(version_id) = version_id {
+            self.meta.fetch(&ModrinthVersionMetadataItem(version_id.clone())).await?
+        } else {
+            let versions = self.meta.fetch(&ModrinthProjectVersionsMetadataItem(&ModrinthProjectVersionsRequest {
+                project_id: project_id.clone(),
+                game_versions: game_version.map(|v| [v].into()),
+                loaders: None,
+            })).await?;
+
+            let mut allowed_versions = versions.0.iter()
+                .filter(|version| release_channel.allows(version.version_type));
+
+            let modrinth_loader = loader.as_modrinth_loader();
+            let version = if modrinth_loader != ModrinthLoader::Unknown {
+                allowed_versions.clone()
+                    .find(|version| if let Some(loaders) = &version.loaders {
+                        loaders.contains(&modrinth_loader)
+                    } else {
+                        false
+                    })
+                    .or(allowed_versions.next())
+            } else {
+                allowed_versions.next()
+            };
+
+            let Some(version) = version else {
+                return Ok(None);
+            };
+            Arc::new(version.clone())
+        };
+
+        if &version.project_id != project_id {
+            return Err(ContentInstallError::MismatchedProjectIdForVersion(
+                version.id.clone(),
+                project_id.clone(),
+                version.project_id.clone()
+            ));
+        }
+
+        let Some(install_file) = version.files.iter().find(|file| file.primary).or(version.files.first()) else {
+            return Ok(None);
+        };
+
+        let install_directory = version.loaders.iter()
+            .flat_map(|loaders| loaders.iter())
+            .find_map(|loader| loader.install_directory());
+
+        Ok(Some(ProviderFile {
+            filename: install_file.filename.clone(),
+            url: install_file.url.clone(),
+            sha1: install_file.hashes.sha1.clone(),
+            size: install_file.size,
+            install_directory,
+        }))
+    }
+}
+
+pub struct CurseForgeProvider<'a> {
+    pub meta: &'a MetadataManager,
+    pub api: CurseForgeApi,
+}
+
+impl CurseForgeProvider<'_> {
+    fn search_request(request: &ContentSearchRequest) -> CurseForgeSearchRequest {
+        let class_id = match request.content_type {
+            ContentSearchType::Mod => CurseForgeClass::Mod,
+            ContentSearchType::Modpack => CurseForgeClass::Modpack,
+            ContentSearchType::ResourcePack => CurseForgeClass::Resourcepack,
+            ContentSearchType::Shader => CurseForgeClass::Shader,
+        };
+
+        let mod_loader_type = if matches!(request.content_type, ContentSearchType::Mod | ContentSearchType::Modpack) {
+            request.loader.and_then(CurseForgeModLoaderType::from_loader)
+        } else {
+            None
+        };
+
+        CurseForgeSearchRequest {
+            class_id: Some(class_id),
+            search_filter: request.query.clone(),
+            game_version: request.game_version.clone(),
+            mod_loader_type,
+            sort_field: CurseForgeSortField::Popularity,
+            index: request.offset,
+            page_size: request.limit,
+        }
+    }
+}
+
+impl ContentProvider for CurseForgeProvider<'_> {
+    type ProjectId = u32;
+    type FileId = u32;
+
+    async fn search(&self, request: &ContentSearchRequest, force_reload: bool) -> (Result<ContentSearchResult, MetaLoadError>, Option<KeepAliveHandle>) {
+        let search = Self::search_request(request);
+        let (result, handle) = self.meta.fetch_with_keepalive(&CurseForgeSearchMetadataItem { api: &self.api, request: &search }, force_reload).await;
+
+        let result = result.map(|result| ContentSearchResult {
+            hits: result.data.iter().map(|curseforge_mod| ContentSearchHit {
+                project_id: curseforge_mod.id.to_string().into(),
+                name: curseforge_mod.name.clone(),
+                summary: curseforge_mod.summary.clone(),
+                author: curseforge_mod.authors.first().map(|author| author.name.clone()),
+                downloads: curseforge_mod.download_count as u64,
+                icon_url: curseforge_mod.logo.as_ref().and_then(|logo| logo.thumbnail_url.clone()),
+            }).collect(),
+            total_hits: result.pagination.total_count,
+        });
+        (result, handle)
+    }
+
+    async fn find_file(
+        &self,
+        project_id: &u32,
+        file_id: Option<&u32>,
+        loader: Loader,
+        game_version: Option<Arc<str>>,
+        release_channel: ReleaseChannel,
+    ) -> Result<Option<ProviderFile>, ContentInstallError> {
+        let Some(file) = self.api.find_file(self.meta, *project_id, file_id.copied(), loader, game_version, release_channel).await? else {
+            return Ok(None);
+        };
+
+        if file.mod_id != *project_id {
+            return Err(ContentInstallError::MismatchedProjectIdForVersion(
+                file.id.to_string().into(),
+                project_id.to_string().into(),
+                file.mod_id.to_string().into(),
+            ));
+        }
+
+        let Some(url) = &file.download_url else {
+            return Err(ContentInstallError::CurseForgeDistributionDisallowed(file.display_name.clone()));
+        };
+        let Some(sha1) = file.sha1() else {
+            return Err(ContentInstallError::MissingSha1(file.file_name.clone()));
+        };
+
+        Ok(Some(ProviderFile {
+            filename: file.file_name.clone(),
+            url: url.clone(),
+            sha1: sha1.clone(),
+            size: file.file_length,
+            install_directory: None,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use auth::test_util::{self, MockResponse, TempDir};
+    use parking_lot::RwLock;
+    use schema::{content::{ContentProviderKind, ContentSearchRequest, ContentSearchType}, curseforge::{CurseForgeClass, CurseForgeModLoaderType}, instance::ReleaseChannel, loader::Loader};
+
+    use super::{ContentProvider, CurseForgeProvider, ModrinthProvider};
+    use crate::{curseforge::CurseForgeApi, endpoints::Endpoints, install_content::ContentInstallError, metadata::manager::MetadataManager, persistent::Persistent};
+
+    const API_KEY: &str = "test-key";
+
+    /// Minimal CurseForge api serving one mod with a release and a beta file, rejecting requests without the api key
+    async fn start_mock_server() -> String {
+        test_util::start_mock_server(|request| {
+            let file = |id: u32, release_type: u8, sha1: &str| serde_json::json!({
+                "id": id,
+                "modId": 1234,
+                "displayName": format!("Example {id}"),
+                "fileName": format!("example-{id}.jar"),
+                "releaseType": release_type,
+                "hashes": [{ "value": sha1, "algo": 1 }],
+                "fileLength": 100,
+                "downloadUrl": format!("https://edge.forgecdn.net/files/{id}/example-{id}.jar"),
+                "gameVersions": ["1.20.1", "Fabric"],
+                "fileFingerprint": 1,
+            });
+
+            let path = request.path.as_str();
+            if request.header("x-api-key") != Some(API_KEY) {
+                MockResponse::json("403 Forbidden", serde_json::json!({}))
+            } else if path.starts_with("/v1/mods/search?") {
+                MockResponse::json("200 OK", serde_json::json!({
+                    "data": [{
+                        "id": 1234,
+                        "name": "Example",
+                        "slug": "example",
+                        "summary": "An example mod",
+                        "downloadCount": 5000.0,
+                        "classId": 6,
+                        "authors": [{ "name": "Author" }],
+                        "logo": { "thumbnailUrl": "https://media.forgecdn.net/thumb.png", "url": null },
+                    }],
+                    "pagination": { "index": 0, "pageSize": 20, "resultCount": 1, "totalCount": 1 },
+                }))
+            } else if path.starts_with("/v1/mods/1234/files?") || path == "/v1/mods/1234/files" {
+                MockResponse::json("200 OK", serde_json::json!({
+                    "data": [
+                        file(2, 2, "2222222222222222222222222222222222222222"),
+                        file(1, 1, "1111111111111111111111111111111111111111"),
+                    ],
+                    "pagination": { "index": 0, "pageSize": 50, "resultCount": 2, "totalCount": 2 },
+                }))
+            } else {
+                MockResponse::json("404 Not Found", serde_json::json!({}))
+            }
+        }).await
+    }
+
+    fn create_metadata_manager(name: &str) -> (MetadataManager, TempDir) {
+        let dir = TempDir::new(&format!("content-provider-{name}"));
+        let config = Persistent::load(dir.join("config.json").into());
+        let endpoints = Arc::new(Endpoints::new(Arc::new(RwLock::new(config))));
+        let (_, _, _, send) = bridge::handle::create_pair();
+        (MetadataManager::new(reqwest::Client::new(), dir.join("metadata").into(), send, endpoints), dir)
+    }
+
+    fn search_request(provider: ContentProviderKind, content_type: ContentSearchType) -> ContentSearchRequest {
+        ContentSearchRequest {
+            provider,
+            query: Some("example".into()),
+            content_type,
+            game_version: Some("1.20.1".into()),
+            loader: Some(Loader::Fabric),
+            offset: 20,
+            limit: 10,
+        }
+    }
+
+    #[test]
+    fn test_modrinth_search_request() {
+        let request = ModrinthProvider::search_request(&search_request(ContentProviderKind::Modrinth, ContentSearchType::Mod));
+        assert_eq!(request.query.as_deref(), Some("example"));
+        assert_eq!(request.facets.as_deref(), Some(r#"[["project_type:mod"],["categories:fabric"],["versions:1.20.1"]]"#));
+        assert_eq!((request.offset, request.limit), (20, 10));
+
+        // Resource packs aren't tagged with mod loaders
+        let request = ModrinthProvider::search_request(&search_request(ContentProviderKind::Modrinth, ContentSearchType::ResourcePack));
+        assert_eq!(request.facets.as_deref(), Some(r#"[["project_type:resourcepack"],["versions:1.20.1"]]"#));
+    }
+
+    #[test]
+    fn test_curseforge_search_request() {
+        let request = CurseForgeProvider::search_request(&search_request(ContentProviderKind::CurseForge, ContentSearchType::Mod));
+        assert_eq!(request.class_id, Some(CurseForgeClass::Mod));
+        assert_eq!(request.mod_loader_type, Some(CurseForgeModLoaderType::Fabric));
+        assert_eq!((request.index, request.page_size), (20, 10));
+
+        let request = CurseForgeProvider::search_request(&search_request(ContentProviderKind::CurseForge, ContentSearchType::Shader));
+        assert_eq!(request.class_id, Some(CurseForgeClass::Shader));
+        assert_eq!(request.mod_loader_type, None);
+    }
+
+    #[tokio::test]
+    async fn test_curseforge_search() {
+        let base_url = start_mock_server().await;
+        let (meta, _dir) = create_metadata_manager("search");
+
+        let provider = CurseForgeProvider { meta: &meta, api: CurseForgeApi::new(base_url.into(), Some(API_KEY.into())) };
+        let (result, _) = provider.search(&search_request(ContentProviderKind::CurseForge, ContentSearchType::Mod), false).await;
+        let result = result.unwrap();
+
+        assert_eq!(result.total_hits, 1);
+        let hit = &result.hits[0];
+        assert_eq!(&*hit.project_id, "1234");
+        assert_eq!(&*hit.name, "Example");
+        assert_eq!(hit.author.as_deref(), Some("Author"));
+        assert_eq!(hit.downloads, 5000);
+        assert_eq!(hit.icon_url.as_deref(), Some("https://media.forgecdn.net/thumb.png"));
+    }
+
+    #[tokio::test]
+    async fn test_curseforge_find_file() {
+        let base_url = start_mock_server().await;
+        let (meta, _dir) = create_metadata_manager("find-file");
+
+        let provider = CurseForgeProvider { meta: &meta, api: CurseForgeApi::new(base_url.clone().into(), Some(API_KEY.into())) };
+
+        let file = provider.find_file(&1234, None, Loader::Fabric, Some("1.20.1".into()), ReleaseChannel::Beta).await.unwrap().unwrap();
+        assert_eq!(&*file.filename, "example-2.jar");
+
+        let file = provider.find_file(&1234, None, Loader::Fabric, Some("1.20.1".into()), ReleaseChannel::Release).await.unwrap().unwrap();
+        assert_eq!(&*file.filename, "example-1.jar");
+        assert_eq!(&*file.sha1, "1111111111111111111111111111111111111111");
+        assert_eq!(file.size, 100);
+
+        let file = provider.find_file(&1234, None, Loader::Forge, Some("1.20.1".into()), ReleaseChannel::Alpha).await.unwrap();
+        assert!(file.is_none());
+
+        let unauthenticated = CurseForgeProvider { meta: &meta, api: CurseForgeApi::new(base_url.into(), None) };
+        let error = unauthenticated.find_file(&1234, None, Loader::Fabric, Some("1.19.2".into()), ReleaseChannel::Alpha).await.unwrap_err();
+        assert!(matches!(error, ContentInstallError::MetaLoadError(_)));
+    }
+}
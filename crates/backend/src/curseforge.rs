@@ -0,0 +1,216 @@
+use std::{fmt::Debug, path::Path, sync::Arc};
+
+use auth::secret::SecretStorageError;
+use reqwest::RequestBuilder;
+use rustc_hash::FxHashMap;
+use schema::{
+    curseforge::{curseforge_fingerprint, CurseForgeFile, CurseForgeModFilesRequest, CurseForgeModLoaderType, CURSEFORGE_API_URL}, instance::ReleaseChannel, loader::Loader
+};
+
+use crate::{metadata::{items::{CurseForgeFileMetadataItem, CurseForgeFingerprintsMetadataItem, CurseForgeModFilesMetadataItem}, manager::{MetaLoadError, MetadataManager}}, mod_metadata::ModUpdateAction, BackendState};
+
+pub(crate) const CURSEFORGE_API_KEY_SECRET: &str = "curseforge_api_key";
+
+#[derive(Clone)]
+pub struct CurseForgeApi {
+    base_url: Arc<str>,
+    api_key: Option<Arc<str>>,
+}
+
+impl Debug for CurseForgeApi {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Don't leak the api key into logs
+        f.debug_struct("CurseForgeApi").field("base_url", &self.base_url).finish_non_exhaustive()
+    }
+}
+
+impl CurseForgeApi {
+    pub fn new(base_url: Arc<str>, api_key: Option<Arc<str>>) -> Self {
+        Self {
+            base_url,
+            api_key,
+        }
+    }
+
+    pub fn get(&self, client: &reqwest::Client, path: &str) -> RequestBuilder {
+        self.authenticate(client.get(format!("{}{}", self.base_url.trim_end_matches('/'), path)))
+    }
+
+    pub fn post(&self, client: &reqwest::Client, path: &str) -> RequestBuilder {
+        self.authenticate(client.post(format!("{}{}", self.base_url.trim_end_matches('/'), path)))
+    }
+
+    fn authenticate(&self, request: RequestBuilder) -> RequestBuilder {
+        if let Some(api_key) = &self.api_key {
+            request.header("x-api-key", &**api_key)
+        } else {
+            request
+        }
+    }
+
+    pub async fn find_file(
+        &self,
+        meta: &MetadataManager,
+        mod_id: u32,
+        file_id: Option<u32>,
+        loader: Loader,
+        game_version: Option<Arc<str>>,
+        release_channel: ReleaseChannel,
+    ) -> Result<Option<CurseForgeFile>, MetaLoadError> {
+        if let Some(file_id) = file_id {
+            let file = meta.fetch(&CurseForgeFileMetadataItem { api: self, mod_id, file_id }).await?;
+            return Ok(Some((*file).clone()));
+        }
+
+        let mod_loader_type = CurseForgeModLoaderType::from_loader(loader);
+        let files = meta.fetch(&CurseForgeModFilesMetadataItem {
+            api: self,
+            request: &CurseForgeModFilesRequest {
+                mod_id,
+                game_version,
+                mod_loader_type,
+            },
+        }).await?;
+
+        let file = files.data.iter().find(|file| {
+            if !release_channel.allows(Some(file.release_type.as_version_type())) {
+                return false;
+            }
+            if let Some(mod_loader_type) = mod_loader_type {
+                file.supports_loader(mod_loader_type)
+            } else {
+                true
+            }
+        });
+
+        Ok(file.cloned())
+    }
+}
+
+impl BackendState {
+    /// Returns None if neither an api key nor a custom base url has been configured,
+    /// since the official api rejects unauthenticated requests
+    pub async fn curseforge_api(&self) -> Option<CurseForgeApi> {
+        let api_key = self.curseforge_api_key().await;
+        let api_url = self.config.write().get().curseforge_api_url.clone();
+
+        if api_key.is_none() && api_url.is_none() {
+            return None;
+        }
+
+        Some(CurseForgeApi::new(
+            api_url.unwrap_or_else(|| CURSEFORGE_API_URL.into()),
+            api_key,
+        ))
+    }
+
+    async fn curseforge_api_key(&self) -> Option<Arc<str>> {
+        let mut cached = self.curseforge_api_key.lock().await;
+        if let Some(api_key) = &*cached {
+            return api_key.clone();
+        }
+
+        let secret_storage = match self.get_secret_storage().await {
+            Ok(secret_storage) => secret_storage,
+            Err(error) => {
+                log::error!("Unable to open secret storage to read the CurseForge api key: {error}");
+                return None;
+            },
+        };
+
+        let api_key = match secret_storage.read_secret(CURSEFORGE_API_KEY_SECRET).await {
+            Ok(api_key) => api_key.map(Arc::from),
+            Err(error) => {
+                log::error!("Unable to read the CurseForge api key: {error}");
+                return None;
+            },
+        };
+        *cached = Some(api_key.clone());
+        api_key
+    }
+
+    pub async fn set_curseforge_api_key(&self, api_key: Option<Arc<str>>) -> Result<(), SecretStorageError> {
+        let mut cached = self.curseforge_api_key.lock().await;
+        let secret_storage = self.get_secret_storage().await?;
+        match &api_key {
+            Some(api_key) => secret_storage.write_secret(CURSEFORGE_API_KEY_SECRET, api_key.trim()).await?,
+            None => secret_storage.delete_secret(CURSEFORGE_API_KEY_SECRET).await?,
+        }
+        *cached = Some(api_key.map(|api_key| api_key.trim().into()));
+        Ok(())
+    }
+
+    /// Identifies files by their CurseForge fingerprints using a single request, returning the id
+    /// of the project each recognized file belongs to
+    pub async fn identify_curseforge_files(&self, api: &CurseForgeApi, files: Vec<([u8; 20], Arc<Path>)>) -> FxHashMap<[u8; 20], u32> {
+        let fingerprinted = tokio::task::spawn_blocking(move || {
+            files.into_iter().filter_map(|(hash, path)| {
+                let bytes = std::fs::read(&path).ok()?;
+                Some((hash, curseforge_fingerprint(&bytes)))
+            }).collect::<Vec<_>>()
+        }).await.unwrap();
+
+        let mut fingerprints: Vec<u32> = fingerprinted.iter().map(|(_, fingerprint)| *fingerprint).collect();
+        fingerprints.sort_unstable();
+        fingerprints.dedup();
+        if fingerprints.is_empty() {
+            return FxHashMap::default();
+        }
+
+        let matches = match self.meta.fetch(&CurseForgeFingerprintsMetadataItem { api, fingerprints: &fingerprints }).await {
+            Ok(matches) => matches,
+            Err(error) => {
+                log::warn!("Unable to identify files by CurseForge fingerprint: {error}");
+                return FxHashMap::default();
+            },
+        };
+
+        let projects: FxHashMap<u32, u32> = matches.exact_matches.iter()
+            .map(|exact_match| (exact_match.file.file_fingerprint, exact_match.id))
+            .collect();
+
+        fingerprinted.into_iter()
+            .filter_map(|(hash, fingerprint)| Some((hash, *projects.get(&fingerprint)?)))
+            .collect()
+    }
+
+    pub async fn check_curseforge_update(
+        &self,
+        api: &CurseForgeApi,
+        mod_id: u32,
+        current_hash: [u8; 20],
+        loader: Loader,
+        game_version: Arc<str>,
+        release_channel: ReleaseChannel,
+    ) -> Result<ModUpdateAction, MetaLoadError> {
+        let file = match api.find_file(&self.meta, mod_id, None, loader, Some(game_version.clone()), release_channel).await {
+            Ok(Some(file)) => file,
+            Ok(None) if release_channel != ReleaseChannel::Alpha => {
+                // The file list is cached, so checking whether any less stable file exists is cheap
+                let any_file = api.find_file(&self.meta, mod_id, None, loader, Some(game_version), ReleaseChannel::Alpha).await?;
+                return Ok(if any_file.is_some() {
+                    ModUpdateAction::NoVersionInChannel
+                } else {
+                    ModUpdateAction::ErrorNotFound
+                });
+            },
+            Ok(None) | Err(MetaLoadError::NonOK(404)) => return Ok(ModUpdateAction::ErrorNotFound),
+            Err(error) => return Err(error),
+        };
+
+        let Some(sha1) = file.sha1() else {
+            return Ok(ModUpdateAction::ErrorInvalidHash);
+        };
+
+        let mut latest_hash = [0u8; 20];
+        let Ok(_) = hex::decode_to_slice(&**sha1, &mut latest_hash) else {
+            return Ok(ModUpdateAction::ErrorInvalidHash);
+        };
+
+        if latest_hash == current_hash {
+            Ok(ModUpdateAction::AlreadyUpToDate)
+        } else {
+            Ok(ModUpdateAction::CurseForge { file })
+        }
+    }
+}
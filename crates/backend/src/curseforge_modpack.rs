@@ -0,0 +1,275 @@
+use std::{path::{Path, PathBuf}, sync::Arc};
+
+use bridge::{instance::InstanceID, message::ManualDownload, modal_action::{ModalAction, ProgressTracker, ProgressTrackerFinishType}, safe_path::SafePath};
+use rc_zip_sync::ReadZip;
+use schema::{curseforge::CurseForgeClass, curseforge_manifest::CurseForgeManifestJson, instance::InstanceConfiguration, loader::Loader};
+use tokio::sync::Semaphore;
+
+use crate::{
+    curseforge::CurseForgeApi, metadata::{items::{CurseForgeFileMetadataItem, CurseForgeModMetadataItem}, manager::MetaLoadError}, persistent::Persistent, zip_writer::ZipWriter, BackendState
+};
+
+#[derive(thiserror::Error, Debug)]
+pub enum CurseForgeModpackImportError {
+    #[error("CurseForge isn't configured, an api key or api url must be set")]
+    NotConfigured,
+    #[error("Failed to perform I/O operation:\n{0}")]
+    IoError(#[from] std::io::Error),
+    #[error("Failed to read modpack:\n{0}")]
+    ZipError(#[from] rc_zip_sync::rc_zip::Error),
+    #[error("Modpack is missing manifest.json, is it a CurseForge modpack?")]
+    MissingManifest,
+    #[error("Failed to parse manifest.json:\n{0}")]
+    SerdeJson(#[from] serde_json::Error),
+    #[error("Unsupported manifest type: {0}")]
+    UnsupportedManifestType(Arc<str>),
+    #[error("Modpack uses an unsupported mod loader")]
+    UnsupportedLoader,
+    #[error("Failed to load metadata:\n{0}")]
+    MetaLoadError(#[from] MetaLoadError),
+    #[error("Unable to create instance")]
+    UnableToCreateInstance,
+    #[error("Can't import into instance, unknown id")]
+    UnknownInstance,
+}
+
+struct CurseForgeModpack {
+    manifest: CurseForgeManifestJson,
+    icon: Option<Vec<u8>>,
+    overrides: Vec<(String, Vec<u8>)>,
+}
+
+struct ResolvedFile {
+    path: String,
+    sha1: Arc<str>,
+    url: Arc<str>,
+    size: usize,
+}
+
+impl BackendState {
+    /// Converts a CurseForge modpack into a .mrpack, placing it in the mods folder of the target instance
+    /// so it gets layered on top of the instance like any other modpack
+    pub async fn import_curseforge_modpack(&self, path: PathBuf, target: Option<InstanceID>, modal_action: &ModalAction) -> Result<Vec<ManualDownload>, CurseForgeModpackImportError> {
+        let Some(api) = self.curseforge_api().await else {
+            return Err(CurseForgeModpackImportError::NotConfigured);
+        };
+
+        let modpack = tokio::task::spawn_blocking(move || read_curseforge_modpack(&path)).await.unwrap()?;
+        let manifest = &modpack.manifest;
+
+        let (loader, loader_version) = manifest.loader();
+        if loader == Loader::Unknown {
+            return Err(CurseForgeModpackImportError::UnsupportedLoader);
+        }
+
+        let files: Vec<_> = manifest.files.iter().filter(|file| file.required).collect();
+
+        let tracker = ProgressTracker::new("Resolving modpack files".into(), self.send.clone());
+        modal_action.trackers.push(tracker.clone());
+        tracker.set_total(files.len());
+        tracker.notify();
+
+        let semaphore = Semaphore::new(8);
+        let futures = files.iter().map(|file| {
+            let (api, semaphore, tracker) = (&api, &semaphore, &tracker);
+            async move {
+                let _permit = semaphore.acquire().await.unwrap();
+                let result = self.resolve_curseforge_modpack_file(api, file.project_id, file.file_id).await;
+                tracker.add_count(1);
+                tracker.notify();
+                result
+            }
+        });
+        let resolved = futures::future::join_all(futures).await;
+
+        let mut downloads = Vec::new();
+        let mut manual_downloads = Vec::new();
+        let mut failed = Vec::new();
+        for (file, result) in files.iter().zip(resolved) {
+            match result {
+                Ok(Ok(resolved)) => downloads.push(resolved),
+                Ok(Err(manual_download)) => manual_downloads.push(manual_download),
+                Err(error) => failed.push((file, error)),
+            }
+        }
+
+        tracker.set_finished(if failed.is_empty() { ProgressTrackerFinishType::Fast } else { ProgressTrackerFinishType::Error });
+        tracker.notify();
+
+        // Importing the rest is still useful unless nothing could be resolved at all
+        if !failed.is_empty() && downloads.is_empty() && manual_downloads.is_empty() {
+            return Err(failed.swap_remove(0).1.into());
+        }
+
+        let dot_minecraft_path = match target {
+            Some(id) => {
+                let mut instance_state = self.instance_state.write();
+                let Some(instance) = instance_state.instances.get_mut(id) else {
+                    return Err(CurseForgeModpackImportError::UnknownInstance);
+                };
+
+                let configuration = instance.configuration.get();
+                let (current_loader, current_loader_version) = (configuration.loader, configuration.preferred_loader_version);
+                if current_loader == Loader::Vanilla || (current_loader == loader && current_loader_version.is_none()) {
+                    instance.configuration.modify(|configuration| {
+                        configuration.loader = loader;
+                        configuration.preferred_loader_version = loader_version;
+                    });
+                }
+
+                instance.dot_minecraft_path.to_path_buf()
+            },
+            None => {
+                let Some(instance_dir) = self.create_instance_sanitized(&manifest.name, &manifest.minecraft.version, loader).await else {
+                    return Err(CurseForgeModpackImportError::UnableToCreateInstance);
+                };
+
+                if loader_version.is_some() {
+                    let info_path = instance_dir.join("info_v1.json");
+                    if let Ok(mut configuration) = Persistent::<InstanceConfiguration>::try_load(info_path.into()) {
+                        configuration.modify(|configuration| {
+                            configuration.preferred_loader_version = loader_version;
+                        });
+                    }
+                }
+
+                instance_dir.join(".minecraft")
+            },
+        };
+
+        let mrpack_filename = sanitize_filename::sanitize_with_options(&*manifest.name, sanitize_filename::Options { windows: true, ..Default::default() });
+        let mrpack_path = dot_minecraft_path.join("mods").join(format!("{mrpack_filename}.mrpack"));
+
+        let mrpack = tokio::task::spawn_blocking(move || write_mrpack(modpack, loader, loader_version.as_deref(), downloads)).await.unwrap()?;
+        let _ = tokio::fs::create_dir_all(mrpack_path.parent().unwrap()).await;
+        tokio::fs::write(&mrpack_path, mrpack).await?;
+
+        if !failed.is_empty() {
+            let mut message = format!("Unable to resolve {} modpack files, they were left out:", failed.len());
+            for (file, error) in &failed {
+                message.push_str(&format!("\nProject {} file {}: {}", file.project_id, file.file_id, error));
+            }
+            self.send.send_warning(message);
+        }
+
+        Ok(manual_downloads)
+    }
+
+    async fn resolve_curseforge_modpack_file(&self, api: &CurseForgeApi, mod_id: u32, file_id: u32) -> Result<Result<ResolvedFile, ManualDownload>, MetaLoadError> {
+        let file = self.meta.fetch(&CurseForgeFileMetadataItem { api, mod_id, file_id }).await?;
+        let project = self.meta.fetch(&CurseForgeModMetadataItem { api, mod_id }).await?;
+
+        let directory = project.class_id
+            .and_then(CurseForgeClass::from_id)
+            .unwrap_or(CurseForgeClass::Mod)
+            .install_directory();
+
+        if let Some(url) = &file.download_url && let Some(sha1) = file.sha1() {
+            return Ok(Ok(ResolvedFile {
+                path: format!("{directory}/{}", file.file_name),
+                sha1: sha1.clone(),
+                url: url.clone(),
+                size: file.file_length,
+            }));
+        }
+
+        let url = if let Some(website_url) = project.links.as_ref().and_then(|links| links.website_url.as_ref()) {
+            format!("{}/files/{}", website_url.trim_end_matches('/'), file.id)
+        } else {
+            format!("https://www.curseforge.com/minecraft/mc-mods/{}/files/{}", project.slug, file.id)
+        };
+
+        Ok(Err(ManualDownload {
+            name: project.name.clone(),
+            filename: file.file_name.clone(),
+            url: url.into(),
+            destination: directory.into(),
+        }))
+    }
+}
+
+fn read_curseforge_modpack(path: &Path) -> Result<CurseForgeModpack, CurseForgeModpackImportError> {
+    let file = std::fs::File::open(path)?;
+    let archive = file.read_zip()?;
+
+    let Some(manifest_file) = archive.by_name("manifest.json") else {
+        return Err(CurseForgeModpackImportError::MissingManifest);
+    };
+    let manifest: CurseForgeManifestJson = serde_json::from_slice(&manifest_file.bytes()?)?;
+    drop(manifest_file);
+
+    if &*manifest.manifest_type != "minecraftModpack" {
+        return Err(CurseForgeModpackImportError::UnsupportedManifestType(manifest.manifest_type.clone()));
+    }
+
+    let overrides_dir = manifest.overrides.as_deref().unwrap_or("overrides").trim_end_matches('/');
+
+    let mut overrides = Vec::new();
+    for entry in archive.entries() {
+        if entry.kind() != rc_zip_sync::rc_zip::EntryKind::File {
+            continue;
+        }
+        let Some(relative) = entry.name.strip_prefix(overrides_dir).and_then(|name| name.strip_prefix('/')) else {
+            continue;
+        };
+        if SafePath::new(relative).is_none() {
+            continue;
+        }
+        overrides.push((format!("overrides/{relative}"), entry.bytes()?));
+    }
+
+    let icon = archive.by_name("icon.png").and_then(|icon| icon.bytes().ok());
+
+    Ok(CurseForgeModpack {
+        manifest,
+        icon,
+        overrides,
+    })
+}
+
+fn write_mrpack(modpack: CurseForgeModpack, loader: Loader, loader_version: Option<&str>, downloads: Vec<ResolvedFile>) -> std::io::Result<Vec<u8>> {
+    let manifest = modpack.manifest;
+
+    let mut dependencies = serde_json::Map::new();
+    dependencies.insert("minecraft".into(), manifest.minecraft.version.as_str().into());
+    if let Some(loader_version) = loader_version {
+        let key = match loader {
+            Loader::Fabric => Some("fabric-loader"),
+            Loader::Forge => Some("forge"),
+            Loader::NeoForge => Some("neoforge"),
+            Loader::Vanilla | Loader::Unknown => None,
+        };
+        if let Some(key) = key {
+            dependencies.insert(key.into(), loader_version.into());
+        }
+    }
+
+    let files: Vec<_> = downloads.iter().map(|download| serde_json::json!({
+        "path": download.path,
+        "hashes": { "sha1": download.sha1 },
+        "downloads": [download.url],
+        "fileSize": download.size,
+    })).collect();
+
+    let mut index = serde_json::json!({
+        "formatVersion": 1,
+        "game": "minecraft",
+        "versionId": manifest.version.as_deref().unwrap_or("1.0.0"),
+        "name": manifest.name,
+        "files": files,
+        "dependencies": dependencies,
+    });
+    if let Some(author) = &manifest.author {
+        index["author"] = author.as_ref().into();
+    }
+
+    let mut zip = ZipWriter::default();
+    zip.add_file("modrinth.index.json", &serde_json::to_vec_pretty(&index)?)?;
+    if let Some(icon) = &modpack.icon {
+        zip.add_file("icon.png", icon)?;
+    }
+    for (name, data) in &modpack.overrides {
+        zip.add_file(name, data)?;
+    }
+    zip.finish()
+}
@@ -15,16 +15,22 @@ pub struct LauncherDirectories {
     pub libraries_dir: Arc<Path>,
     pub log_configs_dir: Arc<Path>,
     pub runtime_base_dir: Arc<Path>,
+    pub authlib_injector_dir: Arc<Path>,
 
     pub content_library_dir: Arc<Path>,
     pub content_meta_dir: Arc<Path>,
 
+    pub skins_dir: Arc<Path>,
+    pub skins_json: Arc<Path>,
+
     pub temp_dir: Arc<Path>,
     pub temp_natives_base_dir: Arc<Path>,
+    pub temp_sessions_dir: Arc<Path>,
 
     pub root_launcher_dir: Arc<Path>,
     pub config_json: Arc<Path>,
     pub accounts_json: Arc<Path>,
+    pub secrets_json: Arc<Path>,
 }
 
 impl LauncherDirectories {
@@ -46,14 +52,22 @@ impl LauncherDirectories {
 
         let runtime_base_dir = launcher_dir.join("runtime");
 
+        // Kept outside of the libraries dir, which is cleaned up based on the libraries instances use
+        let authlib_injector_dir = launcher_dir.join("authlibinjector");
+
         let content_library_dir = launcher_dir.join("contentlibrary");
         let content_meta_dir = launcher_dir.join("contentmeta");
 
+        let skins_dir = launcher_dir.join("skins");
+        let skins_json = skins_dir.join("skins.json");
+
         let temp_dir = launcher_dir.join("temp");
         let temp_natives_base_dir = temp_dir.join("natives");
+        let temp_sessions_dir = temp_dir.join("sessions");
 
         let config_json = launcher_dir.join("config.json");
         let accounts_json = launcher_dir.join("accounts.json");
+        let secrets_json = launcher_dir.join("secrets.json");
 
         Self {
             instances_dir: instances_dir.into(),
@@ -70,16 +84,22 @@ impl LauncherDirectories {
             libraries_dir: libraries_dir.into(),
             log_configs_dir: log_configs_dir.into(),
             runtime_base_dir: runtime_base_dir.into(),
+            authlib_injector_dir: authlib_injector_dir.into(),
 
             content_library_dir: content_library_dir.into(),
             content_meta_dir: content_meta_dir.into(),
 
+            skins_dir: skins_dir.into(),
+            skins_json: skins_json.into(),
+
             temp_dir: temp_dir.into(),
             temp_natives_base_dir: temp_natives_base_dir.into(),
+            temp_sessions_dir: temp_sessions_dir.into(),
 
             root_launcher_dir: launcher_dir.into(),
             config_json: config_json.into(),
             accounts_json: accounts_json.into(),
+            secrets_json: secrets_json.into(),
         }
     }
 }
@@ -0,0 +1,563 @@
+use std::{
+    collections::HashMap, path::{Path, PathBuf}, sync::{atomic::{AtomicU32, AtomicUsize, Ordering}, Arc}, time::{Duration, Instant}
+};
+
+use bridge::modal_action::{ActiveDownload, DownloadStats, ProgressTracker};
+use futures::StreamExt;
+use parking_lot::{Mutex, RwLock};
+use reqwest::{StatusCode, header::{CONTENT_RANGE, HeaderMap, RANGE}};
+use schema::backend_config::BackendConfig;
+use tokio::{io::AsyncWriteExt, sync::{Semaphore, SemaphorePermit}};
+
+use crate::{endpoints::Endpoints, persistent::Persistent};
+
+// Limit max concurrent connections to 8 by default to avoid ratelimiting issues
+const DEFAULT_MAX_CONCURRENT_DOWNLOADS: usize = 8;
+const MAX_ATTEMPTS: u32 = 5;
+const STALL_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(thiserror::Error, Debug)]
+pub enum DownloadError {
+    #[error("Failed to load remote content:\n{0}")]
+    Reqwest(#[from] reqwest::Error),
+    #[error("Failed to perform I/O operation:\n{0}")]
+    IoError(#[from] std::io::Error),
+    #[error("Server responded with {0}")]
+    NotOK(StatusCode),
+    #[error("Download stalled for more than {} seconds", STALL_TIMEOUT.as_secs())]
+    Stalled,
+    #[error("Downloaded file had wrong size. Expected {0}, got {1}")]
+    WrongSize(usize, usize),
+    #[error("Downloaded file had the wrong hash")]
+    WrongHash,
+}
+
+impl DownloadError {
+    fn is_retryable(&self) -> bool {
+        match self {
+            DownloadError::Reqwest(error) => !error.is_builder(),
+            DownloadError::IoError(_) => false,
+            DownloadError::NotOK(status) => status.is_server_error()
+                || *status == StatusCode::REQUEST_TIMEOUT
+                || *status == StatusCode::TOO_MANY_REQUESTS
+                || *status == StatusCode::RANGE_NOT_SATISFIABLE,
+            DownloadError::Stalled | DownloadError::WrongSize(..) | DownloadError::WrongHash => true,
+        }
+    }
+}
+
+pub struct DownloadRequest<'a> {
+    pub url: &'a str,
+    pub path: &'a Path,
+    pub sha1: Option<[u8; 20]>,
+    pub size: Option<usize>,
+    /// Whether the tracker belongs to this download alone, in which case its count follows the bytes written
+    pub track_bytes: bool,
+}
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+enum DownloadKey {
+    Hash([u8; 20]),
+    Path(PathBuf),
+}
+
+/// Downloads files into place for every part of the launcher, sharing a single concurrency and bandwidth limit.
+/// Partial files are kept next to the destination so that retries and later attempts can resume them
+pub struct DownloadManager {
+    http_client: reqwest::Client,
+    endpoints: Arc<Endpoints>,
+    config: Arc<RwLock<Persistent<BackendConfig>>>,
+    semaphore: Arc<Semaphore>,
+    // Number of permits the semaphore should have in total, some may still be held by downloads after shrinking
+    max_concurrent_downloads: AtomicUsize,
+    // Speed limit in KiB/s, 0 when unlimited
+    speed_limit: AtomicU32,
+    next_available: Mutex<Instant>,
+    in_progress: Mutex<HashMap<DownloadKey, Arc<tokio::sync::Mutex<()>>>>,
+    stats: Arc<DownloadStats>,
+}
+
+impl DownloadManager {
+    pub fn new(http_client: reqwest::Client, endpoints: Arc<Endpoints>, config: Arc<RwLock<Persistent<BackendConfig>>>) -> Self {
+        let max_concurrent_downloads = configured_max_concurrent_downloads(config.write().get());
+
+        let download_manager = Self {
+            http_client,
+            endpoints,
+            config,
+            semaphore: Arc::new(Semaphore::new(max_concurrent_downloads)),
+            max_concurrent_downloads: AtomicUsize::new(max_concurrent_downloads),
+            speed_limit: AtomicU32::new(0),
+            next_available: Mutex::new(Instant::now()),
+            in_progress: Default::default(),
+            stats: Default::default(),
+        };
+        download_manager.reload_config();
+        download_manager
+    }
+
+    /// Picks up a changed speed limit and concurrency limit, must be called when the config changes
+    pub fn reload_config(&self) {
+        let (limit, max_concurrent_downloads) = {
+            let mut config = self.config.write();
+            let config = config.get();
+            (config.download_speed_limit_kib.unwrap_or(0), configured_max_concurrent_downloads(config))
+        };
+        self.speed_limit.store(limit, Ordering::Relaxed);
+
+        let previous = self.max_concurrent_downloads.swap(max_concurrent_downloads, Ordering::Relaxed);
+        if max_concurrent_downloads > previous {
+            self.semaphore.add_permits(max_concurrent_downloads - previous);
+        } else if max_concurrent_downloads < previous {
+            let excess = previous - max_concurrent_downloads;
+            let held = excess - self.semaphore.forget_permits(excess);
+            if held > 0 {
+                // The rest are forgotten as the downloads using them finish
+                let semaphore = Arc::clone(&self.semaphore);
+                tokio::spawn(async move {
+                    if let Ok(permits) = semaphore.acquire_many_owned(held as u32).await {
+                        permits.forget();
+                    }
+                });
+            }
+        }
+    }
+
+    /// Waits for one of the shared connections, for requests that don't download a file such as metadata lookups.
+    /// The permit must be dropped before downloading anything
+    pub async fn acquire_connection(&self) -> SemaphorePermit<'_> {
+        self.semaphore.acquire().await.unwrap()
+    }
+
+    /// Downloads the file to the requested path, verifying the size and hash if they're known.
+    /// Concurrent requests for the same file wait for the first one instead of downloading it twice
+    pub async fn download(&self, request: DownloadRequest<'_>, tracker: &ProgressTracker) -> Result<(), DownloadError> {
+        let key = match request.sha1 {
+            Some(hash) => DownloadKey::Hash(hash),
+            None => DownloadKey::Path(request.path.to_path_buf()),
+        };
+        let lock = Arc::clone(self.in_progress.lock().entry(key.clone()).or_default());
+
+        let result = match lock.try_lock() {
+            Ok(_guard) => self.download_with_retries(&request, tracker).await,
+            Err(_) => {
+                let _guard = lock.lock().await;
+                if self.is_complete(&request).await {
+                    if request.track_bytes && let Some(size) = request.size {
+                        tracker.set_count(size);
+                        tracker.notify();
+                    }
+                    Ok(())
+                } else {
+                    self.download_with_retries(&request, tracker).await
+                }
+            },
+        };
+
+        let mut in_progress = self.in_progress.lock();
+        if Arc::strong_count(&lock) <= 2 {
+            in_progress.remove(&key);
+        }
+
+        result
+    }
+
+    async fn is_complete(&self, request: &DownloadRequest<'_>) -> bool {
+        let Some(expected_hash) = request.sha1 else {
+            return request.path.exists();
+        };
+        let path = request.path.to_path_buf();
+        tokio::task::spawn_blocking(move || {
+            crate::check_sha1_hash(&path, expected_hash).unwrap_or(false)
+        }).await.unwrap()
+    }
+
+    async fn download_with_retries(&self, request: &DownloadRequest<'_>, tracker: &ProgressTracker) -> Result<(), DownloadError> {
+        // Queued downloads count towards the time remaining as well
+        let mut active = self.stats.begin(request.size);
+        tracker.set_download_stats(&self.stats);
+
+        let mut attempt = 1;
+        loop {
+            let result = {
+                let _permit = self.semaphore.acquire().await.unwrap();
+                self.try_download(request, tracker, &mut active).await
+            };
+
+            match result {
+                Err(error) if attempt < MAX_ATTEMPTS && error.is_retryable() => {
+                    let delay = Duration::from_secs(1 << (attempt - 1));
+                    log::warn!("Download of {} failed (attempt {attempt}/{MAX_ATTEMPTS}), retrying in {}s: {error}",
+                        request.url, delay.as_secs());
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                },
+                result => return result,
+            }
+        }
+    }
+
+    async fn try_download(&self, request: &DownloadRequest<'_>, tracker: &ProgressTracker, active: &mut ActiveDownload) -> Result<(), DownloadError> {
+        let part_path = request.path.with_added_extension("part");
+
+        let mut offset = tokio::fs::metadata(&part_path).await.map(|metadata| metadata.len() as usize).unwrap_or(0);
+        if let Some(size) = request.size && offset > size {
+            _ = tokio::fs::remove_file(&part_path).await;
+            offset = 0;
+        }
+
+        // A partial file of the right size only needs to be verified
+        if request.size.is_none_or(|size| offset < size) {
+            let mut builder = self.http_client.get(request.url);
+            if offset > 0 {
+                builder = builder.header(RANGE, format!("bytes={offset}-"));
+            }
+
+            let mut response = self.endpoints.send(builder).await?;
+            if offset > 0 && response.status() == StatusCode::PARTIAL_CONTENT && content_range_start(response.headers()) != Some(offset) {
+                log::warn!("Server didn't resume {} at byte {offset}, downloading it from the start", request.url);
+                offset = 0;
+                response = self.endpoints.send(self.http_client.get(request.url)).await?;
+            }
+
+            let status = response.status();
+            let resume = match status {
+                StatusCode::PARTIAL_CONTENT if offset > 0 => true,
+                StatusCode::OK => false,
+                StatusCode::RANGE_NOT_SATISFIABLE => {
+                    _ = tokio::fs::remove_file(&part_path).await;
+                    return Err(DownloadError::NotOK(status));
+                },
+                _ => return Err(DownloadError::NotOK(status)),
+            };
+
+            let mut file = if resume {
+                tokio::fs::OpenOptions::new().append(true).open(&part_path).await?
+            } else {
+                offset = 0;
+                tokio::fs::File::create(&part_path).await?
+            };
+            if let Some(size) = request.size {
+                active.set_remaining(size - offset);
+            }
+
+            if request.track_bytes {
+                tracker.set_count(offset);
+                if let Some(size) = request.size {
+                    tracker.set_total(size);
+                }
+                tracker.notify();
+            }
+
+            let mut stream = response.bytes_stream();
+            loop {
+                let bytes = match tokio::time::timeout(STALL_TIMEOUT, stream.next()).await {
+                    Ok(Some(bytes)) => bytes?,
+                    Ok(None) => break,
+                    Err(_) => return Err(DownloadError::Stalled),
+                };
+
+                self.throttle(bytes.len()).await;
+                file.write_all(&bytes).await?;
+
+                active.add_downloaded(bytes.len());
+                if request.track_bytes {
+                    tracker.add_count(bytes.len());
+                }
+                tracker.notify();
+            }
+
+            file.flush().await?;
+        }
+
+        let actual_size = tokio::fs::metadata(&part_path).await?.len() as usize;
+        if let Some(size) = request.size && actual_size != size {
+            _ = tokio::fs::remove_file(&part_path).await;
+            return Err(DownloadError::WrongSize(size, actual_size));
+        }
+
+        if let Some(expected_hash) = request.sha1 {
+            let path = part_path.clone();
+            let valid_hash = tokio::task::spawn_blocking(move || {
+                crate::check_sha1_hash(&path, expected_hash)
+            }).await.unwrap()?;
+
+            if !valid_hash {
+                _ = tokio::fs::remove_file(&part_path).await;
+                return Err(DownloadError::WrongHash);
+            }
+        }
+
+        tokio::fs::rename(&part_path, request.path).await?;
+        Ok(())
+    }
+
+    /// Waits until the bytes fit within the configured speed limit, spreading them evenly across all downloads
+    async fn throttle(&self, bytes: usize) {
+        let limit = self.speed_limit.load(Ordering::Relaxed);
+        if limit == 0 {
+            return;
+        }
+
+        let delay = reserve_bandwidth(&mut self.next_available.lock(), Instant::now(), bytes, limit);
+        if !delay.is_zero() {
+            tokio::time::sleep(delay).await;
+        }
+    }
+}
+
+fn configured_max_concurrent_downloads(config: &BackendConfig) -> usize {
+    config.max_concurrent_downloads.unwrap_or(DEFAULT_MAX_CONCURRENT_DOWNLOADS).max(1)
+}
+
+/// Reserves the time needed to transfer the bytes at `limit_kib` KiB/s after everything reserved before them,
+/// returning how long to wait until the transfer may start
+fn reserve_bandwidth(next_available: &mut Instant, now: Instant, bytes: usize, limit_kib: u32) -> Duration {
+    let start = (*next_available).max(now);
+    *next_available = start + Duration::from_secs_f64(bytes as f64 / (limit_kib as f64 * 1024.0));
+    start - now
+}
+
+/// The first byte of a `Content-Range: bytes <start>-<end>/<size>` header
+fn content_range_start(headers: &HeaderMap) -> Option<usize> {
+    let range = headers.get(CONTENT_RANGE)?.to_str().ok()?;
+    let (start, _) = range.strip_prefix("bytes ")?.split_once('-')?;
+    start.trim().parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicUsize;
+
+    use auth::test_util::{self, MockResponse, TempDir};
+    use sha1::{Digest, Sha1};
+
+    use super::*;
+
+    const CONTENT_SIZE: usize = 64 * 1024;
+
+    fn content() -> Vec<u8> {
+        (0..CONTENT_SIZE).map(|i| (i % 251) as u8).collect()
+    }
+
+    fn content_sha1() -> [u8; 20] {
+        Sha1::digest(content()).into()
+    }
+
+    /// Serves the content at /file with support for ranges, failing the first `failures` requests.
+    /// Returns the base url and the range header of every request that was received
+    async fn start_mock_server(failures: usize) -> (String, Arc<Mutex<Vec<Option<String>>>>) {
+        let requests: Arc<Mutex<Vec<Option<String>>>> = Default::default();
+        let failures = AtomicUsize::new(failures);
+
+        let received = Arc::clone(&requests);
+        let base_url = test_util::start_mock_server(move |request| {
+            let range = request.header("range").map(str::to_string);
+            received.lock().push(range.clone());
+
+            let offset = range.as_deref()
+                .and_then(|range| range.strip_prefix("bytes=")?.strip_suffix('-')?.parse::<usize>().ok());
+
+            if failures.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1)).is_ok() {
+                MockResponse::new("503 Service Unavailable")
+            } else if request.path != "/file" {
+                MockResponse::new("404 Not Found")
+            } else if let Some(offset) = offset {
+                MockResponse::new("206 Partial Content")
+                    .header("Content-Range", format!("bytes {offset}-{}/{CONTENT_SIZE}", CONTENT_SIZE - 1))
+                    .body(&content()[offset..])
+            } else {
+                MockResponse::new("200 OK").body(content())
+            }
+        }).await;
+
+        (base_url, requests)
+    }
+
+    fn create_download_manager(name: &str) -> (DownloadManager, TempDir) {
+        let dir = TempDir::new(&format!("downloads-{name}"));
+        let config = Arc::new(RwLock::new(Persistent::load(dir.join("config.json").into())));
+        let endpoints = Arc::new(Endpoints::new(Arc::clone(&config)));
+        (DownloadManager::new(reqwest::Client::new(), endpoints, config), dir)
+    }
+
+    fn create_tracker() -> ProgressTracker {
+        let (_, _, _, send) = bridge::handle::create_pair();
+        ProgressTracker::new("Downloading".into(), send)
+    }
+
+    #[tokio::test]
+    async fn test_resume_partial_file() {
+        let (base_url, requests) = start_mock_server(0).await;
+        let (downloads, dir) = create_download_manager("resume");
+        let path = dir.join("file.jar");
+        std::fs::write(path.with_added_extension("part"), &content()[..1000]).unwrap();
+
+        let tracker = create_tracker();
+        downloads.download(DownloadRequest {
+            url: &format!("{base_url}/file"),
+            path: &path,
+            sha1: Some(content_sha1()),
+            size: Some(CONTENT_SIZE),
+            track_bytes: true,
+        }, &tracker).await.unwrap();
+
+        assert_eq!(std::fs::read(&path).unwrap(), content());
+        assert!(!path.with_added_extension("part").exists());
+        assert_eq!(requests.lock().as_slice(), [Some("bytes=1000-".to_string())]);
+        assert_eq!(tracker.get(), (CONTENT_SIZE, CONTENT_SIZE));
+
+        // Nothing is left counted towards the time remaining
+        assert_eq!(downloads.stats.rate(), None);
+    }
+
+    #[tokio::test]
+    async fn test_retry_server_error() {
+        let (base_url, requests) = start_mock_server(1).await;
+        let (downloads, dir) = create_download_manager("retry");
+        let path = dir.join("file.jar");
+
+        downloads.download(DownloadRequest {
+            url: &format!("{base_url}/file"),
+            path: &path,
+            sha1: Some(content_sha1()),
+            size: Some(CONTENT_SIZE),
+            track_bytes: false,
+        }, &create_tracker()).await.unwrap();
+
+        assert_eq!(std::fs::read(&path).unwrap(), content());
+        assert_eq!(requests.lock().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_no_retry_not_found() {
+        let (base_url, requests) = start_mock_server(0).await;
+        let (downloads, dir) = create_download_manager("not-found");
+
+        let result = downloads.download(DownloadRequest {
+            url: &format!("{base_url}/missing"),
+            path: &dir.join("missing.jar"),
+            sha1: None,
+            size: None,
+            track_bytes: false,
+        }, &create_tracker()).await;
+
+        assert!(matches!(result, Err(DownloadError::NotOK(StatusCode::NOT_FOUND))));
+        assert_eq!(requests.lock().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_deduplicate_concurrent_downloads() {
+        let (base_url, requests) = start_mock_server(0).await;
+        let (downloads, dir) = create_download_manager("dedupe");
+        let path = dir.join("file.jar");
+        let url = format!("{base_url}/file");
+        let tracker = create_tracker();
+
+        let request = || DownloadRequest {
+            url: &url,
+            path: &path,
+            sha1: Some(content_sha1()),
+            size: Some(CONTENT_SIZE),
+            track_bytes: false,
+        };
+        let (first, second) = tokio::join!(
+            downloads.download(request(), &tracker),
+            downloads.download(request(), &tracker),
+        );
+        first.unwrap();
+        second.unwrap();
+
+        assert_eq!(std::fs::read(&path).unwrap(), content());
+        assert_eq!(requests.lock().len(), 1);
+        assert!(downloads.in_progress.lock().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_restart_when_resumed_at_wrong_offset() {
+        let requests: Arc<Mutex<Vec<Option<String>>>> = Default::default();
+        let received = Arc::clone(&requests);
+        // Ignores the requested offset and sends the whole file as partial content
+        let base_url = test_util::start_mock_server(move |request| {
+            let range = request.header("range").map(str::to_string);
+            received.lock().push(range.clone());
+            if range.is_some() {
+                MockResponse::new("206 Partial Content")
+                    .header("Content-Range", format!("bytes 0-{}/{CONTENT_SIZE}", CONTENT_SIZE - 1))
+                    .body(content())
+            } else {
+                MockResponse::new("200 OK").body(content())
+            }
+        }).await;
+
+        let (downloads, dir) = create_download_manager("wrong-offset");
+        let path = dir.join("file.jar");
+        std::fs::write(path.with_added_extension("part"), &content()[..1000]).unwrap();
+
+        downloads.download(DownloadRequest {
+            url: &format!("{base_url}/file"),
+            path: &path,
+            sha1: Some(content_sha1()),
+            size: Some(CONTENT_SIZE),
+            track_bytes: false,
+        }, &create_tracker()).await.unwrap();
+
+        assert_eq!(std::fs::read(&path).unwrap(), content());
+        assert_eq!(requests.lock().as_slice(), [Some("bytes=1000-".to_string()), None]);
+    }
+
+    #[test]
+    fn test_content_range_start() {
+        let headers = |value: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(CONTENT_RANGE, value.parse().unwrap());
+            headers
+        };
+        assert_eq!(content_range_start(&headers("bytes 1000-65535/65536")), Some(1000));
+        assert_eq!(content_range_start(&headers("bytes 0-99/*")), Some(0));
+        assert_eq!(content_range_start(&headers("bytes */65536")), None);
+        assert_eq!(content_range_start(&HeaderMap::new()), None);
+    }
+
+    #[test]
+    fn test_reserve_bandwidth() {
+        let now = Instant::now();
+        let mut next_available = now;
+
+        // The first chunk goes through immediately and each one after waits for the ones before it
+        assert_eq!(reserve_bandwidth(&mut next_available, now, 512 * 1024, 1024), Duration::ZERO);
+        assert_eq!(reserve_bandwidth(&mut next_available, now, 512 * 1024, 1024), Duration::from_millis(500));
+        assert_eq!(reserve_bandwidth(&mut next_available, now, 1024 * 1024, 1024), Duration::from_secs(1));
+        assert_eq!(next_available, now + Duration::from_secs(2));
+
+        // Time spent without downloading isn't saved up for a later burst
+        let later = now + Duration::from_secs(10);
+        assert_eq!(reserve_bandwidth(&mut next_available, later, 256 * 1024, 1024), Duration::ZERO);
+        assert_eq!(next_available, later + Duration::from_millis(250));
+    }
+
+    #[tokio::test]
+    async fn test_resize_concurrency_limit() {
+        let (downloads, _dir) = create_download_manager("resize");
+        assert_eq!(downloads.semaphore.available_permits(), DEFAULT_MAX_CONCURRENT_DOWNLOADS);
+
+        downloads.config.write().modify(|config| config.max_concurrent_downloads = Some(2));
+        downloads.reload_config();
+        assert_eq!(downloads.semaphore.available_permits(), 2);
+
+        // Shrinking below the number of active downloads takes effect as they finish
+        let first = downloads.acquire_connection().await;
+        let second = downloads.acquire_connection().await;
+        downloads.config.write().modify(|config| config.max_concurrent_downloads = Some(1));
+        downloads.reload_config();
+        tokio::task::yield_now().await;
+        drop(first);
+        assert_eq!(downloads.semaphore.available_permits(), 0);
+        drop(second);
+        assert_eq!(downloads.semaphore.available_permits(), 1);
+
+        downloads.config.write().modify(|config| config.max_concurrent_downloads = Some(4));
+        downloads.reload_config();
+        assert_eq!(downloads.semaphore.available_permits(), 4);
+    }
+}
@@ -0,0 +1,66 @@
+use std::sync::Arc;
+
+use parking_lot::RwLock;
+use reqwest::{RequestBuilder, Response, Url};
+use schema::backend_config::{BackendConfig, EndpointConfig, EndpointService};
+
+use crate::persistent::Persistent;
+
+/// Redirects requests to the replacement base urls in the backend config, falling back
+/// to the original url if the replacement is unreachable or doesn't have the file
+pub struct Endpoints {
+    config: Arc<RwLock<Persistent<BackendConfig>>>,
+    // Snapshot of the configured endpoints, so requests don't need to take the config lock
+    endpoints: RwLock<Arc<EndpointConfig>>,
+}
+
+impl Endpoints {
+    pub fn new(config: Arc<RwLock<Persistent<BackendConfig>>>) -> Self {
+        let endpoints = RwLock::new(Arc::new(config.write().get().endpoints.clone()));
+        Self {
+            config,
+            endpoints,
+        }
+    }
+
+    /// Takes a new snapshot of the configured endpoints, must be called when the config changes
+    pub fn reload(&self) {
+        let endpoints = self.config.write().get().endpoints.clone();
+        *self.endpoints.write() = Arc::new(endpoints);
+    }
+
+    /// Url of a file of the service, on the configured replacement if there is one
+    pub fn url(&self, service: EndpointService, path: &str) -> String {
+        self.endpoints.read().url(service, path)
+    }
+
+    pub fn rewrite(&self, url: &str) -> Option<Url> {
+        let endpoints = Arc::clone(&self.endpoints.read());
+        if endpoints.is_empty() {
+            return None;
+        }
+        Url::parse(&endpoints.rewrite(url)?).ok()
+    }
+
+    pub async fn send(&self, request: RequestBuilder) -> reqwest::Result<Response> {
+        let (client, request) = request.build_split();
+        let request = request?;
+
+        if let Some(url) = self.rewrite(request.url().as_str())
+            && let Some(mut mirrored) = request.try_clone()
+        {
+            *mirrored.url_mut() = url;
+            match client.execute(mirrored).await {
+                Ok(response) if response.status().is_success() => return Ok(response),
+                Ok(response) => {
+                    log::warn!("Mirror {} responded with {}, falling back to {}", response.url(), response.status(), request.url());
+                },
+                Err(error) => {
+                    log::warn!("Unable to reach mirror for {}, falling back to origin: {error}", request.url());
+                },
+            }
+        }
+
+        client.execute(request).await
+    }
+}
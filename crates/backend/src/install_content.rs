@@ -1,14 +1,12 @@
-use std::{ffi::{OsStr, OsString}, io::Write, path::{Path, PathBuf}, sync::Arc};
+use std::{ffi::{OsStr, OsString}, path::{Path, PathBuf}, sync::Arc};
 
 use bridge::{
     install::{ContentDownload, ContentInstall, ContentInstallFile, ContentInstallPath}, instance::{ContentType, ContentSummary}, modal_action::{ModalAction, ProgressTracker, ProgressTrackerFinishType}, safe_path::SafePath
 };
-use reqwest::StatusCode;
-use schema::{content::ContentSource, loader::Loader, modrinth::{ModrinthLoader, ModrinthProjectVersionsRequest}};
+use schema::{content::ContentSource, instance::ReleaseChannel, loader::Loader};
 use sha1::{Digest, Sha1};
-use tokio::io::AsyncWriteExt;
 
-use crate::{lockfile::Lockfile, metadata::{items::{MinecraftVersionManifestMetadataItem, ModrinthProjectVersionsMetadataItem, ModrinthVersionMetadataItem}, manager::MetaLoadError}, BackendState};
+use crate::{content_provider::{ContentProvider, CurseForgeProvider, ModrinthProvider, ProviderFile}, download_manager::{DownloadError, DownloadRequest}, lockfile::Lockfile, metadata::{items::MinecraftVersionManifestMetadataItem, manager::MetaLoadError}, BackendState};
 
 #[derive(thiserror::Error, Debug)]
 pub enum ContentInstallError {
@@ -18,14 +16,8 @@ pub enum ContentInstallError {
     UnableToDetermineContentType(Arc<str>),
     #[error("Invalid filename: {0}")]
     InvalidFilename(Arc<str>),
-    #[error("Failed to download remote content")]
-    Reqwest(#[from] reqwest::Error),
-    #[error("Remote server returned non-200 status code: {0}")]
-    NotOK(StatusCode),
-    #[error("Downloaded file had the wrong size")]
-    WrongFilesize,
-    #[error("Downloaded file had the wrong hash")]
-    WrongHash,
+    #[error("Failed to download file:\n{0}")]
+    Download(#[from] DownloadError),
     #[error("Hash isn't a valid sha1 hash:\n{0}")]
     InvalidHash(Arc<str>),
     #[error("Failed to perform I/O operation:\n{0}")]
@@ -34,6 +26,12 @@ pub enum ContentInstallError {
     MetaLoadError(#[from] MetaLoadError),
     #[error("Mismatched project id for version {0}, expected {1} got {2}")]
     MismatchedProjectIdForVersion(Arc<str>, Arc<str>, Arc<str>),
+    #[error("CurseForge isn't configured, an api key or api url must be set")]
+    CurseForgeNotConfigured,
+    #[error("The author of {0} doesn't allow it to be downloaded by third-party launchers")]
+    CurseForgeDistributionDisallowed(Arc<str>),
+    #[error("CurseForge file {0} is missing a sha1 hash")]
+    MissingSha1(Arc<str>),
 }
 
 struct InstallFromContentLibrary {
@@ -46,7 +44,7 @@ struct InstallFromContentLibrary {
 }
 
 #[derive(Clone)]
-struct FilenameAndExtension {
+pub(crate) struct FilenameAndExtension {
     filename: Option<OsString>,
     extension: Option<OsString>,
 }
@@ -71,7 +69,13 @@ impl From<&Path> for FilenameAndExtension {
 
 impl BackendState {
     pub async fn install_content(&self, content: ContentInstall, modal_action: ModalAction) {
-        let semaphore = tokio::sync::Semaphore::new(8);
+        let release_channel = if let bridge::install::InstallTarget::Instance(id) = content.target
+            && let Some(instance) = self.instance_state.write().instances.get_mut(id)
+        {
+            instance.configuration.get().release_channel
+        } else {
+            ReleaseChannel::default()
+        };
 
         let mut tasks = Vec::new();
 
@@ -79,103 +83,20 @@ impl BackendState {
             tasks.push(async {
                 match content_file.download {
                     bridge::install::ContentDownload::Modrinth { ref project_id, ref version_id } => {
-                        let version = if let Some(version_id) = version_id {
-                            let version = self.meta.fetch(&ModrinthVersionMetadataItem(version_id.clone())).await?;
-                            Some(version)
-                        } else {
-                            let versions = self.meta.fetch(&ModrinthProjectVersionsMetadataItem(&ModrinthProjectVersionsRequest {
-                                project_id: project_id.clone(),
-                                game_versions: content.version_hint.clone().map(|v| [v].into()),
-                                loaders: None,
-                            })).await?;
-
-                            let modrinth_loader = content.loader_hint.as_modrinth_loader();
-                            let version = if modrinth_loader != ModrinthLoader::Unknown {
-                                versions.0.iter()
-                                    .find(|version| if let Some(loaders) = &version.loaders {
-                                        loaders.contains(&modrinth_loader)
-                                    } else {
-                                        false
-                                    })
-                                    .or(versions.0.first())
-                            } else {
-                                versions.0.first()
-                            };
-
-                            version.map(|v| Arc::new(v.clone()))
+                        let provider = ModrinthProvider { meta: &self.meta };
+                        let file = provider.find_file(project_id, version_id.as_ref(), content.loader_hint,
+                            content.version_hint.clone(), release_channel).await?;
+                        self.install_provider_file(file, content_file, &modal_action).await
+                    },
+                    bridge::install::ContentDownload::CurseForge { project_id, file_id } => {
+                        let Some(api) = self.curseforge_api().await else {
+                            return Err(ContentInstallError::CurseForgeNotConfigured);
                         };
 
-                        if let Some(version) = version {
-                            if &version.project_id != project_id {
-                                return Err(ContentInstallError::MismatchedProjectIdForVersion(
-                                    version.id.clone(),
-                                    project_id.clone(),
-                                    version.project_id.clone()
-                                ));
-                            }
-
-                            let install_file = version
-                                .files
-                                .iter()
-                                .find(|file| file.primary)
-                                .unwrap_or(version.files.first().unwrap());
-
-                            let url = &install_file.url;
-                            let sha1 = &install_file.hashes.sha1;
-                            let size = install_file.size;
-
-                            let Some(safe_filename) = SafePath::new(&install_file.filename) else {
-                                return Err(ContentInstallError::InvalidFilename(install_file.filename.clone()));
-                            };
-
-                            let (path, hash, mod_summary) = self.download_file_into_library(&modal_action,
-                                (&safe_filename).into(), url, sha1, size, &semaphore).await?;
-
-                            let install_path = match &content_file.path {
-                                ContentInstallPath::Raw(path) => path.clone(),
-                                ContentInstallPath::Safe(safe_path) => safe_path.to_path(Path::new("")).into(),
-                                ContentInstallPath::Automatic => {
-                                    let base = if let Some(mod_summary) = &mod_summary {
-                                        match mod_summary.extra {
-                                            ContentType::Fabric | ContentType::Forge | ContentType::NeoForge | ContentType::JavaModule | ContentType::ModrinthModpack { .. } => {
-                                                Path::new("mods")
-                                            },
-                                            ContentType::ResourcePack => {
-                                                Path::new("resourcepacks")
-                                            }
-                                        }
-                                    } else if let Some(loaders) = &version.loaders {
-                                        let mut base = None;
-                                        for loader in loaders.iter() {
-                                            base = loader.install_directory();
-                                            if base.is_some() {
-                                                break;
-                                            }
-                                        }
-                                        if let Some(base) = base {
-                                            Path::new(base)
-                                        } else {
-                                            return Err(ContentInstallError::UnableToDetermineContentType(install_file.filename.clone()))
-                                        }
-                                    } else {
-                                        return Err(ContentInstallError::UnableToDetermineContentType(install_file.filename.clone()))
-                                    };
-
-                                    safe_filename.to_path(base).into()
-                                },
-                            };
-
-                            Ok(InstallFromContentLibrary {
-                                from: path,
-                                replace: content_file.replace_old.clone(),
-                                hash,
-                                install_path,
-                                content_file: content_file.clone(),
-                                mod_summary
-                            })
-                        } else {
-                            Err(ContentInstallError::UnableToFindDependencyVersion)
-                        }
+                        let provider = CurseForgeProvider { meta: &self.meta, api };
+                        let file = provider.find_file(&project_id, file_id.as_ref(), content.loader_hint,
+                            content.version_hint.clone(), release_channel).await?;
+                        self.install_provider_file(file, content_file, &modal_action).await
                     },
                     bridge::install::ContentDownload::Url { ref url, ref sha1, size } => {
                         let name = match &content_file.path {
@@ -185,7 +106,7 @@ impl BackendState {
                         };
 
                         let (path, hash, mod_summary) = self.download_file_into_library(&modal_action,
-                            name, url, sha1, size, &semaphore).await?;
+                            name, url, sha1, size).await?;
 
                         let install_path = match &content_file.path {
                             ContentInstallPath::Raw(path) => path.clone(),
@@ -337,8 +258,53 @@ impl BackendState {
         }
     }
 
-    async fn download_file_into_library(&self, modal_action: &ModalAction, name: FilenameAndExtension, url: &Arc<str>, sha1: &Arc<str>, size: usize, semaphore: &tokio::sync::Semaphore) -> Result<(PathBuf, [u8; 20], Option<Arc<ContentSummary>>), ContentInstallError> {
-        let mut result = self.download_file_into_library_inner(modal_action, name, url, sha1, size, semaphore).await?;
+    async fn install_provider_file(&self, file: Option<ProviderFile>, content_file: &ContentInstallFile, modal_action: &ModalAction) -> Result<InstallFromContentLibrary, ContentInstallError> {
+        let Some(file) = file else {
+            return Err(ContentInstallError::UnableToFindDependencyVersion);
+        };
+
+        let Some(safe_filename) = SafePath::new(&file.filename) else {
+            return Err(ContentInstallError::InvalidFilename(file.filename.clone()));
+        };
+
+        let (path, hash, mod_summary) = self.download_file_into_library(modal_action,
+            (&safe_filename).into(), &file.url, &file.sha1, file.size).await?;
+
+        let install_path = match &content_file.path {
+            ContentInstallPath::Raw(path) => path.clone(),
+            ContentInstallPath::Safe(safe_path) => safe_path.to_path(Path::new("")).into(),
+            ContentInstallPath::Automatic => {
+                let base = if let Some(mod_summary) = &mod_summary {
+                    match mod_summary.extra {
+                        ContentType::Fabric | ContentType::Forge | ContentType::NeoForge | ContentType::JavaModule | ContentType::ModrinthModpack { .. } => {
+                            "mods"
+                        },
+                        ContentType::ResourcePack => {
+                            "resourcepacks"
+                        }
+                    }
+                } else if let Some(install_directory) = file.install_directory {
+                    install_directory
+                } else {
+                    return Err(ContentInstallError::UnableToDetermineContentType(file.filename.clone()))
+                };
+
+                safe_filename.to_path(Path::new(base)).into()
+            },
+        };
+
+        Ok(InstallFromContentLibrary {
+            from: path,
+            replace: content_file.replace_old.clone(),
+            hash,
+            install_path,
+            content_file: content_file.clone(),
+            mod_summary
+        })
+    }
+
+    async fn download_file_into_library(&self, modal_action: &ModalAction, name: FilenameAndExtension, url: &Arc<str>, sha1: &Arc<str>, size: usize) -> Result<(PathBuf, [u8; 20], Option<Arc<ContentSummary>>), ContentInstallError> {
+        let mut result = self.download_file_into_library_inner(modal_action, name, url, sha1, size).await?;
 
         if let Some(summary) = &result.2 {
             if let ContentType::ModrinthModpack { downloads, .. } = &summary.extra {
@@ -355,7 +321,7 @@ impl BackendState {
                     };
 
                     tasks.push(self.download_file_into_library_inner(modal_action, name,
-                        &download.downloads[0], &download.hashes.sha1, download.file_size, semaphore));
+                        &download.downloads[0], &download.hashes.sha1, download.file_size));
                 }
 
                 _ = futures::future::try_join_all(tasks).await;
@@ -366,7 +332,7 @@ impl BackendState {
         Ok(result)
     }
 
-    async fn download_file_into_library_inner(&self, modal_action: &ModalAction, name: FilenameAndExtension, url: &Arc<str>, sha1: &Arc<str>, size: usize, semaphore: &tokio::sync::Semaphore) -> Result<(PathBuf, [u8; 20], Option<Arc<ContentSummary>>), ContentInstallError> {
+    pub(crate) async fn download_file_into_library_inner(&self, modal_action: &ModalAction, name: FilenameAndExtension, url: &Arc<str>, sha1: &Arc<str>, size: usize) -> Result<(PathBuf, [u8; 20], Option<Arc<ContentSummary>>), ContentInstallError> {
         let mut expected_hash = [0u8; 20];
         let Ok(_) = hex::decode_to_slice(&**sha1, &mut expected_hash) else {
             log::warn!("Content install has invalid sha1: {}", sha1);
@@ -386,8 +352,6 @@ impl BackendState {
 
         let lockfile = Lockfile::create(path.with_added_extension("lock").into()).await;
 
-        let _permit = semaphore.acquire().await.unwrap();
-
         let file_name = name.filename.clone();
 
         let title = format!("Downloading {}", file_name.as_deref().map(|s| s.to_string_lossy()).unwrap_or(std::borrow::Cow::Borrowed("???")));
@@ -412,55 +376,17 @@ impl BackendState {
             return Ok((path, expected_hash, summary));
         }
 
-        let response = self.redirecting_http_client.get(&**url).send().await?;
-
-        if response.status() != StatusCode::OK {
-            return Err(ContentInstallError::NotOK(response.status()));
-        }
-
-        // Tokio doesn't have lock, so we use std temporarily to lock it
-        let file = std::fs::File::create(&path)?;
-        _ = file.lock();
-
-        let mut file = tokio::fs::File::from_std(file);
-
-        use futures::StreamExt;
-        let mut stream = response.bytes_stream();
-
-        let mut total_bytes = 0;
-
-        let mut hasher = Sha1::new();
-        while let Some(item) = stream.next().await {
-            let item = item?;
-
-            total_bytes += item.len();
-            tracker.add_count(item.len());
-            tracker.notify();
+        let result = self.downloads.download(DownloadRequest {
+            url,
+            path: &path,
+            sha1: Some(expected_hash),
+            size: Some(size),
+            track_bytes: true,
+        }, &tracker).await;
 
-            hasher.write_all(&item)?;
-            file.write_all(&item).await?;
-        }
-
-        tracker.set_finished(ProgressTrackerFinishType::Fast);
-
-        let actual_hash = hasher.finalize();
-
-        let wrong_hash = *actual_hash != expected_hash;
-        let wrong_size = total_bytes != size;
-
-        if wrong_hash || wrong_size {
-            let _ = file.set_len(0).await;
-            drop(file);
-            let _ = tokio::fs::remove_file(&path).await;
-
-            if wrong_hash {
-                return Err(ContentInstallError::WrongHash);
-            } else if wrong_size {
-                return Err(ContentInstallError::WrongFilesize);
-            } else {
-                unreachable!();
-            }
-        }
+        tracker.set_finished(ProgressTrackerFinishType::from_err(result.is_err()));
+        tracker.notify();
+        result?;
 
         drop(lockfile);
 
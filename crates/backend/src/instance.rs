@@ -1,5 +1,5 @@
 use std::{
-    collections::HashSet, ffi::OsStr, hash::{DefaultHasher, Hash, Hasher}, io::Read, path::Path, process::Child, sync::{
+    collections::HashSet, ffi::OsStr, hash::{DefaultHasher, Hash, Hasher}, io::Read, path::Path, sync::{
         atomic::Ordering, Arc
     }
 };
@@ -18,8 +18,9 @@ use strum::IntoEnumIterator;
 use thiserror::Error;
 
 use ustr::Ustr;
+use uuid::Uuid;
 
-use crate::{id_slab::{GetId, Id}, mod_metadata::ModMetadataManager, persistent::Persistent, BackendStateInstances, IoOrSerializationError};
+use crate::{id_slab::{GetId, Id}, mod_metadata::ModMetadataManager, persistent::Persistent, session::InstanceSession, BackendStateInstances, IoOrSerializationError};
 
 #[derive(Debug)]
 pub struct Instance {
@@ -31,7 +32,10 @@ pub struct Instance {
     pub name: Ustr,
     pub configuration: Persistent<InstanceConfiguration>,
 
-    pub child: Option<Child>,
+    pub sessions: Vec<InstanceSession>,
+    pub launching: bool,
+    /// Accounts of launches that haven't started the game yet
+    pub launching_accounts: Vec<Uuid>,
 
     pub watching_dot_minecraft: bool,
     pub watching_server_dat: bool,
@@ -101,7 +105,7 @@ impl ContentFolderState {
         }
 
         if let Some(ref current_path) = path {
-            if let Some(extension) = current_path.extension() && extension == "pandorachildstate" {
+            if let Some(extension) = current_path.extension() && (extension == "pandorachildstate" || extension == "pandorapinned") {
                 let mut new_path = current_path.to_path_buf();
                 new_path.set_extension("");
                 if let Some(file_name) = new_path.file_name() {
@@ -608,6 +612,7 @@ impl Instance {
                             enabled,
                             content_source: old_summary.content_source.clone(),
                             disabled_children: old_summary.disabled_children.clone(),
+                            pinned: old_summary.pinned,
                         });
                     }
 
@@ -654,7 +659,9 @@ impl Instance {
             name: path.file_name().unwrap().to_string_lossy().into_owned().into(),
             configuration: instance_info,
 
-            child: None,
+            sessions: Vec::new(),
+            launching: false,
+            launching_accounts: Vec::new(),
 
             watching_dot_minecraft: false,
             watching_server_dat: false,
@@ -718,9 +725,18 @@ impl Instance {
         self.configuration = new.configuration;
     }
 
+    pub fn finish_launching(&mut self, account: Uuid) {
+        if let Some(index) = self.launching_accounts.iter().position(|launching| *launching == account) {
+            self.launching_accounts.swap_remove(index);
+        }
+        self.launching = !self.launching_accounts.is_empty();
+    }
+
     pub fn status(&self) -> InstanceStatus {
-        if self.child.is_some() {
+        if !self.sessions.is_empty() {
             InstanceStatus::Running
+        } else if self.launching {
+            InstanceStatus::Launching
         } else {
             InstanceStatus::NotRunning
         }
@@ -737,6 +753,7 @@ impl Instance {
             dot_minecraft_folder: self.dot_minecraft_path.clone(),
             configuration: self.configuration.get().clone(),
             status,
+            sessions: self.sessions.iter().map(InstanceSession::summary).collect(),
         }
     }
 }
@@ -786,6 +803,7 @@ fn create_instance_content_summary(path: &Path, mod_metadata_manager: &Arc<ModMe
     };
 
     let disabled_children = read_disabled_children_for(path).unwrap_or_default();
+    let pinned = crate::pinned_state_path(path).map(|path| path.exists()).unwrap_or(false);
     let content_source = mod_metadata_manager.read_content_sources().get(&summary.hash).unwrap_or_default();
 
     let lowercase_search_keys = summary.id.clone().into_iter()
@@ -803,6 +821,7 @@ fn create_instance_content_summary(path: &Path, mod_metadata_manager: &Arc<ModMe
         enabled,
         content_source,
         disabled_children,
+        pinned,
     })
 }
 
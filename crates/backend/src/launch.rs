@@ -11,7 +11,7 @@ use rc_zip_sync::{ArchiveHandle, ReadZip};
 use regex::Regex;
 use rustc_hash::FxHashMap;
 use schema::{
-    assets_index::AssetsIndex, fabric_launch::FabricLaunch, forge::{ForgeInstallProfile, ForgeInstallProfileLegacy, ForgeSide, VersionFragment}, instance::InstanceConfiguration, java_runtime_component::{JavaRuntimeComponentFile, JavaRuntimeComponentManifest}, loader::Loader, maven::{MavenCoordinate, MavenMetadataXml}, version::{
+    assets_index::AssetsIndex, backend_config::EndpointService, fabric_launch::FabricLaunch, forge::{ForgeInstallProfile, ForgeInstallProfileLegacy, ForgeSide, VersionFragment}, instance::{InstanceConfiguration, InstanceKind}, java_runtime_component::{JavaRuntimeComponentFile, JavaRuntimeComponentManifest}, loader::Loader, maven::{MavenCoordinate, MavenMetadataXml}, version::{
         GameLibrary, GameLibraryArtifact, GameLibraryDownloads, GameLibraryExtractOptions, GameLogging, LaunchArgument, LaunchArgumentValue, MinecraftVersion, OsArch, OsName, PartialMinecraftVersion, Rule, RuleAction
     }, version_manifest::MinecraftVersionManifest
 };
@@ -20,14 +20,17 @@ use sha1::{Digest, Sha1};
 use ustr::Ustr;
 
 use crate::{
-    account::MinecraftLoginInfo, directories::LauncherDirectories, launch_wrapper, metadata::{items::{AssetsIndexMetadataItem, FabricLaunchMetadataItem, FabricLoaderManifestMetadataItem, ForgeInstallerMavenMetadataItem, MinecraftVersionManifestMetadataItem, MinecraftVersionMetadataItem, MojangJavaRuntimeComponentMetadataItem, MojangJavaRuntimesMetadataItem, NeoforgeInstallerMavenMetadataItem}, manager::{
+    account::{AccountKind, MinecraftLoginInfo}, cleanup::ReferencedFiles, directories::LauncherDirectories, download_manager::{DownloadError, DownloadManager, DownloadRequest}, endpoints::Endpoints, launch_wrapper, metadata::{items::{AssetsIndexMetadataItem, FabricLaunchMetadataItem, FabricLoaderManifestMetadataItem, ForgeInstallerMavenMetadataItem, MinecraftVersionManifestMetadataItem, MinecraftVersionMetadataItem, MojangJavaRuntimeComponentMetadataItem, MojangJavaRuntimesMetadataItem, NeoforgeInstallerMavenMetadataItem}, manager::{
         MetaLoadError, MetadataManager,
     }}
 };
 
+mod server;
+
 #[derive(Clone)]
 pub struct Launcher {
     meta: Arc<MetadataManager>,
+    downloads: Arc<DownloadManager>,
     directories: Arc<LauncherDirectories>,
     launch_wrapper: Arc<Path>,
     sender: FrontendHandle,
@@ -61,6 +64,42 @@ pub enum LaunchError {
     CancelledByUser,
     #[error("Loader supports the wrong version of Minecraft: {0}")]
     MismatchedLoaderVersions(Arc<str>),
+    #[error("Unable to run a dedicated server: {0}")]
+    UnsupportedServer(&'static str),
+}
+
+/// Files that existed on disk but didn't match their expected hash, and were redownloaded
+#[derive(Default, Debug)]
+pub struct CorruptedFiles(parking_lot::Mutex<Vec<PathBuf>>);
+
+impl CorruptedFiles {
+    /// Returns whether the file on disk is valid, recording it if it exists with the wrong hash
+    fn check(&self, path: &Path, hash_result: std::io::Result<bool>) -> bool {
+        match hash_result {
+            Ok(true) => true,
+            Ok(false) => {
+                log::warn!("File {:?} has the wrong hash, redownloading", path);
+                self.0.lock().push(path.to_path_buf());
+                false
+            },
+            Err(_) => false,
+        }
+    }
+
+    pub fn into_inner(self) -> Vec<PathBuf> {
+        self.0.into_inner()
+    }
+}
+
+/// Everything needed to build a `LaunchContext` that depends on the installed files
+struct PreparedLaunch {
+    version_info: Arc<MinecraftVersion>,
+    java_path: PathBuf,
+    natives_dir: PathBuf,
+    assets_index_name: String,
+    classpath: Vec<OsString>,
+    log_configuration: Option<OsString>,
+    rule_context: LaunchRuleContext,
 }
 
 #[derive(PartialEq, Eq)]
@@ -70,10 +109,11 @@ pub enum AddVanillaJar {
 }
 
 impl Launcher {
-    pub fn new(meta: Arc<MetadataManager>, directories: Arc<LauncherDirectories>, sender: FrontendHandle) -> Self {
+    pub fn new(meta: Arc<MetadataManager>, downloads: Arc<DownloadManager>, directories: Arc<LauncherDirectories>, sender: FrontendHandle) -> Self {
         let launch_wrapper = launch_wrapper::create_wrapper(&directories.temp_dir).into();
         Self {
             meta,
+            downloads,
             directories,
             launch_wrapper,
             sender,
@@ -95,10 +135,77 @@ impl Launcher {
 
         launch_tracker.set_total(6);
 
+        let prepared = self.prepare_launch(http_client, &dot_minecraft_path, &instance_info, quick_play, launch_tracker, modal_action).await?;
+
+        let launch_context = LaunchContext {
+            launch_wrapper_path: self.launch_wrapper.clone(),
+            java_path: prepared.java_path,
+            natives_dir: prepared.natives_dir,
+            libraries_dir: self.directories.libraries_dir.clone(),
+            game_dir: dot_minecraft_path,
+            configuration: instance_info,
+            assets_root: self.directories.assets_root_dir.clone(),
+            temp_dir: self.directories.temp_dir.clone(),
+            assets_index_name: prepared.assets_index_name,
+            classpath: prepared.classpath,
+            log_configuration: prepared.log_configuration,
+            rule_context: prepared.rule_context,
+            login_info,
+            add_mods
+        };
+
+        if modal_action.has_requested_cancel() {
+            self.sender.send(MessageToFrontend::CloseModal);
+            return Err(LaunchError::CancelledByUser);
+        }
+
+        log::info!("Launching game process");
+        let child = launch_context.launch(&prepared.version_info)?;
+
+        launch_tracker.add_count(1);
+
+        Ok(child)
+    }
+
+    /// Runs every step of launching `instance_info` except starting the game, so that the version, libraries,
+    /// assets and java runtime are installed ahead of time and the instance can later be launched offline
+    pub async fn prepare(
+        &self,
+        http_client: &reqwest::Client,
+        dot_minecraft_path: Arc<Path>,
+        instance_info: &InstanceConfiguration,
+        prepare_tracker: &ProgressTracker,
+        modal_action: &ModalAction,
+    ) -> Result<(), LaunchError> {
+        log::info!("Preparing {:?}", dot_minecraft_path);
+
+        if instance_info.kind == InstanceKind::Server {
+            prepare_tracker.set_total(3);
+            self.prepare_server_launch(http_client, instance_info, &CorruptedFiles::default(), prepare_tracker, modal_action).await?;
+            return Ok(());
+        }
+
+        prepare_tracker.set_total(5);
+
+        self.prepare_launch(http_client, &dot_minecraft_path, instance_info, None, prepare_tracker, modal_action).await?;
+        Ok(())
+    }
+
+    async fn prepare_launch(
+        &self,
+        http_client: &reqwest::Client,
+        dot_minecraft_path: &Arc<Path>,
+        instance_info: &InstanceConfiguration,
+        quick_play: Option<QuickPlayLaunch>,
+        launch_tracker: &ProgressTracker,
+        modal_action: &ModalAction,
+    ) -> Result<PreparedLaunch, LaunchError> {
         log::debug!("Creating launch version");
 
+        let corrupted_files = CorruptedFiles::default();
+
         let (version_info, add_vanilla_jar) = tokio::select! {
-            result = self.create_launch_version(http_client, &modal_action.trackers, launch_tracker, &instance_info) => result?,
+            result = self.create_launch_version(http_client, &modal_action.trackers, launch_tracker, instance_info, &corrupted_files) => result?,
             _ = modal_action.request_cancel.cancelled() => {
                 self.sender.send(MessageToFrontend::CloseModal);
                 return Err(LaunchError::CancelledByUser);
@@ -108,7 +215,7 @@ impl Launcher {
         launch_tracker.add_count(1);
         launch_tracker.notify();
 
-        let _ = std::fs::create_dir_all(&dot_minecraft_path);
+        let _ = std::fs::create_dir_all(dot_minecraft_path);
 
         let launch_rule_context = LaunchRuleContext {
             is_demo_user: false,
@@ -136,16 +243,16 @@ impl Launcher {
 
         let mojang_java_binary_future = self.load_mojang_java_binary(
             &self.meta,
-            http_client,
-            &instance_info,
+            instance_info,
             &version_info,
             &modal_action.trackers,
             launch_tracker,
+            &corrupted_files,
         );
         let load_assets_future =
-            self.load_assets(&self.meta, http_client, &dot_minecraft_path, &version_info, &modal_action.trackers, launch_tracker);
+            self.load_assets(&self.meta, dot_minecraft_path, &version_info, &modal_action.trackers, launch_tracker, &corrupted_files);
         let load_libraries_future =
-            self.load_libraries(http_client, &artifacts, &modal_action.trackers, launch_tracker);
+            self.load_libraries(&artifacts, &modal_action.trackers, launch_tracker, &corrupted_files);
         let load_log_configuration = self.load_log_configuration(http_client, version_info.logging.as_ref());
 
         log::debug!("Loading java, assets, libraries and log configuration");
@@ -214,34 +321,240 @@ impl Launcher {
             }
         }
 
-        let launch_context = LaunchContext {
-            launch_wrapper_path: self.launch_wrapper.clone(),
+        Ok(PreparedLaunch {
+            version_info,
             java_path,
             natives_dir,
-            libraries_dir: self.directories.libraries_dir.clone(),
-            game_dir: dot_minecraft_path,
-            configuration: instance_info,
-            assets_root: self.directories.assets_root_dir.clone(),
-            temp_dir: self.directories.temp_dir.clone(),
             assets_index_name,
             classpath,
             log_configuration,
             rule_context: launch_rule_context,
-            login_info,
-            add_mods
+        })
+    }
+
+    /// Re-hashes the client jar, libraries, assets and java runtime used by `instance_info`,
+    /// redownloading any that are missing or corrupted. Returns the files that were corrupted.
+    pub async fn verify(
+        &self,
+        http_client: &reqwest::Client,
+        dot_minecraft_path: Arc<Path>,
+        instance_info: &InstanceConfiguration,
+        verify_tracker: &ProgressTracker,
+        modal_action: &ModalAction,
+    ) -> Result<Vec<PathBuf>, LaunchError> {
+        if instance_info.loader == Loader::Unknown {
+            return Ok(Vec::new());
+        }
+
+        verify_tracker.set_total(4);
+        verify_tracker.notify();
+
+        let corrupted_files = CorruptedFiles::default();
+
+        if instance_info.kind == InstanceKind::Server {
+            self.prepare_server_launch(http_client, instance_info, &corrupted_files, verify_tracker, modal_action).await?;
+            return Ok(corrupted_files.into_inner());
+        }
+
+        let (version_info, add_vanilla_jar) = tokio::select! {
+            result = self.create_launch_version(http_client, &modal_action.trackers, verify_tracker, instance_info, &corrupted_files) => result?,
+            _ = modal_action.request_cancel.cancelled() => return Err(LaunchError::CancelledByUser),
         };
 
-        if modal_action.has_requested_cancel() {
-            self.sender.send(MessageToFrontend::CloseModal);
-            return Err(LaunchError::CancelledByUser);
+        verify_tracker.add_count(1);
+        verify_tracker.notify();
+
+        let launch_rule_context = LaunchRuleContext {
+            is_demo_user: false,
+            custom_resolution: None,
+            quick_play: None,
+        };
+
+        let mut artifacts = Vec::new();
+        let mut natives_to_extract = HashMap::new();
+        launch_rule_context.collect_libraries(&version_info.libraries, &mut artifacts, &mut natives_to_extract);
+
+        if add_vanilla_jar == AddVanillaJar::Yes {
+            let client_download = &version_info.downloads.client;
+            artifacts.push(GameLibraryArtifact {
+                path: format!("net/minecraft/{0}/minecraft-client-{0}.jar", instance_info.minecraft_version).into(),
+                sha1: Some(client_download.sha1),
+                size: Some(client_download.size),
+                url: client_download.url,
+            });
         }
 
-        log::info!("Launching game process");
-        let child = launch_context.launch(&version_info)?;
+        let joined = futures::future::try_join3(
+            self.load_mojang_java_binary(&self.meta, instance_info, &version_info,
+                &modal_action.trackers, verify_tracker, &corrupted_files).map_err(LaunchError::from),
+            self.load_assets(&self.meta, &dot_minecraft_path, &version_info,
+                &modal_action.trackers, verify_tracker, &corrupted_files).map_err(LaunchError::from),
+            self.load_libraries(&artifacts, &modal_action.trackers, verify_tracker, &corrupted_files).map_err(LaunchError::from),
+        );
 
-        launch_tracker.add_count(1);
+        tokio::select! {
+            result = joined => {
+                result?;
+            },
+            _ = modal_action.request_cancel.cancelled() => return Err(LaunchError::CancelledByUser),
+        }
 
-        Ok(child)
+        Ok(corrupted_files.into_inner())
+    }
+
+    /// Collects the libraries, assets, runtimes and natives that launching with `instance_info` would use.
+    /// Only metadata is fetched, nothing is downloaded or installed.
+    pub async fn collect_referenced_files(
+        &self,
+        http_client: &reqwest::Client,
+        instance_info: &InstanceConfiguration,
+        referenced: &mut ReferencedFiles,
+    ) -> Result<(), LaunchError> {
+        let version_info = match instance_info.loader {
+            Loader::Vanilla | Loader::Fabric => {
+                let tracker = ProgressTracker::new(Arc::from("Resolving version"), self.sender.clone());
+                let (version_info, _) = self.create_launch_version(http_client, &ProgressTrackers::default(), &tracker, instance_info, &CorruptedFiles::default()).await?;
+                version_info
+            },
+            Loader::Forge | Loader::NeoForge => {
+                self.collect_forgelike_referenced_files(instance_info, referenced).await?
+            },
+            Loader::Unknown => return Ok(()),
+        };
+
+        let libraries_dir = &self.directories.libraries_dir;
+        referenced.add_library(libraries_dir, &format!("net/minecraft/{0}/minecraft-client-{0}.jar", instance_info.minecraft_version));
+        if instance_info.kind == InstanceKind::Server {
+            referenced.add_library(libraries_dir, &format!("net/minecraft/{0}/minecraft-server-{0}.jar", instance_info.minecraft_version));
+
+            // The resolved version only has Fabric's client libraries, servers are started with the server ones
+            if instance_info.loader == Loader::Fabric {
+                let fabric_launch = self.meta.fetch(&FabricLaunchMetadataItem {
+                    minecraft_version: instance_info.minecraft_version,
+                    loader_version: self.find_fabric_loader_version(instance_info).await?,
+                }).await?;
+                for library in &fabric_launch.launcher_meta.libraries.server {
+                    referenced.add_library(libraries_dir, &MavenCoordinate::create(&library.name).artifact_path());
+                }
+            }
+        }
+
+        let launch_rule_context = LaunchRuleContext {
+            is_demo_user: false,
+            custom_resolution: None,
+            quick_play: None,
+        };
+
+        let mut artifacts = Vec::new();
+        let mut natives_to_extract = HashMap::new();
+        launch_rule_context.collect_libraries(&version_info.libraries, &mut artifacts, &mut natives_to_extract);
+
+        referenced.natives_dirs.insert(calculate_natives_dirname(&artifacts));
+        for artifact in &artifacts {
+            referenced.add_library(libraries_dir, artifact.path.as_str());
+        }
+
+        let assets_index = self.meta.fetch(&AssetsIndexMetadataItem {
+            url: version_info.asset_index.url,
+            cache: self.directories.assets_index_dir.join(format!("{}.json", version_info.assets)).into(),
+            hash: version_info.asset_index.sha1,
+        }).await?;
+
+        referenced.asset_indexes.insert(version_info.assets);
+        referenced.asset_objects.extend(assets_index.objects.values().map(|object| object.hash));
+
+        let jre_component = if let Some(java_version) = &version_info.java_version {
+            java_version.component
+        } else {
+            "jre-legacy".into()
+        };
+        referenced.runtime_components.insert(jre_component);
+
+        Ok(())
+    }
+
+    async fn collect_forgelike_referenced_files(
+        &self,
+        instance_info: &InstanceConfiguration,
+        referenced: &mut ReferencedFiles,
+    ) -> Result<Arc<MinecraftVersion>, LaunchError> {
+        let minecraft_versions = self.meta.fetch(&MinecraftVersionManifestMetadataItem).await?;
+
+        let Some(version_link) = minecraft_versions.versions.iter().find(|v| v.id == instance_info.minecraft_version) else {
+            return Err(LaunchError::CantFindVersion(instance_info.minecraft_version.as_str()));
+        };
+
+        let base_version = self.meta.fetch(&MinecraftVersionMetadataItem(version_link)).await?;
+
+        let Some((loader_version, installer)) = self.find_forgelike_installer(instance_info).await? else {
+            return Ok(base_version);
+        };
+
+        let libraries_dir = &self.directories.libraries_dir;
+
+        let installer_path = installer.path.replace("{0}", &loader_version);
+        referenced.add_library(libraries_dir, &installer_path);
+
+        // The installer has never been downloaded, so nothing else from it can be on disk
+        let Ok(installer_file) = std::fs::File::open(libraries_dir.join(&installer_path)) else {
+            return Ok(base_version);
+        };
+
+        let installer_zip = installer_file.read_zip()?;
+
+        let install_profile = match read_forgelike_install_profile(&installer_zip)? {
+            ForgelikeInstallProfile::Modern(install_profile) => install_profile,
+            ForgelikeInstallProfile::Legacy(install_profile_legacy) => {
+                let forge_path = MavenCoordinate::create(&install_profile_legacy.install.path).artifact_path();
+                referenced.add_library(libraries_dir, &forge_path);
+
+                let version = install_profile_legacy.version_info.into_partial_version(ForgeSide::Client);
+                return Ok(Arc::new(version.apply_to(&base_version)));
+            },
+        };
+
+        let version = read_forgelike_version(&installer_zip, &install_profile)?;
+
+        for library in install_profile.libraries.iter() {
+            if let Some(artifact) = &library.downloads.artifact {
+                referenced.add_library(libraries_dir, artifact.path.as_str());
+            }
+        }
+
+        let side = instance_info.kind.forge_side();
+
+        // Outputs of the post processors
+        for sided_data in install_profile.data.values() {
+            let value = match side {
+                ForgeSide::Client => &*sided_data.client,
+                ForgeSide::Server => &*sided_data.server,
+            };
+            if value.starts_with('[') && value.ends_with(']') {
+                let artifact = MavenCoordinate::create(&value[1..value.len()-1]);
+                referenced.add_library(libraries_dir, &artifact.artifact_path());
+            }
+        }
+
+        for processor in install_profile.processors.iter() {
+            if let Some(sides) = &processor.sides {
+                if !sides.contains(&side) {
+                    continue;
+                }
+            }
+
+            referenced.add_library(libraries_dir, &MavenCoordinate::create(&processor.jar).artifact_path());
+            for classpath in processor.classpath.iter() {
+                referenced.add_library(libraries_dir, &MavenCoordinate::create(classpath).artifact_path());
+            }
+            for arg in processor.args.iter() {
+                if arg.starts_with('[') && arg.ends_with(']') {
+                    let artifact = MavenCoordinate::create(&arg[1..arg.len()-1]);
+                    referenced.add_library(libraries_dir, &artifact.artifact_path());
+                }
+            }
+        }
+
+        Ok(Arc::new(version.apply_to(&base_version)))
     }
 
     async fn create_launch_version(
@@ -250,6 +563,7 @@ impl Launcher {
         progress_trackers: &ProgressTrackers,
         launch_tracker: &ProgressTracker,
         instance_info: &InstanceConfiguration,
+        corrupted_files: &CorruptedFiles,
     ) -> Result<(Arc<MinecraftVersion>, AddVanillaJar), LaunchError> {
         match instance_info.loader {
             Loader::Vanilla => {
@@ -270,19 +584,7 @@ impl Launcher {
             Loader::Fabric => {
                 let versions = self.meta.fetch(&MinecraftVersionManifestMetadataItem).map_err(LaunchError::from);
 
-                let fabric_loader_version = async move {
-                    if let Some(preferred_version) = instance_info.preferred_loader_version {
-                        Ok(preferred_version)
-                    } else {
-                        let manifest = self.meta.fetch(&FabricLoaderManifestMetadataItem).map_err(LaunchError::from).await?;
-
-                        let mut latest_loader_version = manifest.0.iter().find(|v| v.stable);
-                        if latest_loader_version.is_none() {
-                            latest_loader_version = manifest.0.first();
-                        }
-                        Ok(latest_loader_version.unwrap().version)
-                    }
-                };
+                let fabric_loader_version = self.find_fabric_loader_version(instance_info);
 
                 launch_tracker.add_total(4);
                 launch_tracker.notify();
@@ -335,7 +637,7 @@ impl Launcher {
                     version.libraries.push(GameLibrary {
                         downloads: GameLibraryDownloads {
                             artifact: Some(GameLibraryArtifact {
-                                url: format!("https://maven.fabricmc.net/{}", &artifact_path).into(),
+                                url: self.meta.endpoints.url(EndpointService::FabricMaven, &artifact_path).into(),
                                 path: artifact_path.into(),
                                 sha1: None,
                                 size: None,
@@ -355,7 +657,7 @@ impl Launcher {
                     version.libraries.push(GameLibrary {
                         downloads: GameLibraryDownloads {
                             artifact: Some(GameLibraryArtifact {
-                                url: format!("https://maven.fabricmc.net/{}", &artifact_path).into(),
+                                url: self.meta.endpoints.url(EndpointService::FabricMaven, &artifact_path).into(),
                                 path: artifact_path.into(),
                                 sha1: None,
                                 size: None,
@@ -404,15 +706,8 @@ impl Launcher {
                     self.meta.fetch(&ForgeInstallerMavenMetadataItem)
                 ).await?;
 
-                self.create_forgelike_launch_version(http_client, progress_trackers, launch_tracker, instance_info,
-                    minecraft_versions,
-                    &loader_versions.0,
-                    "https://maven.minecraftforge.net/net/minecraftforge/forge/{0}/forge-{0}-installer.jar.sha1",
-                    "net/minecraftforge/forge/{0}/forge-{0}-installer.jar",
-                    "https://maven.minecraftforge.net/net/minecraftforge/forge/{0}/forge-{0}-installer.jar",
-                    true,
-                    false
-                ).await
+                self.create_forgelike_launch_version(http_client, progress_trackers, launch_tracker, instance_info, corrupted_files,
+                    minecraft_versions, &loader_versions.0, &FORGE_INSTALLER).await
             },
             Loader::NeoForge => {
                 launch_tracker.add_total(7);
@@ -424,33 +719,37 @@ impl Launcher {
                     self.meta.fetch(&NeoforgeInstallerMavenMetadataItem)
                 ).await?;
 
-                self.create_forgelike_launch_version(http_client, progress_trackers, launch_tracker, instance_info,
-                    minecraft_versions,
-                    &loader_versions.0,
-                    "https://maven.neoforged.net/releases/net/neoforged/neoforge/{0}/neoforge-{0}-installer.jar.sha1",
-                    "net/neoforged/neoforge/{0}/neoforge-{0}-installer.jar",
-                    "https://maven.neoforged.net/releases/net/neoforged/neoforge/{0}/neoforge-{0}-installer.jar",
-                    false,
-                    true
-                ).await
+                self.create_forgelike_launch_version(http_client, progress_trackers, launch_tracker, instance_info, corrupted_files,
+                    minecraft_versions, &loader_versions.0, &NEOFORGE_INSTALLER).await
             },
             Loader::Unknown => todo!(),
         }
     }
 
+    async fn find_fabric_loader_version(&self, instance_info: &InstanceConfiguration) -> Result<Ustr, LaunchError> {
+        if let Some(preferred_version) = instance_info.preferred_loader_version {
+            Ok(preferred_version)
+        } else {
+            let manifest = self.meta.fetch(&FabricLoaderManifestMetadataItem).await?;
+
+            let mut latest_loader_version = manifest.0.iter().find(|v| v.stable);
+            if latest_loader_version.is_none() {
+                latest_loader_version = manifest.0.first();
+            }
+            Ok(latest_loader_version.unwrap().version)
+        }
+    }
+
     async fn create_forgelike_launch_version(
         &self,
         http_client: &reqwest::Client,
         progress_trackers: &ProgressTrackers,
         launch_tracker: &ProgressTracker,
         instance_info: &InstanceConfiguration,
+        corrupted_files: &CorruptedFiles,
         minecraft_versions: Arc<MinecraftVersionManifest>,
         loader_versions: &[Ustr],
-        installer_hash_url: &'static str,
-        installer_path: &'static str,
-        installer_url: &'static str,
-        check_mirrors: bool,
-        neoforge_versioning: bool,
+        installer: &ForgelikeInstaller,
     ) -> Result<(Arc<MinecraftVersion>, AddVanillaJar), LaunchError> {
         launch_tracker.add_count(1);
         launch_tracker.notify();
@@ -459,115 +758,97 @@ impl Launcher {
             return Err(LaunchError::CantFindVersion(instance_info.minecraft_version.as_str()));
         };
 
-        let loader_version = if let Some(preferred_loader_version) = instance_info.preferred_loader_version {
-            preferred_loader_version
-        } else {
-            let mut minecraft_version_parts = VersionFragment::string_to_parts(instance_info.minecraft_version.as_str());
-            if neoforge_versioning {
-                // 1.21.5 -> 21.5
-                // 25w14craftmine -> 0.25w14craftmine
-                // 1.21 -> 21.0
-                // 26.1 -> 26.1.0
-                if minecraft_version_parts[0] == VersionFragment::String("25w14craftmine".into()) {
-                    minecraft_version_parts.insert(0, VersionFragment::Number(0))
-                } else {
-                    if minecraft_version_parts.len() < 3 {
-                        minecraft_version_parts.push(VersionFragment::Number(0))
-                    }
-                    if minecraft_version_parts[0] == VersionFragment::Number(1) {
-                        minecraft_version_parts.remove(0);
-                    }
-                }
-            }
+        let Some(loader_version) = find_forgelike_loader_version(instance_info, loader_versions, installer.neoforge_versioning) else {
+            return Err(LaunchError::CantFindVersion(instance_info.minecraft_version.as_str()));
+        };
 
-            let mut latest_loader_version = None;
-            let mut latest_loader_version_parts = Vec::new();
-            for version in loader_versions.iter() {
-                let parts = VersionFragment::string_to_parts(version);
+        let base_version = self.meta.fetch(&MinecraftVersionMetadataItem(version_link)).await?;
 
-                if parts.starts_with(&minecraft_version_parts) {
-                    if parts > latest_loader_version_parts {
-                        latest_loader_version_parts = parts;
-                        latest_loader_version = Some(version.clone());
-                    }
-                }
-            }
-            let Some(latest_loader_version) = latest_loader_version else {
-                return Err(LaunchError::CantFindVersion(instance_info.minecraft_version.as_str()));
-            };
+        launch_tracker.add_count(1);
+        launch_tracker.notify();
+
+        let files = self.load_forgelike_installer(ForgeSide::Client, installer, &loader_version, &base_version, http_client,
+            progress_trackers, launch_tracker, instance_info, corrupted_files).await?;
+
+        let installer_file = std::fs::File::open(&files.installer_path)?;
+
+        let installer_zip = installer_file.read_zip()?;
+
+        match read_forgelike_install_profile(&installer_zip)? {
+            ForgelikeInstallProfile::Modern(install_profile) => {
+                self.create_forgelike_install_version_modern(install_profile, installer_zip,
+                    &files.installer_path, &files.minecraft_jar_path, &files.java_path, base_version, http_client,
+                    progress_trackers, launch_tracker, instance_info, corrupted_files, installer.check_mirrors).await
+            },
+            ForgelikeInstallProfile::Legacy(install_profile_legacy) => {
+                launch_tracker.add_count(1);
+                self.create_forgelike_install_version_legacy(install_profile_legacy, installer_zip,
+                    base_version, http_client, progress_trackers, launch_tracker, instance_info, corrupted_files, installer.check_mirrors).await
+            },
+        }
+    }
 
-            latest_loader_version
+    /// Finds the loader version and installer to use for a Forge or NeoForge instance
+    async fn find_forgelike_installer(&self, instance_info: &InstanceConfiguration) -> Result<Option<(Ustr, &'static ForgelikeInstaller)>, LaunchError> {
+        let (loader_version, installer) = match instance_info.loader {
+            Loader::Forge => {
+                let loader_versions = self.meta.fetch(&ForgeInstallerMavenMetadataItem).await?;
+                (find_forgelike_loader_version(instance_info, &loader_versions.0, FORGE_INSTALLER.neoforge_versioning), &FORGE_INSTALLER)
+            },
+            Loader::NeoForge => {
+                let loader_versions = self.meta.fetch(&NeoforgeInstallerMavenMetadataItem).await?;
+                (find_forgelike_loader_version(instance_info, &loader_versions.0, NEOFORGE_INSTALLER.neoforge_versioning), &NEOFORGE_INSTALLER)
+            },
+            _ => return Ok(None),
         };
+        Ok(loader_version.map(|loader_version| (loader_version, installer)))
+    }
 
-        // Download base Minecraft version and neoforge installer hash
-        let installer_hash_url = installer_hash_url.replace("{0}", &loader_version);
-        let (base_version, installer_sha1) = futures::future::join(
-            self.meta.fetch(&MinecraftVersionMetadataItem(version_link)),
-            Self::download_sha1(http_client, &installer_hash_url)
-        ).await;
-        let base_version = base_version?;
+    /// Downloads the installer, the game jar for `side` and the java runtime used to run the installer's post processors
+    async fn load_forgelike_installer(
+        &self,
+        side: ForgeSide,
+        installer: &ForgelikeInstaller,
+        loader_version: &str,
+        base_version: &MinecraftVersion,
+        http_client: &reqwest::Client,
+        progress_trackers: &ProgressTrackers,
+        launch_tracker: &ProgressTracker,
+        instance_info: &InstanceConfiguration,
+        corrupted_files: &CorruptedFiles,
+    ) -> Result<ForgelikeInstallerFiles, LaunchError> {
+        let installer_path = installer.path.replace("{0}", loader_version);
+        let installer_url = self.meta.endpoints.url(installer.maven, &installer_path);
 
-        launch_tracker.add_count(1);
-        launch_tracker.notify();
+        // When offline the hash is skipped and an installer already on disk is used as-is
+        let installer_sha1 = if self.meta.connectivity.is_offline() {
+            None
+        } else {
+            Self::download_sha1(http_client, &self.meta.endpoints, &format!("{installer_url}.sha1")).await
+        };
 
-        // Download installer jar as an artifact
         let artifacts = &[
             GameLibraryArtifact {
-                path: installer_path.replace("{0}", &loader_version).into(),
+                path: installer_path.into(),
                 sha1: installer_sha1,
                 size: None,
-                url: installer_url.replace("{0}", &loader_version).into(),
-            },
-            GameLibraryArtifact {
-                path: format!("net/minecraft/{0}/minecraft-client-{0}.jar", instance_info.minecraft_version).into(),
-                sha1: Some(base_version.downloads.client.sha1),
-                size: Some(base_version.downloads.client.size),
-                url: base_version.downloads.client.url,
+                url: installer_url.into(),
             },
+            minecraft_jar_artifact(side, instance_info, base_version)?,
         ];
 
-        let mojang_java_binary_future = self.load_mojang_java_binary(
-            &self.meta,
-            http_client,
-            instance_info,
-            &base_version,
-            progress_trackers,
-            launch_tracker,
-        );
-        let load_installer_library_future = self.load_libraries(http_client, artifacts, progress_trackers, launch_tracker);
-
-        let (artifact_load_result, java_load_result) = futures::future::try_join(
-            load_installer_library_future.map_err(LaunchError::from),
-            mojang_java_binary_future.map_err(LaunchError::from),
+        let (artifact_load_result, java_path) = futures::future::try_join(
+            self.load_libraries(artifacts, progress_trackers, launch_tracker, corrupted_files).map_err(LaunchError::from),
+            self.load_mojang_java_binary(&self.meta, instance_info, base_version, progress_trackers, launch_tracker, corrupted_files)
+                .map_err(LaunchError::from),
         ).await?;
-        let installer_path = &artifact_load_result[0].1;
-        let minecraft_jar_path = &artifact_load_result[1].1;
-
-        let installer_file = std::fs::File::open(installer_path)?;
+        let mut artifact_paths = artifact_load_result.into_iter().map(|(_, path)| path);
 
-        let installer_zip = installer_file.read_zip()?;
-
-        // Read install profile
-        let Some(install_profile_file) = installer_zip.by_name("install_profile.json") else {
-            return Err(LaunchError::MissingFileInZipError(Cow::Borrowed("install_profile.json")));
-        };
-
-        let install_profile_bytes = install_profile_file.bytes()?;
-
-        let install_profile = serde_json::from_slice(&install_profile_bytes);
-
-        if install_profile.is_err() {
-            if let Ok(install_profile_legacy) = serde_json::from_slice(&install_profile_bytes) {
-                launch_tracker.add_count(1);
-                let ret = self.create_forgelike_install_version_legacy(install_profile_legacy, installer_zip,
-                    base_version, http_client, progress_trackers, launch_tracker, instance_info, check_mirrors).await;
-                return ret;
-            }
-        }
-
-        self.create_forgelike_install_version_modern(install_profile?, installer_zip,
-            installer_path, minecraft_jar_path, &java_load_result, base_version, http_client,
-            progress_trackers, launch_tracker, instance_info, check_mirrors).await
+        Ok(ForgelikeInstallerFiles {
+            installer_path: artifact_paths.next().unwrap(),
+            minecraft_jar_path: artifact_paths.next().unwrap(),
+            java_path,
+        })
     }
 
     async fn create_forgelike_install_version_modern(
@@ -582,25 +863,66 @@ impl Launcher {
         progress_trackers: &ProgressTrackers,
         launch_tracker: &ProgressTracker,
         instance_info: &InstanceConfiguration,
+        corrupted_files: &CorruptedFiles,
         check_mirrors: bool,
     ) -> Result<(Arc<MinecraftVersion>, AddVanillaJar), LaunchError> {
         if &*install_profile.minecraft != instance_info.minecraft_version.as_str() {
             return Err(LaunchError::MismatchedLoaderVersions(install_profile.minecraft.clone()));
         }
 
-        // Read partial minecraft version
-        let mut version_file_name = &*install_profile.json;
-        if version_file_name.starts_with('/') {
-            version_file_name = &version_file_name[1..];
-        }
-        let Some(version_file) = installer_zip.by_name(&version_file_name) else {
-            return Err(LaunchError::MissingFileInZipError(Cow::Owned(version_file_name.to_string())));
-        };
-        let version: PartialMinecraftVersion = serde_json::from_slice(&version_file.bytes()?)?;
+        let version = read_forgelike_version(&installer_zip, &install_profile)?;
+
+        self.install_forgelike_side(ForgeSide::Client, &install_profile, installer_zip, installer_path, minecraft_jar_path,
+            java_path, http_client, progress_trackers, launch_tracker, corrupted_files, check_mirrors).await?;
+
+        Ok((Arc::new(version.apply_to(&base_version)), AddVanillaJar::No))
+    }
+
+    /// Downloads the libraries of a modern installer and runs its post processors for `side`
+    async fn install_forgelike_side(
+        &self,
+        side: ForgeSide,
+        install_profile: &ForgeInstallProfile,
+        installer_zip: ArchiveHandle<'_, File>,
+        installer_path: &PathBuf,
+        minecraft_jar_path: &PathBuf,
+        java_path: &PathBuf,
+        http_client: &reqwest::Client,
+        progress_trackers: &ProgressTrackers,
+        launch_tracker: &ProgressTracker,
+        corrupted_files: &CorruptedFiles,
+        check_mirrors: bool,
+    ) -> Result<(), LaunchError> {
+        // Every run extracts the installer data into its own directory, so concurrent installs can't overwrite each other's files
+        let forge_temp = self.directories.temp_dir.join(format!("forge_installer-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&forge_temp)?;
+
+        let result = self.install_forgelike_side_in(&forge_temp, side, install_profile, installer_zip, installer_path,
+            minecraft_jar_path, java_path, http_client, progress_trackers, launch_tracker, corrupted_files, check_mirrors).await;
 
+        _ = std::fs::remove_dir_all(&forge_temp);
+
+        result
+    }
+
+    async fn install_forgelike_side_in(
+        &self,
+        forge_temp: &Path,
+        side: ForgeSide,
+        install_profile: &ForgeInstallProfile,
+        installer_zip: ArchiveHandle<'_, File>,
+        installer_path: &PathBuf,
+        minecraft_jar_path: &PathBuf,
+        java_path: &PathBuf,
+        http_client: &reqwest::Client,
+        progress_trackers: &ProgressTrackers,
+        launch_tracker: &ProgressTracker,
+        corrupted_files: &CorruptedFiles,
+        check_mirrors: bool,
+    ) -> Result<(), LaunchError> {
         // Download mirror list
-        let mirror = if check_mirrors {
-            Self::download_random_mirror(http_client, &install_profile.mirror_list).await
+        let mirror = if check_mirrors && !self.meta.connectivity.is_offline() {
+            Self::download_random_mirror(http_client, &self.meta.endpoints, &install_profile.mirror_list).await
         } else {
             None
         };
@@ -619,14 +941,15 @@ impl Launcher {
             Some(artifact)
         }).collect::<Vec<_>>();
 
-        self.load_libraries(http_client, &libraries, progress_trackers, launch_tracker).await?;
-
-        let forge_temp = self.directories.temp_dir.join("forge_installer");
+        self.load_libraries(&libraries, progress_trackers, launch_tracker, corrupted_files).await?;
 
         let mut data = FxHashMap::default();
 
-        for (key, sided_data) in install_profile.data {
-            let value = sided_data.client;
+        for (key, sided_data) in install_profile.data.iter() {
+            let value = match side {
+                ForgeSide::Client => &sided_data.client,
+                ForgeSide::Server => &sided_data.server,
+            };
             if value.is_empty() {
                 continue;
             }
@@ -636,14 +959,14 @@ impl Launcher {
                 let artifact_path = artifact.artifact_path();
                 if let Some(target) = SafePath::new(&artifact_path) {
                     let target = target.to_path(&self.directories.libraries_dir);
-                    data.insert(key, target.into_os_string());
+                    data.insert(key.clone(), target.into_os_string());
                 } else {
                     log::error!("Artifact generated invalid path: {}", artifact_path);
                 }
             } else if value.starts_with('\'') && value.ends_with('\'') {
-                data.insert(key, OsString::from(&value[1..value.len()-1]));
+                data.insert(key.clone(), OsString::from(&value[1..value.len()-1]));
             } else {
-                let mut file_name = &*value;
+                let mut file_name = &**value;
                 if file_name.starts_with('/') {
                     file_name = &file_name[1..];
                 }
@@ -651,9 +974,9 @@ impl Launcher {
                     return Err(LaunchError::MissingFileInZipError(Cow::Owned(file_name.to_string())));
                 };
                 if let Some(target) = SafePath::new(file_name) {
-                    let target = target.to_path(&forge_temp);
+                    let target = target.to_path(forge_temp);
                     crate::write_safe(&target, &file.bytes()?)?;
-                    data.insert(key, target.into_os_string());
+                    data.insert(key.clone(), target.into_os_string());
                 } else {
                     log::error!("Unable to extract {}", file_name);
                 }
@@ -662,7 +985,10 @@ impl Launcher {
 
         drop(installer_zip);
 
-        data.insert("SIDE".into(), "client".into());
+        data.insert("SIDE".into(), match side {
+            ForgeSide::Client => "client".into(),
+            ForgeSide::Server => "server".into(),
+        });
         data.insert("MINECRAFT_JAR".into(), minecraft_jar_path.as_os_str().to_os_string());
         data.insert("MINECRAFT_VERSION".into(), OsString::from(&*install_profile.minecraft));
         // ROOT is omitted
@@ -677,7 +1003,7 @@ impl Launcher {
 
         for processor in install_profile.processors.iter() {
             if let Some(sides) = &processor.sides {
-                if !sides.iter().any(|side| *side == ForgeSide::Client) {
+                if !sides.contains(&side) {
                     processor_tracker.add_count(1);
                     processor_tracker.notify();
 
@@ -735,7 +1061,7 @@ impl Launcher {
 
             let mut command = std::process::Command::new(java_path);
 
-            command.current_dir(&forge_temp);
+            command.current_dir(forge_temp);
             command.stdin(Stdio::inherit());
             command.stdout(Stdio::inherit());
             command.stderr(Stdio::inherit());
@@ -783,7 +1109,7 @@ impl Launcher {
         launch_tracker.add_count(1);
         launch_tracker.notify();
 
-        Ok((Arc::new(version.apply_to(&base_version)), AddVanillaJar::No))
+        Ok(())
     }
 
     async fn create_forgelike_install_version_legacy(
@@ -795,6 +1121,7 @@ impl Launcher {
         progress_trackers: &ProgressTrackers,
         launch_tracker: &ProgressTracker,
         instance_info: &InstanceConfiguration,
+        corrupted_files: &CorruptedFiles,
         check_mirrors: bool,
     ) -> Result<(Arc<MinecraftVersion>, AddVanillaJar), LaunchError> {
         if &*install_profile.install.minecraft != instance_info.minecraft_version.as_str() {
@@ -816,8 +1143,8 @@ impl Launcher {
         let version: PartialMinecraftVersion = install_profile.version_info.into_partial_version(ForgeSide::Client);
 
         // Download mirror list
-        let mirror = if check_mirrors {
-            Self::download_random_mirror(http_client, &install_profile.install.mirror_list).await
+        let mirror = if check_mirrors && !self.meta.connectivity.is_offline() {
+            Self::download_random_mirror(http_client, &self.meta.endpoints, &install_profile.install.mirror_list).await
         } else {
             None
         };
@@ -837,16 +1164,14 @@ impl Launcher {
                 Some(artifact)
             }).collect::<Vec<_>>();
 
-            self.load_libraries(http_client, &libraries, progress_trackers, launch_tracker).await?;
+            self.load_libraries(&libraries, progress_trackers, launch_tracker, corrupted_files).await?;
         }
 
         Ok((Arc::new(version.apply_to(&base_version)), AddVanillaJar::Yes))
     }
 
-    async fn download_sha1(http_client: &reqwest::Client, url: &str) -> Option<Ustr> {
-        let response = http_client
-            .get(url)
-            .send().await.ok()?;
+    async fn download_sha1(http_client: &reqwest::Client, endpoints: &Endpoints, url: &str) -> Option<Ustr> {
+        let response = endpoints.send(http_client.get(url)).await.ok()?;
 
         let bytes = response.bytes().await.ok()?;
 
@@ -857,10 +1182,8 @@ impl Launcher {
         Some(str::from_utf8(&bytes).ok()?.into())
     }
 
-    async fn download_random_mirror(http_client: &reqwest::Client, url: &str) -> Option<Arc<str>> {
-        let response = http_client
-            .get(url)
-            .send().await.ok()?;
+    async fn download_random_mirror(http_client: &reqwest::Client, endpoints: &Endpoints, url: &str) -> Option<Arc<str>> {
+        let response = endpoints.send(http_client.get(url)).await.ok()?;
 
         let bytes = response.bytes().await.ok()?;
 
@@ -879,11 +1202,11 @@ impl Launcher {
     async fn load_mojang_java_binary(
         &self,
         meta: &MetadataManager,
-        http_client: &reqwest::Client,
         configuration: &InstanceConfiguration,
         version_info: &MinecraftVersion,
         progress_trackers: &ProgressTrackers,
         launch_tracker: &ProgressTracker,
+        corrupted_files: &CorruptedFiles,
     ) -> Result<PathBuf, LoadJavaRuntimeError> {
         if let Some(jvm_binary) = &configuration.jvm_binary {
             if jvm_binary.enabled && let Some(path) = &jvm_binary.path {
@@ -990,7 +1313,7 @@ impl Launcher {
         progress_trackers.push(java_runtime_tracker.clone());
         java_runtime_tracker.notify();
 
-        let result = do_java_runtime_load(http_client, runtime_component_dir, fresh_install, runtime, &java_runtime_tracker).await;
+        let result = do_java_runtime_load(&self.downloads, runtime_component_dir, fresh_install, runtime, &java_runtime_tracker, corrupted_files).await;
 
         java_runtime_tracker.set_finished(ProgressTrackerFinishType::from_err(result.is_err()));
         java_runtime_tracker.notify();
@@ -1004,11 +1327,11 @@ impl Launcher {
     async fn load_assets(
         &self,
         meta: &MetadataManager,
-        http_client: &reqwest::Client,
         game_dir: &Arc<Path>,
         version_info: &MinecraftVersion,
         progress_trackers: &ProgressTrackers,
         launch_tracker: &ProgressTracker,
+        corrupted_files: &CorruptedFiles,
     ) -> Result<String, LoadAssetObjectsError> {
         let asset_index = format!("{}", version_info.assets);
 
@@ -1031,7 +1354,7 @@ impl Launcher {
             self.directories.assets_objects_dir.clone()
         };
 
-        let result = do_asset_objects_load(http_client, assets_index, assets_dir, &assets_tracker).await;
+        let result = do_asset_objects_load(&self.downloads, assets_index, assets_dir, &assets_tracker, corrupted_files).await;
 
         assets_tracker.set_finished(ProgressTrackerFinishType::from_err(result.is_err()));
         assets_tracker.notify();
@@ -1046,10 +1369,10 @@ impl Launcher {
 
     async fn load_libraries(
         &self,
-        http_client: &reqwest::Client,
         artifacts: &[GameLibraryArtifact],
         progress_trackers: &ProgressTrackers,
         launch_tracker: &ProgressTracker,
+        corrupted_files: &CorruptedFiles,
     ) -> Result<Vec<(Ustr, PathBuf)>, LoadLibrariesError> {
         let initial_title = Arc::from("Verifying integrity of game libraries");
         let libraries_tracker = ProgressTracker::new(initial_title, self.sender.clone());
@@ -1057,7 +1380,7 @@ impl Launcher {
         libraries_tracker.notify();
 
         let result =
-            do_libraries_load(http_client, artifacts, self.directories.libraries_dir.clone(), &libraries_tracker).await;
+            do_libraries_load(&self.downloads, artifacts, self.directories.libraries_dir.clone(), &libraries_tracker, corrupted_files).await;
 
         libraries_tracker.set_finished(ProgressTrackerFinishType::from_err(result.is_err()));
         libraries_tracker.notify();
@@ -1105,7 +1428,7 @@ impl Launcher {
             return Some(expand_logging_argument(client.argument.as_str(), &path));
         }
 
-        let Ok(response) = http_client.get(client.file.url.as_str()).send().await else {
+        let Ok(response) = self.meta.endpoints.send(http_client.get(client.file.url.as_str())).await else {
             log::error!("Failed to make request to download log configuration");
             return None;
         };
@@ -1323,6 +1646,130 @@ impl Launcher {
     }
 }
 
+/// Where the installer of a Forge-like loader is downloaded from, `{0}` is replaced with the loader version.
+/// The path is relative to the maven, which may be replaced in the endpoint config
+struct ForgelikeInstaller {
+    maven: EndpointService,
+    path: &'static str,
+    check_mirrors: bool,
+    neoforge_versioning: bool,
+}
+
+const FORGE_INSTALLER: ForgelikeInstaller = ForgelikeInstaller {
+    maven: EndpointService::ForgeMaven,
+    path: "net/minecraftforge/forge/{0}/forge-{0}-installer.jar",
+    check_mirrors: true,
+    neoforge_versioning: false,
+};
+
+const NEOFORGE_INSTALLER: ForgelikeInstaller = ForgelikeInstaller {
+    maven: EndpointService::NeoforgeMaven,
+    path: "net/neoforged/neoforge/{0}/neoforge-{0}-installer.jar",
+    check_mirrors: false,
+    neoforge_versioning: true,
+};
+
+struct ForgelikeInstallerFiles {
+    installer_path: PathBuf,
+    minecraft_jar_path: PathBuf,
+    java_path: PathBuf,
+}
+
+/// The vanilla client or server jar that `side` is installed on top of
+fn minecraft_jar_artifact(side: ForgeSide, instance_info: &InstanceConfiguration, base_version: &MinecraftVersion) -> Result<GameLibraryArtifact, LaunchError> {
+    match side {
+        ForgeSide::Client => Ok(GameLibraryArtifact {
+            path: format!("net/minecraft/{0}/minecraft-client-{0}.jar", instance_info.minecraft_version).into(),
+            sha1: Some(base_version.downloads.client.sha1),
+            size: Some(base_version.downloads.client.size),
+            url: base_version.downloads.client.url,
+        }),
+        ForgeSide::Server => {
+            let Some(server_download) = &base_version.downloads.server else {
+                return Err(LaunchError::UnsupportedServer("this version of Minecraft has no dedicated server"));
+            };
+            Ok(GameLibraryArtifact {
+                path: format!("net/minecraft/{0}/minecraft-server-{0}.jar", instance_info.minecraft_version).into(),
+                sha1: Some(server_download.sha1),
+                size: Some(server_download.size),
+                url: server_download.url,
+            })
+        },
+    }
+}
+
+enum ForgelikeInstallProfile {
+    Modern(ForgeInstallProfile),
+    Legacy(ForgeInstallProfileLegacy),
+}
+
+fn read_forgelike_install_profile(installer_zip: &ArchiveHandle<'_, File>) -> Result<ForgelikeInstallProfile, LaunchError> {
+    let Some(install_profile_file) = installer_zip.by_name("install_profile.json") else {
+        return Err(LaunchError::MissingFileInZipError(Cow::Borrowed("install_profile.json")));
+    };
+
+    let install_profile_bytes = install_profile_file.bytes()?;
+
+    match serde_json::from_slice(&install_profile_bytes) {
+        Ok(install_profile) => Ok(ForgelikeInstallProfile::Modern(install_profile)),
+        Err(error) => match serde_json::from_slice(&install_profile_bytes) {
+            Ok(install_profile_legacy) => Ok(ForgelikeInstallProfile::Legacy(install_profile_legacy)),
+            Err(_) => Err(error.into()),
+        },
+    }
+}
+
+/// Reads the partial version that a modern installer installs
+fn read_forgelike_version(installer_zip: &ArchiveHandle<'_, File>, install_profile: &ForgeInstallProfile) -> Result<PartialMinecraftVersion, LaunchError> {
+    let mut version_file_name = &*install_profile.json;
+    if version_file_name.starts_with('/') {
+        version_file_name = &version_file_name[1..];
+    }
+    let Some(version_file) = installer_zip.by_name(version_file_name) else {
+        return Err(LaunchError::MissingFileInZipError(Cow::Owned(version_file_name.to_string())));
+    };
+    Ok(serde_json::from_slice(&version_file.bytes()?)?)
+}
+
+fn find_forgelike_loader_version(instance_info: &InstanceConfiguration, loader_versions: &[Ustr], neoforge_versioning: bool) -> Option<Ustr> {
+    if let Some(preferred_loader_version) = instance_info.preferred_loader_version {
+        return Some(preferred_loader_version);
+    }
+
+    let mut minecraft_version_parts = VersionFragment::string_to_parts(instance_info.minecraft_version.as_str());
+    if neoforge_versioning {
+        // 1.21.5 -> 21.5
+        // 25w14craftmine -> 0.25w14craftmine
+        // 1.21 -> 21.0
+        // 26.1 -> 26.1.0
+        if minecraft_version_parts[0] == VersionFragment::String("25w14craftmine".into()) {
+            minecraft_version_parts.insert(0, VersionFragment::Number(0))
+        } else {
+            if minecraft_version_parts.len() < 3 {
+                minecraft_version_parts.push(VersionFragment::Number(0))
+            }
+            if minecraft_version_parts[0] == VersionFragment::Number(1) {
+                minecraft_version_parts.remove(0);
+            }
+        }
+    }
+
+    let mut latest_loader_version = None;
+    let mut latest_loader_version_parts = Vec::new();
+    for version in loader_versions.iter() {
+        let parts = VersionFragment::string_to_parts(version);
+
+        if parts.starts_with(&minecraft_version_parts) {
+            if parts > latest_loader_version_parts {
+                latest_loader_version_parts = parts;
+                latest_loader_version = Some(version.clone());
+            }
+        }
+    }
+
+    latest_loader_version
+}
+
 fn expand_logging_argument(argument: &str, path: &Path) -> OsString {
     let mut dollar_last = false;
     let mut builder = OsString::new();
@@ -1388,8 +1835,8 @@ pub enum LoadJavaRuntimeError {
     UnknownComponentForPlatform,
     #[error("Mojang runtime path is invalid")]
     InvalidComponentPath,
-    #[error("Downloaded file had wrong response size. Expected {0}, got {1}")]
-    WrongResponseSize(usize, usize),
+    #[error("Failed to download file:\n{0}")]
+    Download(#[from] DownloadError),
     #[error("Downloaded file had wrong raw size")]
     WrongRawSize,
     #[error("Failed to decompress file")]
@@ -1403,16 +1850,15 @@ pub enum LoadJavaRuntimeError {
 }
 
 async fn do_java_runtime_load(
-    http_client: &reqwest::Client,
+    downloads_manager: &DownloadManager,
     runtime_component_dir: PathBuf,
     fresh_install: bool,
     runtime: Arc<JavaRuntimeComponentManifest>,
     java_runtime_tracker: &ProgressTracker,
+    corrupted_files: &CorruptedFiles,
 ) -> Result<PathBuf, LoadJavaRuntimeError> {
     let mut links = HashMap::new();
 
-    // Limit max concurrent connections to 8 to avoid ratelimiting issues
-    let download_semaphore = tokio::sync::Semaphore::new(8);
     let disk_semaphore = tokio::sync::Semaphore::new(32);
     let started_downloading = AtomicBool::new(fresh_install);
 
@@ -1440,21 +1886,20 @@ async fn do_java_runtime_load(
                 total_size += downloads.raw.size;
 
                 let started_downloading = &started_downloading;
-                let download_semaphore = &download_semaphore;
                 let disk_semaphore = &disk_semaphore;
 
                 let task = async move {
-                    let valid_hash_on_disk = {
+                    let hash_on_disk = {
                         let path = path.clone();
                         let permit = disk_semaphore.acquire().await.unwrap();
                         let result = tokio::task::spawn_blocking(move || {
-                            crate::check_sha1_hash(&path, expected_hash).unwrap_or(false)
+                            crate::check_sha1_hash(&path, expected_hash)
                         }).await.unwrap();
                         drop(permit);
                         result
                     };
 
-                    if valid_hash_on_disk {
+                    if corrupted_files.check(&path, hash_on_disk) {
                         java_runtime_tracker.add_count(downloads.raw.size as usize);
                         java_runtime_tracker.notify();
                         return Ok(());
@@ -1465,73 +1910,64 @@ async fn do_java_runtime_load(
                         java_runtime_tracker.set_title(Arc::from("Downloading Java Runtime"));
                     }
 
-                    let (lzma, size, download) = if let Some(lzma) = &downloads.lzma {
-                        (true, lzma.size as usize, lzma)
-                    } else {
-                        (false, downloads.raw.size as usize, &downloads.raw)
-                    };
-
-                    let permit = download_semaphore.acquire().await.unwrap();
-                    let response = http_client.get(download.url.as_str()).send().await?;
-                    let bytes = response.bytes().await?;
-                    drop(permit);
-
-                    if bytes.len() != size {
-                        return Err(LoadJavaRuntimeError::WrongResponseSize(size, bytes.len()));
+                    if let Some(parent) = path.parent() {
+                        _ = std::fs::create_dir_all(parent);
                     }
 
-                    let decompressed_or_raw = if lzma {
-                        let result = tokio::task::spawn_blocking(move || {
+                    if let Some(lzma) = &downloads.lzma {
+                        let mut expected_lzma_hash = [0u8; 20];
+                        let Ok(_) = hex::decode_to_slice(lzma.sha1.as_str(), &mut expected_lzma_hash) else {
+                            return Err(LoadJavaRuntimeError::InvalidHash(lzma.sha1));
+                        };
+
+                        let lzma_path = path.with_added_extension("lzma");
+                        downloads_manager.download(DownloadRequest {
+                            url: lzma.url.as_str(),
+                            path: &lzma_path,
+                            sha1: Some(expected_lzma_hash),
+                            size: Some(lzma.size as usize),
+                            track_bytes: false,
+                        }, java_runtime_tracker).await?;
+
+                        let compressed = tokio::fs::read(&lzma_path).await?;
+                        _ = tokio::fs::remove_file(&lzma_path).await;
+
+                        let decompressed = tokio::task::spawn_blocking(move || {
                             let mut output = Vec::new();
-                            lzma_rs::lzma_decompress(&mut std::io::Cursor::new(bytes), &mut output)?;
-                            Ok(output)
-                        }).await.unwrap();
+                            lzma_rs::lzma_decompress(&mut std::io::Cursor::new(compressed), &mut output)?;
+                            Ok::<_, lzma_rs::error::Error>(output)
+                        }).await.unwrap()?;
 
-                        match result {
-                            Ok(decompressed) => Ok(decompressed),
-                            Err(lzma_error) => {
-                                return Err(LoadJavaRuntimeError::Lzma(lzma_error));
-                            },
+                        if decompressed.len() != downloads.raw.size as usize {
+                            return Err(LoadJavaRuntimeError::WrongRawSize);
                         }
-                    } else {
-                        Err(bytes)
-                    };
-
-                    let decompressed_or_raw = Arc::new(decompressed_or_raw);
-
-                    let bytes = match &*decompressed_or_raw {
-                        Ok(vec) => vec.as_slice(),
-                        Err(bytes) => bytes,
-                    };
 
-                    if bytes.len() != downloads.raw.size as usize {
-                        return Err(LoadJavaRuntimeError::WrongRawSize);
-                    }
+                        let decompressed = Arc::new(decompressed);
+                        let valid_hash = {
+                            let decompressed = Arc::clone(&decompressed);
+                            tokio::task::spawn_blocking(move || {
+                                let mut hasher = Sha1::new();
+                                hasher.update(&*decompressed);
+                                let actual_hash = hasher.finalize();
 
-                    let valid_hash = {
-                        let decompressed_or_raw = Arc::clone(&decompressed_or_raw);
-                        tokio::task::spawn_blocking(move || {
-                            let bytes = match &*decompressed_or_raw {
-                                Ok(vec) => vec.as_slice(),
-                                Err(bytes) => bytes,
-                            };
+                                expected_hash == *actual_hash
+                            }).await.unwrap()
+                        };
 
-                            let mut hasher = Sha1::new();
-                            hasher.update(bytes);
-                            let actual_hash = hasher.finalize();
-
-                            expected_hash == *actual_hash
-                        }).await.unwrap()
-                    };
-
-                    if !valid_hash {
-                        return Err(LoadJavaRuntimeError::WrongHash);
-                    }
+                        if !valid_hash {
+                            return Err(LoadJavaRuntimeError::WrongHash);
+                        }
 
-                    if let Some(parent) = path.parent() {
-                        _ = std::fs::create_dir_all(parent);
+                        tokio::fs::write(&path, &*decompressed).await?;
+                    } else {
+                        downloads_manager.download(DownloadRequest {
+                            url: downloads.raw.url.as_str(),
+                            path: &path,
+                            sha1: Some(expected_hash),
+                            size: Some(downloads.raw.size as usize),
+                            track_bytes: false,
+                        }, java_runtime_tracker).await?;
                     }
-                    tokio::fs::write(&path, bytes).await?;
 
                     #[cfg(unix)]
                     if *executable {
@@ -1597,28 +2033,21 @@ async fn do_java_runtime_load(
 
 #[derive(thiserror::Error, Debug)]
 pub enum LoadAssetObjectsError {
-    #[error("Failed to load remote content")]
-    Reqwest(#[from] reqwest::Error),
-    #[error("Failed to perform I/O operation")]
-    IoError(#[from] std::io::Error),
+    #[error("Failed to download file:\n{0}")]
+    Download(#[from] DownloadError),
     #[error("Hash isn't a valid sha1 hash\n{0}")]
     InvalidHash(Ustr),
-    #[error("Downloaded file had wrong response size. Expected {0}, got {1}")]
-    WrongResponseSize(usize, usize),
-    #[error("Downloaded file had the wrong hash")]
-    WrongHash,
     #[error("Failed to load metadata:\n{0}")]
     MetaLoadError(#[from] MetaLoadError),
 }
 
 async fn do_asset_objects_load(
-    http_client: &reqwest::Client,
+    downloads: &DownloadManager,
     assets_index: Arc<AssetsIndex>,
     assets_objects_dir: Arc<Path>,
     assets_tracker: &ProgressTracker,
+    corrupted_files: &CorruptedFiles,
 ) -> Result<(), LoadAssetObjectsError> {
-    // Limit max concurrent connections to 8 to avoid ratelimiting issues
-    let download_semaphore = tokio::sync::Semaphore::new(8);
     let disk_semaphore = tokio::sync::Semaphore::new(32);
     let started_downloading = AtomicBool::new(false);
 
@@ -1641,23 +2070,22 @@ async fn do_asset_objects_load(
         total_size += asset.size;
 
         let started_downloading = &started_downloading;
-        let download_semaphore = &download_semaphore;
         let disk_semaphore = &disk_semaphore;
 
         let url = format!("https://resources.download.minecraft.net/{}/{}", &asset.hash[..2], &asset.hash);
 
         let task = async move {
-            let valid_hash_on_disk = {
+            let hash_on_disk = {
                 let path = path.clone();
                 let permit = disk_semaphore.acquire().await.unwrap();
                 let result = tokio::task::spawn_blocking(move || {
-                    crate::check_sha1_hash(&path, expected_hash).unwrap_or(false)
+                    crate::check_sha1_hash(&path, expected_hash)
                 }).await.unwrap();
                 drop(permit);
                 result
             };
 
-            if valid_hash_on_disk {
+            if corrupted_files.check(&path, hash_on_disk) {
                 assets_tracker.add_count(asset.size as usize);
                 assets_tracker.notify();
                 return Ok(());
@@ -1668,32 +2096,14 @@ async fn do_asset_objects_load(
                 assets_tracker.set_title(Arc::from("Downloading game assets"));
             }
 
-            let permit = download_semaphore.acquire().await.unwrap();
-            let response = http_client.get(&url).send().await?;
-            let bytes = Arc::new(response.bytes().await?);
-            drop(permit);
+            downloads.download(DownloadRequest {
+                url: &url,
+                path: &path,
+                sha1: Some(expected_hash),
+                size: Some(asset.size as usize),
+                track_bytes: false,
+            }, assets_tracker).await?;
 
-            if bytes.len() != asset.size as usize {
-                return Err(LoadAssetObjectsError::WrongResponseSize(asset.size as usize, bytes.len()));
-            }
-
-            let correct_hash = {
-                let bytes = Arc::clone(&bytes);
-
-                tokio::task::spawn_blocking(move || {
-                    let mut hasher = Sha1::new();
-                    hasher.update(&*bytes);
-                    let actual_hash = hasher.finalize();
-
-                    expected_hash == *actual_hash
-                }).await.unwrap()
-            };
-
-            if !correct_hash {
-                return Err(LoadAssetObjectsError::WrongHash);
-            }
-
-            tokio::fs::write(path.clone(), &*bytes).await?;
             assets_tracker.add_count(asset.size as usize);
             assets_tracker.notify();
             Ok(())
@@ -1711,28 +2121,21 @@ async fn do_asset_objects_load(
 
 #[derive(thiserror::Error, Debug)]
 pub enum LoadLibrariesError {
-    #[error("Failed to load remote content")]
-    Reqwest(#[from] reqwest::Error),
-    #[error("Failed to perform I/O operation")]
-    IoError(#[from] std::io::Error),
+    #[error("Failed to download file:\n{0}")]
+    Download(#[from] DownloadError),
     #[error("Hash isn't a valid sha1 hash\n{0}")]
     InvalidHash(Ustr),
-    #[error("Downloaded file had wrong response size. Expected {0}, got {1}")]
-    WrongResponseSize(usize, usize),
-    #[error("Downloaded file had the wrong hash")]
-    WrongHash,
     #[error("Illegal library path {0}, directory traversal?")]
     IllegalLibraryPath(Ustr),
 }
 
 async fn do_libraries_load(
-    http_client: &reqwest::Client,
+    downloads: &DownloadManager,
     artifacts: &[GameLibraryArtifact],
     libraries_dir: Arc<Path>,
     libraries_tracker: &ProgressTracker,
+    corrupted_files: &CorruptedFiles,
 ) -> Result<Vec<(Ustr, PathBuf)>, LoadLibrariesError> {
-    // Limit max concurrent connections to 8 to avoid ratelimiting issues
-    let download_semaphore = tokio::sync::Semaphore::new(8);
     let disk_semaphore = tokio::sync::Semaphore::new(32);
     let started_downloading = AtomicBool::new(false);
 
@@ -1767,18 +2170,17 @@ async fn do_libraries_load(
         total_size += tracker_size;
 
         let started_downloading = &started_downloading;
-        let download_semaphore = &download_semaphore;
         let disk_semaphore = &disk_semaphore;
 
         let task = async move {
             let valid_hash_on_disk = if let Some(expected_hash) = expected_hash {
-                let artifact_path = artifact_path.clone();
+                let path = artifact_path.clone();
                 let permit = disk_semaphore.acquire().await.unwrap();
                 let result = tokio::task::spawn_blocking(move || {
-                    crate::check_sha1_hash(&artifact_path, expected_hash).unwrap_or(false)
+                    crate::check_sha1_hash(&path, expected_hash)
                 }).await.unwrap();
                 drop(permit);
-                result
+                corrupted_files.check(&artifact_path, result)
             } else {
                 artifact_path.exists()
             };
@@ -1794,36 +2196,14 @@ async fn do_libraries_load(
                 libraries_tracker.set_title(Arc::from("Downloading game libraries"));
             }
 
-            let permit = download_semaphore.acquire().await.unwrap();
-            let response = http_client.get(artifact.url.as_str()).send().await?;
-            let bytes = Arc::new(response.bytes().await?);
-            drop(permit);
-
-            if let Some(artifact_size) = artifact.size && bytes.len() != artifact_size as usize {
-                return Err(LoadLibrariesError::WrongResponseSize(artifact_size as usize, bytes.len()));
-            }
-
-            let correct_hash = {
-                if let Some(expected_hash) = expected_hash {
-                    let bytes = Arc::clone(&bytes);
-
-                    tokio::task::spawn_blocking(move || {
-                        let mut hasher = Sha1::new();
-                        hasher.update(&*bytes);
-                        let actual_hash = hasher.finalize();
-
-                        expected_hash == *actual_hash
-                    }).await.unwrap()
-                } else {
-                    true
-                }
-            };
-
-            if !correct_hash {
-                return Err(LoadLibrariesError::WrongHash);
-            }
+            downloads.download(DownloadRequest {
+                url: artifact.url.as_str(),
+                path: &artifact_path,
+                sha1: expected_hash,
+                size: artifact.size.map(|size| size as usize),
+                track_bytes: false,
+            }, libraries_tracker).await?;
 
-            tokio::fs::write(artifact_path.clone(), &*bytes).await?;
             libraries_tracker.add_count(tracker_size as usize);
             libraries_tracker.notify();
             Ok((artifact.path, artifact_path))
@@ -2099,16 +2479,16 @@ impl LaunchContext {
             command.arg(log_configuration);
         }
 
-        if let Some(memory) = &self.configuration.memory && memory.enabled {
-            command.arg(format!("-Xms{}m", memory.min));
-            command.arg(format!("-Xmx{}m", memory.max.max(memory.min).max(128)));
-        }
-        if let Some(jvm_flags) = &self.configuration.jvm_flags && jvm_flags.enabled {
-            if let Ok(split) = shell_words::split(&jvm_flags.flags) {
-                command.args(split);
-            } else {
-                command.args(jvm_flags.flags.split_whitespace());
-            }
+        add_configured_jvm_arguments(&mut command, &self.configuration);
+
+        if let Some(authlib_injector) = &self.login_info.authlib_injector {
+            let mut javaagent = OsString::from("-javaagent:");
+            javaagent.push(authlib_injector.agent_path.as_os_str());
+            javaagent.push("=");
+            javaagent.push(&*authlib_injector.api_root);
+
+            command.arg(javaagent);
+            command.arg(format!("-Dauthlibinjector.yggdrasil.prefetched={}", authlib_injector.prefetched));
         }
 
         command.arg("com.moulberry.pandora.LaunchWrapper");
@@ -2253,7 +2633,11 @@ impl LaunchContext {
             ArgumentExpansionKey::VersionType => OsStr::new("release").into(),
             ArgumentExpansionKey::QuickPlayPath => OsStr::new("quickPlay/log.json").into(),
             ArgumentExpansionKey::UserProperties => OsStr::new("{}").into(),
-            ArgumentExpansionKey::UserType => OsStr::new("msa").into(),
+            ArgumentExpansionKey::UserType => OsStr::new(match self.login_info.kind {
+                AccountKind::Offline => "legacy",
+                AccountKind::Yggdrasil => "mojang",
+                AccountKind::Microsoft => "msa",
+            }).into(),
             ArgumentExpansionKey::ResolutionWidth => OsString::from(format!("{}", self.rule_context.custom_resolution.unwrap().0)).into(),
             ArgumentExpansionKey::ResolutionHeight => OsString::from(format!("{}", self.rule_context.custom_resolution.unwrap().1)).into(),
             ArgumentExpansionKey::QuickPlaySingleplayer => {
@@ -2281,6 +2665,21 @@ impl LaunchContext {
     }
 }
 
+/// Adds the memory limits and extra flags from the instance settings
+fn add_configured_jvm_arguments(command: &mut std::process::Command, configuration: &InstanceConfiguration) {
+    if let Some(memory) = &configuration.memory && memory.enabled {
+        command.arg(format!("-Xms{}m", memory.min));
+        command.arg(format!("-Xmx{}m", memory.max.max(memory.min).max(128)));
+    }
+    if let Some(jvm_flags) = &configuration.jvm_flags && jvm_flags.enabled {
+        if let Ok(split) = shell_words::split(&jvm_flags.flags) {
+            command.args(split);
+        } else {
+            command.args(jvm_flags.flags.split_whitespace());
+        }
+    }
+}
+
 fn path_is_normal(path: impl AsRef<Path>) -> bool {
     let components = path.as_ref().components();
 
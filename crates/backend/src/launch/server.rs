@@ -0,0 +1,353 @@
+use std::{
+    ffi::OsString, path::{Path, PathBuf}, process::{Child, Stdio}, sync::Arc
+};
+
+use bridge::{
+    message::MessageToFrontend, modal_action::{ModalAction, ProgressTracker, ProgressTrackers}
+};
+use futures::TryFutureExt;
+use rc_zip_sync::ReadZip;
+use schema::{
+    backend_config::EndpointService, forge::ForgeSide, instance::InstanceConfiguration, loader::Loader, maven::MavenCoordinate, version::{GameLibraryArtifact, MinecraftVersion}
+};
+
+use super::{
+    add_configured_jvm_arguments, minecraft_jar_artifact, read_forgelike_install_profile, read_forgelike_version, CorruptedFiles,
+    ForgelikeInstallProfile, LaunchError, Launcher
+};
+use crate::metadata::items::{FabricLaunchMetadataItem, MinecraftVersionManifestMetadataItem, MinecraftVersionMetadataItem};
+
+/// Everything needed to start a dedicated server once its files are installed
+struct PreparedServerLaunch {
+    java_path: PathBuf,
+    arguments: Vec<OsString>,
+}
+
+impl Launcher {
+    /// Starts a dedicated server in `dot_minecraft_path`. Unlike the game, servers are started directly
+    /// without the launch wrapper so that stdin stays available for console commands
+    pub async fn launch_server(
+        &self,
+        http_client: &reqwest::Client,
+        dot_minecraft_path: Arc<Path>,
+        instance_info: InstanceConfiguration,
+        add_mods: Vec<PathBuf>,
+        launch_tracker: &ProgressTracker,
+        modal_action: &ModalAction,
+    ) -> Result<Child, LaunchError> {
+        log::info!("Launching server {:?}", dot_minecraft_path);
+
+        launch_tracker.set_total(4);
+
+        let prepared = self.prepare_server_launch(http_client, &instance_info, &CorruptedFiles::default(), launch_tracker, modal_action).await?;
+
+        if modal_action.has_requested_cancel() {
+            self.sender.send(MessageToFrontend::CloseModal);
+            return Err(LaunchError::CancelledByUser);
+        }
+
+        let _ = std::fs::create_dir_all(&dot_minecraft_path);
+
+        let mut command = std::process::Command::new(&prepared.java_path);
+
+        command.current_dir(&dot_minecraft_path);
+        command.stdin(Stdio::piped());
+        command.stdout(Stdio::piped());
+        command.stderr(Stdio::piped());
+
+        add_configured_jvm_arguments(&mut command, &instance_info);
+
+        if !add_mods.is_empty() && instance_info.loader == Loader::Fabric {
+            let mut add_mods_property = OsString::from("-Dfabric.addMods=");
+            add_mods_property.push(std::env::join_paths(add_mods).unwrap());
+            command.arg(add_mods_property);
+        }
+
+        command.args(prepared.arguments);
+        command.arg("nogui");
+
+        log::info!("Launching server process");
+        let child = command.spawn()?;
+
+        launch_tracker.add_count(1);
+
+        Ok(child)
+    }
+
+    /// Installs the server jar, loader and java runtime, returning how the server should be started
+    async fn prepare_server_launch(
+        &self,
+        http_client: &reqwest::Client,
+        instance_info: &InstanceConfiguration,
+        corrupted_files: &CorruptedFiles,
+        launch_tracker: &ProgressTracker,
+        modal_action: &ModalAction,
+    ) -> Result<PreparedServerLaunch, LaunchError> {
+        tokio::select! {
+            result = self.create_server_launch(http_client, &modal_action.trackers, launch_tracker, instance_info, corrupted_files) => result,
+            _ = modal_action.request_cancel.cancelled() => {
+                self.sender.send(MessageToFrontend::CloseModal);
+                Err(LaunchError::CancelledByUser)
+            }
+        }
+    }
+
+    async fn create_server_launch(
+        &self,
+        http_client: &reqwest::Client,
+        progress_trackers: &ProgressTrackers,
+        launch_tracker: &ProgressTracker,
+        instance_info: &InstanceConfiguration,
+        corrupted_files: &CorruptedFiles,
+    ) -> Result<PreparedServerLaunch, LaunchError> {
+        let minecraft_versions = self.meta.fetch(&MinecraftVersionManifestMetadataItem).await?;
+
+        let Some(version_link) = minecraft_versions.versions.iter().find(|v| v.id == instance_info.minecraft_version) else {
+            return Err(LaunchError::CantFindVersion(instance_info.minecraft_version.as_str()));
+        };
+
+        let base_version = self.meta.fetch(&MinecraftVersionMetadataItem(version_link)).await?;
+
+        launch_tracker.add_count(1);
+        launch_tracker.notify();
+
+        let server_artifact = minecraft_jar_artifact(ForgeSide::Server, instance_info, &base_version)?;
+
+        match instance_info.loader {
+            Loader::Vanilla => {
+                let (java_path, library_paths) = futures::future::try_join(
+                    self.load_mojang_java_binary(&self.meta, instance_info, &base_version, progress_trackers, launch_tracker, corrupted_files)
+                        .map_err(LaunchError::from),
+                    self.load_libraries(&[server_artifact], progress_trackers, launch_tracker, corrupted_files)
+                        .map_err(LaunchError::from),
+                ).await?;
+
+                let server_jar = library_paths.into_iter().next().unwrap().1;
+
+                Ok(PreparedServerLaunch {
+                    java_path,
+                    arguments: vec!["-jar".into(), server_jar.into_os_string()],
+                })
+            },
+            Loader::Fabric => {
+                let loader_version = self.find_fabric_loader_version(instance_info).await?;
+
+                let fabric_launch = self.meta.fetch(&FabricLaunchMetadataItem {
+                    minecraft_version: instance_info.minecraft_version,
+                    loader_version,
+                }).await?;
+
+                // The server jar is kept first so it can be passed to the loader separately from the classpath
+                let mut artifacts = vec![server_artifact];
+
+                let loader_mavens = fabric_launch.loader.iter().map(|loader| loader.maven)
+                    .chain(fabric_launch.intermediary.iter().map(|intermediary| intermediary.maven));
+                for maven in loader_mavens {
+                    let artifact_path = MavenCoordinate::create(&maven).artifact_path();
+                    artifacts.push(GameLibraryArtifact {
+                        url: self.meta.endpoints.url(EndpointService::FabricMaven, &artifact_path).into(),
+                        path: artifact_path.into(),
+                        sha1: None,
+                        size: None,
+                    });
+                }
+
+                let libraries = &fabric_launch.launcher_meta.libraries;
+                for library in libraries.common.iter().chain(libraries.server.iter()) {
+                    let artifact_path = MavenCoordinate::create(&library.name).artifact_path();
+                    artifacts.push(GameLibraryArtifact {
+                        url: format!("{}{}", &library.url, &artifact_path).into(),
+                        path: artifact_path.into(),
+                        sha1: Some(library.sha1),
+                        size: Some(library.size),
+                    });
+                }
+
+                let (java_path, library_paths) = futures::future::try_join(
+                    self.load_mojang_java_binary(&self.meta, instance_info, &base_version, progress_trackers, launch_tracker, corrupted_files)
+                        .map_err(LaunchError::from),
+                    self.load_libraries(&artifacts, progress_trackers, launch_tracker, corrupted_files)
+                        .map_err(LaunchError::from),
+                ).await?;
+
+                let mut library_paths = library_paths.into_iter().map(|(_, path)| path.into_os_string());
+
+                let mut game_jar_path = OsString::from("-Dfabric.gameJarPath=");
+                game_jar_path.push(library_paths.next().unwrap());
+
+                Ok(PreparedServerLaunch {
+                    java_path,
+                    arguments: vec![
+                        game_jar_path,
+                        "-cp".into(),
+                        std::env::join_paths(library_paths).unwrap(),
+                        fabric_launch.launcher_meta.main_class.server.as_str().into(),
+                    ],
+                })
+            },
+            Loader::Forge | Loader::NeoForge => {
+                self.create_forgelike_server_launch(http_client, progress_trackers, launch_tracker, instance_info, corrupted_files,
+                    base_version).await
+            },
+            Loader::Unknown => Err(LaunchError::UnsupportedServer("the loader is unknown")),
+        }
+    }
+
+    async fn create_forgelike_server_launch(
+        &self,
+        http_client: &reqwest::Client,
+        progress_trackers: &ProgressTrackers,
+        launch_tracker: &ProgressTracker,
+        instance_info: &InstanceConfiguration,
+        corrupted_files: &CorruptedFiles,
+        base_version: Arc<MinecraftVersion>,
+    ) -> Result<PreparedServerLaunch, LaunchError> {
+        launch_tracker.add_total(3);
+        launch_tracker.notify();
+
+        let Some((loader_version, installer)) = self.find_forgelike_installer(instance_info).await? else {
+            return Err(LaunchError::CantFindVersion(instance_info.minecraft_version.as_str()));
+        };
+
+        let files = self.load_forgelike_installer(ForgeSide::Server, installer, &loader_version, &base_version, http_client,
+            progress_trackers, launch_tracker, instance_info, corrupted_files).await?;
+
+        let installer_file = std::fs::File::open(&files.installer_path)?;
+        let installer_zip = installer_file.read_zip()?;
+
+        let ForgelikeInstallProfile::Modern(install_profile) = read_forgelike_install_profile(&installer_zip)? else {
+            return Err(LaunchError::UnsupportedServer("this version of Forge is too old"));
+        };
+
+        if &*install_profile.minecraft != instance_info.minecraft_version.as_str() {
+            return Err(LaunchError::MismatchedLoaderVersions(install_profile.minecraft.clone()));
+        }
+
+        // Servers are started with the same arguments as the installer's run script
+        let args_file_name = if cfg!(windows) { "data/win_args.txt" } else { "data/unix_args.txt" };
+        let Some(args_file) = installer_zip.by_name(args_file_name) else {
+            return Err(LaunchError::UnsupportedServer("this version of the loader has no server launch arguments"));
+        };
+        let args = String::from_utf8_lossy(&args_file.bytes()?).into_owned();
+
+        // The arguments reference the libraries of the installed version as well as the installer's
+        let version = read_forgelike_version(&installer_zip, &install_profile)?;
+
+        if let Some(libraries) = &version.libraries {
+            let libraries = libraries.iter()
+                .filter_map(|library| library.downloads.artifact.clone())
+                .collect::<Vec<_>>();
+            self.load_libraries(&libraries, progress_trackers, launch_tracker, corrupted_files).await?;
+        }
+
+        self.install_forgelike_side(ForgeSide::Server, &install_profile, installer_zip, &files.installer_path,
+            &files.minecraft_jar_path, &files.java_path, http_client, progress_trackers, launch_tracker, corrupted_files,
+            installer.check_mirrors).await?;
+
+        let libraries_dir = &self.directories.libraries_dir;
+        let arguments = args.lines()
+            .map(str::trim)
+            .filter(|line| !line.starts_with('#'))
+            .flat_map(str::split_whitespace)
+            .map(|argument| expand_server_argument(argument, libraries_dir))
+            .collect();
+
+        Ok(PreparedServerLaunch {
+            java_path: files.java_path,
+            arguments,
+        })
+    }
+}
+
+/// The installer's arguments expect a libraries folder next to the server, point them at the shared one instead
+fn expand_server_argument(argument: &str, libraries_dir: &Path) -> OsString {
+    if argument.starts_with("-D") && let Some((key, value)) = argument.split_once('=') {
+        let mut expanded = OsString::from(key);
+        expanded.push("=");
+        expanded.push(expand_server_paths(value, libraries_dir));
+        expanded
+    } else {
+        expand_server_paths(argument, libraries_dir)
+    }
+}
+
+fn expand_server_paths(value: &str, libraries_dir: &Path) -> OsString {
+    let separator = if cfg!(windows) { ";" } else { ":" };
+
+    let mut expanded = OsString::new();
+    for (index, path) in value.split(separator).enumerate() {
+        if index > 0 {
+            expanded.push(separator);
+        }
+        if path == "libraries" {
+            expanded.push(libraries_dir);
+        } else if let Some(relative) = path.strip_prefix("libraries/") {
+            expanded.push(libraries_dir.join(relative));
+        } else {
+            expanded.push(path);
+        }
+    }
+    expanded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SEPARATOR: &str = if cfg!(windows) { ";" } else { ":" };
+
+    fn libraries_dir() -> PathBuf {
+        std::env::temp_dir().join("pandora-server-libraries")
+    }
+
+    #[test]
+    fn test_expand_library_directory_property() {
+        let libraries_dir = libraries_dir();
+
+        let mut expected = OsString::from("-DlibraryDirectory=");
+        expected.push(&libraries_dir);
+        assert_eq!(expand_server_argument("-DlibraryDirectory=libraries", &libraries_dir), expected);
+    }
+
+    #[test]
+    fn test_expand_module_path() {
+        let libraries_dir = libraries_dir();
+        let module_path = format!("libraries/a/a.jar{SEPARATOR}libraries/b/b.jar");
+
+        assert_eq!(expand_server_argument("-p", &libraries_dir), OsString::from("-p"));
+
+        let mut expected = OsString::from(libraries_dir.join("a/a.jar"));
+        expected.push(SEPARATOR);
+        expected.push(libraries_dir.join("b/b.jar"));
+        assert_eq!(expand_server_argument(&module_path, &libraries_dir), expected);
+    }
+
+    #[test]
+    fn test_expand_property_with_several_paths() {
+        let libraries_dir = libraries_dir();
+        let argument = format!("-DlegacyClassPath=libraries/a/a.jar{SEPARATOR}user_jars/b.jar{SEPARATOR}libraries");
+
+        let mut expected = OsString::from("-DlegacyClassPath=");
+        expected.push(libraries_dir.join("a/a.jar"));
+        expected.push(SEPARATOR);
+        expected.push("user_jars/b.jar");
+        expected.push(SEPARATOR);
+        expected.push(&libraries_dir);
+        assert_eq!(expand_server_argument(&argument, &libraries_dir), expected);
+    }
+
+    #[test]
+    fn test_keep_unrelated_arguments() {
+        let libraries_dir = libraries_dir();
+        let other_separator = if cfg!(windows) { ":" } else { ";" };
+
+        for argument in ["@user_jvm_args.txt", "--launchTarget", "forgeserver", "-Dlibraries=other", "mylibraries/a.jar"] {
+            assert_eq!(expand_server_argument(argument, &libraries_dir), OsString::from(argument));
+        }
+
+        // Only the platform's separator splits paths
+        let argument = format!("libraries/a.jar{other_separator}libraries/b.jar");
+        let expected = libraries_dir.join(format!("a.jar{other_separator}libraries/b.jar"));
+        assert_eq!(expand_server_argument(&argument, &libraries_dir), expected.into_os_string());
+    }
+}
@@ -12,8 +12,15 @@ mod backend_filesystem;
 mod backend_handler;
 
 mod account;
+mod account_refresh;
 mod arcfactory;
+mod cleanup;
+mod content_provider;
+mod curseforge;
+mod curseforge_modpack;
 mod directories;
+mod download_manager;
+mod endpoints;
 mod install_content;
 mod instance;
 mod java_manifest;
@@ -23,10 +30,22 @@ mod lockfile;
 mod log_reader;
 mod metadata;
 mod mod_metadata;
+mod modrinth_collection;
+mod offline_session;
 mod id_slab;
 mod persistent;
+mod prepare;
+mod proxy;
+mod secrets;
+mod server_mods;
+mod session;
 mod shortcut;
+mod skins;
 mod syncing;
+mod update_content;
+mod verify;
+mod yggdrasil;
+mod zip_writer;
 
 pub(crate) fn is_single_component_path(path: &str) -> bool {
     let path = std::path::Path::new(path);
@@ -88,6 +107,14 @@ pub(crate) fn write_safe(path: &Path, content: &[u8]) -> std::io::Result<()> {
 }
 
 pub(crate) fn child_state_path(path: &Path) -> Option<PathBuf> {
+    content_state_path(path, ".pandorachildstate")
+}
+
+pub(crate) fn pinned_state_path(path: &Path) -> Option<PathBuf> {
+    content_state_path(path, ".pandorapinned")
+}
+
+fn content_state_path(path: &Path, suffix: &str) -> Option<PathBuf> {
     let mut new_path = path.to_path_buf();
 
     if let Some(extension) = new_path.extension() {
@@ -103,7 +130,7 @@ pub(crate) fn child_state_path(path: &Path) -> Option<PathBuf> {
     let mut new_filename = OsString::new();
     new_filename.push(".");
     new_filename.push(filename);
-    new_filename.push(".pandorachildstate");
+    new_filename.push(suffix);
     new_path.set_file_name(new_filename);
 
     Some(new_path)
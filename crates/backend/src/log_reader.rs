@@ -1,7 +1,7 @@
 use std::{
     borrow::Cow,
-    io::{BufRead, BufReader},
-    process::{ChildStderr, ChildStdout},
+    io::{BufRead, BufReader, Write},
+    process::{ChildStderr, ChildStdin, ChildStdout},
     sync::{atomic::AtomicUsize, Arc},
 };
 
@@ -39,11 +39,32 @@ pub fn replace(string: &str) -> Cow<'_, str> {
     replaced
 }
 
-pub fn start_game_output(stdout: ChildStdout, stderr: Option<ChildStderr>, sender: FrontendHandle) {
+/// Opens a game output window for the process, returning its id
+/// Opens a game output window for the process. If `stdin` is given the window also gets a console,
+/// with each line entered written to the process
+pub fn start_game_output(stdout: ChildStdout, stderr: Option<ChildStderr>, stdin: Option<ChildStdin>, sender: FrontendHandle) -> usize {
     let id = GAME_OUTPUT_ID.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
     let keep_alive = KeepAlive::new();
     let keep_alive_handle = keep_alive.create_handle();
-    sender.send(MessageToFrontend::CreateGameOutputWindow { id, keep_alive });
+
+    let console = stdin.map(|mut stdin| {
+        let (console_sender, mut console_receiver) = tokio::sync::mpsc::unbounded_channel::<Arc<str>>();
+        std::thread::spawn(move || {
+            // Ends once the window is closed or the process exits
+            while let Some(command) = console_receiver.blocking_recv() {
+                let written = stdin.write_all(command.as_bytes())
+                    .and_then(|_| stdin.write_all(b"\n"))
+                    .and_then(|_| stdin.flush());
+                if let Err(error) = written {
+                    log::debug!("Unable to write console command: {error}");
+                    break;
+                }
+            }
+        });
+        console_sender
+    });
+
+    sender.send(MessageToFrontend::CreateGameOutputWindow { id, keep_alive, console });
 
     if let Some(stderr) = stderr {
         let sender = sender.clone();
@@ -125,6 +146,8 @@ pub fn start_game_output(stdout: ChildStdout, stderr: Option<ChildStderr>, sende
             });
         }
     });
+
+    id
 }
 
 #[derive(Error, Debug)]
@@ -4,12 +4,12 @@ use std::{
 
 use reqwest::RequestBuilder;
 use schema::{
-    assets_index::AssetsIndex, fabric_launch::FabricLaunch, fabric_loader_manifest::{FABRIC_LOADER_MANIFEST_URL, FabricLoaderManifest}, forge::{ForgeMavenManifest, NeoforgeMavenManifest, VersionFragment}, java_runtime_component::JavaRuntimeComponentManifest, java_runtimes::{JAVA_RUNTIMES_URL, JavaRuntimes}, maven::MavenMetadataXml, modrinth::{MODRINTH_SEARCH_URL, ModrinthLoader, ModrinthProjectVersion, ModrinthProjectVersionsRequest, ModrinthProjectVersionsResult, ModrinthSearchRequest, ModrinthSearchResult, ModrinthVersionFileUpdateResult}, version::MinecraftVersion, version_manifest::{MOJANG_VERSION_MANIFEST_URL, MinecraftVersionLink, MinecraftVersionManifest}
+    assets_index::AssetsIndex, curseforge::{CURSEFORGE_MINECRAFT_GAME_ID, CurseForgeFile, CurseForgeFingerprintMatchesResult, CurseForgeFingerprintsRequest, CurseForgeMod, CurseForgeModFilesRequest, CurseForgeModFilesResult, CurseForgeResponse, CurseForgeSearchRequest, CurseForgeSearchResult}, fabric_launch::FabricLaunch, fabric_loader_manifest::{FABRIC_LOADER_MANIFEST_URL, FabricLoaderManifest}, forge::{ForgeMavenManifest, NeoforgeMavenManifest, VersionFragment}, java_runtime_component::JavaRuntimeComponentManifest, java_runtimes::{JAVA_RUNTIMES_URL, JavaRuntimes}, maven::MavenMetadataXml, modrinth::{MODRINTH_SEARCH_URL, ModrinthCollection, ModrinthLoader, ModrinthProject, ModrinthProjectVersion, ModrinthProjectVersionsRequest, ModrinthProjectVersionsResult, ModrinthSearchRequest, ModrinthSearchResult, ModrinthVersionFileUpdateResult, ModrinthVersionType}, version::MinecraftVersion, version_manifest::{MOJANG_VERSION_MANIFEST_URL, MinecraftVersionLink, MinecraftVersionManifest}
 };
 use serde::Serialize;
 use ustr::Ustr;
 
-use crate::metadata::manager::{MetaLoadError, MetaLoadStateWrapper, MetadataManager, MetadataManagerStates};
+use crate::{curseforge::CurseForgeApi, metadata::manager::{MetaLoadError, MetaLoadStateWrapper, MetadataManager, MetadataManagerStates}};
 
 pub trait MetadataItem: Debug {
     type T: Send + Sync + 'static;
@@ -24,6 +24,10 @@ pub trait MetadataItem: Debug {
     fn cache_file(&self, _metadata_manager: &MetadataManager) -> Option<impl AsRef<Path> + Send + Sync + 'static> {
         None::<PathBuf>
     }
+    /// Whether items without a cache file should still be saved to disk for use in offline mode
+    fn cache_offline(&self) -> bool {
+        true
+    }
     fn data_hash(&self) -> Option<Ustr> {
         None
     }
@@ -264,6 +268,11 @@ impl<'a> MetadataItem for ModrinthSearchMetadataItem<'a> {
         true
     }
 
+    // Search results are too numerous to be worth keeping around for offline use
+    fn cache_offline(&self) -> bool {
+        false
+    }
+
     fn state(&self, states: &mut MetadataManagerStates) -> MetaLoadStateWrapper<Self::T> {
         states.modrinth_search.entry(self.0.clone()).or_default().clone()
     }
@@ -328,10 +337,83 @@ impl MetadataItem for ModrinthVersionMetadataItem {
     }
 }
 
+#[derive(Debug)]
+pub struct ModrinthProjectMetadataItem(pub Arc<str>);
+
+impl MetadataItem for ModrinthProjectMetadataItem {
+    type T = ModrinthProject;
+
+    fn request(&self, client: &reqwest::Client) -> RequestBuilder {
+        let url = format!("https://api.modrinth.com/v2/project/{}", self.0);
+        client.get(url)
+    }
+
+    fn expires(&self) -> bool {
+        true
+    }
+
+    fn state(&self, states: &mut MetadataManagerStates) -> MetaLoadStateWrapper<Self::T> {
+        states.modrinth_projects.entry(self.0.clone()).or_default().clone()
+    }
+
+    fn deserialize(bytes: &[u8]) -> Result<Self::T, MetaLoadError> {
+        Ok(serde_json::from_slice(bytes)?)
+    }
+}
+
+#[derive(Debug)]
+pub struct ModrinthCollectionMetadataItem(pub Arc<str>);
+
+impl MetadataItem for ModrinthCollectionMetadataItem {
+    type T = ModrinthCollection;
+
+    fn request(&self, client: &reqwest::Client) -> RequestBuilder {
+        let url = format!("https://api.modrinth.com/v3/collection/{}", self.0);
+        client.get(url)
+    }
+
+    fn expires(&self) -> bool {
+        true
+    }
+
+    fn state(&self, states: &mut MetadataManagerStates) -> MetaLoadStateWrapper<Self::T> {
+        states.modrinth_collections.entry(self.0.clone()).or_default().clone()
+    }
+
+    fn deserialize(bytes: &[u8]) -> Result<Self::T, MetaLoadError> {
+        Ok(serde_json::from_slice(bytes)?)
+    }
+}
+
+#[derive(Debug)]
+pub struct ModrinthVersionFileMetadataItem(pub Arc<str>);
+
+impl MetadataItem for ModrinthVersionFileMetadataItem {
+    type T = ModrinthProjectVersion;
+
+    fn request(&self, client: &reqwest::Client) -> RequestBuilder {
+        let url = format!("https://api.modrinth.com/v2/version_file/{}", self.0);
+        client.get(url).query(&[("algorithm", "sha1")])
+    }
+
+    fn expires(&self) -> bool {
+        true
+    }
+
+    fn state(&self, states: &mut MetadataManagerStates) -> MetaLoadStateWrapper<Self::T> {
+        states.modrinth_version_files.entry(self.0.clone()).or_default().clone()
+    }
+
+    fn deserialize(bytes: &[u8]) -> Result<Self::T, MetaLoadError> {
+        Ok(serde_json::from_slice(bytes)?)
+    }
+}
+
 #[derive(Clone, Debug, Serialize)]
 pub struct VersionUpdateParameters {
     pub loaders: Arc<[ModrinthLoader]>,
     pub game_versions: Arc<[Ustr]>,
+    pub version_types: &'static [ModrinthVersionType],
 }
 
 #[derive(Debug)]
@@ -365,6 +447,7 @@ impl MetadataItem for ModrinthVersionUpdateMetadataItem {
 pub struct VersionV3UpdateParameters {
     pub loaders: Arc<[Arc<str>]>,
     pub loader_fields: VersionV3LoaderFields,
+    pub version_types: &'static [ModrinthVersionType],
 }
 
 #[derive(Clone, Debug, Serialize)]
@@ -479,3 +562,146 @@ impl MetadataItem for ForgeInstallerMavenMetadataItem {
         Ok(ForgeMavenManifest(versions.into_iter().rev().collect()))
     }
 }
+
+#[derive(Debug)]
+pub struct CurseForgeSearchMetadataItem<'a> {
+    pub api: &'a CurseForgeApi,
+    pub request: &'a CurseForgeSearchRequest,
+}
+
+impl<'a> MetadataItem for CurseForgeSearchMetadataItem<'a> {
+    type T = CurseForgeSearchResult;
+
+    fn request(&self, client: &reqwest::Client) -> RequestBuilder {
+        self.api.get(client, "/v1/mods/search")
+            .query(&[("gameId", CURSEFORGE_MINECRAFT_GAME_ID)])
+            .query(self.request)
+    }
+
+    fn expires(&self) -> bool {
+        true
+    }
+
+    fn cache_offline(&self) -> bool {
+        false
+    }
+
+    fn state(&self, states: &mut MetadataManagerStates) -> MetaLoadStateWrapper<Self::T> {
+        states.curseforge_search.entry(self.request.clone()).or_default().clone()
+    }
+
+    fn deserialize(bytes: &[u8]) -> Result<Self::T, MetaLoadError> {
+        Ok(serde_json::from_slice(bytes)?)
+    }
+}
+
+#[derive(Debug)]
+pub struct CurseForgeModFilesMetadataItem<'a> {
+    pub api: &'a CurseForgeApi,
+    pub request: &'a CurseForgeModFilesRequest,
+}
+
+impl<'a> MetadataItem for CurseForgeModFilesMetadataItem<'a> {
+    type T = CurseForgeModFilesResult;
+
+    fn request(&self, client: &reqwest::Client) -> RequestBuilder {
+        self.api.get(client, &format!("/v1/mods/{}/files", self.request.mod_id))
+            .query(self.request)
+    }
+
+    fn expires(&self) -> bool {
+        true
+    }
+
+    fn state(&self, states: &mut MetadataManagerStates) -> MetaLoadStateWrapper<Self::T> {
+        states.curseforge_mod_files.entry(self.request.clone()).or_default().clone()
+    }
+
+    fn deserialize(bytes: &[u8]) -> Result<Self::T, MetaLoadError> {
+        Ok(serde_json::from_slice(bytes)?)
+    }
+}
+
+#[derive(Debug)]
+pub struct CurseForgeModMetadataItem<'a> {
+    pub api: &'a CurseForgeApi,
+    pub mod_id: u32,
+}
+
+impl<'a> MetadataItem for CurseForgeModMetadataItem<'a> {
+    type T = CurseForgeMod;
+
+    fn request(&self, client: &reqwest::Client) -> RequestBuilder {
+        self.api.get(client, &format!("/v1/mods/{}", self.mod_id))
+    }
+
+    fn expires(&self) -> bool {
+        true
+    }
+
+    fn state(&self, states: &mut MetadataManagerStates) -> MetaLoadStateWrapper<Self::T> {
+        states.curseforge_mods.entry(self.mod_id).or_default().clone()
+    }
+
+    fn deserialize(bytes: &[u8]) -> Result<Self::T, MetaLoadError> {
+        let response: CurseForgeResponse<CurseForgeMod> = serde_json::from_slice(bytes)?;
+        Ok(response.data)
+    }
+}
+
+#[derive(Debug)]
+pub struct CurseForgeFileMetadataItem<'a> {
+    pub api: &'a CurseForgeApi,
+    pub mod_id: u32,
+    pub file_id: u32,
+}
+
+impl<'a> MetadataItem for CurseForgeFileMetadataItem<'a> {
+    type T = CurseForgeFile;
+
+    fn request(&self, client: &reqwest::Client) -> RequestBuilder {
+        self.api.get(client, &format!("/v1/mods/{}/files/{}", self.mod_id, self.file_id))
+    }
+
+    fn expires(&self) -> bool {
+        true
+    }
+
+    fn state(&self, states: &mut MetadataManagerStates) -> MetaLoadStateWrapper<Self::T> {
+        states.curseforge_files.entry((self.mod_id, self.file_id)).or_default().clone()
+    }
+
+    fn deserialize(bytes: &[u8]) -> Result<Self::T, MetaLoadError> {
+        let response: CurseForgeResponse<CurseForgeFile> = serde_json::from_slice(bytes)?;
+        Ok(response.data)
+    }
+}
+
+#[derive(Debug)]
+pub struct CurseForgeFingerprintsMetadataItem<'a> {
+    pub api: &'a CurseForgeApi,
+    /// Sorted and deduplicated so that the same set of files shares a cache entry
+    pub fingerprints: &'a [u32],
+}
+
+impl<'a> MetadataItem for CurseForgeFingerprintsMetadataItem<'a> {
+    type T = CurseForgeFingerprintMatchesResult;
+
+    fn request(&self, client: &reqwest::Client) -> RequestBuilder {
+        self.api.post(client, &format!("/v1/fingerprints/{}", CURSEFORGE_MINECRAFT_GAME_ID))
+            .json(&CurseForgeFingerprintsRequest { fingerprints: self.fingerprints })
+    }
+
+    fn expires(&self) -> bool {
+        true
+    }
+
+    fn state(&self, states: &mut MetadataManagerStates) -> MetaLoadStateWrapper<Self::T> {
+        states.curseforge_fingerprints.entry(self.fingerprints.into()).or_default().clone()
+    }
+
+    fn deserialize(bytes: &[u8]) -> Result<Self::T, MetaLoadError> {
+        let response: CurseForgeResponse<CurseForgeFingerprintMatchesResult> = serde_json::from_slice(bytes)?;
+        Ok(response.data)
+    }
+}
@@ -1,20 +1,24 @@
 use std::{
-    collections::{HashMap, VecDeque}, fmt::Display, path::Path, sync::Arc, time::{Duration, Instant}
+    collections::{HashMap, VecDeque}, fmt::Display, path::{Path, PathBuf}, sync::{atomic::{AtomicBool, Ordering}, Arc}, time::{Duration, Instant, SystemTime}
 };
 
-use bridge::keep_alive::{KeepAlive, KeepAliveHandle};
+use bridge::{handle::FrontendHandle, keep_alive::{KeepAlive, KeepAliveHandle}, message::MessageToFrontend};
+use indexmap::IndexSet;
+use parking_lot::Mutex;
 use reqwest::StatusCode;
 use schema::{
-    assets_index::AssetsIndex, fabric_launch::FabricLaunch, fabric_loader_manifest::FabricLoaderManifest, forge::{ForgeMavenManifest, NeoforgeMavenManifest}, java_runtime_component::JavaRuntimeComponentManifest, java_runtimes::JavaRuntimes, maven::MavenMetadataXml, modrinth::{ModrinthProjectVersion, ModrinthProjectVersionsRequest, ModrinthProjectVersionsResult, ModrinthSearchRequest, ModrinthSearchResult, ModrinthVersionFileUpdateResult}, version::MinecraftVersion, version_manifest::MinecraftVersionManifest
+    assets_index::AssetsIndex, curseforge::{CurseForgeFile, CurseForgeFingerprintMatchesResult, CurseForgeMod, CurseForgeModFilesRequest, CurseForgeModFilesResult, CurseForgeSearchRequest, CurseForgeSearchResult}, fabric_launch::FabricLaunch, fabric_loader_manifest::FabricLoaderManifest, forge::{ForgeMavenManifest, NeoforgeMavenManifest}, java_runtime_component::JavaRuntimeComponentManifest, java_runtimes::JavaRuntimes, maven::MavenMetadataXml, modrinth::{ModrinthCollection, ModrinthProject, ModrinthProjectVersion, ModrinthProjectVersionsRequest, ModrinthProjectVersionsResult, ModrinthSearchRequest, ModrinthSearchResult, ModrinthVersionFileUpdateResult}, version::MinecraftVersion, version_manifest::MinecraftVersionManifest
 };
 use serde::Deserialize;
 use sha1::{Digest, Sha1};
 use tokio::task::JoinHandle;
 use ustr::Ustr;
 
-use crate::metadata::items::MetadataItem;
+use crate::{endpoints::Endpoints, metadata::items::MetadataItem};
 
 const DATA_TTL: Duration = Duration::from_secs(5 * 60);
+const OFFLINE_CACHE_MAX_AGE: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+const OFFLINE_CACHE_MAX_SIZE: u64 = 256 * 1024 * 1024;
 
 pub(super) type MetaLoadStateWrapper<T> = Arc<tokio::sync::Mutex<(Option<KeepAliveHandle>, MetaLoadState<T>)>>;
 
@@ -31,8 +35,16 @@ pub struct MetadataManagerStates {
     pub(super) java_runtime_manifests: HashMap<Ustr, MetaLoadStateWrapper<JavaRuntimeComponentManifest>>,
     pub(super) modrinth_search: HashMap<ModrinthSearchRequest, MetaLoadStateWrapper<ModrinthSearchResult>>,
     pub(super) modrinth_project_versions: HashMap<ModrinthProjectVersionsRequest, MetaLoadStateWrapper<ModrinthProjectVersionsResult>>,
+    pub(super) modrinth_projects: HashMap<Arc<str>, MetaLoadStateWrapper<ModrinthProject>>,
+    pub(super) modrinth_collections: HashMap<Arc<str>, MetaLoadStateWrapper<ModrinthCollection>>,
     pub(super) modrinth_versions: HashMap<Arc<str>, MetaLoadStateWrapper<ModrinthProjectVersion>>,
     pub(super) modrinth_version_updates: HashMap<Arc<str>, MetaLoadStateWrapper<ModrinthVersionFileUpdateResult>>,
+    pub(super) modrinth_version_files: HashMap<Arc<str>, MetaLoadStateWrapper<ModrinthProjectVersion>>,
+    pub(super) curseforge_search: HashMap<CurseForgeSearchRequest, MetaLoadStateWrapper<CurseForgeSearchResult>>,
+    pub(super) curseforge_mod_files: HashMap<CurseForgeModFilesRequest, MetaLoadStateWrapper<CurseForgeModFilesResult>>,
+    pub(super) curseforge_mods: HashMap<u32, MetaLoadStateWrapper<CurseForgeMod>>,
+    pub(super) curseforge_files: HashMap<(u32, u32), MetaLoadStateWrapper<CurseForgeFile>>,
+    pub(super) curseforge_fingerprints: HashMap<Box<[u32]>, MetaLoadStateWrapper<CurseForgeFingerprintMatchesResult>>,
 }
 
 pub struct MetadataManager {
@@ -44,6 +56,10 @@ pub struct MetadataManager {
     pub(super) fabric_loader_manifest_cache: Arc<Path>,
     pub(super) neoforge_installer_maven_cache: Arc<Path>,
     pub(super) forge_installer_maven_cache: Arc<Path>,
+    offline_cache: Arc<Path>,
+
+    pub connectivity: Arc<Connectivity>,
+    pub endpoints: Arc<Endpoints>,
 
     expiring: tokio::sync::Mutex<VecDeque<(Instant, KeepAlive)>>,
 
@@ -60,6 +76,7 @@ pub enum MetaLoadError {
     Error(Arc<str>),
     ErrorWithDescription(Arc<str>, Arc<str>),
     NonOK(u16),
+    Offline,
 }
 
 impl Display for MetaLoadError {
@@ -107,8 +124,75 @@ impl Display for MetaLoadError {
                 f.write_fmt(format_args!("Non-OK response: {}", *status_code))
             }
             Self::TokioJoin(error) => f.debug_tuple("TokioJoin").field(error).finish(),
+            Self::Offline => {
+                f.write_str("Not available while offline")
+            }
+        }
+    }
+}
+
+impl MetaLoadError {
+    /// Whether the error was caused by being unable to reach the server at all
+    pub fn is_connectivity_error(&self) -> bool {
+        match self {
+            Self::Reqwest(error) => error.is_connect(),
+            Self::Offline => true,
+            _ => false,
+        }
+    }
+}
+
+/// Tracks whether the launcher is in offline mode, along with the resources that
+/// were requested while offline but had nothing cached
+pub struct Connectivity {
+    offline: AtomicBool,
+    unavailable: Mutex<IndexSet<Arc<str>>>,
+    // Replaced when going back online, invalidating everything that was loaded while offline
+    offline_keep_alive: Mutex<KeepAlive>,
+    send: FrontendHandle,
+}
+
+impl Connectivity {
+    pub fn new(send: FrontendHandle) -> Self {
+        Self {
+            offline: AtomicBool::new(false),
+            unavailable: Default::default(),
+            offline_keep_alive: Default::default(),
+            send,
+        }
+    }
+
+    pub fn is_offline(&self) -> bool {
+        self.offline.load(Ordering::Relaxed)
+    }
+
+    pub fn set_offline(&self, offline: bool) {
+        if self.offline.swap(offline, Ordering::Relaxed) == offline {
+            return;
+        }
+
+        if offline {
+            log::warn!("Switching to offline mode");
+        } else {
+            log::info!("Switching to online mode");
+            self.unavailable.lock().clear();
+            *self.offline_keep_alive.lock() = KeepAlive::new();
+        }
+        self.send_update();
+    }
+
+    pub fn mark_unavailable(&self, what: Arc<str>) {
+        if self.unavailable.lock().insert(what) {
+            self.send_update();
         }
     }
+
+    pub fn send_update(&self) {
+        self.send.send(MessageToFrontend::ConnectivityUpdated {
+            offline: self.is_offline(),
+            unavailable: self.unavailable.lock().iter().cloned().collect(),
+        });
+    }
 }
 
 impl From<reqwest::Error> for MetaLoadError {
@@ -145,7 +229,7 @@ pub enum MetaLoadState<T> {
 }
 
 impl MetadataManager {
-    pub fn new(http_client: reqwest::Client, directory: Arc<Path>) -> Self {
+    pub fn new(http_client: reqwest::Client, directory: Arc<Path>, send: FrontendHandle, endpoints: Arc<Endpoints>) -> Self {
         Self {
             states: tokio::sync::Mutex::new(MetadataManagerStates::default()),
 
@@ -154,8 +238,12 @@ impl MetadataManager {
             fabric_loader_manifest_cache: directory.join("fabric_loader_manifest.json").into(),
             neoforge_installer_maven_cache: directory.join("neoforge_installer_maven.xml").into(),
             forge_installer_maven_cache: directory.join("forge_installer_maven.xml").into(),
+            offline_cache: directory.join("offline").into(),
             metadata_cache: directory,
 
+            connectivity: Arc::new(Connectivity::new(send)),
+            endpoints,
+
             expiring: Default::default(),
 
             http_client,
@@ -180,20 +268,17 @@ impl MetadataManager {
         let mut wrapper = wrapper.lock().await;
 
         let is_valid = wrapper.0.as_ref().map(|h| h.is_alive()).unwrap_or(true);
-        if !is_valid || matches!(wrapper.1, MetaLoadState::Unloaded) {
-            if item.expires() {
-                let keep_alive = KeepAlive::new();
-                let handle = keep_alive.create_handle();
-                wrapper.0 = Some(handle);
-                self.expiring.lock().await.push_back((Instant::now() + DATA_TTL, keep_alive));
-            }
+        if !is_valid || self.needs_load(&wrapper.1) {
+            wrapper.0 = self.create_keep_alive(item).await;
 
-            let cache_file = item.cache_file(self);
+            let cache_file = self.cache_file_for(item);
             Self::inner_start_loading(
                 &mut wrapper.1,
                 item,
                 cache_file,
                 &self.http_client,
+                &self.connectivity,
+                &self.endpoints,
             );
         }
     }
@@ -207,20 +292,17 @@ impl MetadataManager {
         let mut wrapper = wrapper.lock().await;
 
         let is_valid = wrapper.0.as_ref().map(|h| h.is_alive()).unwrap_or(true);
-        if force_reload || !is_valid || matches!(wrapper.1, MetaLoadState::Unloaded) {
-            if item.expires() {
-                let keep_alive = KeepAlive::new();
-                let handle = keep_alive.create_handle();
-                wrapper.0 = Some(handle);
-                self.expiring.lock().await.push_back((Instant::now() + DATA_TTL, keep_alive));
-            }
+        if force_reload || !is_valid || self.needs_load(&wrapper.1) {
+            wrapper.0 = self.create_keep_alive(item).await;
 
-            let cache_file = item.cache_file(self);
+            let cache_file = self.cache_file_for(item);
             Self::inner_start_loading(
                 &mut wrapper.1,
                 item,
                 cache_file,
                 &self.http_client,
+                &self.connectivity,
+                &self.endpoints,
             );
         }
 
@@ -250,15 +332,77 @@ impl MetadataManager {
         }
     }
 
+    /// Items loaded while offline may have come from an outdated cache, so they're
+    /// kept alive only until the launcher is back online
+    async fn create_keep_alive<I: MetadataItem>(&self, item: &I) -> Option<KeepAliveHandle> {
+        if self.connectivity.is_offline() {
+            Some(self.connectivity.offline_keep_alive.lock().create_handle())
+        } else if item.expires() {
+            let keep_alive = KeepAlive::new();
+            let handle = keep_alive.create_handle();
+            self.expiring.lock().await.push_back((Instant::now() + DATA_TTL, keep_alive));
+            Some(handle)
+        } else {
+            None
+        }
+    }
+
+    pub async fn evict_offline_cache(&self) {
+        let directory = Arc::clone(&self.offline_cache);
+        _ = tokio::task::spawn_blocking(move || {
+            evict_offline_cache(&directory, SystemTime::now(), OFFLINE_CACHE_MAX_AGE, OFFLINE_CACHE_MAX_SIZE);
+        }).await;
+    }
+
+    fn needs_load<T>(&self, state: &MetaLoadState<T>) -> bool {
+        match state {
+            MetaLoadState::Unloaded => true,
+            // Items that were unavailable while offline should be retried once back online
+            MetaLoadState::Error(error) if error.is_connectivity_error() => !self.connectivity.is_offline(),
+            _ => false,
+        }
+    }
+
+    /// Items without a dedicated cache file are stored under a hash of their request so that
+    /// they can still be served in offline mode
+    fn cache_file_for<I: MetadataItem>(&self, item: &I) -> Option<PathBuf> {
+        if let Some(cache_file) = item.cache_file(self) {
+            return Some(cache_file.as_ref().to_path_buf());
+        }
+        if !item.cache_offline() {
+            return None;
+        }
+
+        let request = item.request(&self.http_client).build().ok()?;
+        let mut hasher = Sha1::new();
+        hasher.update(request.method().as_str());
+        hasher.update(request.url().as_str());
+        if let Some(body) = request.body().and_then(|body| body.as_bytes()) {
+            hasher.update(body);
+        }
+        Some(self.offline_cache.join(hex::encode(hasher.finalize())))
+    }
+
     fn inner_start_loading<I: MetadataItem>(
         state: &mut MetaLoadState<I::T>,
         item: &I,
-        cache_file: Option<impl AsRef<Path> + Send + Sync + 'static>,
+        cache_file: Option<PathBuf>,
         http_client: &reqwest::Client,
+        connectivity: &Arc<Connectivity>,
+        endpoints: &Arc<Endpoints>,
     ) {
         log::debug!("Loading metadata {:?}", item);
 
         let request = item.request(http_client);
+        let description: Arc<str> = match request.try_clone().and_then(|request| request.build().ok()) {
+            Some(request) => {
+                let url = request.url();
+                format!("{}{}", url.host_str().unwrap_or_default(), url.path()).into()
+            },
+            None => std::any::type_name::<I::T>().into(),
+        };
+        let connectivity = Arc::clone(connectivity);
+        let endpoints = Arc::clone(endpoints);
         let expected_hash = item.data_hash().and_then(|sha1| {
             let mut expected_hash = [0u8; 20];
             hex::decode_to_slice(sha1.as_str(), &mut expected_hash).ok()?;
@@ -267,8 +411,10 @@ impl MetadataManager {
         let join_handle = tokio::task::spawn(async move {
             let mut file_fallback = None;
 
+            let offline = connectivity.is_offline();
+
             if let Some(cache_file) = &cache_file {
-                let cache_file = cache_file.as_ref().to_owned();
+                let cache_file = cache_file.clone();
                 let meta = tokio::task::spawn_blocking(move || {
                     let Ok(file) = std::fs::read(&cache_file) else {
                         return None;
@@ -301,7 +447,8 @@ impl MetadataManager {
                     }
                 }).await.unwrap();
                 if let Some(meta) = meta {
-                    if expected_hash.is_some() {
+                    // In offline mode the cache is used regardless of how old it is
+                    if expected_hash.is_some() || offline {
                         return Ok(Arc::new(meta));
                     } else {
                         file_fallback = Some(Arc::new(meta));
@@ -309,8 +456,13 @@ impl MetadataManager {
                 }
             }
 
+            if offline {
+                connectivity.mark_unavailable(description);
+                return Err(MetaLoadError::Offline);
+            }
+
             let mut result: Result<Arc<I::T>, MetaLoadError> = async move {
-                let response = request.send().await?;
+                let response = endpoints.send(request).await?;
 
                 let status = response.status();
                 if status != StatusCode::OK {
@@ -356,7 +508,7 @@ impl MetadataManager {
                 }
 
                 if let Some(cache_file) = &cache_file {
-                    if let Some(parent) = cache_file.as_ref().parent() {
+                    if let Some(parent) = cache_file.parent() {
                         let _ = tokio::fs::create_dir_all(parent).await;
                     }
                     let _ = tokio::fs::write(cache_file, bytes).await;
@@ -367,6 +519,10 @@ impl MetadataManager {
             .await;
 
             if let Err(error) = &result {
+                if error.is_connectivity_error() {
+                    connectivity.set_offline(true);
+                }
+
                 if let Some(file_fallback) = file_fallback {
                     log::warn!(
                         "Error while fetching metadata {:?}, using file fallback: {error:?}",
@@ -375,6 +531,9 @@ impl MetadataManager {
                     result = Ok(file_fallback);
                 } else {
                     log::error!("Error while fetching metadata {:?}: {error:?}", std::any::type_name::<I::T>());
+                    if error.is_connectivity_error() {
+                        connectivity.mark_unavailable(description);
+                    }
                 }
             }
 
@@ -384,3 +543,128 @@ impl MetadataManager {
         *state = MetaLoadState::Pending(join_handle);
     }
 }
+
+/// Removes entries that haven't been refreshed within `max_age`, then the least recently
+/// refreshed entries until the rest fit within `max_size`
+fn evict_offline_cache(directory: &Path, now: SystemTime, max_age: Duration, max_size: u64) {
+    let Ok(read_dir) = std::fs::read_dir(directory) else {
+        return;
+    };
+
+    let mut entries = Vec::new();
+    for entry in read_dir.flatten() {
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        if metadata.is_file() {
+            let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+            entries.push((modified, metadata.len(), entry.path()));
+        }
+    }
+
+    // Entries are rewritten every time they're fetched online, so the newest are kept first
+    entries.sort_by_key(|(modified, _, _)| std::cmp::Reverse(*modified));
+
+    let mut total_size = 0;
+    let mut full = false;
+    for (modified, size, path) in entries {
+        let expired = now.duration_since(modified).is_ok_and(|age| age > max_age);
+        full = full || total_size + size > max_size;
+        if expired || full {
+            if let Err(error) = std::fs::remove_file(&path) {
+                log::warn!("Unable to evict offline metadata {:?}: {error}", path);
+            }
+        } else {
+            total_size += size;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicUsize;
+
+    use auth::test_util::{self, MockResponse, TempDir};
+    use parking_lot::RwLock;
+    use schema::backend_config::BackendConfig;
+
+    use crate::{curseforge::CurseForgeApi, metadata::items::CurseForgeModMetadataItem, persistent::Persistent};
+
+    use super::*;
+
+    fn write_entry(dir: &Path, name: &str, size: usize, modified: SystemTime) {
+        let path = dir.join(name);
+        std::fs::write(&path, vec![0u8; size]).unwrap();
+        std::fs::File::options().write(true).open(&path).unwrap().set_modified(modified).unwrap();
+    }
+
+    #[test]
+    fn test_evict_expired() {
+        let dir = TempDir::new("metadata-evict-expired");
+        let now = SystemTime::now();
+        let day = Duration::from_secs(24 * 60 * 60);
+
+        write_entry(&dir, "recent", 10, now - day);
+        write_entry(&dir, "old", 10, now - day * 40);
+
+        evict_offline_cache(&dir, now, day * 30, u64::MAX);
+        assert!(dir.join("recent").exists());
+        assert!(!dir.join("old").exists());
+    }
+
+    #[test]
+    fn test_evict_oldest_over_size() {
+        let dir = TempDir::new("metadata-evict-size");
+        let now = SystemTime::now();
+        let minute = Duration::from_secs(60);
+
+        write_entry(&dir, "newest", 100, now - minute);
+        write_entry(&dir, "middle", 100, now - minute * 2);
+        write_entry(&dir, "oldest", 100, now - minute * 3);
+
+        evict_offline_cache(&dir, now, OFFLINE_CACHE_MAX_AGE, 250);
+        assert!(dir.join("newest").exists());
+        assert!(dir.join("middle").exists());
+        assert!(!dir.join("oldest").exists());
+    }
+
+    /// Serves a CurseForge mod whose name changes with every request
+    async fn start_mock_server(requests: Arc<AtomicUsize>) -> String {
+        test_util::start_mock_server(move |_| {
+            let count = requests.fetch_add(1, Ordering::SeqCst) + 1;
+            MockResponse::json("200 OK", serde_json::json!({
+                "data": {
+                    "id": 1234,
+                    "name": format!("Example {count}"),
+                    "slug": "example",
+                    "downloadCount": 0.0,
+                    "authors": [],
+                },
+            }))
+        }).await
+    }
+
+    #[tokio::test]
+    async fn test_refresh_after_offline() {
+        let requests = Arc::new(AtomicUsize::new(0));
+        let api = CurseForgeApi::new(start_mock_server(Arc::clone(&requests)).await.into(), Some("key".into()));
+
+        let dir = TempDir::new("metadata-refresh");
+        let config: Persistent<BackendConfig> = Persistent::load(dir.join("config.json").into());
+        let endpoints = Arc::new(Endpoints::new(Arc::new(RwLock::new(config))));
+        let (_, _, _, send) = bridge::handle::create_pair();
+        let meta = MetadataManager::new(reqwest::Client::new(), dir.join("metadata").into(), send, endpoints);
+
+        let item = CurseForgeModMetadataItem { api: &api, mod_id: 1234 };
+        assert_eq!(&*meta.fetch(&item).await.unwrap().name, "Example 1");
+
+        // Loading while offline is served from the cache without any request
+        meta.connectivity.set_offline(true);
+        let (result, _) = meta.fetch_with_keepalive(&item, true).await;
+        assert_eq!(&*result.unwrap().name, "Example 1");
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+
+        meta.connectivity.set_offline(false);
+        assert_eq!(&*meta.fetch(&item).await.unwrap().name, "Example 2");
+    }
+}
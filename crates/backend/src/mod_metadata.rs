@@ -9,7 +9,7 @@ use parking_lot::{RwLock, RwLockReadGuard};
 use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
 use rc_zip_sync::EntryHandle;
 use rustc_hash::{FxHashMap, FxHashSet};
-use schema::{content::ContentSource, fabric_mod::{FabricModJson, Icon, Person}, forge_mod::{JarJarMetadata, ModsToml}, modrinth::{ModrinthFile, ModrinthSideRequirement}, mrpack::ModrinthIndexJson, resourcepack::PackMcmeta};
+use schema::{content::ContentSource, curseforge::CurseForgeFile, fabric_mod::{FabricEnvironment, FabricModJson, Icon, Person}, forge_mod::{JarJarMetadata, ModsToml}, modrinth::{ModrinthFile, ModrinthSideRequirement}, mrpack::ModrinthIndexJson, resourcepack::PackMcmeta};
 use serde::{Deserialize, Serialize};
 use serde_with::{serde_as, DeserializeAs};
 use sha1::{Digest, Sha1};
@@ -19,10 +19,16 @@ pub enum ModUpdateAction {
     ErrorNotFound,
     ErrorInvalidHash,
     AlreadyUpToDate,
+    /// Newer versions exist, but none of them are allowed by the release channel
+    NoVersionInChannel,
     ManualInstall,
     Modrinth {
         file: ModrinthFile,
         project_id: Arc<str>,
+        version_id: Arc<str>,
+    },
+    CurseForge {
+        file: CurseForgeFile,
     },
 }
 
@@ -32,8 +38,10 @@ impl ModUpdateAction {
             ModUpdateAction::ErrorNotFound => ContentUpdateStatus::ErrorNotFound,
             ModUpdateAction::ErrorInvalidHash => ContentUpdateStatus::ErrorInvalidHash,
             ModUpdateAction::AlreadyUpToDate => ContentUpdateStatus::AlreadyUpToDate,
+            ModUpdateAction::NoVersionInChannel => ContentUpdateStatus::NoVersionInChannel,
             ModUpdateAction::ManualInstall => ContentUpdateStatus::ManualInstall,
             ModUpdateAction::Modrinth { .. } => ContentUpdateStatus::Modrinth,
+            ModUpdateAction::CurseForge { .. } => ContentUpdateStatus::CurseForge,
         }
     }
 }
@@ -170,18 +178,7 @@ impl ModMetadataManager {
     }
 
     fn load_fabric_mod<R: rc_zip_sync::HasCursor>(self: &Arc<Self>, hash: [u8; 20], archive: &rc_zip_sync::ArchiveHandle<R>, file: EntryHandle<'_, R>) -> Option<Arc<ContentSummary>> {
-        let mut bytes = file.bytes().ok()?;
-
-        // Some mods violate the JSON spec by using raw newline characters inside strings (e.g. BetterGrassify)
-        for byte in bytes.iter_mut() {
-            if *byte == '\n' as u8 {
-                *byte = ' ' as u8;
-            }
-        }
-
-        let fabric_mod_json: FabricModJson = serde_json::from_slice(&bytes).inspect_err(|e| {
-            log::error!("Error parsing fabric.mod.json: {e}");
-        }).ok()?;
+        let fabric_mod_json = parse_fabric_mod_json(file.bytes().ok()?)?;
 
         drop(file);
 
@@ -500,6 +497,30 @@ fn load_icon<R: rc_zip_sync::HasCursor>(icon_file: rc_zip_sync::EntryHandle<R>)
     Some(icon_bytes.into())
 }
 
+fn parse_fabric_mod_json(mut bytes: Vec<u8>) -> Option<FabricModJson> {
+    // Some mods violate the JSON spec by using raw newline characters inside strings (e.g. BetterGrassify)
+    for byte in bytes.iter_mut() {
+        if *byte == '\n' as u8 {
+            *byte = ' ' as u8;
+        }
+    }
+
+    serde_json::from_slice(&bytes).inspect_err(|e| {
+        log::error!("Error parsing fabric.mod.json: {e}");
+    }).ok()
+}
+
+/// Reads the environment a Fabric mod declares in its fabric.mod.json, this isn't part of the cached summary
+/// since only syncing to a server needs it
+pub fn read_fabric_environment(path: &Path) -> Option<FabricEnvironment> {
+    use rc_zip_sync::ReadZip;
+
+    let file = std::fs::File::open(path).ok()?;
+    let archive = file.read_zip().ok()?;
+    let entry = archive.by_name("fabric.mod.json")?;
+    Some(parse_fabric_mod_json(entry.bytes().ok()?)?.environment)
+}
+
 fn create_authors_string(authors: &[Person]) -> Option<String> {
     if !authors.is_empty() {
         let mut authors_string = "By ".to_owned();
@@ -551,6 +572,7 @@ impl ContentSources {
                     ContentSource::ModrinthProject { project: _ } => {
                         old_source == &value || value == ContentSource::ModrinthUnknown
                     },
+                    ContentSource::CurseForgeProject { project: _ } => old_source == &value,
                 };
                 if skip {
                     return false;
@@ -613,6 +635,11 @@ impl ContentSources {
                 data.push(project.len() as u8);
                 data.extend_from_slice(project.as_bytes());
             },
+            ContentSource::CurseForgeProject { project } => {
+                data.push(3_u8);
+                data.push(4_u8);
+                data.extend_from_slice(&project.to_le_bytes());
+            },
         }
     }
 
@@ -702,6 +729,15 @@ impl ContentSources {
 
                         ContentSource::ModrinthProject { project: project_id.into() }
                     },
+                    3 => {
+                        let mut project_buf = [0_u8; 4];
+
+                        if type_and_size_buf[1] != 4 || cursor.read_exact(&mut project_buf).is_err() {
+                            break;
+                        }
+
+                        ContentSource::CurseForgeProject { project: u32::from_le_bytes(project_buf) }
+                    },
                     _ => {
                         cursor.consume(type_and_size_buf[1] as usize);
                         continue;
@@ -0,0 +1,234 @@
+use std::sync::Arc;
+
+use auth::secret::{SecretStorage, SecretStorageError};
+use bridge::{
+    install::{ContentDownload, ContentInstall, ContentInstallFile, ContentInstallPath, InstallTarget}, instance::InstanceID, message::{ModrinthProjectList, ModrinthProjectListSource, SkippedProject}, modal_action::{ModalAction, ProgressTracker, ProgressTrackerFinishType}
+};
+use schema::{content::ContentSource, instance::{InstanceKind, ReleaseChannel}, loader::Loader, modrinth::{ModrinthProject, ModrinthProjectVersionsRequest, ModrinthUser}};
+use tokio::sync::Semaphore;
+
+use crate::{metadata::{items::{ModrinthCollectionMetadataItem, ModrinthProjectMetadataItem, ModrinthProjectVersionsMetadataItem}, manager::MetaLoadError}, BackendState};
+
+pub(crate) const MODRINTH_TOKEN_SECRET: &str = "modrinth_token";
+
+#[derive(thiserror::Error, Debug)]
+pub enum ModrinthApiError {
+    #[error("No Modrinth personal access token has been set")]
+    MissingToken,
+    #[error("Error initializing secret storage: {0}")]
+    SecretStorageInit(Arc<str>),
+    #[error("Error accessing secret storage: {0}")]
+    SecretStorage(#[from] SecretStorageError),
+    #[error("Failed to load metadata:\n{0}")]
+    MetaLoadError(#[from] MetaLoadError),
+    #[error("Failed to request from Modrinth:\n{0}")]
+    Reqwest(#[from] reqwest::Error),
+}
+
+impl BackendState {
+    async fn modrinth_secret_storage(&self) -> Result<Arc<SecretStorage>, ModrinthApiError> {
+        match self.get_secret_storage().await {
+            Ok(secret_storage) => Ok(secret_storage),
+            Err(error) => Err(ModrinthApiError::SecretStorageInit(error.to_string().into())),
+        }
+    }
+
+    pub async fn set_modrinth_token(&self, token: Option<Arc<str>>) -> Result<(), ModrinthApiError> {
+        let secret_storage = self.modrinth_secret_storage().await?;
+        match token {
+            Some(token) => secret_storage.write_secret(MODRINTH_TOKEN_SECRET, token.trim()).await?,
+            None => secret_storage.delete_secret(MODRINTH_TOKEN_SECRET).await?,
+        }
+        Ok(())
+    }
+
+    async fn modrinth_token(&self) -> Result<String, ModrinthApiError> {
+        let secret_storage = self.modrinth_secret_storage().await?;
+        secret_storage.read_secret(MODRINTH_TOKEN_SECRET).await?.ok_or(ModrinthApiError::MissingToken)
+    }
+
+    pub async fn get_modrinth_project_list(&self, source: ModrinthProjectListSource) -> Result<ModrinthProjectList, ModrinthApiError> {
+        match source {
+            ModrinthProjectListSource::Collection(id) => {
+                let collection = self.meta.fetch(&ModrinthCollectionMetadataItem(id)).await?;
+
+                let semaphore = Semaphore::new(8);
+                let futures = collection.projects.iter().map(|project_id| {
+                    let semaphore = &semaphore;
+                    async move {
+                        let _permit = semaphore.acquire().await.unwrap();
+                        self.meta.fetch(&ModrinthProjectMetadataItem(project_id.clone())).await
+                    }
+                });
+                let results = futures::future::join_all(futures).await;
+
+                let mut projects = Vec::with_capacity(results.len());
+                let mut failed = Vec::new();
+                for (project_id, result) in collection.projects.iter().zip(results) {
+                    match result {
+                        Ok(project) => projects.push(project),
+                        Err(error) => failed.push(SkippedProject {
+                            name: project_id.clone(),
+                            reason: error.to_string().into(),
+                        }),
+                    }
+                }
+
+                Ok(ModrinthProjectList {
+                    name: collection.name.clone(),
+                    projects: projects.into(),
+                    failed: failed.into(),
+                })
+            },
+            ModrinthProjectListSource::FollowedProjects => {
+                let token = self.modrinth_token().await?;
+
+                let user: ModrinthUser = self.meta.endpoints.send(self.http_client.get("https://api.modrinth.com/v2/user")
+                    .header("Authorization", &token)).await?
+                    .error_for_status()?
+                    .json().await?;
+
+                let projects: Vec<ModrinthProject> = self.meta.endpoints.send(self.http_client.get(format!("https://api.modrinth.com/v2/user/{}/follows", user.id))
+                    .header("Authorization", &token)).await?
+                    .error_for_status()?
+                    .json().await?;
+
+                Ok(ModrinthProjectList {
+                    name: format!("Followed by {}", user.username).into(),
+                    projects: projects.into_iter().map(Arc::new).collect(),
+                    failed: Arc::from([]),
+                })
+            },
+        }
+    }
+
+    /// Installs the latest compatible version of every project into the instance as a single install,
+    /// returning the projects that were skipped because they have no compatible version or failed to resolve
+    pub async fn install_modrinth_projects(&self, id: InstanceID, project_ids: &[Arc<str>], modal_action: &ModalAction) -> Vec<SkippedProject> {
+        let (loader, minecraft_version, release_channel, kind) = if let Some(instance) = self.instance_state.write().instances.get_mut(id) {
+            let configuration = instance.configuration.get();
+            (configuration.loader, configuration.minecraft_version, configuration.release_channel, configuration.kind)
+        } else {
+            modal_action.set_error_message("Can't install into instance, unknown id".into());
+            return Vec::new();
+        };
+        let tracker = ProgressTracker::new("Resolving project versions".into(), self.send.clone());
+        modal_action.trackers.push(tracker.clone());
+        tracker.set_total(project_ids.len());
+        tracker.notify();
+
+        let semaphore = Semaphore::new(8);
+        let futures = project_ids.iter().map(|project_id| {
+            let (semaphore, tracker) = (&semaphore, &tracker);
+            async move {
+                let _permit = semaphore.acquire().await.unwrap();
+                let result = self.resolve_modrinth_project(project_id, kind, loader, minecraft_version.as_str(), release_channel).await;
+                tracker.add_count(1);
+                tracker.notify();
+                (project_id, result)
+            }
+        });
+
+        let resolved = futures::future::join_all(futures).await;
+        let mut files = Vec::new();
+        let mut skipped = Vec::new();
+        for (project_id, result) in resolved {
+            match result {
+                Ok(version_id) => files.push(ContentInstallFile {
+                    replace_old: None,
+                    path: ContentInstallPath::Automatic,
+                    download: ContentDownload::Modrinth {
+                        project_id: project_id.clone(),
+                        version_id: Some(version_id),
+                    },
+                    content_source: ContentSource::ModrinthProject { project: project_id.clone() },
+                }),
+                Err(skipped_project) => skipped.push(skipped_project),
+            }
+        }
+
+        tracker.set_finished(if files.is_empty() {
+            ProgressTrackerFinishType::Error
+        } else {
+            ProgressTrackerFinishType::Fast
+        });
+        tracker.notify();
+
+        if files.is_empty() {
+            modal_action.set_error_message(format!("None of the projects could be installed for {} {}",
+                loader.name(), minecraft_version).into());
+            return skipped;
+        }
+
+        let content_install = ContentInstall {
+            target: InstallTarget::Instance(id),
+            loader_hint: loader,
+            version_hint: Some(minecraft_version.as_str().into()),
+            files: files.into(),
+        };
+        self.install_content(content_install, modal_action.clone()).await;
+
+        skipped
+    }
+
+    /// Finds the id of the newest version of the project that can be installed into the instance
+    async fn resolve_modrinth_project(
+        &self,
+        project_id: &Arc<str>,
+        kind: InstanceKind,
+        loader: Loader,
+        minecraft_version: &str,
+        release_channel: ReleaseChannel,
+    ) -> Result<Arc<str>, SkippedProject> {
+        let failed = |error: MetaLoadError| SkippedProject {
+            name: project_id.clone(),
+            reason: format!("Unable to load project: {error}").into(),
+        };
+
+        // Projects that don't run on the instance's side are reported as incompatible
+        if kind == InstanceKind::Server {
+            let project = self.meta.fetch(&ModrinthProjectMetadataItem(project_id.clone())).await.map_err(failed)?;
+            let client_side = project.client_side.unwrap_or_default();
+            let server_side = project.server_side.unwrap_or_default();
+            if !kind.supports(client_side, server_side) {
+                return Err(SkippedProject {
+                    name: project.title.clone(),
+                    reason: "Doesn't run on servers".into(),
+                });
+            }
+        }
+
+        let versions = self.meta.fetch(&ModrinthProjectVersionsMetadataItem(&ModrinthProjectVersionsRequest {
+            project_id: project_id.clone(),
+            game_versions: Some([minecraft_version.into()].into()),
+            loaders: None,
+        })).await.map_err(failed)?;
+
+        let modrinth_loader = loader.as_modrinth_loader();
+
+        // Mods must match the instance's loader, other content (resourcepacks, shaders) only the game version
+        let version = versions.0.iter()
+            .filter(|version| release_channel.allows(version.version_type) && !version.files.is_empty())
+            .find(|version| {
+                let Some(loaders) = &version.loaders else {
+                    return false;
+                };
+                let is_mod = loaders.iter().any(|loader| loader.install_directory() == Some("mods"));
+                !is_mod || (loader != Loader::Vanilla && loaders.contains(&modrinth_loader))
+            });
+
+        match version {
+            Some(version) => Ok(version.id.clone()),
+            None => {
+                let name = match self.meta.fetch(&ModrinthProjectMetadataItem(project_id.clone())).await {
+                    Ok(project) => project.title.clone(),
+                    Err(_) => project_id.clone(),
+                };
+                Err(SkippedProject {
+                    name,
+                    reason: format!("No version compatible with {} {minecraft_version}", loader.name()).into(),
+                })
+            },
+        }
+    }
+}
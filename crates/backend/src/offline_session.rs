@@ -0,0 +1,258 @@
+use std::{sync::Arc, time::{Duration, SystemTime}};
+
+use base64::Engine;
+use bridge::modal_action::ModalAction;
+use serde_json::json;
+use tokio::{io::{AsyncReadExt, AsyncWriteExt}, net::{TcpListener, TcpStream}};
+use uuid::Uuid;
+
+use crate::{BackendState, account::AuthlibInjectorLaunch, yggdrasil::YggdrasilLoginError};
+
+/// Larger requests are never sent by the game, so anything past this is rejected
+const MAX_REQUEST_SIZE: usize = 64 * 1024;
+const ACCEPT_BACKOFF_MIN: Duration = Duration::from_millis(50);
+const ACCEPT_BACKOFF_MAX: Duration = Duration::from_secs(5);
+
+#[derive(thiserror::Error, Debug)]
+enum OfflineSessionError {
+    #[error("Unable to start the local session server: {0}")]
+    StartServer(std::io::Error),
+    #[error("{0}")]
+    AuthlibInjector(#[from] YggdrasilLoginError),
+}
+
+impl BackendState {
+    /// Points the game at the local session server so offline accounts see their own skin. The server only listens
+    /// on localhost, so other players don't see it. Launching continues without the skin if that isn't possible
+    pub async fn offline_skin_launch(&self, modal_action: &ModalAction) -> Option<AuthlibInjectorLaunch> {
+        match self.try_offline_skin_launch(modal_action).await {
+            Ok(launch) => Some(launch),
+            Err(error) => {
+                log::warn!("Unable to serve the offline skin: {error}");
+                self.send.send_warning(format!("Launching without the offline skin: {error}"));
+                None
+            },
+        }
+    }
+
+    async fn try_offline_skin_launch(&self, modal_action: &ModalAction) -> Result<AuthlibInjectorLaunch, OfflineSessionError> {
+        let api_root = self.offline_session.get_or_try_init(|| self.start_offline_session_server()).await?.clone();
+        let agent_path = self.ensure_authlib_injector(modal_action).await?;
+        let prefetched = base64::engine::general_purpose::STANDARD.encode(metadata().to_string()).into();
+
+        Ok(AuthlibInjectorLaunch {
+            agent_path,
+            api_root,
+            prefetched,
+        })
+    }
+
+    /// Starts the server on a random local port, returning its api root. It lives for the rest of the session
+    async fn start_offline_session_server(&self) -> Result<Arc<str>, OfflineSessionError> {
+        let listener = TcpListener::bind("127.0.0.1:0").await.map_err(OfflineSessionError::StartServer)?;
+        let address = listener.local_addr().map_err(OfflineSessionError::StartServer)?;
+        let api_root: Arc<str> = format!("http://{address}/").into();
+
+        log::info!("Started offline session server on {address}");
+
+        let backend = self.clone();
+        let root = api_root.clone();
+        tokio::task::spawn(async move {
+            let mut backoff = ACCEPT_BACKOFF_MIN;
+            loop {
+                let stream = match listener.accept().await {
+                    Ok((stream, _)) => stream,
+                    Err(error) => {
+                        // Errors such as running out of file descriptors persist for a while, so don't spin on them
+                        log::warn!("Offline session server failed to accept a connection, retrying in {backoff:?}: {error}");
+                        tokio::time::sleep(backoff).await;
+                        backoff = (backoff * 2).min(ACCEPT_BACKOFF_MAX);
+                        continue;
+                    },
+                };
+                backoff = ACCEPT_BACKOFF_MIN;
+                let backend = backend.clone();
+                let root = root.clone();
+                tokio::task::spawn(async move {
+                    if let Err(error) = backend.handle_offline_session_connection(stream, &root).await {
+                        log::debug!("Offline session request failed: {error}");
+                    }
+                });
+            }
+        });
+
+        Ok(api_root)
+    }
+
+    async fn handle_offline_session_connection(&self, mut stream: TcpStream, api_root: &str) -> std::io::Result<()> {
+        let Some((method, path, body)) = read_request(&mut stream).await? else {
+            return respond(&mut stream, "400 Bad Request", "text/plain", b"").await;
+        };
+
+        let (path, query) = path.split_once('?').unwrap_or((&path, ""));
+        let path = path.trim_end_matches('/');
+
+        match (method.as_str(), path) {
+            ("GET", "") => {
+                respond_json(&mut stream, &metadata()).await
+            },
+            ("GET", "/sessionserver/session/minecraft/hasJoined") => {
+                let username = query.split('&')
+                    .filter_map(|pair| pair.split_once('='))
+                    .find(|(key, _)| *key == "username")
+                    .map(|(_, value)| value);
+                match username.and_then(|username| self.offline_account_by_name(username)) {
+                    Some(uuid) => self.respond_profile(&mut stream, uuid, api_root).await,
+                    None => respond(&mut stream, "204 No Content", "text/plain", b"").await,
+                }
+            },
+            ("POST", "/sessionserver/session/minecraft/join") => {
+                respond(&mut stream, "204 No Content", "text/plain", b"").await
+            },
+            ("POST", "/api/profiles/minecraft") => {
+                let names: Vec<String> = serde_json::from_slice(&body).unwrap_or_default();
+                let profiles: Vec<_> = names.iter()
+                    .filter_map(|name| {
+                        let uuid = self.offline_account_by_name(name)?;
+                        Some(json!({ "id": uuid.simple().to_string(), "name": name }))
+                    })
+                    .collect();
+                respond_json(&mut stream, &serde_json::Value::Array(profiles)).await
+            },
+            ("GET", path) => {
+                if let Some(id) = path.strip_prefix("/sessionserver/session/minecraft/profile/")
+                    && let Ok(uuid) = Uuid::try_parse(id)
+                {
+                    self.respond_profile(&mut stream, uuid, api_root).await
+                } else if let Some(id) = path.strip_prefix("/textures/")
+                    && id.bytes().all(|c| c.is_ascii_hexdigit())
+                    && let Ok(png) = tokio::fs::read(self.directories.skins_dir.join(format!("{id}.png"))).await
+                {
+                    respond(&mut stream, "200 OK", "image/png", &png).await
+                } else {
+                    respond(&mut stream, "404 Not Found", "text/plain", b"").await
+                }
+            },
+            _ => respond(&mut stream, "404 Not Found", "text/plain", b"").await,
+        }
+    }
+
+    fn offline_account_by_name(&self, username: &str) -> Option<Uuid> {
+        let mut account_info = self.account_info.write();
+        account_info.get().accounts.iter()
+            .find(|(_, account)| account.offline && &*account.username == username)
+            .map(|(uuid, _)| *uuid)
+    }
+
+    async fn respond_profile(&self, stream: &mut TcpStream, uuid: Uuid, api_root: &str) -> std::io::Result<()> {
+        let account = {
+            let mut account_info = self.account_info.write();
+            account_info.get().accounts.get(&uuid)
+                .filter(|account| account.offline)
+                .map(|account| (account.username.clone(), account.offline_skin.clone()))
+        };
+
+        let Some((username, skin)) = account else {
+            return respond(stream, "204 No Content", "text/plain", b"").await;
+        };
+
+        let mut textures = json!({});
+        if let Some(skin) = skin && let Some(slim) = self.library_skin_is_slim(&skin) {
+            textures["SKIN"] = json!({ "url": format!("{api_root}textures/{skin}") });
+            if slim {
+                textures["SKIN"]["metadata"] = json!({ "model": "slim" });
+            }
+        }
+
+        let timestamp = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).map(|time| time.as_millis()).unwrap_or(0);
+        let textures = json!({
+            "timestamp": timestamp,
+            "profileId": uuid.simple().to_string(),
+            "profileName": username,
+            "textures": textures,
+        });
+
+        respond_json(stream, &json!({
+            "id": uuid.simple().to_string(),
+            "name": username,
+            "properties": [{
+                "name": "textures",
+                "value": base64::engine::general_purpose::STANDARD.encode(textures.to_string()),
+            }],
+        })).await
+    }
+}
+
+fn metadata() -> serde_json::Value {
+    json!({
+        "meta": {
+            "serverName": "Pandora Launcher offline skins",
+            "implementationName": "PandoraLauncher",
+            "implementationVersion": "1.0.0",
+        },
+        "skinDomains": ["127.0.0.1", "localhost"],
+    })
+}
+
+/// Reads a single request, returning the method, path and body
+async fn read_request(stream: &mut TcpStream) -> std::io::Result<Option<(String, String, Vec<u8>)>> {
+    let mut buf = vec![0_u8; 1024];
+    let mut read = 0;
+
+    loop {
+        if read == buf.len() {
+            if buf.len() >= MAX_REQUEST_SIZE {
+                return Ok(None);
+            }
+            buf.resize(buf.len() * 2, 0);
+        }
+
+        let n = stream.read(&mut buf[read..]).await?;
+        read += n;
+
+        let mut headers = [httparse::EMPTY_HEADER; 32];
+        let mut req = httparse::Request::new(&mut headers);
+        let Ok(parsed) = req.parse(&buf[..read]) else {
+            return Ok(None);
+        };
+
+        let httparse::Status::Complete(header_len) = parsed else {
+            if n == 0 {
+                return Ok(None);
+            }
+            continue;
+        };
+
+        let content_length = req.headers.iter()
+            .find(|header| header.name.eq_ignore_ascii_case("content-length"))
+            .and_then(|header| std::str::from_utf8(header.value).ok()?.trim().parse::<usize>().ok())
+            .unwrap_or(0);
+        if header_len + content_length > MAX_REQUEST_SIZE {
+            return Ok(None);
+        }
+
+        if read < header_len + content_length {
+            if n == 0 {
+                return Ok(None);
+            }
+            continue;
+        }
+
+        let (Some(method), Some(path)) = (req.method, req.path) else {
+            return Ok(None);
+        };
+        let body = buf[header_len..header_len + content_length].to_vec();
+        return Ok(Some((method.to_string(), path.to_string(), body)));
+    }
+}
+
+async fn respond_json(stream: &mut TcpStream, value: &serde_json::Value) -> std::io::Result<()> {
+    respond(stream, "200 OK", "application/json", value.to_string().as_bytes()).await
+}
+
+async fn respond(stream: &mut TcpStream, status: &str, content_type: &str, body: &[u8]) -> std::io::Result<()> {
+    let head = format!("HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n", body.len());
+    stream.write_all(head.as_bytes()).await?;
+    stream.write_all(body).await?;
+    stream.flush().await
+}
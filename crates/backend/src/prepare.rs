@@ -0,0 +1,97 @@
+use std::{path::Path, sync::Arc};
+
+use bridge::{
+    instance::InstanceID, message::MessageToFrontend, modal_action::{ModalAction, ProgressTracker, ProgressTrackerFinishType}
+};
+use schema::{instance::InstanceConfiguration, loader::Loader};
+use tokio_util::sync::CancellationToken;
+
+use crate::BackendState;
+
+impl BackendState {
+    pub async fn prepare_instance(&self, id: InstanceID, modal_action: &ModalAction) {
+        let (configuration, dot_minecraft_path) = if let Some(instance) = self.instance_state.write().instances.get_mut(id) {
+            (instance.configuration.get().clone(), instance.dot_minecraft_path.clone())
+        } else {
+            modal_action.set_error_message("Can't prepare instance, unknown id".into());
+            return;
+        };
+
+        let prepare_tracker = ProgressTracker::new("Preparing instance".into(), self.send.clone());
+        modal_action.trackers.push(prepare_tracker.clone());
+        prepare_tracker.notify();
+
+        let result = self.launcher.prepare(&self.redirecting_http_client, dot_minecraft_path, &configuration, &prepare_tracker, modal_action).await;
+
+        prepare_tracker.set_finished(ProgressTrackerFinishType::from_err(result.is_err()));
+        prepare_tracker.notify();
+
+        match result {
+            Ok(()) => self.send.send_success("Instance is ready to launch"),
+            Err(error) => modal_action.set_error_message(format!("Failed to prepare instance:\n{}", error).into()),
+        }
+    }
+
+    /// Prepares an instance after its version or loader changed, so that it's ready by the time it's launched
+    pub fn prepare_instance_in_background(&self, id: InstanceID) {
+        if let Some(instance) = self.instance_state.write().instances.get_mut(id) {
+            self.prepare_in_background(instance.dot_minecraft_path.clone(), instance.configuration.get().clone());
+        }
+    }
+
+    /// Only one background preparation runs per instance, starting another cancels the previous one
+    pub fn prepare_in_background(&self, dot_minecraft_path: Arc<Path>, configuration: InstanceConfiguration) {
+        if configuration.loader == Loader::Unknown || self.meta.connectivity.is_offline() {
+            return;
+        }
+
+        let cancel = CancellationToken::new();
+        if let Some(previous) = self.background_preparations.lock().insert(dot_minecraft_path.clone(), cancel.clone()) {
+            previous.cancel();
+        }
+
+        let modal_action = ModalAction::default();
+        let prepare_tracker = ProgressTracker::new("Preparing instance".into(), self.send.clone());
+        modal_action.trackers.push(prepare_tracker.clone());
+        prepare_tracker.notify();
+
+        self.send.send(MessageToFrontend::AddBackgroundTask {
+            error_title: "Unable to prepare instance".into(),
+            modal_action: modal_action.clone(),
+        });
+
+        let backend = self.clone();
+        tokio::task::spawn(async move {
+            // The launcher's own cancellation closes any open modal, so the task is dropped instead
+            let result = tokio::select! {
+                result = backend.launcher.prepare(&backend.redirecting_http_client, dot_minecraft_path.clone(),
+                    &configuration, &prepare_tracker, &modal_action) => Some(result),
+                _ = cancel.cancelled() => None,
+            };
+
+            match result {
+                Some(Ok(())) => {
+                    log::info!("Prepared {:?} in the background", dot_minecraft_path);
+                    prepare_tracker.set_finished(ProgressTrackerFinishType::Normal);
+                },
+                Some(Err(error)) => {
+                    log::warn!("Unable to prepare {:?} in the background: {error}", dot_minecraft_path);
+                    prepare_tracker.set_finished(ProgressTrackerFinishType::Error);
+                    modal_action.set_error_message(format!("Failed to prepare instance:\n{}", error).into());
+                },
+                None => {
+                    log::debug!("Cancelled preparing {:?} in the background", dot_minecraft_path);
+                    prepare_tracker.set_finished(ProgressTrackerFinishType::Fast);
+                },
+            }
+            prepare_tracker.notify();
+            modal_action.set_finished();
+
+            // A cancelled token means a newer preparation has already replaced this entry
+            let mut background_preparations = backend.background_preparations.lock();
+            if !cancel.is_cancelled() {
+                background_preparations.remove(&dot_minecraft_path);
+            }
+        });
+    }
+}
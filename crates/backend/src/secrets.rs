@@ -0,0 +1,270 @@
+use std::sync::Arc;
+
+use auth::{
+    secret::{PlatformSecretStorage, SecretStorage, SecretStorageError},
+    secret_file::{FileSecretStorage, SecretKeySource},
+};
+use bridge::modal_action::{ModalAction, ProgressTracker, ProgressTrackerFinishType};
+use schema::backend_config::SecretStorageBackend;
+
+use crate::BackendState;
+
+/// Passphrase for the encrypted secret file, for launches where it can't be entered in the settings
+const PASSPHRASE_ENV: &str = "PANDORA_SECRET_PASSPHRASE";
+
+impl BackendState {
+    /// Opens the configured secret storage the first time it's needed
+    pub async fn get_secret_storage(&self) -> Result<Arc<SecretStorage>, SecretStorageError> {
+        let mut secret_storage = self.secret_storage.lock().await;
+        if let Some(secret_storage) = &*secret_storage {
+            return Ok(secret_storage.clone());
+        }
+
+        let backend = self.config.write().get().secret_storage;
+        let opened = Arc::new(self.open_secret_storage(backend).await?);
+        *secret_storage = Some(opened.clone());
+        Ok(opened)
+    }
+
+    /// Which backend the secrets are currently stored in, never [SecretStorageBackend::Auto]
+    pub async fn secret_storage_backend(&self) -> Result<SecretStorageBackend, SecretStorageError> {
+        Ok(backend_of(&*self.get_secret_storage().await?))
+    }
+
+    async fn open_secret_storage(&self, backend: SecretStorageBackend) -> Result<SecretStorage, SecretStorageError> {
+        match backend {
+            SecretStorageBackend::Keyring => Ok(SecretStorage::Platform(PlatformSecretStorage::new().await?)),
+            SecretStorageBackend::EncryptedFile => Ok(SecretStorage::File(self.open_secret_file().await?)),
+            SecretStorageBackend::Auto => {
+                if let Ok(platform) = PlatformSecretStorage::new().await && platform.is_available().await {
+                    let platform = SecretStorage::Platform(platform);
+                    self.reconcile_secret_file(&platform).await;
+                    return Ok(platform);
+                }
+                log::warn!("No keyring is available, storing secrets in {:?}", self.directories.secrets_json);
+                let file = self.open_secret_file().await?;
+                self.send.send_warning("No system keyring is available, credentials are stored in an encrypted file in the launcher folder instead");
+                Ok(SecretStorage::File(file))
+            },
+        }
+    }
+
+    /// Moves secrets that were stored in the encrypted file while the keyring was unavailable into the keyring
+    async fn reconcile_secret_file(&self, platform: &SecretStorage) {
+        if !tokio::fs::try_exists(&self.directories.secrets_json).await.unwrap_or(false) {
+            return;
+        }
+
+        let file = match self.open_secret_file().await {
+            Ok(file) => SecretStorage::File(file),
+            Err(error) => {
+                log::warn!("Unable to open {:?} to move its secrets into the keyring: {error}", self.directories.secrets_json);
+                self.send.send_warning(format!("Credentials saved while the system keyring was unavailable can't be moved into it: {error}"));
+                return;
+            },
+        };
+
+        let mut moved = 0;
+        for entry in self.known_secret_entries() {
+            match entry.copy(&file, platform).await {
+                Ok(true) => {
+                    moved += 1;
+                    if let Err(error) = entry.delete(&file).await {
+                        log::warn!("Unable to remove {entry:?} from the encrypted file: {error}");
+                    }
+                },
+                Ok(false) => {},
+                Err(error) => log::warn!("Unable to move {entry:?} into the keyring: {error}"),
+            }
+        }
+
+        if moved > 0 {
+            self.send.send_info(format!("Moved {moved} credentials from the encrypted file into the system keyring"));
+        }
+    }
+
+    fn secret_passphrase(&self) -> Option<String> {
+        if let Some(passphrase) = &*self.secret_passphrase.lock() {
+            return Some(passphrase.to_string());
+        }
+        std::env::var(PASSPHRASE_ENV).ok().filter(|passphrase| !passphrase.is_empty())
+    }
+
+    async fn open_secret_file(&self) -> Result<FileSecretStorage, SecretStorageError> {
+        let path = self.directories.secrets_json.to_path_buf();
+
+        if let Some(key_file) = self.config.write().get().secret_key_file.clone() {
+            return FileSecretStorage::open(path, SecretKeySource::KeyFile(key_file.to_path_buf())).await;
+        }
+
+        let Some(passphrase) = self.secret_passphrase() else {
+            return Err(SecretStorageError::MissingPassphrase);
+        };
+
+        FileSecretStorage::open(path, SecretKeySource::Passphrase(passphrase)).await
+    }
+
+    /// Unlocks the encrypted file with `passphrase`, or changes its passphrase if it's already open
+    pub async fn set_secret_passphrase(&self, passphrase: Arc<str>) -> Result<(), SecretStorageError> {
+        let mut secret_storage = self.secret_storage.lock().await;
+
+        match secret_storage.as_deref() {
+            Some(SecretStorage::File(file)) => {
+                if self.config.write().get().secret_key_file.is_some() {
+                    return Err(SecretStorageError::UnexpectedPassphrase);
+                }
+                file.rekey(SecretKeySource::Passphrase(passphrase.to_string())).await?;
+                *self.secret_passphrase.lock() = Some(passphrase);
+            },
+            // Kept for when the secrets are moved into the encrypted file
+            Some(SecretStorage::Platform(_)) => *self.secret_passphrase.lock() = Some(passphrase),
+            None => {
+                let previous = self.secret_passphrase.lock().replace(passphrase);
+                let backend = self.config.write().get().secret_storage;
+                match self.open_secret_storage(backend).await {
+                    Ok(opened) => *secret_storage = Some(Arc::new(opened)),
+                    Err(error) => {
+                        *self.secret_passphrase.lock() = previous;
+                        return Err(error);
+                    },
+                }
+            },
+        }
+
+        Ok(())
+    }
+
+    pub async fn set_secret_storage_backend(&self, backend: SecretStorageBackend, modal_action: &ModalAction) {
+        let tracker = ProgressTracker::new(Arc::from("Moving credentials"), self.send.clone());
+        modal_action.trackers.push(tracker.clone());
+        tracker.notify();
+
+        let result = self.migrate_secret_storage(backend).await;
+
+        tracker.set_finished(ProgressTrackerFinishType::from_err(result.is_err()));
+        tracker.notify();
+
+        match result {
+            Ok((0, now_using)) => self.send.send_success(format!("Now storing credentials in the {}", backend_name(now_using))),
+            Ok((moved, now_using)) => self.send.send_success(format!("Moved {moved} credentials to the {}", backend_name(now_using))),
+            Err(error) => modal_action.set_error_message(format!("Unable to move credentials: {error}").into()),
+        }
+    }
+
+    /// Copies every known secret into the new backend before removing them from the old one, so
+    /// a failure part of the way through doesn't lose anything
+    async fn migrate_secret_storage(&self, backend: SecretStorageBackend) -> Result<(usize, SecretStorageBackend), SecretStorageError> {
+        let mut secret_storage = self.secret_storage.lock().await;
+
+        let old = match &*secret_storage {
+            Some(old) => Some(old.clone()),
+            None => {
+                let configured = self.config.write().get().secret_storage;
+                match self.open_secret_storage(configured).await {
+                    Ok(old) => Some(Arc::new(old)),
+                    Err(error) => {
+                        log::warn!("Unable to open the current secret storage, nothing will be moved: {error}");
+                        None
+                    },
+                }
+            },
+        };
+
+        let new = Arc::new(self.open_secret_storage(backend).await?);
+
+        let mut moved = 0;
+        if let Some(old) = &old && backend_of(old) != backend_of(&new) {
+            let entries = self.known_secret_entries();
+
+            for entry in &entries {
+                if entry.copy(old, &new).await? {
+                    moved += 1;
+                }
+            }
+
+            for entry in &entries {
+                if let Err(error) = entry.delete(old).await {
+                    log::warn!("Unable to remove {entry:?} from the previous secret storage: {error}");
+                }
+            }
+        }
+
+        self.config.write().modify(|config| {
+            config.secret_storage = backend;
+        });
+        let now_using = backend_of(&new);
+        *secret_storage = Some(new);
+
+        Ok((moved, now_using))
+    }
+
+    fn known_secret_entries(&self) -> Vec<SecretEntry> {
+        let mut entries = vec![
+            SecretEntry::Secret(crate::modrinth_collection::MODRINTH_TOKEN_SECRET.into()),
+            SecretEntry::Secret(crate::curseforge::CURSEFORGE_API_KEY_SECRET.into()),
+            SecretEntry::Secret(crate::proxy::PROXY_PASSWORD_SECRET.into()),
+        ];
+
+        let mut account_info = self.account_info.write();
+        for (uuid, account) in &account_info.get().accounts {
+            if account.offline {
+                continue;
+            }
+            if account.yggdrasil_api_root.is_some() {
+                entries.push(SecretEntry::Secret(crate::yggdrasil::credentials_secret_key(*uuid)));
+            } else {
+                entries.push(SecretEntry::Credentials(*uuid));
+            }
+        }
+
+        entries
+    }
+}
+
+#[derive(Debug)]
+enum SecretEntry {
+    Credentials(uuid::Uuid),
+    Secret(String),
+}
+
+impl SecretEntry {
+    /// Returns whether there was anything to copy
+    async fn copy(&self, from: &SecretStorage, to: &SecretStorage) -> Result<bool, SecretStorageError> {
+        match self {
+            SecretEntry::Credentials(uuid) => {
+                let Some(credentials) = from.read_credentials(*uuid).await? else {
+                    return Ok(false);
+                };
+                to.write_credentials(*uuid, &credentials).await?;
+            },
+            SecretEntry::Secret(key) => {
+                let Some(value) = from.read_secret(key).await? else {
+                    return Ok(false);
+                };
+                to.write_secret(key, &value).await?;
+            },
+        }
+        Ok(true)
+    }
+
+    async fn delete(&self, from: &SecretStorage) -> Result<(), SecretStorageError> {
+        match self {
+            SecretEntry::Credentials(uuid) => from.delete_credentials(*uuid).await,
+            SecretEntry::Secret(key) => from.delete_secret(key).await,
+        }
+    }
+}
+
+fn backend_of(secret_storage: &SecretStorage) -> SecretStorageBackend {
+    match secret_storage {
+        SecretStorage::Platform(_) => SecretStorageBackend::Keyring,
+        SecretStorage::File(_) => SecretStorageBackend::EncryptedFile,
+    }
+}
+
+fn backend_name(backend: SecretStorageBackend) -> &'static str {
+    match backend {
+        SecretStorageBackend::Auto | SecretStorageBackend::Keyring => "system keyring",
+        SecretStorageBackend::EncryptedFile => "encrypted file",
+    }
+}
@@ -0,0 +1,473 @@
+use std::{path::{Path, PathBuf}, sync::Arc};
+
+use bridge::{
+    install::{ContentDownload, ContentInstall, ContentInstallFile, ContentInstallPath, InstallTarget}, instance::{ContentType, InstanceContentSummary, InstanceID, InstanceStatus}, message::{ServerModsSyncResult, ServerModsTarget}, modal_action::{ModalAction, ProgressTracker, ProgressTrackerFinishType}, safe_path::SafePath
+};
+use rustc_hash::FxHashSet;
+use schema::{content::ContentSource, fabric_mod::FabricEnvironment, instance::InstanceKind, modrinth::ModrinthSideRequirement};
+use serde::{Deserialize, Serialize};
+
+use crate::{instance::ContentFolder, metadata::items::{ModrinthProjectMetadataItem, ModrinthVersionFileMetadataItem}, mod_metadata, BackendState};
+
+/// Lists the files in the server's mods folder that were put there by syncing, mods added to the server
+/// by hand are never removed or replaced
+const SYNCED_MODS_FILE: &str = ".pandora_synced_mods.json";
+
+#[derive(thiserror::Error, Debug)]
+pub enum SyncServerModsError {
+    #[error("Unknown instance")]
+    UnknownInstance,
+    #[error("Mods can only be synced to a dedicated server instance")]
+    NotAServer,
+    #[error("Stop the server before syncing its mods")]
+    ServerRunning,
+    #[error("Unable to download the modpack's mods: {0}")]
+    ModpackDownload(Arc<str>),
+    #[error("Unable to update the server's mods folder: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct SyncedMods {
+    mods: Vec<Arc<str>>,
+}
+
+struct ServerMod {
+    filename: Arc<str>,
+    source: PathBuf,
+    hash: [u8; 20],
+}
+
+#[derive(Default)]
+struct AppliedServerMods {
+    added: Vec<Arc<str>>,
+    removed: Vec<Arc<str>>,
+    skipped: Vec<Arc<str>>,
+}
+
+impl BackendState {
+    /// Links the mods of the client instance that also run on a server into the server's mods folder, removing
+    /// previously synced mods that are no longer part of the client instance
+    pub async fn sync_server_mods(&self, client: InstanceID, target: ServerModsTarget, modal_action: &ModalAction) -> Result<ServerModsSyncResult, SyncServerModsError> {
+        let id = match target {
+            ServerModsTarget::Instance(id) => id,
+            ServerModsTarget::Folder(path) => return self.sync_server_mods_to(client, path, modal_action).await,
+        };
+
+        let server_dir = if let Some(instance) = self.instance_state.write().instances.get_mut(id) {
+            if instance.configuration.get().kind != InstanceKind::Server {
+                return Err(SyncServerModsError::NotAServer);
+            }
+            if instance.status() != InstanceStatus::NotRunning {
+                return Err(SyncServerModsError::ServerRunning);
+            }
+            // Keeps the server from being started while its mods folder is being changed
+            instance.launching = true;
+            self.send.send(instance.create_modify_message_with_status(InstanceStatus::Launching));
+            instance.dot_minecraft_path.clone()
+        } else {
+            return Err(SyncServerModsError::UnknownInstance);
+        };
+
+        let result = self.sync_server_mods_to(client, server_dir, modal_action).await;
+
+        if let Some(instance) = self.instance_state.write().instances.get_mut(id) {
+            instance.launching = false;
+            self.send.send(instance.create_modify_message());
+        }
+
+        result
+    }
+
+    async fn sync_server_mods_to(&self, client: InstanceID, server_dir: Arc<Path>, modal_action: &ModalAction) -> Result<ServerModsSyncResult, SyncServerModsError> {
+        let (loader, minecraft_version) = if let Some(instance) = self.instance_state.write().instances.get_mut(client) {
+            let configuration = instance.configuration.get();
+            (configuration.loader, configuration.minecraft_version)
+        } else {
+            return Err(SyncServerModsError::UnknownInstance);
+        };
+
+        let Some(mods) = self.clone().load_instance_content(client, ContentFolder::Mods).await else {
+            return Err(SyncServerModsError::UnknownInstance);
+        };
+
+        let content_library_dir = self.directories.content_library_dir.clone();
+
+        let mut server_mods = Vec::new();
+        let mut client_only = Vec::new();
+        let mut modpack_downloads = Vec::new();
+        let mut standalone = Vec::new();
+
+        for summary in mods.iter().filter(|summary| summary.enabled) {
+            let ContentType::ModrinthModpack { downloads, .. } = &summary.content_summary.extra else {
+                standalone.push(summary);
+                continue;
+            };
+
+            for download in downloads.iter() {
+                if !download.path.starts_with("mods/") || summary.disabled_children.contains(&*download.path) {
+                    continue;
+                }
+                let Some(path) = SafePath::new(&download.path) else {
+                    continue;
+                };
+                let Some(filename) = path.file_name() else {
+                    continue;
+                };
+                if let Some(env) = download.env && env.server == ModrinthSideRequirement::Unsupported {
+                    client_only.push(Arc::from(filename));
+                    continue;
+                }
+                let mut hash = [0u8; 20];
+                let Ok(_) = hex::decode_to_slice(&*download.hashes.sha1, &mut hash) else {
+                    continue;
+                };
+
+                server_mods.push(ServerMod {
+                    filename: filename.into(),
+                    source: crate::create_content_library_path(&content_library_dir, hash, path.extension()),
+                    hash,
+                });
+                modpack_downloads.push((path, download.clone()));
+            }
+        }
+
+        // Modpack files that only run on servers aren't downloaded when launching the client
+        if !modpack_downloads.is_empty() {
+            let content_install = ContentInstall {
+                target: InstallTarget::Library,
+                loader_hint: loader,
+                version_hint: Some(minecraft_version.into()),
+                files: modpack_downloads.into_iter().map(|(path, download)| {
+                    ContentInstallFile {
+                        replace_old: None,
+                        path: ContentInstallPath::Safe(path),
+                        download: ContentDownload::Url {
+                            url: download.downloads[0].clone(),
+                            sha1: download.hashes.sha1.clone(),
+                            size: download.file_size,
+                        },
+                        content_source: ContentSource::ModrinthUnknown,
+                    }
+                }).collect(),
+            };
+
+            self.install_content(content_install, modal_action.clone()).await;
+
+            // Syncing without them would remove the modpack's mods from the server
+            if let Some(error) = modal_action.error.read().unwrap().clone() {
+                return Err(SyncServerModsError::ModpackDownload(error));
+            }
+        }
+
+        let tracker = ProgressTracker::new("Checking which mods run on servers".into(), self.send.clone());
+        modal_action.trackers.push(tracker.clone());
+        tracker.set_total(standalone.len());
+        tracker.notify();
+
+        let futures = standalone.into_iter().map(|summary| {
+            let tracker = &tracker;
+            async move {
+                let _permit = self.downloads.acquire_connection().await;
+                let runs_on_server = self.runs_on_server(summary).await;
+                tracker.add_count(1);
+                tracker.notify();
+                (summary, runs_on_server)
+            }
+        });
+
+        for (summary, runs_on_server) in futures::future::join_all(futures).await {
+            if !runs_on_server {
+                client_only.push(summary.filename.clone());
+                continue;
+            }
+
+            // Prefer the content library's copy so the server's mod is a link to the same file
+            let extension = summary.path.extension().and_then(|extension| extension.to_str());
+            let library_path = crate::create_content_library_path(&content_library_dir, summary.content_summary.hash, extension);
+            let source = if library_path.exists() {
+                library_path
+            } else {
+                summary.path.to_path_buf()
+            };
+
+            server_mods.push(ServerMod {
+                filename: summary.filename.clone(),
+                source,
+                hash: summary.content_summary.hash,
+            });
+        }
+
+        tracker.set_finished(ProgressTrackerFinishType::Normal);
+
+        let mut applied = tokio::task::spawn_blocking(move || {
+            apply_server_mods(&server_dir, server_mods, |source, target| std::fs::hard_link(source, target))
+        }).await.unwrap()?;
+
+        applied.added.sort_by(|a, b| lexical_sort::natural_lexical_cmp(a, b));
+        applied.removed.sort_by(|a, b| lexical_sort::natural_lexical_cmp(a, b));
+        applied.skipped.sort_by(|a, b| lexical_sort::natural_lexical_cmp(a, b));
+        client_only.sort_by(|a, b| lexical_sort::natural_lexical_cmp(a, b));
+        client_only.dedup();
+
+        Ok(ServerModsSyncResult {
+            added: applied.added.into(),
+            removed: applied.removed.into(),
+            client_only: client_only.into(),
+            skipped: applied.skipped.into(),
+        })
+    }
+
+    /// Mods that can't be matched to a Modrinth project and don't declare an environment are assumed to run on servers
+    async fn runs_on_server(&self, summary: &InstanceContentSummary) -> bool {
+        if let ContentType::Fabric = summary.content_summary.extra {
+            let path = summary.path.clone();
+            let environment = tokio::task::spawn_blocking(move || mod_metadata::read_fabric_environment(&path)).await.unwrap();
+            if environment == Some(FabricEnvironment::Client) {
+                return false;
+            }
+        }
+
+        let project_id = match &summary.content_source {
+            ContentSource::ModrinthProject { project } => Some(project.clone()),
+            ContentSource::CurseForgeProject { .. } => None,
+            ContentSource::Manual | ContentSource::ModrinthUnknown => {
+                let sha1: Arc<str> = hex::encode(summary.content_summary.hash).into();
+                self.meta.fetch(&ModrinthVersionFileMetadataItem(sha1)).await.ok().map(|version| version.project_id.clone())
+            },
+        };
+
+        let Some(project_id) = project_id else {
+            return true;
+        };
+
+        match self.meta.fetch(&ModrinthProjectMetadataItem(project_id)).await {
+            Ok(project) => InstanceKind::Server.supports(project.client_side.unwrap_or_default(), project.server_side.unwrap_or_default()),
+            Err(error) => {
+                log::warn!("Unable to check whether {:?} runs on servers: {error}", summary.filename);
+                true
+            },
+        }
+    }
+}
+
+/// Mods are linked into the server with `hard_link`, falling back to copying them when linking fails. The synced
+/// mods list is written even when some files couldn't be updated so the mods that were synced stay managed
+fn apply_server_mods(
+    server_dir: &Path,
+    server_mods: Vec<ServerMod>,
+    hard_link: fn(&Path, &Path) -> std::io::Result<()>,
+) -> std::io::Result<AppliedServerMods> {
+    let mods_dir = server_dir.join("mods");
+    std::fs::create_dir_all(&mods_dir)?;
+
+    let synced_mods_path = server_dir.join(SYNCED_MODS_FILE);
+    let previous: FxHashSet<Arc<str>> = std::fs::read(&synced_mods_path).ok()
+        .and_then(|data| serde_json::from_slice::<SyncedMods>(&data).ok())
+        .map(|synced| synced.mods.into_iter().collect())
+        .unwrap_or_default();
+
+    let wanted: FxHashSet<&str> = server_mods.iter().map(|server_mod| &*server_mod.filename).collect();
+
+    let mut applied = AppliedServerMods::default();
+    let mut synced = Vec::new();
+    let mut first_error = None;
+
+    for filename in previous.iter() {
+        if wanted.contains(&**filename) {
+            continue;
+        }
+        match std::fs::remove_file(mods_dir.join(&**filename)) {
+            Ok(()) => applied.removed.push(filename.clone()),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => {},
+            Err(error) => {
+                log::warn!("Unable to remove synced mod {:?}: {error}", filename);
+                // Still in the mods folder, so the next sync should try removing it again
+                synced.push(filename.clone());
+                first_error.get_or_insert(error);
+            },
+        }
+    }
+
+    for server_mod in server_mods {
+        let target = mods_dir.join(&*server_mod.filename);
+        let managed = previous.contains(&server_mod.filename);
+
+        if target.exists() {
+            if !managed {
+                log::info!("Not syncing {:?}, the server already has a mod with that name", server_mod.filename);
+                applied.skipped.push(server_mod.filename);
+                continue;
+            }
+            if crate::check_sha1_hash(&target, server_mod.hash).unwrap_or(false) {
+                synced.push(server_mod.filename);
+                continue;
+            }
+            if let Err(error) = std::fs::remove_file(&target) {
+                log::warn!("Unable to replace synced mod {:?}: {error}", server_mod.filename);
+                synced.push(server_mod.filename);
+                first_error.get_or_insert(error);
+                continue;
+            }
+        }
+
+        if !server_mod.source.exists() {
+            log::warn!("Unable to sync {:?}, {:?} doesn't exist", server_mod.filename, server_mod.source);
+            applied.skipped.push(server_mod.filename);
+            continue;
+        }
+
+        if hard_link(&server_mod.source, &target).is_err()
+            && let Err(error) = std::fs::copy(&server_mod.source, &target)
+        {
+            log::warn!("Unable to sync {:?}: {error}", server_mod.filename);
+            first_error.get_or_insert(error);
+            continue;
+        }
+
+        applied.added.push(server_mod.filename.clone());
+        synced.push(server_mod.filename);
+    }
+
+    crate::write_safe(&synced_mods_path, &serde_json::to_vec(&SyncedMods { mods: synced })?)?;
+
+    match first_error {
+        Some(error) => Err(error),
+        None => Ok(applied),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use auth::test_util::TempDir;
+    use sha1::{Digest, Sha1};
+
+    use super::*;
+
+    fn write_file(path: &Path, contents: &str) {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, contents).unwrap();
+    }
+
+    fn server_mod(library_dir: &Path, filename: &str, contents: &str) -> ServerMod {
+        let source = library_dir.join(filename);
+        write_file(&source, contents);
+        ServerMod {
+            filename: filename.into(),
+            source,
+            hash: Sha1::digest(contents.as_bytes()).into(),
+        }
+    }
+
+    fn write_synced_mods(server_dir: &Path, mods: &[&str]) {
+        let synced = SyncedMods {
+            mods: mods.iter().map(|filename| Arc::from(*filename)).collect(),
+        };
+        std::fs::write(server_dir.join(SYNCED_MODS_FILE), serde_json::to_vec(&synced).unwrap()).unwrap();
+    }
+
+    fn hard_link(source: &Path, target: &Path) -> std::io::Result<()> {
+        std::fs::hard_link(source, target)
+    }
+
+    #[test]
+    fn test_keep_hand_added_mods() {
+        let dir = TempDir::new("server-mods-hand-added");
+        let (library_dir, server_dir) = (dir.join("library"), dir.join("server"));
+        write_file(&server_dir.join("mods/hand-added.jar"), "hand-added");
+        write_file(&server_dir.join("mods/same-name.jar"), "hand-added");
+
+        let server_mods = vec![server_mod(&library_dir, "same-name.jar", "synced")];
+        let applied = apply_server_mods(&server_dir, server_mods, hard_link).unwrap();
+
+        assert!(applied.added.is_empty());
+        assert_eq!(applied.skipped, vec![Arc::<str>::from("same-name.jar")]);
+        assert_eq!(std::fs::read_to_string(server_dir.join("mods/hand-added.jar")).unwrap(), "hand-added");
+        assert_eq!(std::fs::read_to_string(server_dir.join("mods/same-name.jar")).unwrap(), "hand-added");
+    }
+
+    #[test]
+    fn test_remove_previously_synced_mods() {
+        let dir = TempDir::new("server-mods-removed");
+        let (library_dir, server_dir) = (dir.join("library"), dir.join("server"));
+        write_file(&server_dir.join("mods/old.jar"), "old");
+        write_file(&server_dir.join("mods/kept.jar"), "kept");
+        write_synced_mods(&server_dir, &["old.jar", "kept.jar"]);
+
+        let server_mods = vec![server_mod(&library_dir, "kept.jar", "kept")];
+        let applied = apply_server_mods(&server_dir, server_mods, hard_link).unwrap();
+
+        assert_eq!(applied.removed, vec![Arc::<str>::from("old.jar")]);
+        // An unchanged mod isn't linked again
+        assert!(applied.added.is_empty());
+        assert!(!server_dir.join("mods/old.jar").exists());
+        assert!(server_dir.join("mods/kept.jar").exists());
+
+        // The removed mod is forgotten, so a hand-added file with its name is left alone afterwards
+        write_file(&server_dir.join("mods/old.jar"), "hand-added");
+        let applied = apply_server_mods(&server_dir, Vec::new(), hard_link).unwrap();
+        assert_eq!(applied.removed, vec![Arc::<str>::from("kept.jar")]);
+        assert!(server_dir.join("mods/old.jar").exists());
+    }
+
+    #[test]
+    fn test_relink_changed_mods() {
+        let dir = TempDir::new("server-mods-changed");
+        let (library_dir, server_dir) = (dir.join("library"), dir.join("server"));
+        write_file(&server_dir.join("mods/changed.jar"), "old");
+        write_synced_mods(&server_dir, &["changed.jar"]);
+
+        let server_mods = vec![server_mod(&library_dir, "changed.jar", "new")];
+        let applied = apply_server_mods(&server_dir, server_mods, hard_link).unwrap();
+
+        assert_eq!(applied.added, vec![Arc::<str>::from("changed.jar")]);
+        assert_eq!(std::fs::read_to_string(server_dir.join("mods/changed.jar")).unwrap(), "new");
+    }
+
+    #[test]
+    fn test_copy_when_linking_fails() {
+        let dir = TempDir::new("server-mods-copy");
+        let (library_dir, server_dir) = (dir.join("library"), dir.join("server"));
+
+        let server_mods = vec![server_mod(&library_dir, "copied.jar", "copied")];
+        let applied = apply_server_mods(&server_dir, server_mods, |_, _| Err(std::io::ErrorKind::Unsupported.into())).unwrap();
+
+        assert_eq!(applied.added, vec![Arc::<str>::from("copied.jar")]);
+        assert_eq!(std::fs::read_to_string(server_dir.join("mods/copied.jar")).unwrap(), "copied");
+    }
+
+    #[test]
+    fn test_record_synced_mods_when_copying_fails() {
+        let dir = TempDir::new("server-mods-copy-failed");
+        let (library_dir, server_dir) = (dir.join("library"), dir.join("server"));
+
+        let mut broken = server_mod(&library_dir, "broken.jar", "broken");
+        // A directory exists but can't be copied like a file
+        broken.source = library_dir.join("broken");
+        std::fs::create_dir_all(&broken.source).unwrap();
+        let server_mods = vec![server_mod(&library_dir, "copied.jar", "copied"), broken];
+        let result = apply_server_mods(&server_dir, server_mods, |_, _| Err(std::io::ErrorKind::Unsupported.into()));
+
+        assert!(result.is_err());
+        assert!(!server_dir.join("mods/broken.jar").exists());
+
+        // The mod that was copied is still managed by syncing
+        let applied = apply_server_mods(&server_dir, Vec::new(), hard_link).unwrap();
+        assert_eq!(applied.removed, vec![Arc::<str>::from("copied.jar")]);
+        assert!(!server_dir.join("mods/copied.jar").exists());
+    }
+
+    #[test]
+    fn test_skip_missing_sources() {
+        let dir = TempDir::new("server-mods-missing");
+        let (library_dir, server_dir) = (dir.join("library"), dir.join("server"));
+
+        let mut missing = server_mod(&library_dir, "missing.jar", "missing");
+        missing.source = library_dir.join("does-not-exist.jar");
+        let applied = apply_server_mods(&server_dir, vec![missing], hard_link).unwrap();
+
+        assert!(applied.added.is_empty());
+        assert_eq!(applied.skipped, vec![Arc::<str>::from("missing.jar")]);
+        assert!(!server_dir.join("mods/missing.jar").exists());
+    }
+}
@@ -0,0 +1,185 @@
+use std::{
+    path::{Path, PathBuf},
+    process::Child,
+    sync::{Arc, atomic::{AtomicUsize, Ordering}},
+};
+
+use bridge::instance::InstanceSessionSummary;
+use uuid::Uuid;
+
+use crate::syncing::linking;
+
+static SESSION_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// Folders each session writes on its own, they aren't shared with the instance's .minecraft
+const PER_SESSION_DIRS: &[&str] = &["logs", "crash-reports", "quickPlay"];
+
+#[derive(Debug)]
+pub struct InstanceSession {
+    pub id: usize,
+    pub child: Child,
+    pub account: Uuid,
+    pub username: Arc<str>,
+    pub game_output: Option<usize>,
+    /// Mods passed to the game on launch, reused by later sessions since they skip the prelaunch steps
+    pub add_mods: Vec<PathBuf>,
+    /// Separate game directory used when the instance was already running
+    pub session_dir: Option<PathBuf>,
+}
+
+impl InstanceSession {
+    pub fn next_id() -> usize {
+        SESSION_ID.fetch_add(1, Ordering::SeqCst)
+    }
+
+    pub fn summary(&self) -> InstanceSessionSummary {
+        InstanceSessionSummary {
+            id: self.id,
+            account: self.account,
+            username: self.username.clone(),
+            game_output: self.game_output,
+        }
+    }
+
+    pub fn is_alive(&mut self) -> bool {
+        matches!(self.child.try_wait(), Ok(None))
+    }
+
+    /// Removes the separate game directory, should only be called once the game has exited
+    pub fn cleanup(&self) {
+        if let Some(session_dir) = &self.session_dir {
+            remove_session_dir(session_dir);
+        }
+    }
+}
+
+/// Creates a game directory for a session of an instance that is already running. Folders are linked to the
+/// instance's .minecraft so mods, configs and worlds are shared, while files such as options.txt are copied
+/// since the game locks and rewrites them
+pub fn create_session_dir(sessions_dir: &Path, id: usize, dot_minecraft: &Path) -> std::io::Result<PathBuf> {
+    let session_dir = sessions_dir.join(id.to_string());
+    if session_dir.exists() {
+        remove_session_dir(&session_dir);
+    }
+    std::fs::create_dir_all(&session_dir)?;
+
+    for entry in std::fs::read_dir(dot_minecraft)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        let path = entry.path();
+        let target = session_dir.join(entry.file_name());
+
+        if file_type.is_dir() || (file_type.is_symlink() && path.is_dir()) {
+            if PER_SESSION_DIRS.iter().any(|dir| entry.file_name() == *dir) {
+                continue;
+            }
+            linking::link_dir(&path, &target)?;
+        } else if path.is_file() {
+            std::fs::copy(&path, &target)?;
+        }
+    }
+
+    Ok(session_dir)
+}
+
+pub fn remove_session_dir(session_dir: &Path) {
+    // Unlink the shared folders first so nothing in the instance can be removed by accident
+    if let Ok(read_dir) = std::fs::read_dir(session_dir) {
+        for entry in read_dir.flatten() {
+            if let Err(error) = linking::unlink_dir(&entry.path()) {
+                log::warn!("Unable to unlink {:?}: {error}", entry.path());
+                return;
+            }
+        }
+    }
+    if let Err(error) = std::fs::remove_dir_all(session_dir) {
+        log::warn!("Unable to remove session directory {session_dir:?}: {error}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use auth::test_util::TempDir;
+
+    use super::*;
+
+    fn write_file(path: &Path, contents: &str) {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, contents).unwrap();
+    }
+
+    #[test]
+    fn test_create_session_dir() {
+        let dir = TempDir::new("session-create");
+        let dot_minecraft = dir.join(".minecraft");
+        write_file(&dot_minecraft.join("options.txt"), "fov:70");
+        write_file(&dot_minecraft.join("mods/example.jar"), "mod");
+        write_file(&dot_minecraft.join("logs/latest.log"), "log");
+
+        let session_dir = create_session_dir(&dir.join("sessions"), 1, &dot_minecraft).unwrap();
+
+        // Shared folders show the instance's files, per-session folders start empty
+        assert_eq!(std::fs::read_to_string(session_dir.join("mods/example.jar")).unwrap(), "mod");
+        assert!(!session_dir.join("logs").exists());
+
+        // Files are copies, so the session rewriting them leaves the instance's alone
+        std::fs::write(session_dir.join("options.txt"), "fov:90").unwrap();
+        assert_eq!(std::fs::read_to_string(dot_minecraft.join("options.txt")).unwrap(), "fov:70");
+    }
+
+    #[test]
+    fn test_remove_session_dir_keeps_instance_files() {
+        let dir = TempDir::new("session-remove");
+        let dot_minecraft = dir.join(".minecraft");
+        write_file(&dot_minecraft.join("options.txt"), "fov:70");
+        write_file(&dot_minecraft.join("mods/example.jar"), "mod");
+        write_file(&dot_minecraft.join("saves/world/level.dat"), "level");
+
+        let session_dir = create_session_dir(&dir.join("sessions"), 1, &dot_minecraft).unwrap();
+        write_file(&session_dir.join("logs/latest.log"), "log");
+
+        remove_session_dir(&session_dir);
+
+        assert!(!session_dir.exists());
+        assert!(dot_minecraft.join("options.txt").is_file());
+        assert!(dot_minecraft.join("mods/example.jar").is_file());
+        assert!(dot_minecraft.join("saves/world/level.dat").is_file());
+    }
+
+    #[test]
+    fn test_recreate_stale_session_dir_keeps_instance_files() {
+        let dir = TempDir::new("session-stale");
+        let sessions_dir = dir.join("sessions");
+        let dot_minecraft = dir.join(".minecraft");
+        write_file(&dot_minecraft.join("mods/example.jar"), "mod");
+
+        // A session directory left behind by a crash still links into the instance
+        create_session_dir(&sessions_dir, 1, &dot_minecraft).unwrap();
+        let session_dir = create_session_dir(&sessions_dir, 1, &dot_minecraft).unwrap();
+
+        assert!(dot_minecraft.join("mods/example.jar").is_file());
+        assert!(session_dir.join("mods/example.jar").is_file());
+
+        remove_session_dir(&session_dir);
+        assert!(dot_minecraft.join("mods/example.jar").is_file());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_remove_session_dir_keeps_linked_folders() {
+        let dir = TempDir::new("session-linked");
+        let dot_minecraft = dir.join(".minecraft");
+        let shared_screenshots = dir.join("screenshots");
+        write_file(&shared_screenshots.join("screenshot.png"), "png");
+        write_file(&dot_minecraft.join("options.txt"), "fov:70");
+        std::os::unix::fs::symlink(&shared_screenshots, dot_minecraft.join("screenshots")).unwrap();
+
+        let session_dir = create_session_dir(&dir.join("sessions"), 1, &dot_minecraft).unwrap();
+        assert!(session_dir.join("screenshots/screenshot.png").is_file());
+
+        remove_session_dir(&session_dir);
+
+        assert!(shared_screenshots.join("screenshot.png").is_file());
+        assert!(dot_minecraft.join("screenshots/screenshot.png").is_file());
+    }
+}
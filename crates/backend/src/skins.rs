@@ -0,0 +1,415 @@
+use std::{io::Cursor, path::Path, sync::Arc};
+
+use auth::{
+    models::{MinecraftAccessToken, MinecraftProfileResponse, SkinState, SkinVariant},
+    profile::{self, ProfileError},
+    secret::SecretStorageError,
+};
+use bridge::{
+    modal_action::{ModalAction, ProgressTracker},
+    skin::{CapeEntry, SkinAction, SkinLibraryEntry, SkinManagerState},
+};
+use image::{RgbaImage, imageops::{self, FilterType}};
+use serde::{Deserialize, Serialize};
+use sha1::{Digest, Sha1};
+use uuid::Uuid;
+
+use crate::{BackendState, backend::LoginError};
+
+const PREVIEW_SCALE: u32 = 8;
+
+#[derive(thiserror::Error, Debug)]
+pub enum SkinError {
+    #[error("No account is selected")]
+    NoAccount,
+    #[error("Skins can only be changed for Microsoft and offline accounts")]
+    UnsupportedAccount,
+    #[error("Offline accounts can't have capes")]
+    OfflineCape,
+    #[error("Error initializing secret storage: {0}")]
+    SecretStorageInit(Arc<str>),
+    #[error("Error accessing secret storage: {0}")]
+    SecretStorage(#[from] SecretStorageError),
+    #[error("Error logging in: {0}")]
+    Login(#[from] LoginError),
+    #[error("{0}")]
+    Profile(#[from] ProfileError),
+    #[error("Unable to read skin: {0}")]
+    InvalidImage(#[from] image::ImageError),
+    #[error("Skins must be 64x64 or 64x32 pixels, this one is {0}x{1}")]
+    WrongDimensions(u32, u32),
+    #[error("This skin isn't in the library")]
+    UnknownSkin,
+    #[error("Unable to download the active skin: {0}")]
+    Reqwest(#[from] reqwest::Error),
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct SkinLibrary {
+    skins: Vec<SkinLibraryItem>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SkinLibraryItem {
+    /// Hex sha1 of the PNG, which is stored as `<id>.png` in the skins directory
+    id: Arc<str>,
+    name: Arc<str>,
+    slim: bool,
+}
+
+impl BackendState {
+    pub async fn manage_skins(&self, action: SkinAction) -> Result<SkinManagerState, SkinError> {
+        if let Some(uuid) = self.selected_offline_account() {
+            return self.manage_offline_skin(uuid, action).await;
+        }
+
+        let profile = match action {
+            SkinAction::Load => None,
+            SkinAction::AddToLibrary { path, name, slim } => {
+                self.add_skin_to_library(&path, name, slim).await?;
+                None
+            },
+            SkinAction::RemoveFromLibrary { id } => {
+                self.remove_skin_from_library(&id).await;
+                None
+            },
+            SkinAction::Apply { id } => {
+                let slim = self.skin_library.write().get().skins.iter()
+                    .find(|skin| skin.id == id)
+                    .map(|skin| skin.slim)
+                    .ok_or(SkinError::UnknownSkin)?;
+                let png = tokio::fs::read(self.directories.skins_dir.join(format!("{id}.png"))).await?;
+
+                let (_, access_token) = self.skin_login().await?;
+                Some(profile::upload_skin(&self.http_client, &access_token, slim, &png).await?)
+            },
+            SkinAction::Reset => {
+                let (_, access_token) = self.skin_login().await?;
+                Some(profile::reset_skin(&self.http_client, &access_token).await?)
+            },
+            SkinAction::SetCape { id } => {
+                let (_, access_token) = self.skin_login().await?;
+                Some(profile::set_active_cape(&self.http_client, &access_token, id.as_deref()).await?)
+            },
+        };
+
+        if let Some(profile) = &profile {
+            self.update_profile_head(profile);
+        }
+
+        Ok(self.skin_manager_state(profile).await)
+    }
+
+    /// Offline accounts keep their skin locally, it's served to the game by the offline session server
+    async fn manage_offline_skin(&self, uuid: Uuid, action: SkinAction) -> Result<SkinManagerState, SkinError> {
+        match action {
+            SkinAction::Load => {},
+            SkinAction::AddToLibrary { path, name, slim } => self.add_skin_to_library(&path, name, slim).await?,
+            SkinAction::RemoveFromLibrary { id } => self.remove_skin_from_library(&id).await,
+            SkinAction::Apply { id } => {
+                if self.library_skin_is_slim(&id).is_none() {
+                    return Err(SkinError::UnknownSkin);
+                }
+                self.set_offline_skin(uuid, Some(id));
+            },
+            SkinAction::Reset => self.set_offline_skin(uuid, None),
+            SkinAction::SetCape { .. } => return Err(SkinError::OfflineCape),
+        }
+
+        let active_skin = {
+            let mut account_info = self.account_info.write();
+            account_info.get().accounts.get(&uuid).and_then(|account| account.offline_skin.clone())
+        };
+
+        let active_skin_preview = match active_skin {
+            Some(id) => match (self.library_skin_is_slim(&id), tokio::fs::read(self.directories.skins_dir.join(format!("{id}.png"))).await) {
+                (Some(slim), Ok(png)) => render_preview_png(png, slim).await,
+                _ => None,
+            },
+            None => None,
+        };
+
+        Ok(SkinManagerState {
+            library: self.skin_library_entries().await.into(),
+            active_skin_preview,
+            capes: Arc::from([]),
+            profile_error: None,
+        })
+    }
+
+    fn selected_offline_account(&self) -> Option<Uuid> {
+        let mut account_info = self.account_info.write();
+        let account_info = account_info.get();
+        let uuid = account_info.selected_account?;
+        account_info.accounts.get(&uuid).filter(|account| account.offline).map(|_| uuid)
+    }
+
+    fn set_offline_skin(&self, uuid: Uuid, skin: Option<Arc<str>>) {
+        self.account_info.write().modify(|account_info| {
+            if let Some(account) = account_info.accounts.get_mut(&uuid) {
+                account.offline_skin = skin;
+            }
+        });
+    }
+
+    /// Returns whether the skin uses slim arms, or None if it isn't in the library
+    pub(crate) fn library_skin_is_slim(&self, id: &str) -> Option<bool> {
+        self.skin_library.write().get().skins.iter().find(|skin| &*skin.id == id).map(|skin| skin.slim)
+    }
+
+    async fn remove_skin_from_library(&self, id: &str) {
+        // The file is removed while holding the lock, so a concurrent add of the same skin can't lose its file
+        let mut skin_library = self.skin_library.write();
+        skin_library.modify(|library| library.skins.retain(|skin| &*skin.id != id));
+        _ = std::fs::remove_file(self.directories.skins_dir.join(format!("{id}.png")));
+    }
+
+    async fn add_skin_to_library(&self, path: &Path, name: Arc<str>, slim: bool) -> Result<(), SkinError> {
+        let png = tokio::fs::read(path).await?;
+        validate_skin(&png)?;
+
+        let id: Arc<str> = hex::encode(Sha1::digest(&png)).into();
+
+        let skin_path = self.directories.skins_dir.join(format!("{id}.png"));
+        let mut skin_library = self.skin_library.write();
+        crate::write_safe(&skin_path, &png)?;
+
+        skin_library.modify(|library| {
+            if let Some(existing) = library.skins.iter_mut().find(|skin| skin.id == id) {
+                existing.name = name;
+                existing.slim = slim;
+            } else {
+                library.skins.push(SkinLibraryItem { id, name, slim });
+            }
+        });
+
+        Ok(())
+    }
+
+    /// Refreshes the selected Microsoft account without any interaction, since skins are changed from a dialog
+    async fn skin_login(&self) -> Result<(MinecraftProfileResponse, MinecraftAccessToken), SkinError> {
+        let uuid = {
+            let mut account_info = self.account_info.write();
+            let account_info = account_info.get();
+            let uuid = account_info.selected_account.ok_or(SkinError::NoAccount)?;
+            let account = account_info.accounts.get(&uuid).ok_or(SkinError::NoAccount)?;
+            if account.offline || account.yggdrasil_api_root.is_some() {
+                return Err(SkinError::UnsupportedAccount);
+            }
+            uuid
+        };
+
+        let account_lock = self.account_lock(uuid);
+        let _account_guard = account_lock.lock().await;
+
+        let secret_storage = match self.get_secret_storage().await {
+            Ok(secret_storage) => secret_storage,
+            Err(error) => return Err(SkinError::SecretStorageInit(error.to_string().into())),
+        };
+
+        let mut credentials = secret_storage.read_credentials(uuid).await?.ok_or(LoginError::InteractionRequired)?;
+
+        let modal_action = ModalAction::default();
+        let login_tracker = ProgressTracker::new(Arc::from("Logging in"), self.send.clone());
+        let result = self.login(&mut credentials, &login_tracker, &modal_action, false).await?;
+
+        if let Err(error) = secret_storage.write_credentials(uuid, &credentials).await {
+            log::warn!("Unable to write credentials to keychain: {error}");
+        }
+
+        Ok(result)
+    }
+
+    async fn skin_manager_state(&self, profile: Option<MinecraftProfileResponse>) -> SkinManagerState {
+        let library = self.skin_library_entries().await;
+
+        let profile = match profile {
+            Some(profile) => Ok(profile),
+            None => self.skin_login().await.map(|(profile, _)| profile),
+        };
+
+        let profile = match profile {
+            Ok(profile) => profile,
+            Err(error) => {
+                return SkinManagerState {
+                    library: library.into(),
+                    active_skin_preview: None,
+                    capes: Arc::from([]),
+                    profile_error: Some(error.to_string().into()),
+                };
+            },
+        };
+
+        let active_skin_preview = match profile.skins.iter().find(|skin| skin.state == SkinState::Active) {
+            Some(skin) => match self.download_skin(&skin.url).await {
+                Ok(png) => render_preview_png(png, skin.variant == SkinVariant::Slim).await,
+                Err(error) => {
+                    log::warn!("Unable to download skin from {}: {error}", skin.url);
+                    None
+                },
+            },
+            None => None,
+        };
+
+        let capes = profile.capes.iter().map(|cape| CapeEntry {
+            id: cape.id.clone(),
+            alias: cape.alias.clone(),
+            active: cape.state == SkinState::Active,
+        }).collect();
+
+        SkinManagerState {
+            library: library.into(),
+            active_skin_preview,
+            capes,
+            profile_error: None,
+        }
+    }
+
+    async fn skin_library_entries(&self) -> Vec<SkinLibraryEntry> {
+        let items: Vec<_> = self.skin_library.write().get().skins.iter()
+            .map(|skin| (skin.id.clone(), skin.name.clone(), skin.slim))
+            .collect();
+
+        let mut library = Vec::with_capacity(items.len());
+        for (id, name, slim) in items {
+            let Ok(png) = tokio::fs::read(self.directories.skins_dir.join(format!("{id}.png"))).await else {
+                log::warn!("Skin {id} is missing from the skins directory");
+                continue;
+            };
+            let Some(preview) = render_preview_png(png, slim).await else {
+                continue;
+            };
+            library.push(SkinLibraryEntry { id, name, slim, preview });
+        }
+        library
+    }
+
+    async fn download_skin(&self, url: &str) -> Result<Vec<u8>, SkinError> {
+        let response = self.meta.endpoints.send(self.http_client.get(url)).await?.error_for_status()?;
+        Ok(response.bytes().await?.to_vec())
+    }
+}
+
+fn validate_skin(png: &[u8]) -> Result<(), SkinError> {
+    let image = image::load_from_memory_with_format(png, image::ImageFormat::Png)?;
+    match (image.width(), image.height()) {
+        (64, 64) | (64, 32) => Ok(()),
+        (width, height) => Err(SkinError::WrongDimensions(width, height)),
+    }
+}
+
+async fn render_preview_png(png: Vec<u8>, slim: bool) -> Option<Arc<[u8]>> {
+    tokio::task::spawn_blocking(move || {
+        let skin = image::load_from_memory(&png).ok()?.to_rgba8();
+        if skin.width() != 64 || (skin.height() != 64 && skin.height() != 32) {
+            return None;
+        }
+
+        let preview = render_preview(&skin, slim);
+
+        let mut bytes = Vec::new();
+        preview.write_to(&mut Cursor::new(&mut bytes), image::ImageFormat::Png).ok()?;
+        Some(Arc::from(bytes))
+    }).await.ok().flatten()
+}
+
+/// Renders the front and back of the skin side by side as flat images, including the outer layer
+fn render_preview(skin: &RgbaImage, slim: bool) -> RgbaImage {
+    let legacy = skin.height() == 32;
+    let arm = if slim { 3 } else { 4 };
+
+    let part = |x: u32, y: u32, width: u32| imageops::crop_imm(skin, x, y, width, 12).to_image();
+    let head = |x: u32, y: u32| imageops::crop_imm(skin, x, y, 8, 8).to_image();
+
+    let mut canvas = RgbaImage::new(36, 32);
+
+    // Front
+    let right_arm = part(44, 20, arm);
+    let right_leg = part(4, 20, 4);
+    let (left_arm, left_leg) = if legacy {
+        (imageops::flip_horizontal(&right_arm), imageops::flip_horizontal(&right_leg))
+    } else {
+        (part(36, 52, arm), part(20, 52, 4))
+    };
+    imageops::overlay(&mut canvas, &head(8, 8), 4, 0);
+    imageops::overlay(&mut canvas, &head(40, 8), 4, 0);
+    imageops::overlay(&mut canvas, &part(20, 20, 8), 4, 8);
+    imageops::overlay(&mut canvas, &right_arm, 4 - arm as i64, 8);
+    imageops::overlay(&mut canvas, &left_arm, 12, 8);
+    imageops::overlay(&mut canvas, &right_leg, 4, 20);
+    imageops::overlay(&mut canvas, &left_leg, 8, 20);
+    if !legacy {
+        imageops::overlay(&mut canvas, &part(20, 36, 8), 4, 8);
+        imageops::overlay(&mut canvas, &part(44, 36, arm), 4 - arm as i64, 8);
+        imageops::overlay(&mut canvas, &part(52, 52, arm), 12, 8);
+        imageops::overlay(&mut canvas, &part(4, 36, 4), 4, 20);
+        imageops::overlay(&mut canvas, &part(4, 52, 4), 8, 20);
+    }
+
+    // Back, where the left side of the player is on the left of the image
+    let right_arm = part(48 + arm, 20, arm);
+    let right_leg = part(12, 20, 4);
+    let (left_arm, left_leg) = if legacy {
+        (imageops::flip_horizontal(&right_arm), imageops::flip_horizontal(&right_leg))
+    } else {
+        (part(40 + arm, 52, arm), part(28, 52, 4))
+    };
+    imageops::overlay(&mut canvas, &head(24, 8), 24, 0);
+    imageops::overlay(&mut canvas, &head(56, 8), 24, 0);
+    imageops::overlay(&mut canvas, &part(32, 20, 8), 24, 8);
+    imageops::overlay(&mut canvas, &left_arm, 24 - arm as i64, 8);
+    imageops::overlay(&mut canvas, &right_arm, 32, 8);
+    imageops::overlay(&mut canvas, &left_leg, 24, 20);
+    imageops::overlay(&mut canvas, &right_leg, 28, 20);
+    if !legacy {
+        imageops::overlay(&mut canvas, &part(32, 36, 8), 24, 8);
+        imageops::overlay(&mut canvas, &part(56 + arm, 52, arm), 24 - arm as i64, 8);
+        imageops::overlay(&mut canvas, &part(48 + arm, 36, arm), 32, 8);
+        imageops::overlay(&mut canvas, &part(12, 52, 4), 24, 20);
+        imageops::overlay(&mut canvas, &part(12, 36, 4), 28, 20);
+    }
+
+    imageops::resize(&canvas, canvas.width() * PREVIEW_SCALE, canvas.height() * PREVIEW_SCALE, FilterType::Nearest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode(image: RgbaImage, format: image::ImageFormat) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        image.write_to(&mut Cursor::new(&mut bytes), format).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn test_validate_accepts_modern_and_legacy_skins() {
+        assert!(validate_skin(&encode(RgbaImage::new(64, 64), image::ImageFormat::Png)).is_ok());
+        assert!(validate_skin(&encode(RgbaImage::new(64, 32), image::ImageFormat::Png)).is_ok());
+    }
+
+    #[test]
+    fn test_validate_rejects_wrong_dimensions() {
+        let result = validate_skin(&encode(RgbaImage::new(128, 128), image::ImageFormat::Png));
+        assert!(matches!(result, Err(SkinError::WrongDimensions(128, 128))));
+    }
+
+    #[test]
+    fn test_validate_rejects_other_formats() {
+        let result = validate_skin(&encode(RgbaImage::new(64, 64), image::ImageFormat::Bmp));
+        assert!(matches!(result, Err(SkinError::InvalidImage(_))));
+
+        assert!(matches!(validate_skin(b"not an image"), Err(SkinError::InvalidImage(_))));
+    }
+
+    #[test]
+    fn test_preview_has_front_and_back() {
+        let preview = render_preview(&RgbaImage::new(64, 64), false);
+        assert_eq!((preview.width(), preview.height()), (36 * PREVIEW_SCALE, 32 * PREVIEW_SCALE));
+
+        let legacy = render_preview(&RgbaImage::new(64, 32), true);
+        assert_eq!((legacy.width(), legacy.height()), (36 * PREVIEW_SCALE, 32 * PREVIEW_SCALE));
+    }
+}
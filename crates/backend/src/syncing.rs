@@ -321,7 +321,7 @@ pub fn disable_all(target: SyncTarget, directories: &LauncherDirectories) -> std
 }
 
 #[cfg(unix)]
-mod linking {
+pub(crate) mod linking {
     use std::path::Path;
 
     pub fn link_dir(original: &Path, link: &Path) -> std::io::Result<()> {
@@ -347,10 +347,18 @@ mod linking {
 
         Ok(())
     }
+
+    pub fn unlink_dir(link: &Path) -> std::io::Result<()> {
+        if std::fs::read_link(link).is_ok() {
+            std::fs::remove_file(link)?;
+        }
+
+        Ok(())
+    }
 }
 
 #[cfg(windows)]
-mod linking {
+pub(crate) mod linking {
     use std::path::Path;
 
     pub fn link_dir(original: &Path, link: &Path) -> std::io::Result<()> {
@@ -376,4 +384,12 @@ mod linking {
 
         Ok(())
     }
+
+    pub fn unlink_dir(link: &Path) -> std::io::Result<()> {
+        if junction::get_target(link).is_ok() {
+            junction::delete(link)?;
+        }
+
+        Ok(())
+    }
 }
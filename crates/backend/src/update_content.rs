@@ -0,0 +1,331 @@
+use std::sync::Arc;
+
+use bridge::{
+    install::{ContentDownload, ContentInstall, ContentInstallFile, ContentInstallPath, InstallTarget}, instance::{ContentType, InstanceContentID, InstanceContentSummary, InstanceID}, message::{ContentUpdate, ContentUpdates}, modal_action::ModalAction
+};
+use schema::{content::ContentSource, instance::ReleaseChannel, loader::Loader, modrinth::{ModrinthLoader, ModrinthProjectVersion, ModrinthProjectVersionsRequest, ModrinthVersionType}};
+use strum::IntoEnumIterator;
+
+use crate::{instance::{ContentFolder, Instance}, metadata::{items::{ModrinthProjectVersionsMetadataItem, ModrinthVersionMetadataItem}, manager::MetaLoadError}, mod_metadata::ModUpdateAction, BackendState};
+
+pub struct ContentUpdateFile {
+    pub file: ContentInstallFile,
+    pub old_filename: Arc<str>,
+    pub new_filename: Arc<str>,
+}
+
+impl BackendState {
+    pub fn create_content_update_file(&self, instance: &Instance, content_id: InstanceContentID) -> Result<ContentUpdateFile, &'static str> {
+        let (mod_summary, update_info) = self.get_content_update_action(instance, content_id)?;
+        create_update_file(mod_summary, update_info)
+    }
+
+    fn get_content_update_action<'a>(&self, instance: &'a Instance, content_id: InstanceContentID) -> Result<(&'a InstanceContentSummary, ModUpdateAction), &'static str> {
+        let Some((mod_summary, _)) = instance.try_get_content(content_id) else {
+            return Err("Can't update mod in instance, unknown mod id");
+        };
+
+        if mod_summary.pinned {
+            return Err("Can't update mod in instance, mod is pinned");
+        }
+
+        let Some(update_info) = self.mod_metadata_manager.updates.read().get(&mod_summary.content_summary.hash).cloned() else {
+            return Err("Can't update mod in instance, missing update action");
+        };
+
+        Ok((mod_summary, update_info))
+    }
+
+    /// Updates found by the last check may be less stable than the given release channel allows, in which case
+    /// the newest allowed version that is newer than the installed one is picked instead
+    async fn resolve_update_for_channel(
+        &self,
+        summary: &InstanceContentSummary,
+        action: ModUpdateAction,
+        release_channel: ReleaseChannel,
+        loader: Loader,
+        minecraft_version: &Arc<str>,
+    ) -> Result<(ModUpdateAction, Option<Arc<ModrinthProjectVersion>>), MetaLoadError> {
+        // Resource packs and modpacks aren't tied to the instance's loader
+        let content_loader = match summary.content_summary.extra {
+            ContentType::ResourcePack | ContentType::ModrinthModpack { .. } => Loader::Vanilla,
+            _ => loader,
+        };
+
+        match action {
+            ModUpdateAction::Modrinth { ref project_id, ref version_id, .. } => {
+                let version = self.meta.fetch(&ModrinthVersionMetadataItem(version_id.clone())).await?;
+                if release_channel.allows(version.version_type) {
+                    return Ok((action, Some(version)));
+                }
+
+                let versions = self.meta.fetch(&ModrinthProjectVersionsMetadataItem(&ModrinthProjectVersionsRequest {
+                    project_id: project_id.clone(),
+                    game_versions: Some([minecraft_version.clone()].into()),
+                    loaders: None,
+                })).await?;
+
+                let modrinth_loader = content_loader.as_modrinth_loader();
+                let current_sha1 = hex::encode(summary.content_summary.hash);
+
+                // Versions are listed newest first, so anything after the installed version would be a downgrade
+                for candidate in versions.0.iter() {
+                    if candidate.files.iter().any(|file| file.hashes.sha1.eq_ignore_ascii_case(&current_sha1)) {
+                        break;
+                    }
+                    if !release_channel.allows(candidate.version_type) {
+                        continue;
+                    }
+                    if modrinth_loader != ModrinthLoader::Unknown && let Some(loaders) = &candidate.loaders && !loaders.contains(&modrinth_loader) {
+                        continue;
+                    }
+                    let Some(file) = candidate.files.iter().find(|file| file.primary).or(candidate.files.first()) else {
+                        continue;
+                    };
+
+                    let action = ModUpdateAction::Modrinth {
+                        file: file.clone(),
+                        project_id: project_id.clone(),
+                        version_id: candidate.id.clone(),
+                    };
+                    return Ok((action, Some(Arc::new(candidate.clone()))));
+                }
+
+                Ok((ModUpdateAction::NoVersionInChannel, None))
+            },
+            ModUpdateAction::CurseForge { ref file } => {
+                if release_channel.allows(Some(file.release_type.as_version_type())) {
+                    return Ok((action, None));
+                }
+                let Some(api) = self.curseforge_api().await else {
+                    return Ok((ModUpdateAction::ErrorNotFound, None));
+                };
+                let action = self.check_curseforge_update(&api, file.mod_id, summary.content_summary.hash,
+                    content_loader, minecraft_version.clone(), release_channel).await?;
+                Ok((action, None))
+            },
+            action => Ok((action, None)),
+        }
+    }
+
+    pub async fn get_content_updates(&self, id: InstanceID, release_channel: Option<ReleaseChannel>) -> Result<ContentUpdates, Arc<str>> {
+        if self.meta.connectivity.is_offline() {
+            return Err("Can't check for updates while offline".into());
+        }
+
+        let (loader, minecraft_version, release_channel) = if let Some(instance) = self.instance_state.write().instances.get_mut(id) {
+            let configuration = instance.configuration.get();
+            let minecraft_version: Arc<str> = configuration.minecraft_version.as_str().into();
+            (configuration.loader, minecraft_version, release_channel.unwrap_or(configuration.release_channel))
+        } else {
+            return Err("Unknown instance".into());
+        };
+
+        let mut content = Vec::new();
+        for folder in ContentFolder::iter() {
+            let Some(summaries) = self.clone().load_instance_content(id, folder).await else {
+                return Err("Unable to load instance content".into());
+            };
+            content.extend_from_slice(&*summaries);
+        }
+
+        let pending: Vec<_> = {
+            let updates = self.mod_metadata_manager.updates.read();
+            content.iter().filter_map(|summary| {
+                if !summary.update_status().can_update() {
+                    return None;
+                }
+                updates.get(&summary.content_summary.hash).cloned().map(|action| (summary, action))
+            }).collect()
+        };
+
+        let futures = pending.into_iter().map(|(summary, action)| {
+            let minecraft_version = &minecraft_version;
+            async move {
+                let name = summary.content_summary.name.clone().unwrap_or(summary.filename.clone());
+                let from_version = summary.content_summary.version_str.clone();
+
+                let permit = self.downloads.acquire_connection().await;
+                let result = self.resolve_update_for_channel(summary, action, release_channel, loader, minecraft_version).await;
+                drop(permit);
+
+                let update = match result {
+                    Ok((ModUpdateAction::Modrinth { .. }, Some(version))) => {
+                        Some(ContentUpdate {
+                            content_id: summary.id,
+                            name: name.clone(),
+                            from_version,
+                            to_version: version.version_number.clone().or(version.name.clone()).unwrap_or(version.id.clone()),
+                            version_type: version.version_type.unwrap_or(ModrinthVersionType::Release),
+                            changelog: version.changelog.clone(),
+                        })
+                    },
+                    Ok((ModUpdateAction::CurseForge { file }, _)) => {
+                        Some(ContentUpdate {
+                            content_id: summary.id,
+                            name: name.clone(),
+                            from_version,
+                            to_version: file.display_name.clone(),
+                            version_type: file.release_type.as_version_type(),
+                            changelog: None,
+                        })
+                    },
+                    Ok(_) => None,
+                    Err(error) => {
+                        log::warn!("Unable to fetch update information for {}: {error}", name);
+                        return (name, None, true);
+                    },
+                };
+
+                (name, update, false)
+            }
+        });
+
+        let mut updates = Vec::new();
+        let mut failed = Vec::new();
+        for (name, update, is_err) in futures::future::join_all(futures).await {
+            if is_err {
+                failed.push(name);
+            } else if let Some(update) = update {
+                updates.push(update);
+            }
+        }
+
+        Ok(ContentUpdates {
+            release_channel,
+            updates,
+            failed,
+        })
+    }
+
+    pub async fn update_contents(&self, id: InstanceID, content_ids: Vec<InstanceContentID>, release_channel: ReleaseChannel, modal_action: &ModalAction) {
+        let mut pending = Vec::new();
+        let mut failed: Vec<(Arc<str>, Arc<str>)> = Vec::new();
+
+        let (loader, minecraft_version) = if let Some(instance) = self.instance_state.write().instances.get_mut(id) {
+            let configuration = instance.configuration.get();
+            let (loader, minecraft_version) = (configuration.loader, configuration.minecraft_version);
+
+            for content_id in content_ids {
+                match self.get_content_update_action(instance, content_id) {
+                    Ok((summary, action)) => pending.push((summary.clone(), action)),
+                    Err(error) => {
+                        log::warn!("Skipping content update: {}", error);
+                        let name = instance.try_get_content(content_id)
+                            .map(|(summary, _)| summary.filename.clone())
+                            .unwrap_or_else(|| "Unknown content".into());
+                        failed.push((name, error.into()));
+                    },
+                }
+            }
+
+            (loader, Arc::<str>::from(minecraft_version.as_str()))
+        } else {
+            modal_action.set_error_message("Can't update instance, unknown id".into());
+            return;
+        };
+
+        let futures = pending.iter().map(|(summary, action)| {
+            let minecraft_version = &minecraft_version;
+            async move {
+                let _permit = self.downloads.acquire_connection().await;
+                (summary, self.resolve_update_for_channel(summary, action.clone(), release_channel, loader, minecraft_version).await)
+            }
+        });
+
+        let mut updates = Vec::new();
+        for (summary, result) in futures::future::join_all(futures).await {
+            let update = match result {
+                Ok((action, _)) => create_update_file(summary, action),
+                Err(error) => {
+                    log::warn!("Skipping content update for {:?}: {error}", summary.filename);
+                    failed.push((summary.filename.clone(), format!("Unable to fetch update information: {error}").into()));
+                    continue;
+                },
+            };
+            match update {
+                Ok(update) => updates.push(update),
+                Err(error) => {
+                    log::warn!("Skipping content update: {}", error);
+                    failed.push((summary.filename.clone(), error.into()));
+                },
+            }
+        }
+
+        let mut failed_summary = String::new();
+        for (name, error) in &failed {
+            failed_summary.push_str(&format!("\n{name}: {error}"));
+        }
+
+        if updates.is_empty() {
+            modal_action.set_error_message(format!("None of the selected content could be updated:{failed_summary}").into());
+            return;
+        }
+
+        let content_install = ContentInstall {
+            target: InstallTarget::Instance(id),
+            loader_hint: loader,
+            version_hint: Some(minecraft_version),
+            files: updates.iter().map(|update| update.file.clone()).collect(),
+        };
+
+        self.install_content(content_install, modal_action.clone()).await;
+
+        if modal_action.error.read().unwrap().is_some() {
+            return;
+        }
+
+        let mut summary = if updates.len() == 1 {
+            "Updated 1 file".to_string()
+        } else {
+            format!("Updated {} files", updates.len())
+        };
+        for update in &updates {
+            summary.push_str(&format!("\n{} → {}", update.old_filename, update.new_filename));
+        }
+        if failed.is_empty() {
+            self.send.send_success(summary);
+        } else {
+            summary.push_str(&format!("\n\nUnable to update {}:{failed_summary}", failed.len()));
+            self.send.send_warning(summary);
+        }
+    }
+}
+
+fn create_update_file(mod_summary: &InstanceContentSummary, update_info: ModUpdateAction) -> Result<ContentUpdateFile, &'static str> {
+    let (url, sha1, size, filename, content_source) = match update_info {
+        ModUpdateAction::ErrorNotFound => return Err("Can't update mod in instance, 404 not found"),
+        ModUpdateAction::ErrorInvalidHash => return Err("Can't update mod in instance, returned invalid hash"),
+        ModUpdateAction::AlreadyUpToDate => return Err("Can't update mod in instance, already up-to-date"),
+        ModUpdateAction::NoVersionInChannel => return Err("Can't update mod in instance, no newer version in the release channel"),
+        ModUpdateAction::ManualInstall => return Err("Can't update mod in instance, mod was manually installed"),
+        ModUpdateAction::Modrinth { file, project_id, .. } => {
+            (file.url, file.hashes.sha1, file.size, file.filename, ContentSource::ModrinthProject { project: project_id })
+        },
+        ModUpdateAction::CurseForge { file } => {
+            let Some(url) = file.download_url.clone() else {
+                return Err("Can't update mod in instance, the author doesn't allow downloads by third-party launchers");
+            };
+            let Some(sha1) = file.sha1().cloned() else {
+                return Err("Can't update mod in instance, returned invalid hash");
+            };
+            (url, sha1, file.file_length, file.file_name, ContentSource::CurseForgeProject { project: file.mod_id })
+        },
+    };
+
+    let mut path = mod_summary.path.with_file_name(&*filename);
+    if !mod_summary.enabled {
+        path.add_extension("disabled");
+    }
+    debug_assert!(path.is_absolute());
+    Ok(ContentUpdateFile {
+        file: ContentInstallFile {
+            replace_old: Some(mod_summary.path.clone()),
+            path: ContentInstallPath::Raw(path.into()),
+            download: ContentDownload::Url { url, sha1, size },
+            content_source,
+        },
+        old_filename: mod_summary.filename.clone(),
+        new_filename: filename,
+    })
+}
@@ -0,0 +1,237 @@
+use std::{path::{Path, PathBuf}, sync::Arc};
+
+use bridge::{
+    instance::{ContentType, InstanceContentSummary, InstanceID, InstanceStatus}, modal_action::{ModalAction, ProgressTracker, ProgressTrackerFinishType}
+};
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+use rustc_hash::FxHashSet;
+use schema::{content::ContentSource, curseforge::CurseForgeModFilesRequest, instance::InstanceConfiguration};
+use strum::IntoEnumIterator;
+
+use crate::{install_content::{ContentInstallError, FilenameAndExtension}, instance::ContentFolder, metadata::items::{CurseForgeModFilesMetadataItem, ModrinthVersionFileMetadataItem}, BackendState};
+
+#[derive(thiserror::Error, Debug)]
+pub enum VerifyError {
+    #[error("Can't verify an instance while it is launching or running")]
+    InstanceActive,
+    #[error("Unable to find a download for content library file {0:?}")]
+    NoDownload(PathBuf),
+    #[error("Unable to redownload content library file {0:?}:\n{1}")]
+    Redownload(PathBuf, ContentInstallError),
+}
+
+struct CorruptedLibraryFile {
+    path: PathBuf,
+    sha1: Arc<str>,
+}
+
+impl BackendState {
+    pub async fn verify_instance(&self, id: InstanceID, modal_action: &ModalAction) {
+        let (configuration, dot_minecraft_path) = if let Some(instance) = self.instance_state.write().instances.get_mut(id) {
+            if instance.status() != InstanceStatus::NotRunning {
+                modal_action.set_error_message(format!("{}", VerifyError::InstanceActive).into());
+                return;
+            }
+            // Keeps the instance from being launched while its files are being repaired
+            instance.launching = true;
+            self.send.send(instance.create_modify_message_with_status(InstanceStatus::Launching));
+            (instance.configuration.get().clone(), instance.dot_minecraft_path.clone())
+        } else {
+            return;
+        };
+
+        self.verify_instance_files(id, dot_minecraft_path, &configuration, modal_action).await;
+
+        if let Some(instance) = self.instance_state.write().instances.get_mut(id) {
+            instance.launching = false;
+            self.send.send(instance.create_modify_message());
+        }
+    }
+
+    async fn verify_instance_files(&self, id: InstanceID, dot_minecraft_path: Arc<Path>, configuration: &InstanceConfiguration, modal_action: &ModalAction) {
+
+        let verify_tracker = ProgressTracker::new("Verifying game files".into(), self.send.clone());
+        modal_action.trackers.push(verify_tracker.clone());
+        verify_tracker.notify();
+
+        let result = self.launcher.verify(&self.redirecting_http_client, dot_minecraft_path, configuration, &verify_tracker, modal_action).await;
+
+        verify_tracker.set_finished(ProgressTrackerFinishType::from_err(result.is_err()));
+        verify_tracker.notify();
+
+        let mut repaired = match result {
+            Ok(repaired) => repaired,
+            Err(error) => {
+                modal_action.set_error_message(format!("Failed to verify game files:\n{}", error).into());
+                return;
+            },
+        };
+
+        let (content_repaired, unrepairable) = self.verify_content_library(id, modal_action).await;
+        repaired.extend(content_repaired);
+
+        for path in &repaired {
+            log::info!("Repaired corrupted file {:?}", path);
+        }
+
+        if !unrepairable.is_empty() {
+            let mut message = format!("Repaired {} corrupted files, but {} could not be repaired:", repaired.len(), unrepairable.len());
+            for error in &unrepairable {
+                log::warn!("{}", error);
+                message.push('\n');
+                message.push_str(&format!("{}", error));
+            }
+            modal_action.set_error_message(message.into());
+        } else if repaired.is_empty() {
+            self.send.send_success("Verified instance, no corrupted files were found");
+        } else {
+            self.send.send_warning(format!("Verified instance, repaired {} corrupted files", repaired.len()));
+        }
+    }
+
+    /// Only the content library files used by this instance are checked, the rest of the library belongs to other instances
+    async fn verify_content_library(&self, id: InstanceID, modal_action: &ModalAction) -> (Vec<PathBuf>, Vec<VerifyError>) {
+        let hash_tracker = ProgressTracker::new("Verifying content library".into(), self.send.clone());
+        modal_action.trackers.push(hash_tracker.clone());
+        hash_tracker.notify();
+
+        let mut contents = Vec::new();
+        for folder in ContentFolder::iter() {
+            if let Some(content) = self.clone().load_instance_content(id, folder).await {
+                contents.push(content);
+            }
+        }
+
+        let corrupted = {
+            let content_library_dir = self.directories.content_library_dir.clone();
+            let hash_tracker = hash_tracker.clone();
+            tokio::task::spawn_blocking(move || {
+                let files = list_referenced_library_files(&content_library_dir, contents.iter().flat_map(|content| content.iter()));
+
+                hash_tracker.set_total(files.len());
+                hash_tracker.notify();
+
+                files.par_iter().filter_map(|(path, sha1)| {
+                    let mut expected_hash = [0u8; 20];
+                    hex::decode_to_slice(&**sha1, &mut expected_hash).ok()?;
+
+                    let valid = crate::check_sha1_hash(path, expected_hash);
+
+                    hash_tracker.add_count(1);
+                    hash_tracker.notify();
+
+                    match valid {
+                        Ok(false) => Some(CorruptedLibraryFile { path: path.clone(), sha1: sha1.clone() }),
+                        _ => None,
+                    }
+                }).collect::<Vec<_>>()
+            }).await.unwrap()
+        };
+
+        hash_tracker.set_finished(ProgressTrackerFinishType::Normal);
+        hash_tracker.notify();
+
+        let mut repaired = Vec::new();
+        let mut unrepairable = Vec::new();
+
+        // The download manager limits how many files are redownloaded at once
+        let futures = corrupted.iter().map(|file| {
+            log::warn!("Content library file {:?} has the wrong hash", file.path);
+            self.redownload_content_library_file(modal_action, file)
+        });
+
+        for (file, result) in corrupted.iter().zip(futures::future::join_all(futures).await) {
+            match result {
+                Ok(()) => repaired.push(file.path.clone()),
+                Err(error) => unrepairable.push(error),
+            }
+        }
+
+        (repaired, unrepairable)
+    }
+
+    async fn redownload_content_library_file(&self, modal_action: &ModalAction, file: &CorruptedLibraryFile) -> Result<(), VerifyError> {
+        let no_download = || VerifyError::NoDownload(file.path.clone());
+
+        let mut hash = [0u8; 20];
+        hex::decode_to_slice(&*file.sha1, &mut hash).map_err(|_| no_download())?;
+        let content_source = self.mod_metadata_manager.read_content_sources().get(&hash);
+        if let Some(ContentSource::CurseForgeProject { project }) = content_source {
+            return self.redownload_curseforge_library_file(modal_action, file, project).await;
+        }
+
+        let version = self.meta.fetch(&ModrinthVersionFileMetadataItem(file.sha1.clone())).await.map_err(|_| no_download())?;
+
+        let Some(download) = version.files.iter().find(|download| download.hashes.sha1.eq_ignore_ascii_case(&file.sha1)) else {
+            return Err(no_download());
+        };
+
+        self.download_file_into_library_inner(modal_action, FilenameAndExtension::from(file.path.as_path()),
+            &download.url, &download.hashes.sha1, download.size).await
+            .map_err(|error| VerifyError::Redownload(file.path.clone(), error))?;
+
+        Ok(())
+    }
+
+    async fn redownload_curseforge_library_file(&self, modal_action: &ModalAction, file: &CorruptedLibraryFile, project: u32) -> Result<(), VerifyError> {
+        let no_download = || VerifyError::NoDownload(file.path.clone());
+
+        let api = self.curseforge_api().await.ok_or_else(no_download)?;
+        let files = self.meta.fetch(&CurseForgeModFilesMetadataItem {
+            api: &api,
+            request: &CurseForgeModFilesRequest {
+                mod_id: project,
+                game_version: None,
+                mod_loader_type: None,
+            },
+        }).await.map_err(|_| no_download())?;
+
+        let Some((download, sha1)) = files.data.iter().find_map(|download| {
+            let sha1 = download.sha1().filter(|sha1| sha1.eq_ignore_ascii_case(&file.sha1))?;
+            Some((download, sha1))
+        }) else {
+            return Err(no_download());
+        };
+
+        // Files from projects that disallow third-party distribution have no download url
+        let Some(url) = &download.download_url else {
+            return Err(no_download());
+        };
+
+        self.download_file_into_library_inner(modal_action, FilenameAndExtension::from(file.path.as_path()),
+            url, sha1, download.file_length).await
+            .map_err(|error| VerifyError::Redownload(file.path.clone(), error))?;
+
+        Ok(())
+    }
+}
+
+/// Library files of the instance's content, including the files that its modpacks download
+fn list_referenced_library_files<'a>(content_library_dir: &Path, summaries: impl Iterator<Item = &'a InstanceContentSummary>) -> Vec<(PathBuf, Arc<str>)> {
+    let mut files = Vec::new();
+    let mut seen = FxHashSet::default();
+
+    let mut push_file = |hash: [u8; 20], extension: Option<&str>| {
+        let path = crate::create_content_library_path(content_library_dir, hash, extension);
+        if seen.insert(path.clone()) {
+            files.push((path, Arc::from(hex::encode(hash))));
+        }
+    };
+
+    for summary in summaries {
+        push_file(summary.content_summary.hash, summary.path.extension().and_then(|extension| extension.to_str()));
+
+        if let ContentType::ModrinthModpack { downloads, .. } = &summary.content_summary.extra {
+            for download in downloads.iter() {
+                let mut hash = [0u8; 20];
+                if hex::decode_to_slice(&*download.hashes.sha1, &mut hash).is_err() {
+                    continue;
+                }
+                let extension = Path::new(&*download.path).extension().and_then(|extension| extension.to_str());
+                push_file(hash, extension);
+            }
+        }
+    }
+
+    files
+}
@@ -0,0 +1,309 @@
+use std::{path::PathBuf, sync::Arc};
+
+use auth::{
+    secret::{SecretStorage, SecretStorageError},
+    yggdrasil::{YggdrasilClient, YggdrasilCredentials, YggdrasilError},
+};
+use bridge::modal_action::{ModalAction, ProgressTracker, ProgressTrackerFinishType};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::{
+    BackendState, account::{AccountKind, AuthlibInjectorLaunch, BackendAccount, MinecraftLoginInfo}, download_manager::{DownloadError, DownloadRequest}
+};
+
+const AUTHLIB_INJECTOR_LATEST_URL: &str = "https://authlib-injector.yushi.moe/artifact/latest.json";
+
+#[derive(thiserror::Error, Debug)]
+pub enum YggdrasilLoginError {
+    #[error("Error initializing secret storage: {0}")]
+    SecretStorageInit(Arc<str>),
+    #[error("Error accessing secret storage: {0}")]
+    SecretStorage(#[from] SecretStorageError),
+    #[error("{0}")]
+    Yggdrasil(#[from] YggdrasilError),
+    #[error("The session has expired, remove the account and add it again")]
+    MissingCredentials,
+    #[error("Unable to download authlib-injector:\n{0}")]
+    Download(#[from] DownloadError),
+    #[error("Unable to fetch the latest authlib-injector version:\n{0}")]
+    Reqwest(#[from] reqwest::Error),
+    #[error("Downloaded authlib-injector had the wrong hash")]
+    WrongHash,
+}
+
+impl YggdrasilLoginError {
+    /// Whether the stored session can't be refreshed anymore and the account has to be added again
+    pub fn requires_relogin(&self) -> bool {
+        matches!(self, Self::MissingCredentials | Self::Yggdrasil(YggdrasilError::Rejected(_)))
+    }
+}
+
+#[derive(Deserialize)]
+struct AuthlibInjectorArtifact {
+    version: Arc<str>,
+    download_url: Arc<str>,
+    checksums: AuthlibInjectorChecksums,
+}
+
+#[derive(Deserialize)]
+struct AuthlibInjectorChecksums {
+    sha256: Arc<str>,
+}
+
+pub(crate) fn credentials_secret_key(uuid: Uuid) -> String {
+    format!("yggdrasil_{}", uuid.as_hyphenated())
+}
+
+impl BackendState {
+    async fn yggdrasil_secret_storage(&self) -> Result<Arc<SecretStorage>, YggdrasilLoginError> {
+        match self.get_secret_storage().await {
+            Ok(secret_storage) => Ok(secret_storage),
+            Err(error) => Err(YggdrasilLoginError::SecretStorageInit(error.to_string().into())),
+        }
+    }
+
+    async fn write_yggdrasil_credentials(&self, uuid: Uuid, credentials: &YggdrasilCredentials) -> Result<(), YggdrasilLoginError> {
+        let secret_storage = self.yggdrasil_secret_storage().await?;
+        let json = serde_json::to_string(credentials).map_err(|_| SecretStorageError::SerializationError)?;
+        secret_storage.write_secret(&credentials_secret_key(uuid), &json).await?;
+        Ok(())
+    }
+
+    async fn read_yggdrasil_credentials(&self, uuid: Uuid) -> Result<Option<YggdrasilCredentials>, YggdrasilLoginError> {
+        let secret_storage = self.yggdrasil_secret_storage().await?;
+        let Some(json) = secret_storage.read_secret(&credentials_secret_key(uuid)).await? else {
+            return Ok(None);
+        };
+        Ok(Some(serde_json::from_str(&json).map_err(|_| SecretStorageError::SerializationError)?))
+    }
+
+    /// Deletes the stored credentials of a removed account, then invalidates its session on the server
+    pub async fn remove_yggdrasil_credentials(&self, uuid: Uuid, api_root: Arc<str>) {
+        let credentials = match self.read_yggdrasil_credentials(uuid).await {
+            Ok(credentials) => credentials,
+            Err(error) => {
+                log::warn!("Unable to read yggdrasil credentials of removed account {uuid}: {error}");
+                None
+            },
+        };
+
+        let secret_storage = match self.yggdrasil_secret_storage().await {
+            Ok(secret_storage) => secret_storage,
+            Err(error) => {
+                log::error!("Unable to delete yggdrasil credentials of removed account {uuid}: {error}");
+                return;
+            },
+        };
+        if let Err(error) = secret_storage.delete_secret(&credentials_secret_key(uuid)).await {
+            log::error!("Unable to delete yggdrasil credentials of removed account {uuid}: {error}");
+        }
+
+        if let Some(credentials) = credentials {
+            let client = YggdrasilClient::new(self.redirecting_http_client.clone(), api_root);
+            if let Err(error) = client.invalidate(&credentials).await {
+                log::warn!("Unable to invalidate yggdrasil session of removed account {uuid}: {error}");
+            }
+        }
+    }
+
+    pub async fn add_yggdrasil_account(&self, server_url: Arc<str>, username: Arc<str>, password: Arc<str>, modal_action: &ModalAction) {
+        let login_tracker = ProgressTracker::new(Arc::from("Logging in"), self.send.clone());
+        modal_action.trackers.push(login_tracker.clone());
+        login_tracker.notify();
+
+        let result = self.add_yggdrasil_account_inner(&server_url, &username, &password).await;
+
+        login_tracker.set_finished(ProgressTrackerFinishType::from_err(result.is_err()));
+        login_tracker.notify();
+
+        match result {
+            Ok(name) => self.send.send_success(format!("Logged in as {name}")),
+            Err(error) => modal_action.set_error_message(format!("Error logging in: {error}").into()),
+        }
+    }
+
+    async fn add_yggdrasil_account_inner(&self, server_url: &str, username: &str, password: &str) -> Result<Arc<str>, YggdrasilLoginError> {
+        let client = YggdrasilClient::resolve(self.redirecting_http_client.clone(), server_url).await?;
+        let session = client.authenticate(username, password).await?;
+
+        self.write_yggdrasil_credentials(session.profile.id, &session.credentials).await?;
+
+        let api_root = client.api_root().clone();
+        self.account_info.write().modify(|info| {
+            info.accounts.insert(session.profile.id, BackendAccount {
+                username: session.profile.name.clone(),
+                offline: false,
+                head: None,
+                yggdrasil_api_root: Some(api_root),
+                needs_relogin: false,
+                offline_skin: None,
+            });
+            info.selected_account = Some(session.profile.id);
+        });
+
+        Ok(session.profile.name)
+    }
+
+    pub async fn yggdrasil_login_flow(&self, modal_action: &ModalAction, uuid: Uuid, api_root: Arc<str>) -> Option<MinecraftLoginInfo> {
+        let login_tracker = ProgressTracker::new(Arc::from("Logging in"), self.send.clone());
+        modal_action.trackers.push(login_tracker.clone());
+        login_tracker.notify();
+
+        let account_lock = self.account_lock(uuid);
+        let _account_guard = account_lock.lock().await;
+
+        let result = self.yggdrasil_login(uuid, api_root, modal_action).await;
+
+        login_tracker.set_finished(ProgressTrackerFinishType::from_err(result.is_err()));
+        login_tracker.notify();
+
+        match result {
+            Ok(login_info) => Some(login_info),
+            Err(error) => {
+                modal_action.set_error_message(format!("Error logging in: {error}").into());
+                modal_action.set_finished();
+                None
+            },
+        }
+    }
+
+    /// Refreshes the stored access token if the server no longer accepts it, returning the new profile name when it was refreshed
+    pub(crate) async fn refresh_yggdrasil_session(&self, client: &YggdrasilClient, uuid: Uuid) -> Result<(YggdrasilCredentials, Option<Arc<str>>), YggdrasilLoginError> {
+        let credentials = self.read_yggdrasil_credentials(uuid).await?.ok_or(YggdrasilLoginError::MissingCredentials)?;
+
+        if client.validate(&credentials).await? {
+            return Ok((credentials, None));
+        }
+
+        log::debug!("Yggdrasil access token is no longer valid, refreshing");
+        let session = client.refresh(&credentials).await?;
+        self.write_yggdrasil_credentials(uuid, &session.credentials).await?;
+        Ok((session.credentials, Some(session.profile.name)))
+    }
+
+    async fn yggdrasil_login(&self, uuid: Uuid, api_root: Arc<str>, modal_action: &ModalAction) -> Result<MinecraftLoginInfo, YggdrasilLoginError> {
+        let client = YggdrasilClient::new(self.redirecting_http_client.clone(), api_root.clone());
+
+        let (credentials, username) = self.refresh_yggdrasil_session(&client, uuid).await?;
+
+        let prefetched = client.fetch_prefetched_metadata().await?;
+        let agent_path = self.ensure_authlib_injector(modal_action).await?;
+
+        let username = {
+            let mut account_info = self.account_info.write();
+            match username {
+                Some(username) => {
+                    account_info.modify(|info| {
+                        if let Some(account) = info.accounts.get_mut(&uuid) {
+                            account.username = username.clone();
+                        }
+                    });
+                    username
+                },
+                None => account_info.get().accounts.get(&uuid).map(|account| account.username.clone())
+                    .ok_or(YggdrasilLoginError::MissingCredentials)?,
+            }
+        };
+
+        Ok(MinecraftLoginInfo {
+            uuid,
+            username,
+            kind: AccountKind::Yggdrasil,
+            access_token: Some(credentials.minecraft_access_token()),
+            authlib_injector: Some(AuthlibInjectorLaunch {
+                agent_path,
+                api_root,
+                prefetched,
+            }),
+        })
+    }
+
+    /// Downloads the latest authlib-injector if it isn't present, falling back to an existing copy when the
+    /// latest version can't be determined
+    pub(crate) async fn ensure_authlib_injector(&self, modal_action: &ModalAction) -> Result<PathBuf, YggdrasilLoginError> {
+        let base_dir = &self.directories.authlib_injector_dir;
+
+        let artifact = match self.fetch_authlib_injector_artifact().await {
+            Ok(artifact) => artifact,
+            Err(error) => {
+                if let Some(existing) = find_existing_authlib_injector(base_dir) {
+                    log::warn!("Unable to fetch latest authlib-injector, using {:?}: {error}", existing);
+                    return Ok(existing);
+                }
+                return Err(error);
+            },
+        };
+
+        let path = base_dir.join(&*artifact.version).join(format!("authlib-injector-{}.jar", artifact.version));
+
+        let mut expected_hash = [0u8; 32];
+        hex::decode_to_slice(&*artifact.checksums.sha256, &mut expected_hash).map_err(|_| YggdrasilLoginError::WrongHash)?;
+
+        if check_sha256_hash(path.clone(), expected_hash).await {
+            return Ok(path);
+        }
+
+        let tracker = ProgressTracker::new(Arc::from("Downloading authlib-injector"), self.send.clone());
+        modal_action.trackers.push(tracker.clone());
+        tracker.notify();
+
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await.map_err(DownloadError::from)?;
+        }
+
+        let result = self.downloads.download(DownloadRequest {
+            url: &artifact.download_url,
+            path: &path,
+            sha1: None,
+            size: None,
+            track_bytes: true,
+        }, &tracker).await;
+
+        let result = match result {
+            Ok(()) if check_sha256_hash(path.clone(), expected_hash).await => Ok(path),
+            Ok(()) => {
+                _ = tokio::fs::remove_file(&path).await;
+                Err(YggdrasilLoginError::WrongHash)
+            },
+            Err(error) => Err(error.into()),
+        };
+
+        tracker.set_finished(ProgressTrackerFinishType::from_err(result.is_err()));
+        tracker.notify();
+
+        result
+    }
+
+    async fn fetch_authlib_injector_artifact(&self) -> Result<AuthlibInjectorArtifact, YggdrasilLoginError> {
+        let response = self.meta.endpoints.send(self.redirecting_http_client.get(AUTHLIB_INJECTOR_LATEST_URL)).await?;
+        let bytes = response.error_for_status()?.bytes().await?;
+        serde_json::from_slice(&bytes).map_err(|_| YggdrasilLoginError::Yggdrasil(YggdrasilError::SerializationError))
+    }
+}
+
+fn find_existing_authlib_injector(base_dir: &std::path::Path) -> Option<PathBuf> {
+    let mut versions: Vec<_> = std::fs::read_dir(base_dir).ok()?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.file_name().to_string_lossy().into_owned())
+        .collect();
+    versions.sort_by(|a, b| lexical_sort::natural_lexical_cmp(a, b));
+
+    versions.into_iter().rev()
+        .map(|version| base_dir.join(&version).join(format!("authlib-injector-{version}.jar")))
+        .find(|path| path.is_file())
+}
+
+async fn check_sha256_hash(path: PathBuf, expected_hash: [u8; 32]) -> bool {
+    tokio::task::spawn_blocking(move || {
+        let Ok(mut file) = std::fs::File::open(path) else {
+            return false;
+        };
+        let mut hasher = Sha256::new();
+        if std::io::copy(&mut file, &mut hasher).is_err() {
+            return false;
+        }
+        expected_hash == *hasher.finalize()
+    }).await.unwrap_or(false)
+}
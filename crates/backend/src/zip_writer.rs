@@ -0,0 +1,146 @@
+use std::io::Write;
+
+use flate2::{write::DeflateEncoder, Compression, Crc};
+
+const LOCAL_FILE_HEADER_SIGNATURE: u32 = 0x04034b50;
+const CENTRAL_DIRECTORY_HEADER_SIGNATURE: u32 = 0x02014b50;
+const END_OF_CENTRAL_DIRECTORY_SIGNATURE: u32 = 0x06054b50;
+
+const VERSION: u16 = 20;
+const FLAG_UTF8: u16 = 1 << 11;
+const METHOD_DEFLATE: u16 = 8;
+// 1980-01-01 00:00, the earliest date representable in a zip file
+const DOS_DATE: u16 = 0x21;
+const DOS_TIME: u16 = 0;
+
+/// Minimal in-memory zip writer, only supporting deflated files without zip64 extensions
+#[derive(Default)]
+pub struct ZipWriter {
+    data: Vec<u8>,
+    central_directory: Vec<u8>,
+    entries: u16,
+}
+
+impl ZipWriter {
+    pub fn add_file(&mut self, name: &str, contents: &[u8]) -> std::io::Result<()> {
+        if self.entries == u16::MAX || contents.len() > u32::MAX as usize {
+            return Err(std::io::Error::other("zip file is too large"));
+        }
+
+        let mut crc = Crc::new();
+        crc.update(contents);
+        let crc = crc.sum();
+
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(contents)?;
+        let compressed = encoder.finish()?;
+
+        let offset = self.data.len() as u32;
+        let name_len = name.len() as u16;
+
+        let data = &mut self.data;
+        data.extend_from_slice(&LOCAL_FILE_HEADER_SIGNATURE.to_le_bytes());
+        data.extend_from_slice(&VERSION.to_le_bytes());
+        data.extend_from_slice(&FLAG_UTF8.to_le_bytes());
+        data.extend_from_slice(&METHOD_DEFLATE.to_le_bytes());
+        data.extend_from_slice(&DOS_TIME.to_le_bytes());
+        data.extend_from_slice(&DOS_DATE.to_le_bytes());
+        data.extend_from_slice(&crc.to_le_bytes());
+        data.extend_from_slice(&(compressed.len() as u32).to_le_bytes());
+        data.extend_from_slice(&(contents.len() as u32).to_le_bytes());
+        data.extend_from_slice(&name_len.to_le_bytes());
+        data.extend_from_slice(&0u16.to_le_bytes()); // extra field length
+        data.extend_from_slice(name.as_bytes());
+        data.extend_from_slice(&compressed);
+
+        let central = &mut self.central_directory;
+        central.extend_from_slice(&CENTRAL_DIRECTORY_HEADER_SIGNATURE.to_le_bytes());
+        central.extend_from_slice(&VERSION.to_le_bytes()); // version made by
+        central.extend_from_slice(&VERSION.to_le_bytes()); // version needed
+        central.extend_from_slice(&FLAG_UTF8.to_le_bytes());
+        central.extend_from_slice(&METHOD_DEFLATE.to_le_bytes());
+        central.extend_from_slice(&DOS_TIME.to_le_bytes());
+        central.extend_from_slice(&DOS_DATE.to_le_bytes());
+        central.extend_from_slice(&crc.to_le_bytes());
+        central.extend_from_slice(&(compressed.len() as u32).to_le_bytes());
+        central.extend_from_slice(&(contents.len() as u32).to_le_bytes());
+        central.extend_from_slice(&name_len.to_le_bytes());
+        central.extend_from_slice(&0u16.to_le_bytes()); // extra field length
+        central.extend_from_slice(&0u16.to_le_bytes()); // comment length
+        central.extend_from_slice(&0u16.to_le_bytes()); // disk number
+        central.extend_from_slice(&0u16.to_le_bytes()); // internal attributes
+        central.extend_from_slice(&0u32.to_le_bytes()); // external attributes
+        central.extend_from_slice(&offset.to_le_bytes());
+        central.extend_from_slice(name.as_bytes());
+
+        self.entries += 1;
+
+        if self.data.len() > u32::MAX as usize {
+            return Err(std::io::Error::other("zip file is too large"));
+        }
+
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> std::io::Result<Vec<u8>> {
+        let central_directory_offset = self.data.len();
+        let central_directory_size = self.central_directory.len();
+
+        if central_directory_offset + central_directory_size > u32::MAX as usize {
+            return Err(std::io::Error::other("zip file is too large"));
+        }
+
+        self.data.append(&mut self.central_directory);
+
+        let data = &mut self.data;
+        data.extend_from_slice(&END_OF_CENTRAL_DIRECTORY_SIGNATURE.to_le_bytes());
+        data.extend_from_slice(&0u16.to_le_bytes()); // disk number
+        data.extend_from_slice(&0u16.to_le_bytes()); // disk with central directory
+        data.extend_from_slice(&self.entries.to_le_bytes());
+        data.extend_from_slice(&self.entries.to_le_bytes());
+        data.extend_from_slice(&(central_directory_size as u32).to_le_bytes());
+        data.extend_from_slice(&(central_directory_offset as u32).to_le_bytes());
+        data.extend_from_slice(&0u16.to_le_bytes()); // comment length
+
+        Ok(self.data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rc_zip_sync::{rc_zip::EntryKind, ReadZip};
+
+    use super::ZipWriter;
+
+    #[test]
+    fn test_round_trip() {
+        let large: Vec<u8> = (0..100_000u32).flat_map(|i| (i % 251).to_le_bytes()).collect();
+
+        let mut writer = ZipWriter::default();
+        writer.add_file("modrinth.index.json", br#"{"formatVersion":1}"#).unwrap();
+        writer.add_file("overrides/config/ünïcode.toml", b"key = \"value\"\n").unwrap();
+        writer.add_file("overrides/empty.txt", b"").unwrap();
+        writer.add_file("overrides/large.bin", &large).unwrap();
+        let zip = writer.finish().unwrap();
+
+        let archive = zip.as_slice().read_zip().unwrap();
+        let names: Vec<&str> = archive.entries().map(|entry| entry.name.as_str()).collect();
+        assert_eq!(names, ["modrinth.index.json", "overrides/config/ünïcode.toml", "overrides/empty.txt", "overrides/large.bin"]);
+
+        for entry in archive.entries() {
+            assert!(entry.kind() == EntryKind::File);
+        }
+
+        assert_eq!(archive.by_name("modrinth.index.json").unwrap().bytes().unwrap(), br#"{"formatVersion":1}"#);
+        assert_eq!(archive.by_name("overrides/config/ünïcode.toml").unwrap().bytes().unwrap(), b"key = \"value\"\n");
+        assert!(archive.by_name("overrides/empty.txt").unwrap().bytes().unwrap().is_empty());
+        assert_eq!(archive.by_name("overrides/large.bin").unwrap().bytes().unwrap(), large);
+    }
+
+    #[test]
+    fn test_empty_archive() {
+        let zip = ZipWriter::default().finish().unwrap();
+        let archive = zip.as_slice().read_zip().unwrap();
+        assert_eq!(archive.entries().count(), 0);
+    }
+}
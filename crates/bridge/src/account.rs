@@ -7,4 +7,6 @@ pub struct Account {
     pub uuid: Uuid,
     pub username: Arc<str>,
     pub head: Option<Arc<[u8]>>,
+    /// The stored credentials can no longer be refreshed, the user has to log in again
+    pub needs_relogin: bool,
 }
@@ -42,6 +42,10 @@ pub enum ContentDownload {
         project_id: Arc<str>,
         version_id: Option<Arc<str>>,
     },
+    CurseForge {
+        project_id: u32,
+        file_id: Option<u32>,
+    },
     Url {
         url: Arc<str>,
         sha1: Arc<str>,
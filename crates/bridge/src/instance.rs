@@ -1,6 +1,7 @@
 use std::{collections::HashSet, path::Path, sync::Arc};
 
 use schema::{content::ContentSource, modification::ModrinthModpackFileDownload};
+use uuid::Uuid;
 
 use crate::safe_path::SafePath;
 
@@ -41,6 +42,16 @@ pub enum InstanceStatus {
     Running,
 }
 
+/// A running game process of an instance, several can run at once with different accounts
+#[derive(Debug, Clone)]
+pub struct InstanceSessionSummary {
+    pub id: usize,
+    pub account: Uuid,
+    pub username: Arc<str>,
+    /// Id of the game output window, if one was opened
+    pub game_output: Option<usize>,
+}
+
 #[derive(Debug, Clone)]
 pub struct InstanceWorldSummary {
     pub title: Arc<str>,
@@ -68,6 +79,17 @@ pub struct InstanceContentSummary {
     pub enabled: bool,
     pub content_source: ContentSource,
     pub disabled_children: HashSet<String>,
+    pub pinned: bool,
+}
+
+impl InstanceContentSummary {
+    pub fn update_status(&self) -> ContentUpdateStatus {
+        if self.pinned {
+            ContentUpdateStatus::Pinned
+        } else {
+            self.content_summary.update_status.load(std::sync::atomic::Ordering::Relaxed)
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -105,13 +127,16 @@ pub enum ContentUpdateStatus {
     ErrorNotFound,
     ErrorInvalidHash,
     AlreadyUpToDate,
+    NoVersionInChannel,
     Modrinth,
+    CurseForge,
+    Pinned,
 }
 
 impl ContentUpdateStatus {
     pub fn can_update(&self) -> bool {
         match self {
-            ContentUpdateStatus::Modrinth => true,
+            ContentUpdateStatus::Modrinth | ContentUpdateStatus::CurseForge => true,
             _ => false,
         }
     }
@@ -9,4 +9,5 @@ pub mod meta;
 pub mod modal_action;
 pub mod safe_path;
 pub mod serial;
+pub mod skin;
 pub mod notify_signal;
@@ -1,14 +1,15 @@
 use std::{ffi::OsString, path::{Path, PathBuf}, sync::Arc};
 
 use enumset::{EnumSet, EnumSetType};
-use schema::{backend_config::{BackendConfig, SyncTarget}, instance::{InstanceConfiguration, InstanceJvmBinaryConfiguration, InstanceJvmFlagsConfiguration, InstanceMemoryConfiguration}, loader::Loader};
+use schema::{backend_config::{BackendConfig, ProxyConfig, SecretStorageBackend, SyncTarget}, instance::{InstanceConfiguration, InstanceJvmBinaryConfiguration, InstanceJvmFlagsConfiguration, InstanceKind, InstanceMemoryConfiguration, ReleaseChannel}, loader::Loader, modrinth::{ModrinthProject, ModrinthVersionType}};
 use ustr::Ustr;
 use uuid::Uuid;
 
 use crate::{
     account::Account, game_output::GameOutputLogLevel, install::ContentInstall, instance::{
-        InstanceID, InstanceContentID, InstanceContentSummary, InstanceServerSummary, InstanceStatus, InstanceWorldSummary,
-    }, keep_alive::{KeepAlive, KeepAliveHandle}, meta::{MetadataRequest, MetadataResult}, modal_action::ModalAction
+        InstanceID, InstanceContentID, InstanceContentSummary, InstanceServerSummary, InstanceSessionSummary, InstanceStatus, InstanceWorldSummary,
+    }, keep_alive::{KeepAlive, KeepAliveHandle}, meta::{MetadataRequest, MetadataResult}, modal_action::ModalAction,
+    skin::{SkinAction, SkinManagerState},
 };
 
 #[derive(Debug)]
@@ -17,10 +18,14 @@ pub enum MessageToBackend {
         request: MetadataRequest,
         force_reload: bool,
     },
+    SetOfflineMode {
+        offline: bool,
+    },
     CreateInstance {
         name: Ustr,
         version: Ustr,
         loader: Loader,
+        kind: InstanceKind,
     },
     DeleteInstance {
         id: InstanceID,
@@ -53,12 +58,24 @@ pub enum MessageToBackend {
         id: InstanceID,
         jvm_binary: InstanceJvmBinaryConfiguration,
     },
+    SetInstanceReleaseChannel {
+        id: InstanceID,
+        release_channel: ReleaseChannel,
+    },
+    SetInstanceAccount {
+        id: InstanceID,
+        account: Option<Uuid>,
+    },
     KillInstance {
         id: InstanceID,
+        /// Kills every session of the instance when not given
+        session: Option<usize>,
     },
     StartInstance {
         id: InstanceID,
         quick_play: Option<QuickPlayLaunch>,
+        /// Launches with this account instead of the instance's account or the selected account
+        account: Option<Uuid>,
         modal_action: ModalAction,
     },
     RequestLoadWorlds {
@@ -84,6 +101,11 @@ pub enum MessageToBackend {
         path: Arc<str>,
         enabled: bool,
     },
+    SetContentPinned {
+        id: InstanceID,
+        content_ids: Vec<InstanceContentID>,
+        pinned: bool,
+    },
     DeleteContent {
         id: InstanceID,
         content_ids: Vec<InstanceContentID>,
@@ -117,6 +139,18 @@ pub enum MessageToBackend {
     GetBackendConfiguration {
         channel: tokio::sync::oneshot::Sender<BackendConfig>,
     },
+    GetSecretStorageBackend {
+        channel: tokio::sync::oneshot::Sender<Result<SecretStorageBackend, Arc<str>>>,
+    },
+    /// Unlocks the encrypted secret file, or changes its passphrase if it's already unlocked
+    SetSecretPassphrase {
+        passphrase: Arc<str>,
+        channel: tokio::sync::oneshot::Sender<Result<(), Arc<str>>>,
+    },
+    SetSecretStorageBackend {
+        backend: SecretStorageBackend,
+        modal_action: ModalAction,
+    },
     SetSyncing {
         target: SyncTarget,
         value: bool,
@@ -131,9 +165,16 @@ pub enum MessageToBackend {
     AddNewAccount {
         modal_action: ModalAction,
     },
+    AddYggdrasilAccount {
+        server_url: Arc<str>,
+        username: Arc<str>,
+        password: Arc<str>,
+        modal_action: ModalAction,
+    },
     AddOfflineAccount {
         name: Arc<str>,
-        uuid: Uuid
+        /// Derived from the name the same way vanilla offline mode does when not given
+        uuid: Option<Uuid>,
     },
     SelectAccount {
         uuid: Uuid,
@@ -144,10 +185,100 @@ pub enum MessageToBackend {
     SetOpenGameOutputAfterLaunching {
         value: bool,
     },
+    SetDownloadSpeedLimit {
+        kib_per_second: Option<u32>,
+    },
+    SetDeviceCodeLogin {
+        value: bool,
+    },
     CreateInstanceShortcut {
         id: InstanceID,
         path: PathBuf
     },
+    GetUnusedFilesSummary {
+        channel: tokio::sync::oneshot::Sender<Result<UnusedFilesSummary, Arc<str>>>,
+    },
+    CleanupUnusedFiles {
+        /// Paths from the summary the user confirmed, files that became used since aren't deleted
+        paths: Arc<[Arc<Path>]>,
+        modal_action: ModalAction,
+    },
+    VerifyInstance {
+        id: InstanceID,
+        modal_action: ModalAction,
+    },
+    PrepareInstance {
+        id: InstanceID,
+        modal_action: ModalAction,
+    },
+    GetContentUpdates {
+        id: InstanceID,
+        /// Uses the instance's release channel if None
+        release_channel: Option<ReleaseChannel>,
+        channel: tokio::sync::oneshot::Sender<Result<ContentUpdates, Arc<str>>>,
+    },
+    UpdateContents {
+        instance: InstanceID,
+        content_ids: Vec<InstanceContentID>,
+        release_channel: ReleaseChannel,
+        modal_action: ModalAction,
+    },
+    DownloadImage {
+        url: Arc<str>,
+        // Larger images are downscaled before being converted to png
+        max_width: u32,
+        channel: tokio::sync::oneshot::Sender<Result<Arc<[u8]>, Arc<str>>>,
+    },
+    ImportCurseForgeModpack {
+        path: PathBuf,
+        // Creates a new instance if None
+        target: Option<InstanceID>,
+        modal_action: ModalAction,
+        manual_downloads: tokio::sync::oneshot::Sender<Vec<ManualDownload>>,
+    },
+    SetModrinthToken {
+        // Removes the stored token if None
+        token: Option<Arc<str>>,
+    },
+    SetProxy {
+        proxy: Option<ProxyConfig>,
+        // Kept in the secret storage rather than the config
+        password: Option<Arc<str>>,
+    },
+    SetCurseForgeApiKey {
+        // Removes the stored key if None
+        api_key: Option<Arc<str>>,
+    },
+    ManageSkins {
+        action: SkinAction,
+        channel: tokio::sync::oneshot::Sender<Result<SkinManagerState, Arc<str>>>,
+    },
+    GetModrinthProjectList {
+        source: ModrinthProjectListSource,
+        channel: tokio::sync::oneshot::Sender<Result<ModrinthProjectList, Arc<str>>>,
+    },
+    InstallModrinthProjects {
+        instance: InstanceID,
+        project_ids: Arc<[Arc<str>]>,
+        modal_action: ModalAction,
+        // Projects that weren't installed, either because no version is compatible with the instance or because resolving them failed
+        skipped: tokio::sync::oneshot::Sender<Vec<SkippedProject>>,
+    },
+    GetServerProperties {
+        id: InstanceID,
+        // Empty until the server has been started once
+        channel: tokio::sync::oneshot::Sender<Result<Arc<[(Arc<str>, Arc<str>)]>, Arc<str>>>,
+    },
+    SetServerProperties {
+        id: InstanceID,
+        properties: Arc<[(Arc<str>, Arc<str>)]>,
+    },
+    SyncServerMods {
+        client: InstanceID,
+        target: ServerModsTarget,
+        modal_action: ModalAction,
+        result: tokio::sync::oneshot::Sender<ServerModsSyncResult>,
+    },
 }
 
 #[derive(Debug)]
@@ -171,6 +302,7 @@ pub enum MessageToFrontend {
         dot_minecraft_folder: Arc<Path>,
         configuration: InstanceConfiguration,
         status: InstanceStatus,
+        sessions: Arc<[InstanceSessionSummary]>,
     },
     InstanceWorldsUpdated {
         id: InstanceID,
@@ -191,6 +323,8 @@ pub enum MessageToFrontend {
     CreateGameOutputWindow {
         id: usize,
         keep_alive: KeepAlive,
+        // Lines typed into the window are written to the process' stdin, used for server consoles
+        console: Option<tokio::sync::mpsc::UnboundedSender<Arc<str>>>,
     },
     AddGameOutput {
         id: usize,
@@ -202,10 +336,23 @@ pub enum MessageToFrontend {
         notification_type: BridgeNotificationType,
         message: Arc<str>,
     },
+    /// Shows the progress of work the backend started on its own, such as preparing an instance in the background
+    AddBackgroundTask {
+        error_title: Arc<str>,
+        modal_action: ModalAction,
+    },
     AccountsUpdated {
         accounts: Arc<[Account]>,
         selected_account: Option<Uuid>,
     },
+    ConnectivityUpdated {
+        offline: bool,
+        // Resources that were needed while offline but weren't cached
+        unavailable: Arc<[Arc<str>]>,
+    },
+    ProxyUpdated {
+        proxy: Option<ProxyInfo>,
+    },
     Refresh,
     CloseModal,
     MoveInstanceToTop {
@@ -233,6 +380,119 @@ pub struct SyncState {
     pub cannot_sync: enum_map::EnumMap<SyncTarget, usize>,
 }
 
+#[derive(Debug, Default)]
+pub struct UnusedFilesSummary {
+    pub files: enum_map::EnumMap<UnusedFilesCategory, usize>,
+    pub bytes: enum_map::EnumMap<UnusedFilesCategory, u64>,
+    pub paths: Arc<[Arc<Path>]>,
+}
+
+impl UnusedFilesSummary {
+    pub fn total_bytes(&self) -> u64 {
+        self.bytes.values().sum()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ContentUpdates {
+    pub release_channel: ReleaseChannel,
+    pub updates: Vec<ContentUpdate>,
+    /// Names of the content whose update information couldn't be fetched
+    pub failed: Vec<Arc<str>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ContentUpdate {
+    pub content_id: InstanceContentID,
+    pub name: Arc<str>,
+    pub from_version: Arc<str>,
+    pub to_version: Arc<str>,
+    pub version_type: ModrinthVersionType,
+    pub changelog: Option<Arc<str>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ModrinthProjectListSource {
+    Collection(Arc<str>),
+    // Requires a personal access token
+    FollowedProjects,
+}
+
+#[derive(Debug, Clone)]
+pub struct ModrinthProjectList {
+    pub name: Arc<str>,
+    pub projects: Arc<[Arc<ModrinthProject>]>,
+    // Projects of the list that couldn't be loaded, named by their id
+    pub failed: Arc<[SkippedProject]>,
+}
+
+#[derive(Clone)]
+pub struct ProxyInfo {
+    /// Proxy url with the credentials included
+    pub url: Arc<str>,
+    /// Hosts that bypass the proxy, in the format of the NO_PROXY variable
+    pub no_proxy: Option<Arc<str>>,
+}
+
+impl std::fmt::Debug for ProxyInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Don't leak the credentials into logs
+        f.debug_struct("ProxyInfo").field("no_proxy", &self.no_proxy).finish_non_exhaustive()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SkippedProject {
+    pub name: Arc<str>,
+    pub reason: Arc<str>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ServerModsTarget {
+    Instance(InstanceID),
+    /// The root of a server that isn't managed by the launcher, mods are put in its mods folder
+    Folder(Arc<Path>),
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ServerModsSyncResult {
+    pub added: Arc<[Arc<str>]>,
+    pub removed: Arc<[Arc<str>]>,
+    pub client_only: Arc<[Arc<str>]>,
+    /// Mods blocked by a file of the same name that wasn't synced, or whose file is missing
+    pub skipped: Arc<[Arc<str>]>,
+}
+
+/// A file that couldn't be downloaded automatically and must be fetched by the user
+#[derive(Debug, Clone)]
+pub struct ManualDownload {
+    pub name: Arc<str>,
+    pub filename: Arc<str>,
+    pub url: Arc<str>,
+    pub destination: Arc<str>,
+}
+
+#[derive(enum_map::Enum, strum::EnumIter, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnusedFilesCategory {
+    Libraries,
+    AssetIndexes,
+    AssetObjects,
+    JavaRuntimes,
+    Natives,
+}
+
+impl UnusedFilesCategory {
+    pub fn name(self) -> &'static str {
+        match self {
+            UnusedFilesCategory::Libraries => "Libraries",
+            UnusedFilesCategory::AssetIndexes => "Asset indexes",
+            UnusedFilesCategory::AssetObjects => "Asset objects",
+            UnusedFilesCategory::JavaRuntimes => "Java runtimes",
+            UnusedFilesCategory::Natives => "Extracted natives",
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum BridgeNotificationType {
     Success,
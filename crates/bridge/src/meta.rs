@@ -1,6 +1,6 @@
 use std::sync::Arc;
 
-use schema::{fabric_loader_manifest::FabricLoaderManifest, forge::{ForgeMavenManifest, NeoforgeMavenManifest}, maven::MavenMetadataXml, modrinth::{ModrinthProjectVersionsRequest, ModrinthProjectVersionsResult, ModrinthSearchRequest, ModrinthSearchResult}, version_manifest::MinecraftVersionManifest};
+use schema::{content::{ContentSearchRequest, ContentSearchResult}, curseforge::{CurseForgeModFilesRequest, CurseForgeModFilesResult}, fabric_loader_manifest::FabricLoaderManifest, forge::{ForgeMavenManifest, NeoforgeMavenManifest}, maven::MavenMetadataXml, modrinth::{ModrinthProject, ModrinthProjectVersionsRequest, ModrinthProjectVersionsResult, ModrinthSearchRequest, ModrinthSearchResult}, version_manifest::MinecraftVersionManifest};
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
 pub enum MetadataRequest {
@@ -10,6 +10,9 @@ pub enum MetadataRequest {
     NeoforgeMavenManifest,
     ModrinthSearch(ModrinthSearchRequest),
     ModrinthProjectVersions(ModrinthProjectVersionsRequest),
+    ModrinthProject(Arc<str>),
+    CurseForgeModFiles(CurseForgeModFilesRequest),
+    ContentSearch(ContentSearchRequest),
 }
 
 #[derive(Debug)]
@@ -20,4 +23,7 @@ pub enum MetadataResult {
     NeoforgeMavenManifest(Arc<NeoforgeMavenManifest>),
     ModrinthSearchResult(Arc<ModrinthSearchResult>),
     ModrinthProjectVersionsResult(Arc<ModrinthProjectVersionsResult>),
+    ModrinthProject(Arc<ModrinthProject>),
+    CurseForgeModFilesResult(Arc<CurseForgeModFilesResult>),
+    ContentSearchResult(Arc<ContentSearchResult>),
 }
@@ -4,7 +4,7 @@ use std::{
         Arc, RwLock,
         atomic::{AtomicUsize, Ordering},
     },
-    time::Instant,
+    time::{Duration, Instant},
 };
 
 use atomic_time::AtomicOptionInstant;
@@ -117,6 +117,7 @@ struct ProgressTrackerInner {
     finished_at: AtomicOptionInstant,
     finish_type: AtomicProgressTrackerFinishType,
     title: RwLock<Arc<str>>,
+    download_stats: RwLock<Option<Arc<DownloadStats>>>,
 }
 
 #[atomic_enum::atomic_enum]
@@ -156,6 +157,7 @@ impl ProgressTracker {
                 finished_at: AtomicOptionInstant::none(),
                 finish_type: AtomicProgressTrackerFinishType::new(ProgressTrackerFinishType::Normal),
                 title: RwLock::new(title),
+                download_stats: RwLock::new(None),
             }),
             sender,
             notify_serial: AtomicOptionSerial::default(),
@@ -219,7 +221,124 @@ impl ProgressTracker {
         self.inner.total.store(total, Ordering::SeqCst);
     }
 
+    /// Marks the tracker as downloading, showing the speed and time remaining of all active downloads
+    pub fn set_download_stats(&self, stats: &Arc<DownloadStats>) {
+        let mut download_stats = self.inner.download_stats.write().unwrap();
+        if download_stats.is_none() {
+            *download_stats = Some(Arc::clone(stats));
+        }
+    }
+
+    /// Returns the download speed in bytes per second and the estimated time remaining
+    /// if the tracker is downloading and there's enough data to make an estimate
+    pub fn download_rate(&self) -> Option<(f64, Option<Duration>)> {
+        if self.get_finished_at().is_some() {
+            return None;
+        }
+        self.inner.download_stats.read().unwrap().as_ref()?.rate()
+    }
+
     pub fn notify(&self) {
         self.sender.send_with_serial(MessageToFrontend::Refresh, &self.notify_serial);
     }
 }
+
+/// Progress of every download that's queued or running, shared by their trackers so that the
+/// time remaining covers all of them instead of extrapolating from a single tracker
+#[derive(Debug)]
+pub struct DownloadStats {
+    active: AtomicUsize,
+    // Downloads without a known size, while there are any the time remaining can't be estimated
+    unknown_size: AtomicUsize,
+    remaining: AtomicUsize,
+    downloaded: AtomicUsize,
+    started_at: AtomicOptionInstant,
+}
+
+impl Default for DownloadStats {
+    fn default() -> Self {
+        Self {
+            active: AtomicUsize::new(0),
+            unknown_size: AtomicUsize::new(0),
+            remaining: AtomicUsize::new(0),
+            downloaded: AtomicUsize::new(0),
+            started_at: AtomicOptionInstant::none(),
+        }
+    }
+}
+
+impl DownloadStats {
+    /// Registers a download until the returned handle is dropped
+    pub fn begin(self: &Arc<Self>, size: Option<usize>) -> ActiveDownload {
+        // The speed is averaged over the current period of activity
+        if self.active.fetch_add(1, Ordering::SeqCst) == 0 {
+            self.downloaded.store(0, Ordering::SeqCst);
+            self.started_at.store(Some(Instant::now()), Ordering::SeqCst);
+        }
+        if size.is_none() {
+            self.unknown_size.fetch_add(1, Ordering::SeqCst);
+        }
+
+        let mut active = ActiveDownload {
+            stats: Arc::clone(self),
+            known_size: size.is_some(),
+            remaining: 0,
+        };
+        active.set_remaining(size.unwrap_or(0));
+        active
+    }
+
+    pub fn rate(&self) -> Option<(f64, Option<Duration>)> {
+        if self.active.load(Ordering::SeqCst) == 0 {
+            return None;
+        }
+
+        let elapsed = self.started_at.load(Ordering::SeqCst)?.elapsed().as_secs_f64();
+        if elapsed < 1.0 {
+            return None;
+        }
+
+        let speed = self.downloaded.load(Ordering::SeqCst) as f64 / elapsed;
+        let eta = if speed > 0.0 && self.unknown_size.load(Ordering::SeqCst) == 0 {
+            Some(Duration::from_secs_f64(self.remaining.load(Ordering::SeqCst) as f64 / speed))
+        } else {
+            None
+        };
+
+        Some((speed, eta))
+    }
+}
+
+pub struct ActiveDownload {
+    stats: Arc<DownloadStats>,
+    known_size: bool,
+    // This download's share of the remaining bytes
+    remaining: usize,
+}
+
+impl ActiveDownload {
+    /// Updates the bytes left to download, e.g. after resuming from a partial file or starting over
+    pub fn set_remaining(&mut self, remaining: usize) {
+        if remaining > self.remaining {
+            self.stats.remaining.fetch_add(remaining - self.remaining, Ordering::SeqCst);
+        } else {
+            self.stats.remaining.fetch_sub(self.remaining - remaining, Ordering::SeqCst);
+        }
+        self.remaining = remaining;
+    }
+
+    pub fn add_downloaded(&mut self, bytes: usize) {
+        self.stats.downloaded.fetch_add(bytes, Ordering::SeqCst);
+        self.set_remaining(self.remaining.saturating_sub(bytes));
+    }
+}
+
+impl Drop for ActiveDownload {
+    fn drop(&mut self) {
+        self.set_remaining(0);
+        if !self.known_size {
+            self.stats.unknown_size.fetch_sub(1, Ordering::SeqCst);
+        }
+        self.stats.active.fetch_sub(1, Ordering::SeqCst);
+    }
+}
@@ -0,0 +1,46 @@
+use std::{path::PathBuf, sync::Arc};
+
+#[derive(Debug, Clone)]
+pub struct SkinLibraryEntry {
+    pub id: Arc<str>,
+    pub name: Arc<str>,
+    pub slim: bool,
+    /// PNG with the front and back of the skin side by side
+    pub preview: Arc<[u8]>,
+}
+
+#[derive(Debug, Clone)]
+pub struct CapeEntry {
+    pub id: Arc<str>,
+    pub alias: Arc<str>,
+    pub active: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct SkinManagerState {
+    pub library: Arc<[SkinLibraryEntry]>,
+    pub active_skin_preview: Option<Arc<[u8]>>,
+    pub capes: Arc<[CapeEntry]>,
+    /// Set when the selected account's profile couldn't be loaded, the library can still be edited
+    pub profile_error: Option<Arc<str>>,
+}
+
+#[derive(Debug)]
+pub enum SkinAction {
+    Load,
+    AddToLibrary {
+        path: PathBuf,
+        name: Arc<str>,
+        slim: bool,
+    },
+    RemoveFromLibrary {
+        id: Arc<str>,
+    },
+    Apply {
+        id: Arc<str>,
+    },
+    Reset,
+    SetCape {
+        id: Option<Arc<str>>,
+    },
+}
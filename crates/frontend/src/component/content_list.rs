@@ -7,7 +7,7 @@ use bridge::{
 };
 use gpui::{prelude::*, *};
 use gpui_component::{
-    button::{Button, ButtonVariants}, h_flex, list::{ListDelegate, ListItem, ListState}, switch::Switch, v_flex, ActiveTheme as _, Icon, IconName, IndexPath, Sizable
+    button::{Button, ButtonVariants}, h_flex, list::{ListDelegate, ListItem, ListState}, switch::Switch, v_flex, ActiveTheme as _, Icon, IconName, IndexPath, Selectable, Sizable
 };
 use parking_lot::Mutex;
 use rustc_hash::FxHashSet;
@@ -129,7 +129,7 @@ impl ContentListDelegate {
             }))
         };
 
-        let update_button = match summary.content_summary.update_status.load(Ordering::Relaxed) {
+        let update_button = match summary.update_status() {
             bridge::instance::ContentUpdateStatus::Unknown => None,
             bridge::instance::ContentUpdateStatus::ManualInstall => Some(
                 Button::new(("update", element_id)).warning().icon(Icon::default().path("icons/file-question-mark.svg"))
@@ -147,11 +147,21 @@ impl ContentListDelegate {
                 Button::new(("update", element_id)).icon(Icon::default().path("icons/check.svg"))
                     .tooltip("Up-to-date as of last check")
             ),
-            bridge::instance::ContentUpdateStatus::Modrinth => {
+            bridge::instance::ContentUpdateStatus::NoVersionInChannel => Some(
+                Button::new(("update", element_id)).warning().icon(Icon::default().path("icons/triangle-alert.svg"))
+                    .tooltip("No newer version in the instance's release channel")
+            ),
+            bridge::instance::ContentUpdateStatus::Pinned => None,
+            status @ (bridge::instance::ContentUpdateStatus::Modrinth | bridge::instance::ContentUpdateStatus::CurseForge) => {
                 let loading = self.updating.lock().contains(&element_id);
+                let tooltip = if status == bridge::instance::ContentUpdateStatus::CurseForge {
+                    "Download update from CurseForge"
+                } else {
+                    "Download update from Modrinth"
+                };
                 Some(
                     Button::new(("update", element_id)).success().loading(loading).icon(Icon::default().path("icons/download.svg"))
-                        .tooltip("Download update from Modrinth").on_click({
+                        .tooltip(tooltip).on_click({
                             let backend_handle = self.backend_handle.clone();
                             let updating = self.updating.clone();
                             cx.listener(move |this, _, window, cx| {
@@ -161,7 +171,7 @@ impl ContentListDelegate {
                                 let delegate = this.delegate_mut();
                                 if delegate.is_selected(element_id) {
                                     for summary in &delegate.content {
-                                        if delegate.is_selected(summary.filename_hash) && summary.content_summary.update_status.load(Ordering::Relaxed).can_update() {
+                                        if delegate.is_selected(summary.filename_hash) && summary.update_status().can_update() {
                                             updating.insert(summary.filename_hash);
                                             crate::root::update_single_mod(id, summary.id, &backend_handle, window, cx);
                                         }
@@ -179,6 +189,34 @@ impl ContentListDelegate {
             },
         };
 
+        let pinned = summary.pinned;
+        let pin_tooltip = if pinned {
+            "Pinned - will not be updated"
+        } else {
+            "Pin to the current version"
+        };
+        let pin_button = Button::new(("pin", element_id)).icon(Icon::default().path("icons/pin.svg"))
+            .selected(pinned).tooltip(pin_tooltip).on_click({
+                let backend_handle = self.backend_handle.clone();
+                cx.listener(move |this, _, _, cx| {
+                    cx.stop_propagation();
+                    let delegate = this.delegate();
+                    let content_ids = if delegate.is_selected(element_id) {
+                        delegate.content.iter().filter_map(|summary| {
+                            delegate.is_selected(summary.filename_hash).then(|| summary.id)
+                        }).collect()
+                    } else {
+                        vec![content_id]
+                    };
+
+                    backend_handle.send(MessageToBackend::SetContentPinned {
+                        id,
+                        content_ids,
+                        pinned: !pinned,
+                    });
+                })
+            });
+
         let backend_handle = self.backend_handle.clone();
 
         let toggle_control = Switch::new(("toggle", element_id))
@@ -248,11 +286,10 @@ impl ContentListDelegate {
             .border_1()
             .when(selected, |content| content.border_color(cx.theme().selection).bg(cx.theme().selection.alpha(0.2)));
 
-        if let Some(update_button) = update_button {
-            item_content = item_content.child(h_flex().absolute().right_4().gap_2().child(update_button).child(delete_button))
-        } else {
-            item_content = item_content.child(delete_button.absolute().right_4())
-        }
+        item_content = item_content.child(h_flex().absolute().right_4().gap_2()
+            .when_some(update_button, |this, update_button| this.child(update_button))
+            .child(pin_button)
+            .child(delete_button));
 
         ListItem::new(("item", element_id)).p_1().child(item_content).on_click(cx.listener(move |this, click: &ClickEvent, _, cx| {
             cx.stop_propagation();
@@ -127,7 +127,7 @@ impl TableDelegate for InstanceList {
                             let name = item.name.clone();
                             let id = item.id;
                             move |_, window, cx| {
-                                root::start_instance(id, name.clone(), None, &backend_handle, window, cx);
+                                root::start_instance(id, name.clone(), None, None, &backend_handle, window, cx);
                             }
                         }))
                         .child(Button::new("view").w(relative(0.5)).small().info().label("View").on_click({
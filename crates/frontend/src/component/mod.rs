@@ -6,4 +6,5 @@ pub mod named_dropdown;
 pub mod page_path;
 pub mod progress_bar;
 pub mod readonly_text_field;
+pub mod release_channel_group;
 pub mod search_helper;
@@ -3,8 +3,9 @@ use std::sync::Arc;
 use gpui::SharedString;
 use gpui_component::breadcrumb::{Breadcrumb, BreadcrumbItem};
 use gpui::*;
+use schema::modrinth::ModrinthProject;
 
-use crate::{entity::{DataEntities, instance::InstanceEntries}, ui::PageType};
+use crate::{entity::{DataEntities, instance::InstanceEntries, metadata::{AsMetadataResult, FrontendMetadataResult}}, ui::PageType};
 
 pub struct PagePath {
     pages: Arc<[PageType]>,
@@ -15,6 +16,10 @@ impl PagePath {
         Self { pages }
     }
 
+    pub fn pages(&self) -> Arc<[PageType]> {
+        self.pages.clone()
+    }
+
     pub fn create_breadcrumb(&self, data: &DataEntities, cx: &App) -> Breadcrumb {
         let mut breadcrumb = Breadcrumb::new().text_xl();
 
@@ -31,6 +36,16 @@ impl PagePath {
                         "Modrinth".into()
                     }
                 },
+                PageType::ModrinthProject { project, .. } => {
+                    let request = bridge::meta::MetadataRequest::ModrinthProject(project.as_str().into());
+                    data.metadata.read(cx).data.get(&request).and_then(|state| {
+                        let result: FrontendMetadataResult<ModrinthProject> = state.read(cx).result();
+                        match result {
+                            FrontendMetadataResult::Loaded(project) => Some(SharedString::new(project.title.clone())),
+                            _ => None,
+                        }
+                    }).unwrap_or("Project".into())
+                },
                 PageType::InstancePage(instance_id, _) => {
                     InstanceEntries::find_title_by_id(&data.instances, instance_id, cx)
                         .unwrap_or("<instance name>".into())
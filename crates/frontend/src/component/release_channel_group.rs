@@ -0,0 +1,66 @@
+use gpui::{prelude::*, *};
+use gpui_component::{button::{Button, ButtonGroup, ButtonVariants}, Selectable, Sizable};
+use schema::instance::ReleaseChannel;
+
+const CHANNELS: [(ReleaseChannel, &str, &str); 3] = [
+    (ReleaseChannel::Release, "release", "Release"),
+    (ReleaseChannel::Beta, "beta", "Beta"),
+    (ReleaseChannel::Alpha, "alpha", "Alpha"),
+];
+
+#[derive(IntoElement)]
+pub struct ReleaseChannelGroup {
+    id: ElementId,
+    selected: ReleaseChannel,
+    small: bool,
+    on_select: Option<Box<dyn Fn(&ReleaseChannel, &mut Window, &mut App)>>,
+}
+
+impl ReleaseChannelGroup {
+    pub fn new(id: impl Into<ElementId>, selected: ReleaseChannel) -> Self {
+        Self {
+            id: id.into(),
+            selected,
+            small: false,
+            on_select: None,
+        }
+    }
+
+    pub fn small(mut self) -> Self {
+        self.small = true;
+        self
+    }
+
+    /// Only called when a channel other than the selected one is clicked
+    pub fn on_select(mut self, handler: impl Fn(&ReleaseChannel, &mut Window, &mut App) + 'static) -> Self {
+        self.on_select = Some(Box::new(handler));
+        self
+    }
+}
+
+impl RenderOnce for ReleaseChannelGroup {
+    fn render(self, _: &mut Window, _: &mut App) -> impl IntoElement {
+        let mut group = ButtonGroup::new(self.id).outline();
+        if self.small {
+            group = group.small();
+        }
+
+        for (channel, id, label) in CHANNELS {
+            group = group.child(Button::new(id).label(label).selected(self.selected == channel));
+        }
+
+        let selected = self.selected;
+        if let Some(on_select) = self.on_select {
+            group = group.on_click(move |clicked: &Vec<usize>, window, cx| {
+                let Some((channel, _, _)) = clicked.first().and_then(|index| CHANNELS.get(*index)) else {
+                    return;
+                };
+                if *channel != selected {
+                    (on_select)(channel, window, cx);
+                }
+            });
+        }
+
+        group
+    }
+}
@@ -0,0 +1,24 @@
+use std::sync::Arc;
+
+use gpui::{App, Entity};
+
+#[derive(Default)]
+pub struct ConnectivityState {
+    pub offline: bool,
+    pub unavailable: Arc<[Arc<str>]>,
+}
+
+impl ConnectivityState {
+    pub fn set(
+        entity: &Entity<Self>,
+        offline: bool,
+        unavailable: Arc<[Arc<str>]>,
+        cx: &mut App,
+    ) {
+        entity.update(cx, |state, cx| {
+            state.offline = offline;
+            state.unavailable = unavailable;
+            cx.notify();
+        });
+    }
+}
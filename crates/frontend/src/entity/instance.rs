@@ -1,7 +1,7 @@
 use std::{path::Path, sync::Arc};
 
 use bridge::{
-    instance::{InstanceID, InstanceContentSummary, InstanceServerSummary, InstanceStatus, InstanceWorldSummary},
+    instance::{InstanceID, InstanceContentSummary, InstanceServerSummary, InstanceSessionSummary, InstanceStatus, InstanceWorldSummary},
     message::AtomicBridgeDataLoadState,
 };
 use gpui::{prelude::*, *};
@@ -34,6 +34,7 @@ impl InstanceEntries {
                 dot_minecraft_folder,
                 configuration,
                 status: InstanceStatus::NotRunning,
+                sessions: [].into(),
                 worlds_state,
                 worlds: cx.new(|_| [].into()),
                 servers_state,
@@ -88,6 +89,7 @@ impl InstanceEntries {
         dot_minecraft_folder: Arc<Path>,
         configuration: InstanceConfiguration,
         status: InstanceStatus,
+        sessions: Arc<[InstanceSessionSummary]>,
         cx: &mut App,
     ) {
         entity.update(cx, |entries, cx| {
@@ -97,6 +99,7 @@ impl InstanceEntries {
                     instance.dot_minecraft_folder = dot_minecraft_folder.clone();
                     instance.configuration = configuration.clone();
                     instance.status = status;
+                    instance.sessions = sessions.clone();
                     instance.title = instance.create_title().into();
                     cx.notify();
 
@@ -191,6 +194,7 @@ pub struct InstanceEntry {
     pub dot_minecraft_folder: Arc<Path>,
     pub configuration: InstanceConfiguration,
     pub status: InstanceStatus,
+    pub sessions: Arc<[InstanceSessionSummary]>,
     pub worlds_state: Arc<AtomicBridgeDataLoadState>,
     pub worlds: Entity<Arc<[InstanceWorldSummary]>>,
     pub servers_state: Arc<AtomicBridgeDataLoadState>,
@@ -5,10 +5,11 @@ use gpui::Entity;
 use parking_lot::RwLock;
 
 use crate::entity::{
-    account::AccountEntries, instance::InstanceEntries, metadata::FrontendMetadata
+    account::AccountEntries, connectivity::ConnectivityState, instance::InstanceEntries, metadata::FrontendMetadata
 };
 
 pub mod account;
+pub mod connectivity;
 pub mod instance;
 pub mod metadata;
 
@@ -17,6 +18,7 @@ pub struct DataEntities {
     pub instances: Entity<InstanceEntries>,
     pub metadata: Entity<FrontendMetadata>,
     pub accounts: Entity<AccountEntries>,
+    pub connectivity: Entity<ConnectivityState>,
     pub backend_handle: BackendHandle,
     pub theme_folder: Arc<Path>,
     pub panic_messages: Arc<PanicMessages>,
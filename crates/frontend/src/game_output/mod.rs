@@ -3,15 +3,42 @@ use std::{cell::RefCell, collections::HashMap, num::NonZeroUsize, ops::Range, rc
 use ftree::FenwickTree;
 use gpui::{prelude::*, *};
 use gpui_component::{
-    button::Button, h_flex, input::{Input, InputEvent, InputState}, scroll::{Scrollbar, ScrollbarHandle}, v_flex, ActiveTheme as _, Icon, IconName, Sizable
+    button::Button, h_flex, input::{Input, InputEvent, InputState}, scroll::{Scrollbar, ScrollbarHandle}, v_flex, ActiveTheme as _, Icon, IconName, Root, Sizable
 };
 use lru::LruCache;
 use rustc_hash::FxBuildHasher;
 
 use bridge::{game_output::GameOutputLogLevel, keep_alive::KeepAlive};
+use tokio::sync::mpsc::UnboundedSender;
 
 use crate::CloseWindow;
 
+/// Open game output windows, keyed by the id the backend gave them
+#[derive(Default)]
+pub struct GameOutputWindows {
+    pub windows: HashMap<usize, (WindowHandle<Root>, Entity<GameOutput>)>,
+}
+
+impl Global for GameOutputWindows {}
+
+impl GameOutputWindows {
+    pub fn get(id: usize, cx: &App) -> Option<(WindowHandle<Root>, Entity<GameOutput>)> {
+        cx.try_global::<Self>().and_then(|windows| windows.windows.get(&id).cloned())
+    }
+
+    /// Brings the window to the front, returns false if it has already been closed
+    pub fn focus(id: usize, cx: &mut App) -> bool {
+        let Some((window, _)) = Self::get(id, cx) else {
+            return false;
+        };
+        if window.update(cx, |_, window, _| window.activate_window()).is_ok() {
+            return true;
+        }
+        cx.default_global::<Self>().windows.remove(&id);
+        false
+    }
+}
+
 struct CachedShapedLogLevels {
     fatal: Arc<ShapedLine>,
     error: Arc<ShapedLine>,
@@ -831,9 +858,17 @@ pub struct GameOutputRoot {
     search_state: Entity<InputState>,
     _search_task: Task<()>,
     _search_input_subscription: Subscription,
+    console: Option<Console>,
     focus_handle: FocusHandle,
 }
 
+/// Command input for processes that accept input on stdin, such as dedicated servers
+struct Console {
+    input_state: Entity<InputState>,
+    sender: UnboundedSender<Arc<str>>,
+    _input_subscription: Subscription,
+}
+
 #[derive(Clone)]
 pub struct ScrollHandler {
     state: Rc<RefCell<GameOutputScrollState>>,
@@ -939,6 +974,7 @@ impl GameOutputRoot {
     pub fn new(
         keep_alive: KeepAlive,
         game_output: Entity<GameOutput>,
+        console: Option<UnboundedSender<Arc<str>>>,
         window: &mut Window,
         cx: &mut Context<Self>,
     ) -> Self {
@@ -948,6 +984,16 @@ impl GameOutputRoot {
 
         let _search_input_subscription = cx.subscribe_in(&search_state, window, Self::on_search_input_event);
 
+        let console = console.map(|sender| {
+            let input_state = cx.new(|cx| InputState::new(window, cx).placeholder("Enter a command"));
+            let _input_subscription = cx.subscribe_in(&input_state, window, Self::on_console_input_event);
+            Console {
+                input_state,
+                sender,
+                _input_subscription,
+            }
+        });
+
         let focus_handle = cx.focus_handle();
         focus_handle.focus(window, cx);
 
@@ -958,10 +1004,45 @@ impl GameOutputRoot {
             search_state,
             _search_task: Task::ready(()),
             _search_input_subscription,
+            console,
             focus_handle,
         }
     }
 
+    fn on_console_input_event(
+        &mut self,
+        state: &Entity<InputState>,
+        event: &InputEvent,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let InputEvent::PressEnter { secondary: false } = event else {
+            return;
+        };
+        let Some(console) = &self.console else {
+            return;
+        };
+
+        let command = state.read(cx).value();
+        let command = command.trim();
+        if command.is_empty() {
+            return;
+        }
+
+        let (level, text) = if console.sender.send(command.into()).is_ok() {
+            (GameOutputLogLevel::Other, format!("> {command}"))
+        } else {
+            (GameOutputLogLevel::Error, "Unable to send the command, the process has exited".to_string())
+        };
+        self.game_output.update(cx, |game_output, _| {
+            game_output.add(chrono::Utc::now().timestamp_millis(), level, Arc::new([text.into()]));
+        });
+
+        state.update(cx, |input, cx| input.set_value("", window, cx));
+        self.scroll_handler.state.borrow_mut().scrolling = GameOutputScrolling::Bottom;
+        cx.notify();
+    }
+
     fn on_search_input_event(
         &mut self,
         state: &Entity<InputState>,
@@ -1075,6 +1156,10 @@ impl Render for GameOutputRoot {
             })))
             .child(Button::new("upload").label("Upload"));
 
+        let console = self.console.as_ref().map(|console| {
+            Input::new(&console.input_state).prefix(Icon::new(IconName::ArrowRight).small())
+        });
+
         v_flex()
             .size_full()
             .border_12()
@@ -1098,6 +1183,7 @@ impl Render for GameOutputRoot {
                             .child(Scrollbar::vertical(&self.scroll_handler)),
                     ),
             )
+            .children(console)
             .on_scroll_wheel(cx.listener(|root, event: &ScrollWheelEvent, _, cx| {
                 let state = root.scroll_handler.state.borrow();
                 let delta = event.delta.pixel_delta(state.line_height).y;
@@ -2,8 +2,9 @@ use std::{io::Write, path::Path, sync::Arc, time::Duration};
 
 use gpui::{App, SharedString, Task};
 use rand::RngCore;
-use schema::modrinth::ModrinthProjectType;
+use schema::{loader::Loader, modrinth::{ModrinthProjectType, ModrinthSearchIndex}};
 use serde::{Deserialize, Serialize};
+use ustr::Ustr;
 
 use crate::ui::SerializedPageType;
 
@@ -32,9 +33,30 @@ pub struct InterfaceConfig {
     #[serde(default, deserialize_with = "schema::try_deserialize")]
     pub modrinth_page_project_type: ModrinthProjectType,
     #[serde(default, deserialize_with = "schema::try_deserialize")]
+    pub modrinth_search_filters: ModrinthSearchFilters,
+    #[serde(default, deserialize_with = "schema::try_deserialize")]
     pub hide_main_window_on_launch: bool,
 }
 
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct ModrinthSearchFilters {
+    #[serde(default, deserialize_with = "schema::try_deserialize")]
+    pub sort: ModrinthSearchIndex,
+    #[serde(default, deserialize_with = "schema::try_deserialize")]
+    pub loaders: Vec<Loader>,
+    // Only used when not installing for an instance, otherwise the instance's version is used
+    #[serde(default, deserialize_with = "schema::try_deserialize")]
+    pub game_version: Option<Ustr>,
+    #[serde(default, deserialize_with = "schema::try_deserialize")]
+    pub client_side: bool,
+    #[serde(default, deserialize_with = "schema::try_deserialize")]
+    pub server_side: bool,
+    #[serde(default, deserialize_with = "schema::try_deserialize")]
+    pub open_source: bool,
+    #[serde(default, deserialize_with = "schema::try_deserialize")]
+    pub hide_installed: bool,
+}
+
 
 
 impl InterfaceConfig {
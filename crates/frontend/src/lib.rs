@@ -4,9 +4,9 @@ use std::{
     path::{Path, PathBuf}, sync::{Arc, atomic::AtomicBool}
 };
 
-use bridge::
-    handle::{BackendHandle, FrontendReceiver}
-;
+use bridge::{
+    handle::{BackendHandle, FrontendReceiver}, message::ProxyInfo
+};
 use gpui::*;
 use gpui_component::{
     notification::{Notification, NotificationType}, Root, StyledExt, WindowExt
@@ -16,7 +16,7 @@ use parking_lot::RwLock;
 
 use crate::{
     entity::{
-        DataEntities, PanicMessages, account::AccountEntries, instance::InstanceEntries, metadata::FrontendMetadata
+        DataEntities, PanicMessages, account::AccountEntries, connectivity::ConnectivityState, instance::InstanceEntries, metadata::FrontendMetadata
     }, interface_config::InterfaceConfig, processor::Processor, root::{LauncherRoot, LauncherRootGlobal}
 };
 
@@ -70,6 +70,23 @@ pub const MAIN_FONT: &'static str = "Inter 24pt";
 
 actions!([Quit, CloseWindow]);
 
+const USER_AGENT: &str = "PandoraLauncher/0.1.0 (https://github.com/Moulberry/PandoraLauncher)";
+
+/// Creates the client used for images and other frontend requests, recreated whenever the backend's proxy changes
+pub(crate) fn create_http_client(proxy: Option<&ProxyInfo>) -> Arc<reqwest_client::ReqwestClient> {
+    let client = match proxy {
+        Some(proxy) => match gpui::http_client::Url::parse(&proxy.url) {
+            Ok(url) => reqwest_client::ReqwestClient::proxy_no_proxy_and_user_agent(Some(url), proxy.no_proxy.as_deref(), USER_AGENT),
+            Err(error) => {
+                log::error!("Invalid proxy url for the frontend: {error}");
+                reqwest_client::ReqwestClient::user_agent(USER_AGENT)
+            },
+        },
+        None => reqwest_client::ReqwestClient::user_agent(USER_AGENT),
+    };
+    Arc::new(client.unwrap())
+}
+
 pub fn start(
     launcher_dir: PathBuf,
     panic_message: Arc<RwLock<Option<String>>>,
@@ -77,12 +94,7 @@ pub fn start(
     backend_handle: BackendHandle,
     recv: FrontendReceiver,
 ) {
-    let http_client = std::sync::Arc::new(
-        reqwest_client::ReqwestClient::user_agent(
-            "PandoraLauncher/0.1.0 (https://github.com/Moulberry/PandoraLauncher)",
-        )
-        .unwrap(),
-    );
+    let http_client = create_http_client(None);
 
     Application::new().with_http_client(http_client).with_assets(Assets).run(move |cx: &mut App| {
         let _ = cx.text_system().add_fonts(vec![
@@ -144,11 +156,13 @@ pub fn start(
         });
         let metadata = cx.new(|_| FrontendMetadata::new(backend_handle.clone()));
         let accounts = cx.new(|_| AccountEntries::default());
+        let connectivity = cx.new(|_| ConnectivityState::default());
         let data = DataEntities {
             instances,
             metadata,
             backend_handle,
             accounts,
+            connectivity,
             theme_folder: theme_folder.into(),
             panic_messages: Arc::new(PanicMessages {
                 panic_message,
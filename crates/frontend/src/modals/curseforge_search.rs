@@ -0,0 +1,181 @@
+use bridge::{install::{ContentDownload, ContentInstall, ContentInstallFile, ContentInstallPath, InstallTarget}, instance::InstanceID, meta::MetadataRequest};
+use gpui::{prelude::*, *};
+use gpui_component::{
+    button::{Button, ButtonVariants}, dialog::Dialog, h_flex, input::{Input, InputEvent, InputState}, scroll::ScrollableElement, skeleton::Skeleton, spinner::Spinner, v_flex, ActiveTheme, Sizable
+};
+use schema::{content::{ContentProviderKind, ContentSearchRequest, ContentSearchResult, ContentSearchType, ContentSource}, loader::Loader};
+use ustr::Ustr;
+
+use crate::{
+    component::error_alert::ErrorAlert,
+    entity::{metadata::{AsMetadataResult, FrontendMetadata, FrontendMetadataResult, FrontendMetadataState}, DataEntities},
+    root,
+};
+
+const PAGE_SIZE: usize = 20;
+
+struct SearchDialog {
+    data: DataEntities,
+    instance: InstanceID,
+    loader: Loader,
+    minecraft_version: Ustr,
+    content_type: ContentSearchType,
+    input_state: Entity<InputState>,
+    search: Option<Entity<FrontendMetadataState>>,
+    _search_subscription: Option<Subscription>,
+    _input_subscription: Subscription,
+}
+
+pub fn open(
+    instance: InstanceID,
+    loader: Loader,
+    minecraft_version: Ustr,
+    content_type: ContentSearchType,
+    data: &DataEntities,
+    window: &mut Window,
+    cx: &mut App,
+) {
+    let input_state = cx.new(|cx| InputState::new(window, cx).placeholder("Search CurseForge..."));
+
+    let dialog = cx.new(|cx| {
+        let _input_subscription = cx.subscribe(&input_state, |this: &mut SearchDialog, _, event: &InputEvent, cx| {
+            if let InputEvent::PressEnter { .. } = event {
+                this.search(cx);
+            }
+        });
+
+        let mut dialog = SearchDialog {
+            data: data.clone(),
+            instance,
+            loader,
+            minecraft_version,
+            content_type,
+            input_state,
+            search: None,
+            _search_subscription: None,
+            _input_subscription,
+        };
+        dialog.search(cx);
+        dialog
+    });
+    window.open_dialog(cx, move |modal, window, cx| {
+        dialog.update(cx, |this, cx| this.render(modal, window, cx))
+    });
+}
+
+impl SearchDialog {
+    fn search(&mut self, cx: &mut Context<Self>) {
+        let query = self.input_state.read(cx).value();
+        let query = query.trim();
+
+        let request = MetadataRequest::ContentSearch(ContentSearchRequest {
+            provider: ContentProviderKind::CurseForge,
+            query: if query.is_empty() { None } else { Some(query.into()) },
+            content_type: self.content_type,
+            game_version: Some(self.minecraft_version.as_str().into()),
+            loader: if self.loader == Loader::Vanilla { None } else { Some(self.loader) },
+            offset: 0,
+            limit: PAGE_SIZE,
+        });
+
+        let search = FrontendMetadata::request(&self.data.metadata, request, cx);
+        self._search_subscription = Some(cx.observe(&search, |_, _, cx| cx.notify()));
+        self.search = Some(search);
+        cx.notify();
+    }
+
+    fn install(&self, project_id: u32, window: &mut Window, cx: &mut App) {
+        // Only mod files are tagged with a loader on CurseForge
+        let loader_hint = match self.content_type {
+            ContentSearchType::Mod | ContentSearchType::Modpack => self.loader,
+            ContentSearchType::ResourcePack | ContentSearchType::Shader => Loader::Unknown,
+        };
+
+        let content_install = ContentInstall {
+            target: InstallTarget::Instance(self.instance),
+            loader_hint,
+            version_hint: Some(self.minecraft_version.as_str().into()),
+            files: [ContentInstallFile {
+                replace_old: None,
+                path: ContentInstallPath::Automatic,
+                download: ContentDownload::CurseForge { project_id, file_id: None },
+                content_source: ContentSource::CurseForgeProject { project: project_id },
+            }].into(),
+        };
+        root::start_install(content_install, &self.data.backend_handle, window, cx);
+    }
+
+    fn render(&mut self, modal: Dialog, _window: &mut Window, cx: &mut Context<Self>) -> Dialog {
+        let modal = modal.title("Add from CurseForge").w(px(640.0));
+
+        let search_bar = h_flex()
+            .gap_2()
+            .child(div().flex_1().child(Input::new(&self.input_state)))
+            .child(Button::new("search").success().label("Search").on_click(cx.listener(|this, _, _, cx| {
+                this.search(cx);
+            })));
+
+        let modal = modal.child(search_bar);
+
+        let Some(search) = &self.search else {
+            return modal;
+        };
+
+        let result: FrontendMetadataResult<ContentSearchResult> = search.read(cx).result();
+        let result = match result {
+            FrontendMetadataResult::Loading => {
+                return modal.child(h_flex().gap_2().child("Searching...").child(Spinner::new()));
+            },
+            FrontendMetadataResult::Error(error) => {
+                return modal.child(ErrorAlert::new("error", "Error requesting from CurseForge".into(), error));
+            },
+            FrontendMetadataResult::Loaded(result) => result,
+        };
+
+        if result.hits.is_empty() {
+            return modal.child("No results");
+        }
+
+        let theme = cx.theme();
+        let mut hits = v_flex().gap_2();
+        for (index, hit) in result.hits.iter().enumerate() {
+            let Ok(project_id) = hit.project_id.parse::<u32>() else {
+                continue;
+            };
+
+            let image = if let Some(icon_url) = &hit.icon_url
+                && !icon_url.is_empty()
+            {
+                gpui::img(SharedUri::from(icon_url))
+                    .with_fallback(|| Skeleton::new().rounded_lg().size_8().into_any_element())
+            } else {
+                gpui::img(ImageSource::Resource(Resource::Embedded(
+                    "images/default_mod.png".into(),
+                )))
+            };
+
+            let mut title = SharedString::from(hit.name.clone());
+            if let Some(author) = &hit.author {
+                title = format!("{} by {}", title, author).into();
+            }
+
+            hits = hits.child(h_flex()
+                .gap_2()
+                .child(image.rounded_lg().size_8().min_w_8().min_h_8())
+                .child(v_flex()
+                    .flex_1()
+                    .overflow_hidden()
+                    .child(title)
+                    .when_some(hit.summary.clone(), |this, summary| {
+                        this.child(div().text_sm().text_color(theme.muted_foreground).truncate().child(SharedString::from(summary)))
+                    }))
+                .child(Button::new(("install", index)).success().small().label("Install").on_click(
+                    cx.listener(move |this, _, window, cx| {
+                        this.install(project_id, window, cx);
+                    })
+                )));
+        }
+
+        modal.child(div().max_h(px(480.0)).child(hits).overflow_y_scrollbar())
+    }
+}
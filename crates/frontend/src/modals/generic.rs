@@ -1,6 +1,6 @@
 use std::sync::Arc;
 
-use bridge::modal_action::{ModalAction, ProgressTrackerFinishType};
+use bridge::modal_action::{ModalAction, ProgressTracker, ProgressTrackerFinishType};
 use gpui::{prelude::*, *};
 use gpui_component::{
     button::{Button, ButtonVariants}, dialog::DialogButtonProps, notification::Notification, v_flex, IconName, WindowExt
@@ -11,6 +11,26 @@ use crate::component::{
     progress_bar::{ProgressBar, ProgressBarColor},
 };
 
+/// The tracker's title, followed by the download speed and time remaining while it's downloading
+fn tracker_title(tracker: &ProgressTracker) -> SharedString {
+    let title = tracker.get_title();
+    let Some((speed, eta)) = tracker.download_rate() else {
+        return SharedString::from(title);
+    };
+
+    let speed = if speed < 1000.0 * 1000.0 {
+        format!("{:.0}kB/s", speed / 1000.0)
+    } else {
+        format!("{:.1}MB/s", speed / 1000.0 / 1000.0)
+    };
+
+    match eta.map(|eta| eta.as_secs()) {
+        Some(secs) if secs >= 60 => format!("{title} ({speed}, {}m {}s left)", secs / 60, secs % 60).into(),
+        Some(secs) => format!("{title} ({speed}, {secs}s left)").into(),
+        None => format!("{title} ({speed})").into(),
+    }
+}
+
 pub fn show_notification(
     window: &mut Window,
     cx: &mut App,
@@ -75,8 +95,7 @@ pub fn show_notification_with_note(
                     window.request_animation_frame();
                 }
 
-                let title = tracker.get_title();
-                progress_entries.push(div().gap_3().child(SharedString::from(title)).child(progress_bar).opacity(opacity));
+                progress_entries.push(div().gap_3().child(tracker_title(tracker)).child(progress_bar).opacity(opacity));
             }
             drop(trackers);
 
@@ -170,8 +189,7 @@ pub fn show_modal(
                 window.request_animation_frame();
             }
 
-            let title = tracker.get_title();
-            progress_entries.push(div().gap_3().child(SharedString::from(title)).child(progress_bar).opacity(opacity));
+            progress_entries.push(div().gap_3().child(tracker_title(tracker)).child(progress_bar).opacity(opacity));
         }
         drop(trackers);
 
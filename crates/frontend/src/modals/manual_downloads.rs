@@ -0,0 +1,36 @@
+use bridge::message::ManualDownload;
+use gpui::{prelude::*, *};
+use gpui_component::{
+    button::{Button, ButtonVariants}, h_flex, scroll::ScrollableElement, v_flex, ActiveTheme, Sizable, WindowExt
+};
+
+pub fn open_manual_downloads(
+    manual_downloads: Vec<ManualDownload>,
+    window: &mut Window,
+    cx: &mut App,
+) {
+    window.open_dialog(cx, move |modal, _, cx| {
+        let theme = cx.theme();
+
+        let mut list = v_flex().gap_2();
+        for (index, download) in manual_downloads.iter().enumerate() {
+            let url = download.url.clone();
+            list = list.child(h_flex()
+                .gap_2()
+                .justify_between()
+                .child(v_flex()
+                    .child(SharedString::from(download.name.clone()))
+                    .child(div().text_sm().text_color(theme.muted_foreground)
+                        .child(format!("{} → {}", download.filename, download.destination))))
+                .child(Button::new(("open", index)).success().small().label("Open").on_click(move |_, _, cx| {
+                    cx.open_url(&url);
+                })));
+        }
+
+        modal
+            .title("Manual downloads required")
+            .child("The authors of these files don't allow them to be downloaded by third-party launchers. \
+                Download them from CurseForge and place them into the listed folder of the instance.")
+            .child(div().max_h(px(480.0)).child(list).overflow_y_scrollbar())
+    });
+}
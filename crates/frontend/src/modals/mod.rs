@@ -1,5 +1,14 @@
+pub mod curseforge_search;
 pub mod generic;
+pub mod manual_downloads;
+pub mod modrinth_collection;
 pub mod modrinth_install;
 pub mod modrinth_install_auto;
+pub mod offline;
 pub mod delete_instance;
+pub mod server_properties;
 pub mod settings;
+pub mod skins;
+pub mod start_session;
+pub mod sync_server_mods;
+pub mod update_all;
@@ -0,0 +1,225 @@
+use std::sync::Arc;
+
+use bridge::{instance::InstanceID, message::{MessageToBackend, ModrinthProjectList, ModrinthProjectListSource, SkippedProject}};
+use gpui::{prelude::*, *};
+use gpui_component::{
+    button::{Button, ButtonVariants}, dialog::Dialog, h_flex, input::{Input, InputState}, scroll::ScrollableElement, select::{Select, SelectState}, skeleton::Skeleton, spinner::Spinner, v_flex, ActiveTheme, Disableable, IndexPath, WindowExt
+};
+
+use crate::{
+    component::{error_alert::ErrorAlert, instance_dropdown::InstanceDropdown},
+    entity::{instance::InstanceEntry, DataEntities},
+    root,
+};
+
+struct CollectionDialog {
+    data: DataEntities,
+    input_state: Entity<InputState>,
+    instances: Option<Entity<SelectState<InstanceDropdown>>>,
+    list: Option<Result<ModrinthProjectList, SharedString>>,
+    load_task: Option<Task<()>>,
+}
+
+pub fn open(install_for: Option<InstanceID>, data: &DataEntities, window: &mut Window, cx: &mut App) {
+    let input_state = cx.new(|cx| InputState::new(window, cx).placeholder("Collection ID or URL"));
+
+    let entries: Arc<[InstanceEntry]> = data.instances.read(cx).entries.values()
+        .map(|instance| instance.read(cx).clone())
+        .collect();
+    let instances = if !entries.is_empty() {
+        let selected = install_for.and_then(|id| entries.iter().position(|entry| entry.id == id)).unwrap_or(0);
+        let dropdown = InstanceDropdown::create(entries, window, cx);
+        dropdown.update(cx, |dropdown, cx| {
+            dropdown.set_selected_index(Some(IndexPath::default().row(selected)), window, cx)
+        });
+        Some(dropdown)
+    } else {
+        None
+    };
+
+    let dialog = cx.new(|_| CollectionDialog {
+        data: data.clone(),
+        input_state,
+        instances,
+        list: None,
+        load_task: None,
+    });
+    window.open_dialog(cx, move |modal, window, cx| {
+        dialog.update(cx, |this, cx| this.render(modal, window, cx))
+    });
+}
+
+pub fn open_skipped_projects(
+    skipped: Vec<SkippedProject>,
+    window: &mut Window,
+    cx: &mut App,
+) {
+    window.open_dialog(cx, move |modal, _, cx| {
+        modal
+            .title("Some projects were skipped")
+            .child("These projects weren't installed into the instance.")
+            .child(div().max_h(px(480.0)).child(skipped_list(&skipped, cx)).overflow_y_scrollbar())
+    });
+}
+
+fn skipped_list(skipped: &[SkippedProject], cx: &App) -> Div {
+    let theme = cx.theme();
+    let mut list = v_flex().gap_1();
+    for project in skipped.iter() {
+        list = list.child(v_flex()
+            .child(SharedString::from(project.name.clone()))
+            .child(div().text_sm().text_color(theme.muted_foreground).child(SharedString::from(project.reason.clone()))));
+    }
+    list
+}
+
+/// Accepts either a bare collection ID or a link such as https://modrinth.com/collection/<id>
+fn parse_collection_id(input: &str) -> Option<Arc<str>> {
+    let input = input.trim();
+    let id = match input.split_once("modrinth.com/collection/") {
+        Some((_, rest)) => rest,
+        None => input,
+    };
+    let id = id.split(['/', '?', '#']).next().unwrap_or_default();
+    if id.is_empty() {
+        None
+    } else {
+        Some(id.into())
+    }
+}
+
+impl CollectionDialog {
+    fn load(&mut self, source: ModrinthProjectListSource, cx: &mut Context<Self>) {
+        let (send, recv) = tokio::sync::oneshot::channel();
+        self.load_task = Some(cx.spawn(async move |dialog, cx| {
+            let result = recv.await.unwrap_or_else(|_| Err("Backend didn't respond".into()));
+            let _ = dialog.update(cx, move |dialog, cx| {
+                dialog.list = Some(result.map_err(SharedString::from));
+                dialog.load_task = None;
+                cx.notify();
+            });
+        }));
+
+        self.list = None;
+        self.data.backend_handle.send(MessageToBackend::GetModrinthProjectList {
+            source,
+            channel: send,
+        });
+        cx.notify();
+    }
+
+    fn render(&mut self, modal: Dialog, _window: &mut Window, cx: &mut Context<Self>) -> Dialog {
+        let modal = modal.title("Install from collection").w(px(640.0));
+
+        let loading = self.load_task.is_some();
+        let source_bar = h_flex()
+            .gap_2()
+            .child(div().flex_1().child(Input::new(&self.input_state)))
+            .child(Button::new("load-collection").success().label("Load").disabled(loading).on_click(cx.listener(|this, _, _, cx| {
+                let Some(id) = parse_collection_id(&this.input_state.read(cx).value()) else {
+                    return;
+                };
+                this.load(ModrinthProjectListSource::Collection(id), cx);
+            })))
+            .child(Button::new("load-followed").info().label("Followed projects").disabled(loading).on_click(cx.listener(|this, _, _, cx| {
+                this.load(ModrinthProjectListSource::FollowedProjects, cx);
+            })));
+
+        let modal = modal.child(source_bar);
+
+        if loading {
+            return modal.child(h_flex().gap_2().child("Loading projects...").child(Spinner::new()));
+        }
+
+        let list = match &self.list {
+            None => return modal,
+            Some(Err(error)) => {
+                return modal.child(ErrorAlert::new("error", "Error requesting from Modrinth".into(), error.clone()));
+            },
+            Some(Ok(list)) => list,
+        };
+
+        let theme = cx.theme();
+        let mut projects = v_flex().gap_2();
+        for project in list.projects.iter() {
+            let image = if let Some(icon_url) = &project.icon_url
+                && !icon_url.is_empty()
+            {
+                gpui::img(SharedUri::from(icon_url))
+                    .with_fallback(|| Skeleton::new().rounded_lg().size_8().into_any_element())
+            } else {
+                gpui::img(ImageSource::Resource(Resource::Embedded(
+                    "images/default_mod.png".into(),
+                )))
+            };
+
+            projects = projects.child(h_flex()
+                .gap_2()
+                .child(image.rounded_lg().size_8().min_w_8().min_h_8())
+                .child(v_flex()
+                    .overflow_hidden()
+                    .child(SharedString::from(project.title.clone()))
+                    .child(div().text_sm().text_color(theme.muted_foreground).truncate()
+                        .child(SharedString::from(project.description.clone())))));
+        }
+
+        let mut content = v_flex()
+            .gap_2()
+            .child(div().font_bold().child(SharedString::from(format!("{} ({} projects)", list.name, list.projects.len()))))
+            .child(div().max_h(px(360.0)).child(projects).overflow_y_scrollbar());
+
+        if !list.failed.is_empty() {
+            content = content
+                .child(div().font_bold().child(SharedString::from(format!("{} projects couldn't be loaded", list.failed.len()))))
+                .child(div().max_h(px(120.0)).child(skipped_list(&list.failed, cx)).overflow_y_scrollbar());
+        }
+
+        if let Some(instances) = &self.instances {
+            let selected_instance: Option<InstanceEntry> = instances.read(cx).selected_value().cloned();
+            content = content.child(h_flex()
+                .gap_2()
+                .child(div().flex_1().child(Select::new(instances).placeholder("Select an instance").title_prefix("Instance: ")))
+                .when_some(selected_instance, |content, instance| {
+                    let project_ids: Arc<[Arc<str>]> = list.projects.iter().map(|project| project.id.clone()).collect();
+                    content.child(Button::new("install-all").success().label("Install all").disabled(project_ids.is_empty()).on_click(
+                        cx.listener(move |this, _, window, cx| {
+                            window.close_all_dialogs(cx);
+                            root::start_install_modrinth_projects(instance.id, project_ids.clone(), &this.data.backend_handle, window, cx);
+                        }),
+                    ))
+                }));
+        } else {
+            content = content.child("Create an instance to install these projects into");
+        }
+
+        modal.child(content)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_bare_id() {
+        assert_eq!(parse_collection_id("AbCd1234").as_deref(), Some("AbCd1234"));
+        assert_eq!(parse_collection_id("  AbCd1234\n").as_deref(), Some("AbCd1234"));
+    }
+
+    #[test]
+    fn test_parse_url() {
+        assert_eq!(parse_collection_id("https://modrinth.com/collection/AbCd1234").as_deref(), Some("AbCd1234"));
+        assert_eq!(parse_collection_id("modrinth.com/collection/AbCd1234/").as_deref(), Some("AbCd1234"));
+        assert_eq!(parse_collection_id("https://modrinth.com/collection/AbCd1234/mods").as_deref(), Some("AbCd1234"));
+        assert_eq!(parse_collection_id("https://modrinth.com/collection/AbCd1234?page=2").as_deref(), Some("AbCd1234"));
+        assert_eq!(parse_collection_id("https://modrinth.com/collection/AbCd1234#top").as_deref(), Some("AbCd1234"));
+    }
+
+    #[test]
+    fn test_parse_empty() {
+        assert_eq!(parse_collection_id(""), None);
+        assert_eq!(parse_collection_id("   "), None);
+        assert_eq!(parse_collection_id("https://modrinth.com/collection/"), None);
+        assert_eq!(parse_collection_id("https://modrinth.com/collection/?page=2"), None);
+    }
+}
@@ -9,7 +9,7 @@ use gpui_component::{
 use relative_path::RelativePath;
 use rustc_hash::FxHashMap;
 use schema::{
-    content::ContentSource, loader::Loader, modrinth::{
+    content::ContentSource, instance::ReleaseChannel, loader::Loader, modrinth::{
         ModrinthDependency, ModrinthDependencyType, ModrinthLoader, ModrinthProjectType, ModrinthProjectVersion, ModrinthProjectVersionsRequest, ModrinthProjectVersionsResult, ModrinthVersionStatus, ModrinthVersionType
     }
 };
@@ -52,6 +52,8 @@ struct InstallDialog {
     loader_select_state: Option<Entity<SelectState<Vec<SharedString>>>>,
     skip_loader_check_for_mod_version: bool,
     install_dependencies: bool,
+    release_channel: ReleaseChannel,
+    preferred_version: Option<Arc<str>>,
 
     mod_version_select_state: Option<Entity<SelectState<SearchableVec<ModVersionItem>>>>,
 }
@@ -64,6 +66,20 @@ pub fn open(
     data: &DataEntities,
     window: &mut Window,
     cx: &mut App,
+) {
+    open_with_version(name, project_id, project_type, install_for, None, data, window, cx);
+}
+
+/// Opens the install dialog with the given version preselected, if it's compatible with the target
+pub fn open_with_version(
+    name: &str,
+    project_id: Arc<str>,
+    project_type: ModrinthProjectType,
+    install_for: Option<InstanceID>,
+    preferred_version: Option<Arc<str>>,
+    data: &DataEntities,
+    window: &mut Window,
+    cx: &mut App,
 ) {
     let project_versions = FrontendMetadata::request(
         &data.metadata,
@@ -75,7 +91,7 @@ pub fn open(
         cx,
     );
 
-    open_from_entity(SharedString::new(name), project_versions, project_id, project_type, install_for, data.clone(), window, cx);
+    open_from_entity(SharedString::new(name), project_versions, project_id, project_type, install_for, preferred_version, data.clone(), window, cx);
 }
 
 fn open_from_entity(
@@ -84,6 +100,7 @@ fn open_from_entity(
     project_id: Arc<str>,
     project_type: ModrinthProjectType,
     install_for: Option<InstanceID>,
+    preferred_version: Option<Arc<str>>,
     data: DataEntities,
     window: &mut Window,
     cx: &mut App,
@@ -95,7 +112,7 @@ fn open_from_entity(
         FrontendMetadataResult::Loading => {
             let _subscription = window.observe(&project_versions, cx, move |project_versions, window, cx| {
                 window.close_all_dialogs(cx);
-                open_from_entity(name.clone(), project_versions, project_id.clone(), project_type, install_for, data.clone(), window, cx);
+                open_from_entity(name.clone(), project_versions, project_id.clone(), project_type, install_for, preferred_version.clone(), data.clone(), window, cx);
             });
             window.open_dialog(cx, move |dialog, _, _| {
                 let _ = &_subscription;
@@ -161,6 +178,7 @@ fn open_from_entity(
 
                 let minecraft_version = instance.configuration.minecraft_version.as_str();
                 let instance_loader = instance.configuration.loader;
+                let release_channel = instance.configuration.release_channel;
 
                 let Some(loaders) = version_matrix.get(minecraft_version) else {
                     let error_message = SharedString::from(&format!("No mod versions found for {}", minecraft_version));
@@ -209,6 +227,8 @@ fn open_from_entity(
                     last_selected_minecraft_version: None,
                     skip_loader_check_for_mod_version: false,
                     install_dependencies: true,
+                    release_channel,
+                    preferred_version,
                     mod_version_select_state: None,
                     last_selected_loader: None,
                 };
@@ -270,6 +290,8 @@ fn open_from_entity(
                     last_selected_minecraft_version: None,
                     skip_loader_check_for_mod_version: false,
                     install_dependencies: true,
+                    release_channel: ReleaseChannel::default(),
+                    preferred_version,
                     mod_version_select_state: None,
                     last_selected_loader: None,
                 };
@@ -335,6 +357,7 @@ impl InstallDialog {
                             dialog.child(Button::new("instance").success().h_full().label("Add to instance").on_click(
                                 cx.listener(move |this, _, _, _| {
                                     this.target = Some(InstallTarget::Instance(instance.id));
+                                    this.release_channel = instance.configuration.release_channel;
                                     this.fixed_minecraft_version = Some(instance.configuration.minecraft_version.as_str());
                                     if (this.project_type == ModrinthProjectType::Mod
                                         || this.project_type == ModrinthProjectType::Modpack)
@@ -384,10 +407,15 @@ impl InstallDialog {
                         lexical_sort::natural_lexical_cmp(a, b).reverse()
                     }
                 });
+                let preferred_index = self.preferred_version.as_ref().and_then(|preferred_version| {
+                    let version = self.project_versions.iter().find(|version| version.id == *preferred_version)?;
+                    let game_versions = version.game_versions.as_ref()?;
+                    keys.iter().position(|key| game_versions.iter().any(|game_version| game_version.as_str() == key.as_str()))
+                });
                 self.minecraft_version_select_state = Some(cx.new(|cx| {
                     let mut select_state =
                         SelectState::new(SearchableVec::new(keys), None, window, cx).searchable(true);
-                    select_state.set_selected_index(Some(IndexPath::default()), window, cx);
+                    select_state.set_selected_index(Some(IndexPath::default().row(preferred_index.unwrap_or(0))), window, cx);
                     select_state
                 }));
             }
@@ -445,7 +473,13 @@ impl InstallDialog {
                     let previous = self
                         .loader_select_state
                         .as_ref()
-                        .and_then(|state| state.read(cx).selected_value().cloned());
+                        .and_then(|state| state.read(cx).selected_value().cloned())
+                        .or_else(|| {
+                            let preferred_version = self.preferred_version.as_ref()?;
+                            let version = self.project_versions.iter().find(|version| version.id == *preferred_version)?;
+                            let loader = version.loaders.as_ref()?.iter().find(|loader| loaders.loaders.contains(**loader))?;
+                            Some(SharedString::new_static(loader.pretty_name()))
+                        });
                     self.loader_select_state = Some(cx.new(|cx| {
                         let mut select_state = SelectState::new(keys, None, window, cx);
                         if let Some(previous) = previous {
@@ -530,6 +564,9 @@ impl InstallDialog {
             let mut highest_alpha = None;
 
             for (index, version) in mod_versions.iter().enumerate() {
+                if !self.release_channel.allows(version.version.version_type) {
+                    continue;
+                }
                 match version.version.version_type {
                     Some(ModrinthVersionType::Release) => {
                         highest_release = Some(index);
@@ -549,7 +586,10 @@ impl InstallDialog {
                 }
             }
 
-            let highest = highest_release.or(highest_beta).or(highest_alpha);
+            let preferred = self.preferred_version.as_ref().and_then(|preferred_version| {
+                mod_versions.iter().position(|version| version.version.id == *preferred_version)
+            });
+            let highest = preferred.or(highest_release).or(highest_beta).or(highest_alpha);
 
             self.mod_version_select_state = Some(cx.new(|cx| {
                 let mut select_state =
@@ -148,7 +148,7 @@ fn handle_project_versions(
                 if is_mod && !loaders.contains(&modrinth_loader) {
                     return false;
                 }
-                true
+                configuration.release_channel.allows(version.version_type)
             }).collect::<Vec<_>>();
 
             let mut highest_release = None;
@@ -0,0 +1,50 @@
+use bridge::message::MessageToBackend;
+use gpui::{prelude::*, *};
+use gpui_component::{
+    button::{Button, ButtonVariants}, scroll::ScrollableElement, v_flex, ActiveTheme, WindowExt
+};
+
+use crate::entity::DataEntities;
+
+pub fn open_offline_status(
+    data: &DataEntities,
+    window: &mut Window,
+    cx: &mut App,
+) {
+    let connectivity = data.connectivity.clone();
+    let backend_handle = data.backend_handle.clone();
+    window.open_dialog(cx, move |modal, _, cx| {
+        let state = connectivity.read(cx);
+        let theme = cx.theme();
+
+        let unavailable = if state.unavailable.is_empty() {
+            v_flex().child(div().text_sm().text_color(theme.muted_foreground).child("Everything requested so far was available from the cache"))
+        } else {
+            let mut list = v_flex().gap_1();
+            for what in state.unavailable.iter() {
+                list = list.child(div().text_sm().child(SharedString::from(what.clone())));
+            }
+            v_flex()
+                .gap_1()
+                .child("These weren't cached and can't be loaded until you're back online:")
+                .child(div().max_h(px(320.0)).child(list).overflow_y_scrollbar())
+        };
+
+        modal
+            .title("Offline mode")
+            .child(v_flex()
+                .gap_2()
+                .child("Pandora couldn't reach the internet, so metadata is being loaded from the cache \
+                    and update checks are disabled. Instances can still be launched if their files have already been downloaded.")
+                .child(unavailable)
+                .child(Button::new("go-online").success().label("Try to reconnect").on_click({
+                    let backend_handle = backend_handle.clone();
+                    move |_, window, cx| {
+                        backend_handle.send(MessageToBackend::SetOfflineMode {
+                            offline: false,
+                        });
+                        window.close_all_dialogs(cx);
+                    }
+                })))
+    });
+}
@@ -0,0 +1,111 @@
+use std::sync::Arc;
+
+use bridge::{handle::BackendHandle, instance::InstanceID, message::MessageToBackend};
+use gpui::{prelude::*, *};
+use gpui_component::{
+    button::{Button, ButtonVariants}, dialog::Dialog, h_flex, input::{Input, InputState}, scroll::ScrollableElement, spinner::Spinner, v_flex, ActiveTheme, Sizable, WindowExt
+};
+
+use crate::component::error_alert::ErrorAlert;
+
+struct ServerPropertiesDialog {
+    instance: InstanceID,
+    backend_handle: BackendHandle,
+    properties: Option<Result<Arc<[(Arc<str>, Arc<str>)]>, SharedString>>,
+    inputs: Option<Vec<(Arc<str>, Arc<str>, Entity<InputState>)>>,
+    _get_properties_task: Task<()>,
+}
+
+pub fn open_server_properties(
+    instance: InstanceID,
+    backend_handle: BackendHandle,
+    window: &mut Window,
+    cx: &mut App,
+) {
+    let dialog = cx.new(|cx| {
+        let (send, recv) = tokio::sync::oneshot::channel();
+        let get_properties_task = cx.spawn(async move |dialog, cx| {
+            let result = recv.await.unwrap_or_else(|_| Err("Backend didn't respond".into()));
+            let _ = dialog.update(cx, move |dialog: &mut ServerPropertiesDialog, cx| {
+                dialog.properties = Some(result.map_err(SharedString::from));
+                cx.notify();
+            });
+        });
+
+        backend_handle.send(MessageToBackend::GetServerProperties {
+            id: instance,
+            channel: send,
+        });
+
+        ServerPropertiesDialog {
+            instance,
+            backend_handle,
+            properties: None,
+            inputs: None,
+            _get_properties_task: get_properties_task,
+        }
+    });
+
+    window.open_dialog(cx, move |modal, window, cx| {
+        dialog.update(cx, |this, cx| this.render(modal, window, cx))
+    });
+}
+
+impl ServerPropertiesDialog {
+    fn render(&mut self, modal: Dialog, window: &mut Window, cx: &mut Context<Self>) -> Dialog {
+        let modal = modal.title("server.properties").w(px(640.0));
+
+        let properties = match &self.properties {
+            None => {
+                return modal.child(h_flex().gap_2().child("Loading...").child(Spinner::new()));
+            },
+            Some(Err(error)) => {
+                return modal.child(ErrorAlert::new("error", "Error reading server.properties".into(), error.clone()));
+            },
+            Some(Ok(properties)) => properties.clone(),
+        };
+
+        if properties.is_empty() {
+            return modal.child("The server creates server.properties the first time it starts, start the server once to edit it");
+        }
+
+        let inputs = self.inputs.get_or_insert_with(|| {
+            properties.iter().map(|(key, value)| {
+                let input = cx.new(|cx| InputState::new(window, cx).default_value(value.to_string()));
+                (key.clone(), value.clone(), input)
+            }).collect()
+        });
+
+        let theme = cx.theme();
+        let mut list = v_flex().gap_1();
+        for (key, _, input) in inputs.iter() {
+            list = list.child(h_flex()
+                .gap_2()
+                .child(div().w(px(220.0)).flex_shrink_0().text_sm().text_color(theme.muted_foreground).child(SharedString::from(key.clone())))
+                .child(Input::new(input).small()));
+        }
+
+        modal
+            .child(div().text_sm().text_color(theme.muted_foreground)
+                .child("Changes take effect the next time the server starts"))
+            .child(div().max_h(px(480.0)).child(list).overflow_y_scrollbar())
+            .child(Button::new("save").success().label("Save").on_click(cx.listener(|this, _, window, cx| {
+                let Some(inputs) = &this.inputs else {
+                    return;
+                };
+                let changed: Arc<[(Arc<str>, Arc<str>)]> = inputs.iter()
+                    .filter_map(|(key, original, input)| {
+                        let value = input.read(cx).value();
+                        (value.as_str() != &**original).then(|| (key.clone(), Arc::from(value.as_str())))
+                    })
+                    .collect();
+                if !changed.is_empty() {
+                    this.backend_handle.send(MessageToBackend::SetServerProperties {
+                        id: this.instance,
+                        properties: changed,
+                    });
+                }
+                window.close_dialog(cx);
+            })))
+    }
+}
@@ -1,19 +1,34 @@
 use std::{path::Path, sync::Arc};
 
-use bridge::{handle::BackendHandle, message::MessageToBackend};
+use bridge::{handle::BackendHandle, message::{MessageToBackend, UnusedFilesSummary}};
 use gpui::*;
-use gpui_component::{button::{Button, ButtonVariants}, checkbox::Checkbox, select::{SearchableVec, Select, SelectEvent, SelectState}, sheet::Sheet, spinner::Spinner, tab::{Tab, TabBar, TabVariant}, v_flex, ActiveTheme, IconName, Sizable, ThemeRegistry};
-use schema::backend_config::BackendConfig;
+use gpui_component::{button::{Button, ButtonVariants}, checkbox::Checkbox, h_flex, input::{Input, InputState}, scroll::ScrollableElement, select::{SearchableVec, Select, SelectEvent, SelectState}, sheet::Sheet, spinner::Spinner, tab::{Tab, TabBar, TabVariant}, v_flex, ActiveTheme, Disableable, IconName, Sizable, ThemeRegistry};
+use schema::backend_config::{BackendConfig, ProxyConfig, SecretStorageBackend};
 
-use crate::{entity::DataEntities, interface_config::InterfaceConfig};
+use crate::{entity::{connectivity::ConnectivityState, DataEntities}, interface_config::InterfaceConfig};
 
 struct Settings {
     theme_folder: Arc<Path>,
     theme_select: Entity<SelectState<SearchableVec<SharedString>>>,
+    modrinth_token_input: Entity<InputState>,
+    curseforge_api_key_input: Entity<InputState>,
+    download_speed_limit_input: Entity<InputState>,
+    proxy_url_input: Entity<InputState>,
+    proxy_username_input: Entity<InputState>,
+    proxy_password_input: Entity<InputState>,
+    proxy_no_proxy_input: Entity<InputState>,
+    connectivity: Entity<ConnectivityState>,
     backend_handle: BackendHandle,
     pending_request: bool,
     backend_config: Option<BackendConfig>,
     get_configuration_task: Option<Task<()>>,
+    unused_files: Option<Result<UnusedFilesSummary, SharedString>>,
+    get_unused_files_task: Option<Task<()>>,
+    secret_storage_backend: Option<Result<SecretStorageBackend, SharedString>>,
+    get_secret_storage_task: Option<Task<()>>,
+    secret_passphrase_input: Entity<InputState>,
+    secret_passphrase_error: Option<SharedString>,
+    set_secret_passphrase_task: Option<Task<()>>,
 }
 
 pub fn build_settings_sheet(data: &DataEntities, window: &mut Window, cx: &mut App) -> impl Fn(Sheet, &mut Window, &mut App) -> Sheet + 'static {
@@ -42,16 +57,46 @@ pub fn build_settings_sheet(data: &DataEntities, window: &mut Window, cx: &mut A
             gpui_component::Theme::global_mut(cx).apply_config(&theme);
         }).detach();
 
+        let modrinth_token_input = cx.new(|cx| InputState::new(window, cx).placeholder("Personal access token").masked(true));
+        let curseforge_api_key_input = cx.new(|cx| InputState::new(window, cx).placeholder("Api key").masked(true));
+
+        let download_speed_limit_input = cx.new(|cx| InputState::new(window, cx).placeholder("Download speed limit in KiB/s"));
+
+        let proxy_url_input = cx.new(|cx| InputState::new(window, cx).placeholder("Proxy url, e.g. socks5h://127.0.0.1:1080"));
+        let proxy_username_input = cx.new(|cx| InputState::new(window, cx).placeholder("Username (optional)"));
+        let proxy_password_input = cx.new(|cx| InputState::new(window, cx).placeholder("Password (optional)").masked(true));
+        let proxy_no_proxy_input = cx.new(|cx| InputState::new(window, cx).placeholder("Hosts that bypass the proxy, e.g. localhost,.lan"));
+
+        let secret_passphrase_input = cx.new(|cx| InputState::new(window, cx).placeholder("Passphrase").masked(true));
+
+        cx.observe(&data.connectivity, |_, _, cx| cx.notify()).detach();
+
         let mut settings = Settings {
             theme_folder,
             theme_select,
+            modrinth_token_input,
+            curseforge_api_key_input,
+            download_speed_limit_input,
+            proxy_url_input,
+            proxy_username_input,
+            proxy_password_input,
+            proxy_no_proxy_input,
+            connectivity: data.connectivity.clone(),
             backend_handle: data.backend_handle.clone(),
             pending_request: false,
             backend_config: None,
             get_configuration_task: None,
+            unused_files: None,
+            get_unused_files_task: None,
+            secret_storage_backend: None,
+            get_secret_storage_task: None,
+            secret_passphrase_input,
+            secret_passphrase_error: None,
+            set_secret_passphrase_task: None,
         };
 
         settings.update_backend_configuration(cx);
+        settings.update_secret_storage_backend(cx);
 
         settings
     });
@@ -106,6 +151,246 @@ impl Settings {
             channel: send,
         });
     }
+
+    pub fn update_secret_storage_backend(&mut self, cx: &mut Context<Self>) {
+        let (send, recv) = tokio::sync::oneshot::channel();
+        self.get_secret_storage_task = Some(cx.spawn(async move |page, cx| {
+            let result = recv.await.unwrap_or_else(|_| Err("Backend didn't respond".into()));
+            let _ = page.update(cx, move |settings, cx| {
+                settings.secret_storage_backend = Some(result.map_err(SharedString::from));
+                settings.get_secret_storage_task = None;
+                cx.notify();
+            });
+        }));
+
+        self.backend_handle.send(MessageToBackend::GetSecretStorageBackend {
+            channel: send,
+        });
+    }
+
+    fn render_secret_storage(&self, cx: &mut Context<Self>) -> Div {
+        let mut content = v_flex().gap_2();
+
+        let current = match &self.secret_storage_backend {
+            None => return content.child(Spinner::new()),
+            Some(Ok(backend)) => Some(*backend),
+            Some(Err(error)) => {
+                content = content.child(div().text_sm().text_color(cx.theme().danger).child(error.clone()));
+                None
+            },
+        };
+
+        let description = match current {
+            Some(SecretStorageBackend::EncryptedFile) => "Credentials are stored in an encrypted file in the launcher folder",
+            Some(_) => "Credentials are stored in the system keyring",
+            None => "Credentials can't be stored until a secret storage is available",
+        };
+
+        content = content
+            .child(div().text_sm().child(description))
+            .child(h_flex().gap_2()
+                .child(Button::new("use-keyring").label("Use system keyring")
+                    .disabled(current == Some(SecretStorageBackend::Keyring))
+                    .on_click(cx.listener(|settings, _, window, cx| {
+                        settings.set_secret_storage_backend(SecretStorageBackend::Keyring, window, cx);
+                    })))
+                .child(Button::new("use-encrypted-file").label("Use encrypted file")
+                    .disabled(current == Some(SecretStorageBackend::EncryptedFile))
+                    .on_click(cx.listener(|settings, _, window, cx| {
+                        settings.set_secret_storage_backend(SecretStorageBackend::EncryptedFile, window, cx);
+                    }))));
+
+        let passphrase_label = if current.is_none() { "Unlock" } else { "Set passphrase" };
+        content = content.child(h_flex().gap_2()
+            .child(div().flex_1().child(Input::new(&self.secret_passphrase_input)))
+            .child(Button::new("set-secret-passphrase").label(passphrase_label)
+                .loading(self.set_secret_passphrase_task.is_some())
+                .on_click(cx.listener(|settings, _, window, cx| {
+                    settings.set_secret_passphrase(window, cx);
+                }))));
+
+        if let Some(error) = &self.secret_passphrase_error {
+            content = content.child(div().text_sm().text_color(cx.theme().danger).child(error.clone()));
+        }
+
+        content.child(div().text_sm().text_color(cx.theme().muted_foreground)
+            .child("The encrypted file is protected by a passphrase, which isn't saved and has to be entered again after restarting. PANDORA_SECRET_PASSPHRASE can be set instead for headless launches"))
+    }
+
+    fn set_secret_passphrase(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let passphrase = self.secret_passphrase_input.read(cx).value();
+        if passphrase.is_empty() || self.set_secret_passphrase_task.is_some() {
+            return;
+        }
+
+        let (send, recv) = tokio::sync::oneshot::channel();
+        self.backend_handle.send(MessageToBackend::SetSecretPassphrase {
+            passphrase: Arc::<str>::from(&*passphrase),
+            channel: send,
+        });
+        self.secret_passphrase_input.update(cx, |input, cx| input.set_value("", window, cx));
+
+        self.set_secret_passphrase_task = Some(cx.spawn(async move |page, cx| {
+            let result = recv.await.unwrap_or_else(|_| Err("Backend didn't respond".into()));
+            let _ = page.update(cx, move |settings, cx| {
+                settings.secret_passphrase_error = result.err().map(SharedString::from);
+                settings.set_secret_passphrase_task = None;
+                settings.update_secret_storage_backend(cx);
+                cx.notify();
+            });
+        }));
+        cx.notify();
+    }
+
+    fn render_proxy(&self, cx: &mut Context<Self>) -> Div {
+        let current = match self.backend_config.as_ref().and_then(|config| config.proxy.as_ref()) {
+            Some(proxy) => match &proxy.username {
+                Some(username) => format!("Current proxy: {} as {username}", proxy.url),
+                None => format!("Current proxy: {}", proxy.url),
+            },
+            None => "No proxy set, the system proxy is used".to_string(),
+        };
+
+        v_flex().gap_2()
+            .child(div().text_sm().text_color(cx.theme().muted_foreground).child(SharedString::from(current)))
+            .child(Input::new(&self.proxy_url_input))
+            .child(h_flex().gap_2()
+                .child(div().flex_1().child(Input::new(&self.proxy_username_input)))
+                .child(div().flex_1().child(Input::new(&self.proxy_password_input))))
+            .child(Input::new(&self.proxy_no_proxy_input))
+            .child(h_flex().gap_2()
+                .child(Button::new("save-proxy").success().label("Save proxy").on_click(cx.listener(|settings, _, window, cx| {
+                    let url = settings.proxy_url_input.read(cx).value();
+                    if url.trim().is_empty() {
+                        return;
+                    }
+                    fn optional(input: &Entity<InputState>, cx: &App) -> Option<Arc<str>> {
+                        let value = input.read(cx).value();
+                        Some(Arc::<str>::from(value.trim())).filter(|value| !value.is_empty())
+                    }
+                    let proxy = ProxyConfig {
+                        url: url.trim().into(),
+                        username: optional(&settings.proxy_username_input, cx),
+                        no_proxy: optional(&settings.proxy_no_proxy_input, cx),
+                    };
+                    settings.backend_handle.send(MessageToBackend::SetProxy {
+                        password: optional(&settings.proxy_password_input, cx),
+                        proxy: Some(proxy),
+                    });
+                    for input in [&settings.proxy_url_input, &settings.proxy_username_input, &settings.proxy_password_input, &settings.proxy_no_proxy_input] {
+                        input.update(cx, |input, cx| input.set_value("", window, cx));
+                    }
+                    settings.update_backend_configuration(cx);
+                })))
+                .child(Button::new("clear-proxy").danger().label("Remove proxy").on_click(cx.listener(|settings, _, _, cx| {
+                    settings.backend_handle.send(MessageToBackend::SetProxy {
+                        proxy: None,
+                        password: None,
+                    });
+                    settings.update_backend_configuration(cx);
+                }))))
+            .child(div().text_sm().text_color(cx.theme().muted_foreground)
+                .child("Used for every connection the launcher makes. The password is kept in the launcher's secret storage"))
+    }
+
+    fn set_secret_storage_backend(&mut self, backend: SecretStorageBackend, window: &mut Window, cx: &mut Context<Self>) {
+        crate::root::start_set_secret_storage_backend(&self.backend_handle, backend, window, cx);
+        self.secret_storage_backend = None;
+        self.update_secret_storage_backend(cx);
+        cx.notify();
+    }
+
+    pub fn find_unused_files(&mut self, cx: &mut Context<Self>) {
+        if self.get_unused_files_task.is_some() {
+            return;
+        }
+
+        let (send, recv) = tokio::sync::oneshot::channel();
+        self.get_unused_files_task = Some(cx.spawn(async move |page, cx| {
+            let result = recv.await.unwrap_or_else(|_| Err("Backend didn't respond".into()));
+            let _ = page.update(cx, move |settings, cx| {
+                settings.unused_files = Some(result.map_err(SharedString::from));
+                settings.get_unused_files_task = None;
+                cx.notify();
+            });
+        }));
+
+        self.backend_handle.send(MessageToBackend::GetUnusedFilesSummary {
+            channel: send,
+        });
+        cx.notify();
+    }
+
+    fn render_unused_files(&self, cx: &mut Context<Self>) -> Div {
+        let mut content = v_flex().gap_2();
+
+        if self.get_unused_files_task.is_some() {
+            return content.child(Spinner::new());
+        }
+
+        match &self.unused_files {
+            None => {
+                content = content.child(Button::new("find-unused-files").info().icon(IconName::Search).label("Find unused libraries, assets and runtimes")
+                    .on_click(cx.listener(|settings, _, _, cx| {
+                        settings.find_unused_files(cx);
+                    })));
+            },
+            Some(Err(error)) => {
+                content = content
+                    .child(div().text_sm().text_color(cx.theme().danger).child(error.clone()))
+                    .child(Button::new("find-unused-files").info().icon(IconName::Search).label("Try again")
+                        .on_click(cx.listener(|settings, _, _, cx| {
+                            settings.find_unused_files(cx);
+                        })));
+            },
+            Some(Ok(summary)) => {
+                for (category, &files) in summary.files.iter() {
+                    if files == 0 {
+                        continue;
+                    }
+                    content = content.child(div().text_sm().child(SharedString::from(format!("{}: {} files ({})",
+                        category.name(), files, format_bytes(summary.bytes[category])))));
+                }
+
+                let total_bytes = summary.total_bytes();
+                if total_bytes == 0 {
+                    content = content.child(div().text_sm().child("No unused files found"));
+                } else {
+                    let mut paths = v_flex();
+                    for path in summary.paths.iter() {
+                        paths = paths.child(div().text_xs().text_color(cx.theme().muted_foreground)
+                            .child(SharedString::from(path.to_string_lossy().into_owned())));
+                    }
+
+                    let confirmed_paths = summary.paths.clone();
+                    content = content
+                        .child(div().text_sm().child("The following files will be deleted, check that none of them are still needed:"))
+                        .child(div().max_h(px(240.0)).child(paths).overflow_y_scrollbar())
+                        .child(Button::new("delete-unused-files").danger().icon(IconName::Delete)
+                            .label(SharedString::from(format!("Delete the listed files ({})", format_bytes(total_bytes))))
+                            .on_click(cx.listener(move |settings, _, window, cx| {
+                                settings.unused_files = None;
+                                crate::root::start_cleanup_unused_files(confirmed_paths.clone(), &settings.backend_handle, window, cx);
+                                cx.notify();
+                            })));
+                }
+            },
+        }
+
+        content
+    }
+}
+
+fn format_bytes(bytes: u64) -> String {
+    if bytes < 1000 {
+        format!("{} bytes", bytes)
+    } else if bytes < 1000*1000 {
+        format!("{}kB", bytes/1000)
+    } else if bytes < 1000*1000*1000 {
+        format!("{}MB", bytes/1000/1000)
+    } else {
+        format!("{}GB", bytes/1000/1000/1000)
+    }
 }
 
 impl Render for Settings {
@@ -175,6 +460,107 @@ impl Render for Settings {
             div = div.child(Spinner::new().large());
         }
 
+        let speed_limit = match self.backend_config.as_ref().and_then(|config| config.download_speed_limit_kib) {
+            Some(limit) => format!("Current limit: {limit} KiB/s"),
+            None => "Current limit: unlimited".to_string(),
+        };
+
+        div = div.child(crate::labelled(
+            "Network",
+            v_flex().gap_2()
+                .child(Checkbox::new("offline-mode")
+                    .label("Offline mode")
+                    .checked(self.connectivity.read(cx).offline)
+                    .on_click(cx.listener(|settings, value: &bool, _, _| {
+                        settings.backend_handle.send(MessageToBackend::SetOfflineMode {
+                            offline: *value,
+                        });
+                    })))
+                .child(div().text_sm().text_color(cx.theme().muted_foreground).child(SharedString::from(speed_limit)))
+                .child(h_flex().gap_2()
+                    .child(div().flex_1().child(Input::new(&self.download_speed_limit_input)))
+                    .child(Button::new("set-speed-limit").success().label("Set limit").on_click(cx.listener(|settings, _, window, cx| {
+                        let value = settings.download_speed_limit_input.read(cx).value();
+                        let Ok(limit) = value.trim().parse::<u32>() else {
+                            return;
+                        };
+                        settings.backend_handle.send(MessageToBackend::SetDownloadSpeedLimit {
+                            kib_per_second: Some(limit).filter(|limit| *limit > 0),
+                        });
+                        settings.download_speed_limit_input.update(cx, |input, cx| input.set_value("", window, cx));
+                        settings.update_backend_configuration(cx);
+                    })))
+                    .child(Button::new("clear-speed-limit").label("Unlimited").on_click(cx.listener(|settings, _, _, cx| {
+                        settings.backend_handle.send(MessageToBackend::SetDownloadSpeedLimit {
+                            kib_per_second: None,
+                        });
+                        settings.update_backend_configuration(cx);
+                    }))))
+                .child(Checkbox::new("device-code-login")
+                    .label("Log in with a code instead of opening a browser")
+                    .checked(self.backend_config.as_ref().is_some_and(|config| config.device_code_login))
+                    .on_click(cx.listener(|settings, value: &bool, _, cx| {
+                        settings.backend_handle.send(MessageToBackend::SetDeviceCodeLogin {
+                            value: *value,
+                        });
+                        settings.update_backend_configuration(cx);
+                    })))
+        ));
+
+        div = div.child(crate::labelled("Proxy", self.render_proxy(cx)));
+
+        div = div.child(crate::labelled(
+            "Modrinth",
+            v_flex().gap_2()
+                .child(Input::new(&self.modrinth_token_input))
+                .child(h_flex().gap_2()
+                    .child(Button::new("save-modrinth-token").success().label("Save token").on_click(cx.listener(|settings, _, window, cx| {
+                        let token = settings.modrinth_token_input.read(cx).value();
+                        if token.trim().is_empty() {
+                            return;
+                        }
+                        settings.backend_handle.send(MessageToBackend::SetModrinthToken {
+                            token: Some(token.trim().into()),
+                        });
+                        settings.modrinth_token_input.update(cx, |input, cx| input.set_value("", window, cx));
+                    })))
+                    .child(Button::new("clear-modrinth-token").danger().label("Remove token").on_click(cx.listener(|settings, _, _, _| {
+                        settings.backend_handle.send(MessageToBackend::SetModrinthToken {
+                            token: None,
+                        });
+                    }))))
+                .child(gpui::div().text_sm().text_color(cx.theme().muted_foreground)
+                    .child("Used to list the projects you follow. The token is kept in the launcher's secret storage"))
+        ));
+
+        div = div.child(crate::labelled(
+            "CurseForge",
+            v_flex().gap_2()
+                .child(Input::new(&self.curseforge_api_key_input))
+                .child(h_flex().gap_2()
+                    .child(Button::new("save-curseforge-api-key").success().label("Save api key").on_click(cx.listener(|settings, _, window, cx| {
+                        let api_key = settings.curseforge_api_key_input.read(cx).value();
+                        if api_key.trim().is_empty() {
+                            return;
+                        }
+                        settings.backend_handle.send(MessageToBackend::SetCurseForgeApiKey {
+                            api_key: Some(api_key.trim().into()),
+                        });
+                        settings.curseforge_api_key_input.update(cx, |input, cx| input.set_value("", window, cx));
+                    })))
+                    .child(Button::new("clear-curseforge-api-key").danger().label("Remove api key").on_click(cx.listener(|settings, _, _, _| {
+                        settings.backend_handle.send(MessageToBackend::SetCurseForgeApiKey {
+                            api_key: None,
+                        });
+                    }))))
+                .child(gpui::div().text_sm().text_color(cx.theme().muted_foreground)
+                    .child("Required to search and download from CurseForge. The key is kept in the launcher's secret storage"))
+        ));
+
+        div = div.child(crate::labelled("Credentials", self.render_secret_storage(cx)));
+
+        div = div.child(crate::labelled("Storage", self.render_unused_files(cx)));
+
         div
     }
 }
@@ -0,0 +1,181 @@
+use bridge::{
+    handle::BackendHandle,
+    message::MessageToBackend,
+    skin::{SkinAction, SkinManagerState},
+};
+use gpui::{prelude::*, *};
+use gpui_component::{
+    button::{Button, ButtonVariants}, checkbox::Checkbox, dialog::Dialog, h_flex, input::{Input, InputState}, scroll::ScrollableElement, spinner::Spinner, v_flex, ActiveTheme, Disableable, WindowExt
+};
+
+use crate::{component::error_alert::ErrorAlert, png_render_cache};
+
+struct SkinsDialog {
+    backend_handle: BackendHandle,
+    name_input: Entity<InputState>,
+    slim: bool,
+    state: Option<SkinManagerState>,
+    error: Option<SharedString>,
+    task: Option<Task<()>>,
+    _pick_task: Option<Task<()>>,
+}
+
+pub fn open(backend_handle: &BackendHandle, window: &mut Window, cx: &mut App) {
+    let name_input = cx.new(|cx| InputState::new(window, cx).placeholder("Name (defaults to the file name)"));
+
+    let dialog = cx.new(|cx| {
+        let mut dialog = SkinsDialog {
+            backend_handle: backend_handle.clone(),
+            name_input,
+            slim: false,
+            state: None,
+            error: None,
+            task: None,
+            _pick_task: None,
+        };
+        dialog.send(SkinAction::Load, cx);
+        dialog
+    });
+    window.open_dialog(cx, move |modal, window, cx| {
+        dialog.update(cx, |this, cx| this.render(modal, window, cx))
+    });
+}
+
+impl SkinsDialog {
+    fn send(&mut self, action: SkinAction, cx: &mut Context<Self>) {
+        let (send, recv) = tokio::sync::oneshot::channel();
+        self.task = Some(cx.spawn(async move |dialog, cx| {
+            let result = recv.await.unwrap_or_else(|_| Err("Backend didn't respond".into()));
+            let _ = dialog.update(cx, move |dialog, cx| {
+                match result {
+                    Ok(state) => {
+                        dialog.state = Some(state);
+                        dialog.error = None;
+                    },
+                    Err(error) => dialog.error = Some(SharedString::from(error)),
+                }
+                dialog.task = None;
+                cx.notify();
+            });
+        }));
+
+        self.backend_handle.send(MessageToBackend::ManageSkins {
+            action,
+            channel: send,
+        });
+        cx.notify();
+    }
+
+    fn add_from_file(&mut self, cx: &mut Context<Self>) {
+        let receiver = cx.prompt_for_paths(PathPromptOptions {
+            files: true,
+            directories: false,
+            multiple: false,
+            prompt: Some("Select skin".into())
+        });
+
+        self._pick_task = Some(cx.spawn(async move |dialog, cx| {
+            let Ok(Ok(Some(paths))) = receiver.await else {
+                return;
+            };
+            let Some(path) = paths.into_iter().next() else {
+                return;
+            };
+            let _ = dialog.update(cx, move |dialog, cx| {
+                let name = dialog.name_input.read(cx).value();
+                let name = if name.trim().is_empty() {
+                    path.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_else(|| "Skin".into())
+                } else {
+                    name.trim().to_string()
+                };
+                let slim = dialog.slim;
+                dialog.send(SkinAction::AddToLibrary { path, name: name.into(), slim }, cx);
+            });
+        }));
+    }
+
+    fn render(&mut self, modal: Dialog, _window: &mut Window, cx: &mut Context<Self>) -> Dialog {
+        let mut modal = modal.title("Skins").w(px(720.0));
+
+        let busy = self.task.is_some();
+        if busy {
+            modal = modal.child(h_flex().gap_2().child("Loading...").child(Spinner::new()));
+        }
+
+        if let Some(error) = &self.error {
+            modal = modal.child(ErrorAlert::new("skin-error", "Unable to update skin".into(), error.clone()));
+        }
+
+        let Some(state) = self.state.clone() else {
+            return modal;
+        };
+
+        let current = if let Some(profile_error) = &state.profile_error {
+            v_flex().gap_2()
+                .child(div().font_bold().child("Current skin"))
+                .child(div().text_sm().text_color(cx.theme().muted_foreground).child(SharedString::from(profile_error.clone())))
+        } else {
+            let mut capes = h_flex().gap_2().flex_wrap()
+                .child(Button::new("no-cape").label("No cape").disabled(busy)
+                    .when(!state.capes.iter().any(|cape| cape.active), |this| this.info())
+                    .on_click(cx.listener(|this, _, _, cx| this.send(SkinAction::SetCape { id: None }, cx))));
+            for cape in state.capes.iter() {
+                let id = cape.id.clone();
+                capes = capes.child(Button::new(SharedString::from(cape.id.clone())).label(SharedString::from(cape.alias.clone()))
+                    .disabled(busy)
+                    .when(cape.active, |this| this.info())
+                    .on_click(cx.listener(move |this, _, _, cx| this.send(SkinAction::SetCape { id: Some(id.clone()) }, cx))));
+            }
+
+            v_flex().gap_2()
+                .child(div().font_bold().child("Current skin"))
+                .child(h_flex().gap_4()
+                    .when_some(state.active_skin_preview.clone(), |this, preview| {
+                        this.child(png_render_cache::render(preview, cx).w(px(144.0)).h(px(128.0)))
+                    })
+                    .child(v_flex().gap_2()
+                        .child(Button::new("reset-skin").label("Reset to default skin").disabled(busy)
+                            .on_click(cx.listener(|this, _, _, cx| this.send(SkinAction::Reset, cx))))))
+                .when(!state.capes.is_empty(), |this| {
+                    this.child(div().font_bold().child("Cape")).child(capes)
+                })
+        };
+
+        let mut library = v_flex().gap_2();
+        for skin in state.library.iter() {
+            let apply_id = skin.id.clone();
+            let remove_id = skin.id.clone();
+            library = library.child(h_flex()
+                .gap_4()
+                .child(png_render_cache::render(skin.preview.clone(), cx).w(px(72.0)).h(px(64.0)))
+                .child(v_flex().flex_1()
+                    .child(SharedString::from(skin.name.clone()))
+                    .child(div().text_sm().text_color(cx.theme().muted_foreground).child(if skin.slim { "Slim" } else { "Classic" })))
+                .child(Button::new(SharedString::from(format!("apply-{}", skin.id))).success().label("Apply")
+                    .disabled(busy || state.profile_error.is_some())
+                    .on_click(cx.listener(move |this, _, _, cx| this.send(SkinAction::Apply { id: apply_id.clone() }, cx))))
+                .child(Button::new(SharedString::from(format!("remove-{}", skin.id))).danger().label("Remove")
+                    .disabled(busy)
+                    .on_click(cx.listener(move |this, _, _, cx| this.send(SkinAction::RemoveFromLibrary { id: remove_id.clone() }, cx)))));
+        }
+        if state.library.is_empty() {
+            library = library.child(div().text_sm().text_color(cx.theme().muted_foreground).child("No skins in the library yet"));
+        }
+
+        let add = h_flex().gap_2()
+            .child(div().flex_1().child(Input::new(&self.name_input)))
+            .child(Checkbox::new("slim").label("Slim arms").checked(self.slim).on_click(cx.listener(|this, value: &bool, _, cx| {
+                this.slim = *value;
+                cx.notify();
+            })))
+            .child(Button::new("add-skin").success().label("Add from file").disabled(busy).on_click(cx.listener(|this, _, _, cx| {
+                this.add_from_file(cx);
+            })));
+
+        modal.child(v_flex().gap_3()
+            .child(current)
+            .child(div().font_bold().child("Library"))
+            .child(div().max_h(px(320.0)).child(library).overflow_y_scrollbar())
+            .child(add))
+    }
+}
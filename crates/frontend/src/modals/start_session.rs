@@ -0,0 +1,48 @@
+use std::sync::Arc;
+
+use bridge::{account::Account, handle::BackendHandle, instance::{InstanceID, InstanceSessionSummary}};
+use gpui::{prelude::*, *};
+use gpui_component::{
+    button::{Button, ButtonVariants}, v_flex, ActiveTheme, WindowExt
+};
+
+use crate::root;
+
+/// Lets the user pick an account that isn't already playing the instance and starts another session with it
+pub fn open_start_session(
+    instance: InstanceID,
+    instance_name: SharedString,
+    accounts: Arc<[Account]>,
+    sessions: Arc<[InstanceSessionSummary]>,
+    backend_handle: BackendHandle,
+    window: &mut Window,
+    cx: &mut App,
+) {
+    let available: Vec<Account> = accounts.iter()
+        .filter(|account| !sessions.iter().any(|session| session.account == account.uuid))
+        .cloned()
+        .collect();
+
+    window.open_dialog(cx, move |dialog, _, cx| {
+        let mut content = v_flex().gap_2()
+            .child(div().text_sm().text_color(cx.theme().muted_foreground)
+                .child("Each session of an instance has to use a different account"));
+
+        if available.is_empty() {
+            content = content.child("Every account is already playing this instance, add another account to start another session");
+        }
+
+        for (index, account) in available.iter().enumerate() {
+            let uuid = account.uuid;
+            let instance_name = instance_name.clone();
+            let backend_handle = backend_handle.clone();
+            content = content.child(Button::new(("account", index)).success().label(SharedString::from(account.username.clone()))
+                .on_click(move |_, window, cx| {
+                    window.close_dialog(cx);
+                    root::start_instance(instance, instance_name.clone(), None, Some(uuid), &backend_handle, window, cx);
+                }));
+        }
+
+        dialog.title("Start another session").child(content)
+    });
+}
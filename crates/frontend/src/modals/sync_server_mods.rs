@@ -0,0 +1,137 @@
+use std::sync::Arc;
+
+use bridge::{instance::InstanceID, message::{ServerModsSyncResult, ServerModsTarget}};
+use gpui::{prelude::*, *};
+use gpui_component::{
+    button::{Button, ButtonVariants}, dialog::Dialog, h_flex, scroll::ScrollableElement, select::{Select, SelectState}, v_flex, ActiveTheme, IndexPath, WindowExt
+};
+use schema::instance::InstanceKind;
+
+use crate::{
+    component::instance_dropdown::InstanceDropdown,
+    entity::{instance::InstanceEntry, DataEntities},
+    root,
+};
+
+struct SyncServerModsDialog {
+    client: InstanceID,
+    data: DataEntities,
+    servers: Option<Entity<SelectState<InstanceDropdown>>>,
+    _pick_folder_task: Option<Task<()>>,
+}
+
+pub fn open(client: InstanceID, data: &DataEntities, window: &mut Window, cx: &mut App) {
+    let servers: Arc<[InstanceEntry]> = data.instances.read(cx).entries.values()
+        .map(|instance| instance.read(cx))
+        .filter(|instance| instance.configuration.kind == InstanceKind::Server && instance.id != client)
+        .cloned()
+        .collect();
+    let servers = if !servers.is_empty() {
+        let dropdown = InstanceDropdown::create(servers, window, cx);
+        dropdown.update(cx, |dropdown, cx| {
+            dropdown.set_selected_index(Some(IndexPath::default()), window, cx)
+        });
+        Some(dropdown)
+    } else {
+        None
+    };
+
+    let dialog = cx.new(|_| SyncServerModsDialog {
+        client,
+        data: data.clone(),
+        servers,
+        _pick_folder_task: None,
+    });
+    window.open_dialog(cx, move |modal, window, cx| {
+        dialog.update(cx, |this, cx| this.render(modal, window, cx))
+    });
+}
+
+pub fn open_sync_result(result: ServerModsSyncResult, window: &mut Window, cx: &mut App) {
+    window.open_dialog(cx, move |modal, _, cx| {
+        let theme = cx.theme();
+        let mut content = v_flex().gap_3();
+
+        if result.added.is_empty() && result.removed.is_empty() && result.skipped.is_empty() {
+            content = content.child("The server's mods were already up-to-date");
+        }
+
+        let sections = [
+            ("Added", &result.added),
+            ("Removed", &result.removed),
+            ("Left out as client-only", &result.client_only),
+            ("Skipped, the server has a mod of the same name or the file is missing", &result.skipped),
+        ];
+        for (title, names) in sections {
+            if names.is_empty() {
+                continue;
+            }
+            let mut list = v_flex().gap_1();
+            for name in names.iter() {
+                list = list.child(div().text_sm().text_color(theme.muted_foreground).child(SharedString::from(name.clone())));
+            }
+            content = content.child(v_flex()
+                .gap_1()
+                .child(div().font_bold().child(format!("{} ({})", title, names.len())))
+                .child(list));
+        }
+
+        modal
+            .title("Synced mods to server")
+            .child(div().max_h(px(480.0)).child(content).overflow_y_scrollbar())
+    });
+}
+
+impl SyncServerModsDialog {
+    fn render(&mut self, modal: Dialog, _window: &mut Window, cx: &mut Context<Self>) -> Dialog {
+        let modal = modal.title("Sync mods to server").w(px(560.0));
+
+        let description = div().text_sm().text_color(cx.theme().muted_foreground)
+            .child("Mods that also run on servers are linked into the server's mods folder. Mods that were synced before but have since been removed from this instance are removed from the server");
+
+        let mut content = v_flex().gap_3().child(description);
+
+        if let Some(servers) = &self.servers {
+            let selected_server: Option<InstanceEntry> = servers.read(cx).selected_value().cloned();
+            content = content.child(h_flex()
+                .gap_2()
+                .child(div().flex_1().child(Select::new(servers).placeholder("Select a server").title_prefix("Server: ")))
+                .when_some(selected_server, |content, server| {
+                    content.child(Button::new("sync-instance").success().label("Sync").on_click(
+                        cx.listener(move |this, _, window, cx| {
+                            window.close_all_dialogs(cx);
+                            root::start_sync_server_mods(this.client, ServerModsTarget::Instance(server.id), &this.data.backend_handle, window, cx);
+                        }),
+                    ))
+                }));
+        } else {
+            content = content.child("Create a dedicated server instance, or pick the folder of an existing server");
+        }
+
+        content = content.child(Button::new("sync-folder").info().label("Sync to a server folder...").on_click(cx.listener(|this, _, window, cx| {
+            let receiver = cx.prompt_for_paths(PathPromptOptions {
+                files: false,
+                directories: true,
+                multiple: false,
+                prompt: Some("Select server folder".into())
+            });
+
+            let client = this.client;
+            let backend_handle = this.data.backend_handle.clone();
+            this._pick_folder_task = Some(window.spawn(cx, async move |cx| {
+                let Ok(Ok(Some(paths))) = receiver.await else {
+                    return;
+                };
+                let Some(path) = paths.into_iter().next() else {
+                    return;
+                };
+                _ = cx.update(|window, cx| {
+                    window.close_all_dialogs(cx);
+                    root::start_sync_server_mods(client, ServerModsTarget::Folder(path.into()), &backend_handle, window, cx);
+                });
+            }));
+        })));
+
+        modal.child(content)
+    }
+}
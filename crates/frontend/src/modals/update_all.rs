@@ -0,0 +1,178 @@
+use bridge::{handle::BackendHandle, instance::{InstanceContentID, InstanceID}, message::{ContentUpdates, MessageToBackend}};
+use gpui::{prelude::*, *};
+use gpui_component::{
+    button::{Button, ButtonVariants}, checkbox::Checkbox, dialog::Dialog, h_flex, scroll::ScrollableElement, spinner::Spinner, text::TextView, v_flex, ActiveTheme, Disableable, Sizable, WindowExt
+};
+use rustc_hash::FxHashSet;
+use schema::{instance::ReleaseChannel, modrinth::ModrinthVersionType};
+
+use crate::component::{error_alert::ErrorAlert, release_channel_group::ReleaseChannelGroup};
+
+struct UpdateAllDialog {
+    instance: InstanceID,
+    backend_handle: BackendHandle,
+    updates: Option<Result<ContentUpdates, SharedString>>,
+    deselected: FxHashSet<InstanceContentID>,
+    expanded_changelogs: FxHashSet<InstanceContentID>,
+    _get_updates_task: Task<()>,
+}
+
+pub fn open_update_all(
+    instance: InstanceID,
+    backend_handle: BackendHandle,
+    window: &mut Window,
+    cx: &mut App,
+) {
+    let dialog = cx.new(|cx| {
+        let mut dialog = UpdateAllDialog {
+            instance,
+            backend_handle,
+            updates: None,
+            deselected: FxHashSet::default(),
+            expanded_changelogs: FxHashSet::default(),
+            _get_updates_task: Task::ready(()),
+        };
+        dialog.get_updates(None, cx);
+        dialog
+    });
+
+    window.open_dialog(cx, move |modal, window, cx| {
+        dialog.update(cx, |this, cx| this.render(modal, window, cx))
+    });
+}
+
+impl UpdateAllDialog {
+    /// The backend picks the newest version allowed by the release channel for each update
+    fn get_updates(&mut self, release_channel: Option<ReleaseChannel>, cx: &mut Context<Self>) {
+        let (send, recv) = tokio::sync::oneshot::channel();
+        self._get_updates_task = cx.spawn(async move |dialog, cx| {
+            let result = recv.await.unwrap_or_else(|_| Err("Backend didn't respond".into()));
+            let _ = dialog.update(cx, move |dialog: &mut UpdateAllDialog, cx| {
+                dialog.updates = Some(result.map(|mut updates| {
+                    updates.updates.sort_by(|a, b| lexical_sort::natural_lexical_cmp(&a.name, &b.name));
+                    updates
+                }).map_err(SharedString::from));
+                cx.notify();
+            });
+        });
+
+        self.updates = None;
+        self.backend_handle.send(MessageToBackend::GetContentUpdates {
+            id: self.instance,
+            release_channel,
+            channel: send,
+        });
+        cx.notify();
+    }
+
+    fn render(&mut self, modal: Dialog, _window: &mut Window, cx: &mut Context<Self>) -> Dialog {
+        let modal = modal.title("Update all");
+
+        let updates = match &self.updates {
+            None => {
+                return modal.child(h_flex().gap_2().child("Fetching updates...").child(Spinner::new()));
+            },
+            Some(Err(error)) => {
+                return modal.child(ErrorAlert::new("error", "Error fetching updates".into(), error.clone()));
+            },
+            Some(Ok(updates)) => updates,
+        };
+
+        let release_channel = updates.release_channel;
+        let channel_group = ReleaseChannelGroup::new("channel", release_channel)
+            .small()
+            .on_select(cx.listener(|this, release_channel: &ReleaseChannel, _, cx| {
+                this.get_updates(Some(*release_channel), cx);
+            }));
+
+        let theme = cx.theme();
+
+        let failed = (!updates.failed.is_empty()).then(|| {
+            div().text_sm().text_color(theme.warning).child(SharedString::from(format!("Unable to fetch update information for {}",
+                updates.failed.join(", "))))
+        });
+
+        let modal = modal
+            .child(h_flex().gap_2().child("Release channel:").child(channel_group))
+            .children(failed);
+
+        if updates.updates.is_empty() {
+            return modal.child("Everything is up-to-date. Use 'Check for updates' to look for new versions");
+        }
+
+        let mut list = v_flex().gap_2();
+        let mut selected = Vec::new();
+
+        for (index, update) in updates.updates.iter().enumerate() {
+            let content_id = update.content_id;
+            let checked = !self.deselected.contains(&content_id);
+            if checked {
+                selected.push(content_id);
+            }
+
+            let label = SharedString::from(format!("{} ({} → {})", update.name, update.from_version, update.to_version));
+            let version_type = match update.version_type {
+                ModrinthVersionType::Release => None,
+                ModrinthVersionType::Beta => Some("Beta"),
+                ModrinthVersionType::Alpha => Some("Alpha"),
+                ModrinthVersionType::Other => Some("Other"),
+            };
+
+            let expanded = self.expanded_changelogs.contains(&content_id);
+
+            let header = h_flex()
+                .gap_2()
+                .child(Checkbox::new(("update", index)).checked(checked).label(label).on_click(cx.listener(move |this, value: &bool, _, cx| {
+                    if *value {
+                        this.deselected.remove(&content_id);
+                    } else {
+                        this.deselected.insert(content_id);
+                    }
+                    cx.notify();
+                })))
+                .when_some(version_type, |header, version_type| {
+                    header.child(div().text_xs().text_color(theme.warning).child(version_type))
+                })
+                .when(update.changelog.is_some(), |header| {
+                    header.child(Button::new(("changelog", index)).compact().small().label(if expanded { "Hide changelog" } else { "Changelog" })
+                        .on_click(cx.listener(move |this, _, _, cx| {
+                            if !this.expanded_changelogs.remove(&content_id) {
+                                this.expanded_changelogs.insert(content_id);
+                            }
+                            cx.notify();
+                        })))
+                });
+
+            let entry = v_flex()
+                .gap_1()
+                .child(header)
+                .when(expanded, |entry| {
+                    entry.when_some(update.changelog.clone(), |entry, changelog| {
+                        entry.child(div()
+                            .ml_6()
+                            .p_2()
+                            .rounded_md()
+                            .border_1()
+                            .border_color(theme.border)
+                            .text_sm()
+                            .child(TextView::markdown(("changelog_text", index), SharedString::new(changelog))))
+                    })
+                });
+
+            list = list.child(entry);
+        }
+
+        let update_label = if selected.len() == 1 {
+            SharedString::new_static("Update 1 item")
+        } else {
+            SharedString::new(format!("Update {} items", selected.len()))
+        };
+
+        modal
+            .child(div().max_h(px(480.0)).child(list).overflow_y_scrollbar())
+            .child(Button::new("update").success().label(update_label).disabled(selected.is_empty()).on_click(cx.listener(move |this, _, window, cx| {
+                window.close_dialog(cx);
+                crate::root::start_update_contents(this.instance, selected.clone(), release_channel, &this.backend_handle, window, cx);
+            })))
+    }
+}
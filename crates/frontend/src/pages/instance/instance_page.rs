@@ -5,12 +5,13 @@ use bridge::{
 };
 use gpui::{prelude::*, *};
 use gpui_component::{
-    button::{Button, ButtonVariants}, h_flex, tab::{Tab, TabBar}, Icon, IconName
+    button::{Button, ButtonVariants}, h_flex, notification::NotificationType, tab::{Tab, TabBar}, Icon, IconName, WindowExt
 };
+use schema::instance::InstanceKind;
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    component::page_path::PagePath, entity::{DataEntities, instance::InstanceEntry}, pages::instance::{logs_subpage::InstanceLogsSubpage, mods_subpage::InstanceModsSubpage, quickplay_subpage::InstanceQuickplaySubpage, resource_packs_subpage::InstanceResourcePacksSubpage, settings_subpage::InstanceSettingsSubpage}, root, ui
+    component::page_path::PagePath, game_output::GameOutputWindows, entity::{DataEntities, instance::InstanceEntry}, pages::instance::{logs_subpage::InstanceLogsSubpage, mods_subpage::InstanceModsSubpage, quickplay_subpage::InstanceQuickplaySubpage, resource_packs_subpage::InstanceResourcePacksSubpage, settings_subpage::InstanceSettingsSubpage}, root, ui
 };
 
 pub struct InstancePage {
@@ -60,25 +61,71 @@ impl Render for InstancePage {
         let id = instance.id;
         let name = instance.name.clone();
         let backend_handle = self.backend_handle.clone();
+        let is_server = instance.configuration.kind == InstanceKind::Server;
 
         let button = match instance.status {
             InstanceStatus::NotRunning => {
-                Button::new("start_instance").success().icon(play_icon).label("Start Instance").on_click(
+                let label = if is_server { "Start Server" } else { "Start Instance" };
+                Button::new("start_instance").success().icon(play_icon).label(label).on_click(
                     move |_, window, cx| {
-                        root::start_instance(id, name.clone(), None, &backend_handle, window, cx);
+                        root::start_instance(id, name.clone(), None, None, &backend_handle, window, cx);
                     },
-                )
+                ).into_any_element()
             },
             InstanceStatus::Launching => {
-                Button::new("launching").warning().icon(IconName::Loader).label("Launching...")
+                Button::new("launching").warning().icon(IconName::Loader).label("Launching...").into_any_element()
+            },
+            InstanceStatus::Running => {
+                let mut buttons = h_flex().gap_3();
+                // A dedicated server can only run once, it has no accounts to split sessions between
+                if !is_server {
+                    buttons = buttons.child(Button::new("start_session").success().icon(play_icon).label("Start another session").on_click({
+                        let accounts = self.data.accounts.read(cx).accounts.clone();
+                        let sessions = instance.sessions.clone();
+                        let backend_handle = backend_handle.clone();
+                        move |_, window, cx| {
+                            crate::modals::start_session::open_start_session(id, name.clone(), accounts.clone(), sessions.clone(),
+                                backend_handle.clone(), window, cx);
+                        }
+                    }));
+                }
+                for session in instance.sessions.iter() {
+                    let session_id = session.id;
+                    if let Some(game_output) = session.game_output {
+                        let label = if is_server {
+                            SharedString::new_static("Show Console")
+                        } else if instance.sessions.len() == 1 {
+                            SharedString::new_static("Show Output")
+                        } else {
+                            SharedString::new(format!("Show {} Output", session.username))
+                        };
+                        buttons = buttons.child(Button::new(("show_session_output", session_id))
+                            .info()
+                            .label(label)
+                            .on_click(move |_, window, cx| {
+                                if !GameOutputWindows::focus(game_output, cx) {
+                                    window.push_notification((NotificationType::Warning, "The game output window has been closed"), cx);
+                                }
+                            }));
+                    }
+                    let label = if is_server {
+                        SharedString::new_static("Kill Server")
+                    } else if instance.sessions.len() == 1 {
+                        SharedString::new_static("Kill Instance")
+                    } else {
+                        SharedString::new(format!("Kill {}", session.username))
+                    };
+                    let backend_handle = backend_handle.clone();
+                    buttons = buttons.child(Button::new(("kill_session", session_id))
+                        .danger()
+                        .icon(IconName::Close)
+                        .label(label)
+                        .on_click(move |_, _, _| {
+                            backend_handle.send(MessageToBackend::KillInstance { id, session: Some(session_id) });
+                        }));
+                }
+                buttons.into_any_element()
             },
-            InstanceStatus::Running => Button::new("kill_instance")
-                .danger()
-                .icon(IconName::Close)
-                .label("Kill Instance")
-                .on_click(move |_, _, _| {
-                    backend_handle.send(MessageToBackend::KillInstance { id });
-                }),
         };
 
         let open_dot_minecraft_button = Button::new("open_dot_minecraft")
@@ -149,10 +196,10 @@ impl InstanceSubpageType {
                 InstanceLogsSubpage::new(instance, backend_handle, window, cx)
             })),
             InstanceSubpageType::Mods => InstanceSubpage::Mods(cx.new(|cx| {
-                InstanceModsSubpage::new(instance, backend_handle, window, cx)
+                InstanceModsSubpage::new(instance, data, backend_handle, window, cx)
             })),
             InstanceSubpageType::ResourcePacks => InstanceSubpage::ResourcePacks(cx.new(|cx| {
-                InstanceResourcePacksSubpage::new(instance, backend_handle, window, cx)
+                InstanceResourcePacksSubpage::new(instance, data, backend_handle, window, cx)
             })),
             InstanceSubpageType::Settings => InstanceSubpage::Settings(cx.new(|cx| {
                 InstanceSettingsSubpage::new(instance, data, backend_handle, window, cx)
@@ -11,10 +11,10 @@ use gpui_component::{
 };
 use parking_lot::Mutex;
 use rustc_hash::FxHashSet;
-use schema::{content::ContentSource, loader::Loader, modrinth::ModrinthProjectType};
+use schema::{content::{ContentSearchType, ContentSource}, instance::InstanceKind, loader::Loader, modrinth::ModrinthProjectType};
 use ustr::Ustr;
 
-use crate::{component::content_list::ContentListDelegate, entity::instance::InstanceEntry, interface_config::InterfaceConfig, png_render_cache, root, ui::PageType};
+use crate::{component::content_list::ContentListDelegate, entity::{DataEntities, instance::InstanceEntry}, interface_config::InterfaceConfig, png_render_cache, root, ui::PageType};
 
 use super::instance_page::InstanceSubpageType;
 
@@ -23,6 +23,8 @@ pub struct InstanceModsSubpage {
     instance_title: SharedString,
     instance_loader: Loader,
     instance_version: Ustr,
+    instance_kind: InstanceKind,
+    data: DataEntities,
     backend_handle: BackendHandle,
     mods_state: Arc<AtomicBridgeDataLoadState>,
     mod_list: Entity<ListState<ContentListDelegate>>,
@@ -33,6 +35,7 @@ pub struct InstanceModsSubpage {
 impl InstanceModsSubpage {
     pub fn new(
         instance: &Entity<InstanceEntry>,
+        data: &DataEntities,
         backend_handle: BackendHandle,
         window: &mut gpui::Window,
         cx: &mut gpui::Context<Self>,
@@ -41,6 +44,7 @@ impl InstanceModsSubpage {
         let instance_title = instance.title().into();
         let instance_loader = instance.configuration.loader;
         let instance_version = instance.configuration.minecraft_version;
+        let instance_kind = instance.configuration.kind;
         let instance_id = instance.id;
 
         let mods_state = Arc::clone(&instance.mods_state);
@@ -65,6 +69,8 @@ impl InstanceModsSubpage {
             instance_title,
             instance_loader,
             instance_version,
+            instance_kind,
+            data: data.clone(),
             backend_handle,
             mods_state,
             mod_list,
@@ -95,6 +101,13 @@ impl Render for InstanceModsSubpage {
                     crate::root::start_update_check(instance_id, &backend_handle, window, cx);
                 }
             }))
+            .child(Button::new("updateall").label("Update all").success().compact().small().on_click({
+                let backend_handle = self.backend_handle.clone();
+                let instance_id = self.instance;
+                move |_, window, cx| {
+                    crate::modals::update_all::open_update_all(instance_id, backend_handle.clone(), window, cx);
+                }
+            }))
             .child(Button::new("addmr").label("Add from Modrinth").success().compact().small().on_click({
                 let instance = self.instance;
                 move |_, window, cx| {
@@ -106,6 +119,15 @@ impl Render for InstanceModsSubpage {
                     root::switch_page(page, path, window, cx);
                 }
             }))
+            .child(Button::new("addcf").label("Add from CurseForge").success().compact().small().on_click({
+                let data = self.data.clone();
+                let instance = self.instance;
+                let loader = self.instance_loader;
+                let version = self.instance_version;
+                move |_, window, cx| {
+                    crate::modals::curseforge_search::open(instance, loader, version, ContentSearchType::Mod, &data, window, cx);
+                }
+            }))
             .child(Button::new("addfile").label("Add from file").success().compact().small().on_click({
                 let backend_handle = self.backend_handle.clone();
                 let instance = self.instance;
@@ -155,7 +177,42 @@ impl Render for InstanceModsSubpage {
                     });
                     this._add_from_file_task = Some(add_from_file_task);
                 })
-            }));
+            }))
+            .child(Button::new("addcfpack").label("Add CurseForge modpack").success().compact().small().on_click({
+                let backend_handle = self.backend_handle.clone();
+                let instance = self.instance;
+                cx.listener(move |this, _, window, cx| {
+                    let receiver = cx.prompt_for_paths(PathPromptOptions {
+                        files: true,
+                        directories: false,
+                        multiple: false,
+                        prompt: Some("Select CurseForge modpack".into())
+                    });
+
+                    let backend_handle = backend_handle.clone();
+                    let import_task = window.spawn(cx, async move |cx| {
+                        let Ok(Ok(Some(paths))) = receiver.await else {
+                            return;
+                        };
+                        let Some(path) = paths.into_iter().next() else {
+                            return;
+                        };
+                        _ = cx.update(|window, cx| {
+                            crate::root::start_import_curseforge_modpack(path, Some(instance), &backend_handle, window, cx);
+                        });
+                    });
+                    this._add_from_file_task = Some(import_task);
+                })
+            }))
+            .when(self.instance_kind == InstanceKind::Client && self.instance_loader != Loader::Vanilla, |header| {
+                header.child(Button::new("syncserver").label("Sync to server").info().compact().small().on_click({
+                    let data = self.data.clone();
+                    let instance = self.instance;
+                    move |_, window, cx| {
+                        crate::modals::sync_server_mods::open(instance, &data, window, cx);
+                    }
+                }))
+            });
 
         v_flex().p_4().size_full().child(header).child(
             div()
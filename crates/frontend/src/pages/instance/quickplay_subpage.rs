@@ -198,6 +198,7 @@ impl ListDelegate for WorldsListDelegate {
                                 id,
                                 name.clone(),
                                 Some(QuickPlayLaunch::Singleplayer(target.clone())),
+                                None,
                                 &backend_handle,
                                 window,
                                 cx,
@@ -266,6 +267,7 @@ impl ListDelegate for ServersListDelegate {
                                 id,
                                 name.clone(),
                                 Some(QuickPlayLaunch::Multiplayer(target.clone())),
+                                None,
                                 &backend_handle,
                                 window,
                                 cx,
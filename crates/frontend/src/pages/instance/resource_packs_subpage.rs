@@ -11,10 +11,10 @@ use gpui_component::{
 };
 use parking_lot::Mutex;
 use rustc_hash::FxHashSet;
-use schema::{content::ContentSource, loader::Loader, modrinth::ModrinthProjectType};
+use schema::{content::{ContentSearchType, ContentSource}, loader::Loader, modrinth::ModrinthProjectType};
 use ustr::Ustr;
 
-use crate::{component::content_list::ContentListDelegate, entity::instance::InstanceEntry, interface_config::InterfaceConfig, png_render_cache, root, ui::PageType};
+use crate::{component::content_list::ContentListDelegate, entity::{DataEntities, instance::InstanceEntry}, interface_config::InterfaceConfig, png_render_cache, root, ui::PageType};
 
 use super::instance_page::InstanceSubpageType;
 
@@ -23,6 +23,7 @@ pub struct InstanceResourcePacksSubpage {
     instance_title: SharedString,
     instance_loader: Loader,
     instance_version: Ustr,
+    data: DataEntities,
     backend_handle: BackendHandle,
     resource_packs_state: Arc<AtomicBridgeDataLoadState>,
     resource_pack_list: Entity<ListState<ContentListDelegate>>,
@@ -33,6 +34,7 @@ pub struct InstanceResourcePacksSubpage {
 impl InstanceResourcePacksSubpage {
     pub fn new(
         instance: &Entity<InstanceEntry>,
+        data: &DataEntities,
         backend_handle: BackendHandle,
         window: &mut gpui::Window,
         cx: &mut gpui::Context<Self>,
@@ -65,6 +67,7 @@ impl InstanceResourcePacksSubpage {
             instance_title,
             instance_loader,
             instance_version,
+            data: data.clone(),
             backend_handle,
             resource_packs_state,
             resource_pack_list,
@@ -95,6 +98,13 @@ impl Render for InstanceResourcePacksSubpage {
                     crate::root::start_update_check(instance_id, &backend_handle, window, cx);
                 }
             }))
+            .child(Button::new("updateall").label("Update all").success().compact().small().on_click({
+                let backend_handle = self.backend_handle.clone();
+                let instance_id = self.instance;
+                move |_, window, cx| {
+                    crate::modals::update_all::open_update_all(instance_id, backend_handle.clone(), window, cx);
+                }
+            }))
             .child(Button::new("addmr").label("Add from Modrinth").success().compact().small().on_click({
                 let instance = self.instance;
                 move |_, window, cx| {
@@ -106,6 +116,15 @@ impl Render for InstanceResourcePacksSubpage {
                     root::switch_page(page, path, window, cx);
                 }
             }))
+            .child(Button::new("addcf").label("Add from CurseForge").success().compact().small().on_click({
+                let data = self.data.clone();
+                let instance = self.instance;
+                let loader = self.instance_loader;
+                let version = self.instance_version;
+                move |_, window, cx| {
+                    crate::modals::curseforge_search::open(instance, loader, version, ContentSearchType::ResourcePack, &data, window, cx);
+                }
+            }))
             .child(Button::new("addfile").label("Add from file").success().compact().small().on_click({
                 let backend_handle = self.backend_handle.clone();
                 let instance = self.instance;
@@ -5,11 +5,30 @@ use bridge::{
 };
 use gpui::{prelude::*, *};
 use gpui_component::{
-    ActiveTheme as _, Disableable, Selectable, Sizable, WindowExt, button::{Button, ButtonGroup, ButtonVariants}, checkbox::Checkbox, h_flex, input::{Input, InputEvent, InputState, NumberInput, NumberInputEvent}, notification::{Notification, NotificationType}, select::{SearchableVec, Select, SelectEvent, SelectState}, spinner::Spinner, v_flex
+    ActiveTheme as _, Disableable, IconName, Selectable, Sizable, WindowExt, button::{Button, ButtonGroup, ButtonVariants}, checkbox::Checkbox, h_flex, input::{Input, InputEvent, InputState, NumberInput, NumberInputEvent}, notification::{Notification, NotificationType}, select::{SearchableVec, Select, SelectEvent, SelectItem, SelectState}, spinner::Spinner, v_flex
 };
-use schema::{fabric_loader_manifest::FabricLoaderManifest, forge::{ForgeMavenManifest, NeoforgeMavenManifest}, instance::{InstanceJvmBinaryConfiguration, InstanceJvmFlagsConfiguration, InstanceMemoryConfiguration}, loader::Loader, version_manifest::MinecraftVersionManifest};
+use schema::{fabric_loader_manifest::FabricLoaderManifest, forge::{ForgeMavenManifest, NeoforgeMavenManifest}, instance::{InstanceJvmBinaryConfiguration, InstanceKind, InstanceJvmFlagsConfiguration, InstanceMemoryConfiguration, ReleaseChannel}, loader::Loader, version_manifest::MinecraftVersionManifest};
+use uuid::Uuid;
 
-use crate::{entity::{DataEntities, instance::InstanceEntry, metadata::{AsMetadataResult, FrontendMetadata, FrontendMetadataResult, FrontendMetadataState, TypelessFrontendMetadataResult}}, interface_config::InterfaceConfig, pages::instances_page::VersionList};
+use crate::{component::release_channel_group::ReleaseChannelGroup, entity::{DataEntities, instance::InstanceEntry, metadata::{AsMetadataResult, FrontendMetadata, FrontendMetadataResult, FrontendMetadataState, TypelessFrontendMetadataResult}}, interface_config::InterfaceConfig, pages::instances_page::VersionList};
+
+#[derive(Clone)]
+struct AccountItem {
+    name: SharedString,
+    account: Option<Uuid>,
+}
+
+impl SelectItem for AccountItem {
+    type Value = Option<Uuid>;
+
+    fn title(&self) -> SharedString {
+        self.name.clone()
+    }
+
+    fn value(&self) -> &Self::Value {
+        &self.account
+    }
+}
 
 #[derive(PartialEq, Eq)]
 enum NewNameChangeState {
@@ -28,6 +47,8 @@ pub struct InstanceSettingsSubpage {
     loader: Loader,
     loader_versions_state: TypelessFrontendMetadataResult,
     loader_version_select_state: Entity<SelectState<SearchableVec<&'static str>>>,
+    release_channel: ReleaseChannel,
+    account_select_state: Entity<SelectState<SearchableVec<AccountItem>>>,
     memory_override_enabled: bool,
     memory_min_input_state: Entity<InputState>,
     memory_max_input_state: Entity<InputState>,
@@ -57,6 +78,7 @@ impl InstanceSettingsSubpage {
         let memory = entry.configuration.memory.unwrap_or_default();
         let jvm_flags = entry.configuration.jvm_flags.clone().unwrap_or_default();
         let jvm_binary = entry.configuration.jvm_binary.clone().unwrap_or_default();
+        let release_channel = entry.configuration.release_channel;
 
         let new_name_input_state = cx.new(|cx| InputState::new(window, cx));
         cx.subscribe(&new_name_input_state, Self::on_new_name_input).detach();
@@ -85,6 +107,12 @@ impl InstanceSettingsSubpage {
         });
         cx.subscribe(&loader_version_select_state, Self::on_loader_version_selected).detach();
 
+        let account_select_state = cx.new(|cx| SelectState::new(SearchableVec::new(vec![]), None, window, cx));
+        cx.subscribe(&account_select_state, Self::on_account_selected).detach();
+        cx.observe_in(&data.accounts, window, |page, _, window, cx| {
+            page.update_accounts(window, cx);
+        }).detach();
+
         let memory_min_input_state = cx.new(|cx| {
             InputState::new(window, cx).default_value(memory.min.to_string())
         });
@@ -117,6 +145,8 @@ impl InstanceSettingsSubpage {
             jvm_flags_input_state,
             jvm_binary_enabled: jvm_binary.enabled,
             jvm_binary_path: jvm_binary.path.clone(),
+            release_channel,
+            account_select_state,
             new_name_change_state: NewNameChangeState::NoChange,
             backend_handle,
             loader_versions_state: TypelessFrontendMetadataResult::Loading,
@@ -125,6 +155,7 @@ impl InstanceSettingsSubpage {
         };
         page.update_minecraft_versions(minecraft_versions, window, cx);
         page.update_loader_versions(window, cx);
+        page.update_accounts(window, cx);
         page
     }
 }
@@ -180,6 +211,31 @@ impl InstanceSettingsSubpage {
         });
     }
 
+    fn update_accounts(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let accounts = self.data.accounts.read(cx).accounts.clone();
+        let bound_account = self.instance.read(cx).configuration.account;
+
+        let mut items = vec![AccountItem {
+            name: "Selected account".into(),
+            account: None,
+        }];
+        items.extend(accounts.iter().map(|account| AccountItem {
+            name: SharedString::from(account.username.clone()),
+            account: Some(account.uuid),
+        }));
+        if let Some(bound_account) = bound_account && !accounts.iter().any(|account| account.uuid == bound_account) {
+            items.push(AccountItem {
+                name: format!("Removed account ({bound_account})").into(),
+                account: Some(bound_account),
+            });
+        }
+
+        self.account_select_state.update(cx, move |select_state, cx| {
+            select_state.set_items(SearchableVec::new(items), window, cx);
+            select_state.set_selected_value(&bound_account, window, cx);
+        });
+    }
+
     fn update_loader_versions(&mut self, window: &mut Window, cx: &mut Context<Self>) {
         let loader_versions = match self.loader {
             Loader::Vanilla | Loader::Unknown => {
@@ -318,6 +374,23 @@ impl InstanceSettingsSubpage {
         });
     }
 
+    pub fn on_account_selected(
+        &mut self,
+        _state: Entity<SelectState<SearchableVec<AccountItem>>>,
+        event: &SelectEvent<SearchableVec<AccountItem>>,
+        cx: &mut Context<Self>,
+    ) {
+        let SelectEvent::Confirm(value) = event;
+        let account = value.flatten();
+
+        if self.instance.read(cx).configuration.account != account {
+            self.backend_handle.send(MessageToBackend::SetInstanceAccount {
+                id: self.instance_id,
+                account,
+            });
+        }
+    }
+
     pub fn on_memory_step(
         &mut self,
         state: &Entity<InputState>,
@@ -537,6 +610,37 @@ impl Render for InstanceSettingsSubpage {
             }
         }
 
+        basic_content = basic_content.child(crate::labelled(
+            "Release Channel",
+            ReleaseChannelGroup::new("release_channel", self.release_channel)
+                .on_select(cx.listener(|page, release_channel: &ReleaseChannel, _, cx| {
+                    page.release_channel = *release_channel;
+                    page.backend_handle.send(MessageToBackend::SetInstanceReleaseChannel {
+                        id: page.instance_id,
+                        release_channel: *release_channel,
+                    });
+                    cx.notify();
+                }))
+        ));
+
+        if self.instance.read(cx).configuration.kind == InstanceKind::Server {
+            basic_content = basic_content.child(crate::labelled(
+                "Server",
+                Button::new("server_properties").info().icon(IconName::Settings).label("Edit server.properties").on_click({
+                    let instance_id = self.instance_id;
+                    let backend_handle = self.backend_handle.clone();
+                    move |_, window, cx| {
+                        crate::modals::server_properties::open_server_properties(instance_id, backend_handle.clone(), window, cx);
+                    }
+                })
+            ));
+        } else {
+            basic_content = basic_content.child(crate::labelled(
+                "Account",
+                Select::new(&self.account_select_state).w_full()
+            ));
+        }
+
         let runtime_content = v_flex()
             .gap_4()
             .size_full()
@@ -657,6 +761,20 @@ impl Render for InstanceSettingsSubpage {
                     }).detach();
                 }
             }))
+            .child(Button::new("verify").label("Verify and repair files").info().on_click({
+                let instance_id = self.instance_id;
+                let backend_handle = self.backend_handle.clone();
+                move |_: &ClickEvent, window, cx| {
+                    crate::root::start_verify_instance(instance_id, &backend_handle, window, cx);
+                }
+            }))
+            .child(Button::new("prepare").label("Download files for offline play").info().on_click({
+                let instance_id = self.instance_id;
+                let backend_handle = self.backend_handle.clone();
+                move |_: &ClickEvent, window, cx| {
+                    crate::root::start_prepare_instance(instance_id, &backend_handle, window, cx);
+                }
+            }))
             .child(Button::new("delete").label("Delete this instance").danger().on_click({
                 let instance = self.instance.clone();
                 let backend_handle = self.backend_handle.clone();
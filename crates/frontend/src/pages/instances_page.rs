@@ -17,7 +17,7 @@ use gpui_component::{
     table::{Table, TableState},
     v_flex,
 };
-use schema::{loader::Loader, version_manifest::{MinecraftVersionManifest, MinecraftVersionType}};
+use schema::{instance::InstanceKind, loader::Loader, version_manifest::{MinecraftVersionManifest, MinecraftVersionType}};
 
 use crate::{
     component::{instance_list::InstanceList, page_path::PagePath},
@@ -32,6 +32,7 @@ pub struct InstancesPage {
     instances: Entity<InstanceEntries>,
 
     backend_handle: BackendHandle,
+    _import_modpack_task: Option<Task<()>>,
 }
 
 impl InstancesPage {
@@ -43,6 +44,7 @@ impl InstancesPage {
             metadata: data.metadata.clone(),
             instances: data.instances.clone(),
             backend_handle: data.backend_handle.clone(),
+            _import_modpack_task: None,
         }
     }
 }
@@ -57,7 +59,15 @@ impl Render for InstancesPage {
                 this.show_create_instance_modal(window, cx);
             }));
 
-        ui::page(cx, h_flex().gap_8().child("Instances").child(create_instance))
+        let import_modpack = Button::new("import_modpack")
+            .outline()
+            .icon(IconName::FolderOpen)
+            .label("Import CurseForge Modpack")
+            .on_click(cx.listener(|this, _, window, cx| {
+                this.import_curseforge_modpack(window, cx);
+            }));
+
+        ui::page(cx, h_flex().gap_8().child("Instances").child(h_flex().gap_2().child(create_instance).child(import_modpack)))
             .child(Table::new(&self.instance_table).bordered(false))
     }
 }
@@ -108,12 +118,36 @@ impl SelectDelegate for VersionList {
 }
 
 impl InstancesPage {
+    pub fn import_curseforge_modpack(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let receiver = cx.prompt_for_paths(PathPromptOptions {
+            files: true,
+            directories: false,
+            multiple: false,
+            prompt: Some("Select CurseForge modpack".into())
+        });
+
+        let backend_handle = self.backend_handle.clone();
+        self._import_modpack_task = Some(window.spawn(cx, async move |cx| {
+            let Ok(Ok(Some(paths))) = receiver.await else {
+                return;
+            };
+            let Some(path) = paths.into_iter().next() else {
+                return;
+            };
+            _ = cx.update(|window, cx| {
+                crate::root::start_import_curseforge_modpack(path, None, &backend_handle, window, cx);
+            });
+        }));
+    }
+
     pub fn show_create_instance_modal(&mut self, window: &mut Window, cx: &mut Context<Self>) {
         let selected_loader = Arc::new(AtomicUsize::new(0));
         let loaded_versions = Arc::new(AtomicBool::new(false));
         let error_loading_versions = Arc::new(RwLock::new(None));
         let show_snapshots = Arc::new(AtomicBool::new(false));
         let name_invalid = Arc::new(AtomicBool::new(false));
+        let create_server = Arc::new(AtomicBool::new(false));
+        let accepted_eula = Arc::new(AtomicBool::new(false));
 
         let instance_names: Arc<[SharedString]> =
             self.instances.read(cx).entries.iter().map(|(_, v)| v.read(cx).name.clone()).collect();
@@ -371,7 +405,22 @@ impl InstancesPage {
 
             let minecraft_version_dropdown = minecraft_version_dropdown.clone();
 
+            let is_server = create_server.load(Ordering::Relaxed);
             let name_is_invalid = name_invalid.load(Ordering::Relaxed);
+            let can_create = !name_is_invalid && (!is_server || accepted_eula.load(Ordering::Relaxed));
+
+            let kind_button_group = ButtonGroup::new("kind")
+                .outline()
+                .child(Button::new("kind-client").label("Client").selected(!is_server))
+                .child(Button::new("kind-server").label("Dedicated Server").selected(is_server))
+                .on_click({
+                    let create_server = Arc::clone(&create_server);
+                    move |selected: &Vec<usize>, _, _| {
+                        if let Some(selected) = selected.first() {
+                            create_server.store(*selected == 1, Ordering::Relaxed);
+                        }
+                    }
+                });
 
             let content = v_flex()
                 .gap_3()
@@ -379,8 +428,23 @@ impl InstancesPage {
                     "Name",
                     Input::new(&name_input_state).when(name_is_invalid, |this| this.border_color(cx.theme().danger)),
                 ))
+                .child(crate::labelled("Type", kind_button_group))
                 .child(crate::labelled("Version", v_flex().gap_2().child(version_dropdown).child(show_snapshots_button)))
-                .child(crate::labelled("Modloader", loader_button_group));
+                .child(crate::labelled("Modloader", loader_button_group))
+                .when(is_server, |content| {
+                    let accepted_eula = Arc::clone(&accepted_eula);
+                    content.child(h_flex()
+                        .gap_2()
+                        .child(Checkbox::new("accept-eula")
+                            .checked(accepted_eula.load(Ordering::Relaxed))
+                            .label("I agree to the Minecraft EULA")
+                            .on_click(move |value, _, _| {
+                                accepted_eula.store(*value, Ordering::Relaxed);
+                            }))
+                        .child(Button::new("view-eula").label("View EULA").ghost().on_click(|_, _, cx| {
+                            cx.open_url("https://aka.ms/MinecraftEULA");
+                        })))
+                });
 
             let text_input_state = name_input_state.clone();
             let backend_handle = backend_handle.clone();
@@ -388,7 +452,7 @@ impl InstancesPage {
 
             modal
                 .footer(move |ok, cancel, window, cx| {
-                    if name_is_invalid {
+                    if !can_create {
                         vec![
                             cancel(window, cx),
                             div().child(ok(window, cx)).opacity(0.5).into_any_element(),
@@ -400,7 +464,7 @@ impl InstancesPage {
                 .overlay_closable(false)
                 .title("Create Instance")
                 .on_ok(move |_, _, cx| {
-                    if name_is_invalid {
+                    if !can_create {
                         return false;
                     }
                     let Some(selected_version) = minecraft_version_dropdown.read(cx).selected_value().cloned() else {
@@ -417,6 +481,7 @@ impl InstancesPage {
                         name: name.as_str().into(),
                         version: selected_version.as_str().into(),
                         loader: selected_loader_value,
+                        kind: if is_server { InstanceKind::Server } else { InstanceKind::Client },
                     });
 
                     true
@@ -1,4 +1,5 @@
 pub mod instance;
 pub mod instances_page;
 pub mod modrinth_page;
+pub mod modrinth_project_page;
 pub mod syncing_page;
@@ -3,19 +3,30 @@ use std::{ops::Range, sync::{atomic::AtomicBool, Arc}, time::Duration};
 use bridge::{instance::{AtomicContentUpdateStatus, ContentUpdateStatus, InstanceID, InstanceContentID, InstanceContentSummary}, message::MessageToBackend, meta::MetadataRequest, modal_action::ModalAction};
 use gpui::{prelude::*, *};
 use gpui_component::{
-    ActiveTheme, Icon, IconName, Selectable, StyledExt, WindowExt, breadcrumb::Breadcrumb, button::{Button, ButtonGroup, ButtonVariant, ButtonVariants}, checkbox::Checkbox, h_flex, input::{Input, InputEvent, InputState}, notification::NotificationType, scroll::{ScrollableElement, Scrollbar}, skeleton::Skeleton, tooltip::Tooltip, v_flex
+    ActiveTheme, Disableable, Icon, IconName, IndexPath, Selectable, StyledExt, WindowExt, breadcrumb::Breadcrumb, button::{Button, ButtonGroup, ButtonVariant, ButtonVariants}, checkbox::Checkbox, h_flex, input::{Input, InputEvent, InputState}, notification::NotificationType, scroll::{ScrollableElement, Scrollbar}, select::{SearchableVec, Select, SelectEvent, SelectState}, skeleton::Skeleton, tooltip::Tooltip, v_flex
 };
 use rustc_hash::{FxHashMap, FxHashSet};
-use schema::{content::ContentSource, loader::Loader, modrinth::{
-    ModrinthHit, ModrinthProjectType, ModrinthSearchRequest, ModrinthSearchResult, ModrinthSideRequirement
-}};
+use schema::{content::ContentSource, instance::InstanceKind, loader::Loader, modrinth::{
+    ModrinthFacets, ModrinthHit, ModrinthProjectType, ModrinthSearchIndex, ModrinthSearchRequest, ModrinthSearchResult, ModrinthSideRequirement
+}, version_manifest::{MinecraftVersionManifest, MinecraftVersionType}};
+use ustr::Ustr;
 
 use crate::{
     component::{error_alert::ErrorAlert, page_path::PagePath}, entity::{
-        DataEntities, instance::InstanceEntries, metadata::{AsMetadataResult, FrontendMetadata, FrontendMetadataResult}
-    }, interface_config::InterfaceConfig, ts, ui
+        DataEntities, instance::InstanceEntries, metadata::{AsMetadataResult, FrontendMetadata, FrontendMetadataResult, FrontendMetadataState}
+    }, interface_config::{InterfaceConfig, ModrinthSearchFilters}, ts, ui
 };
 
+const ANY_GAME_VERSION: &str = "Any version";
+
+const SORT_OPTIONS: [ModrinthSearchIndex; 5] = [
+    ModrinthSearchIndex::Relevance,
+    ModrinthSearchIndex::Downloads,
+    ModrinthSearchIndex::Follows,
+    ModrinthSearchIndex::Newest,
+    ModrinthSearchIndex::Updated,
+];
+
 pub struct ModrinthSearchPage {
     data: DataEntities,
     hits: Vec<ModrinthHit>,
@@ -23,25 +34,34 @@ pub struct ModrinthSearchPage {
     install_for: Option<InstanceID>,
     loading: Option<Subscription>,
     pending_clear: bool,
+    loaded_hits: usize,
     total_hits: usize,
     search_state: Entity<InputState>,
     _search_input_subscription: Subscription,
     _delayed_clear_task: Task<()>,
     filter_project_type: ModrinthProjectType,
-    filter_loaders: FxHashSet<Loader>,
+    filters: ModrinthSearchFilters,
+    filter_game_version: Option<Ustr>,
     filter_categories: FxHashSet<&'static str>,
     show_categories: Arc<AtomicBool>,
     can_install_latest: bool,
+    // Servers only list content that runs on the server
+    server_only: bool,
     installed_mods_by_project: FxHashMap<Arc<str>, Vec<InstalledMod>>,
     last_search: Arc<str>,
     scroll_handle: UniformListScrollHandle,
     search_error: Option<SharedString>,
     image_cache: Entity<RetainAllImageCache>,
+    game_version_select_state: Entity<SelectState<SearchableVec<SharedString>>>,
+    sort_select_state: Entity<SelectState<Vec<SharedString>>>,
+    _minecraft_versions_subscription: Subscription,
+    _select_subscriptions: [Subscription; 2],
 }
 
 struct InstalledMod {
     mod_id: InstanceContentID,
     status: Arc<AtomicContentUpdateStatus>,
+    pinned: bool,
 }
 
 impl ModrinthSearchPage {
@@ -49,12 +69,19 @@ impl ModrinthSearchPage {
         let search_state = cx.new(|cx| InputState::new(window, cx).placeholder("Search mods...").clean_on_escape());
 
         let mut can_install_latest = false;
+        let mut server_only = false;
         let mut installed_mods_by_project: FxHashMap<Arc<str>, Vec<InstalledMod>> = FxHashMap::default();
 
+        let filters = InterfaceConfig::get(cx).modrinth_search_filters.clone();
+        let mut filter_game_version = filters.game_version;
+
         if let Some(install_for) = install_for {
+            filter_game_version = None;
             if let Some(entry) = data.instances.read(cx).entries.get(&install_for) {
                 let instance = entry.read(cx);
                 can_install_latest = instance.configuration.loader != Loader::Vanilla;
+                server_only = instance.configuration.kind == InstanceKind::Server;
+                filter_game_version = Some(instance.configuration.minecraft_version);
 
                 let mods = instance.mods.read(cx);
                 for summary in mods.iter() {
@@ -66,6 +93,7 @@ impl ModrinthSearchPage {
                     installed.push(InstalledMod {
                         mod_id: summary.id,
                         status: summary.content_summary.update_status.clone(),
+                        pinned: summary.pinned,
                     })
                 }
             }
@@ -83,6 +111,31 @@ impl ModrinthSearchPage {
             filter_project_type = ModrinthProjectType::Mod;
         }
 
+        let game_version_select_state = cx.new(|cx| {
+            let versions = std::iter::once(SharedString::new_static(ANY_GAME_VERSION))
+                .chain(filter_game_version.map(|version| SharedString::new_static(version.as_str())))
+                .collect();
+            let mut select_state = SelectState::new(SearchableVec::new(versions), None, window, cx).searchable(true);
+            select_state.set_selected_index(Some(IndexPath::default().row(if filter_game_version.is_some() { 1 } else { 0 })), window, cx);
+            select_state
+        });
+        let sort_select_state = cx.new(|cx| {
+            let options = SORT_OPTIONS.iter().map(|sort| SharedString::new_static(sort.pretty_name())).collect();
+            let mut select_state = SelectState::new(options, None, window, cx);
+            let selected = SORT_OPTIONS.iter().position(|sort| *sort == filters.sort).unwrap_or(0);
+            select_state.set_selected_index(Some(IndexPath::default().row(selected)), window, cx);
+            select_state
+        });
+        let _select_subscriptions = [
+            cx.subscribe_in(&game_version_select_state, window, Self::on_game_version_selected),
+            cx.subscribe_in(&sort_select_state, window, Self::on_sort_selected),
+        ];
+
+        let minecraft_versions = FrontendMetadata::request(&data.metadata, MetadataRequest::MinecraftVersionManifest, cx);
+        let _minecraft_versions_subscription = cx.observe_in(&minecraft_versions, window, |page, versions, window, cx| {
+            page.update_minecraft_versions(versions, window, cx);
+        });
+
         let mut page = Self {
             data: data.clone(),
             hits: Vec::new(),
@@ -90,25 +143,110 @@ impl ModrinthSearchPage {
             install_for,
             loading: None,
             pending_clear: false,
+            loaded_hits: 0,
             total_hits: 1,
             search_state,
             _search_input_subscription,
             _delayed_clear_task: Task::ready(()),
             filter_project_type,
-            filter_loaders: FxHashSet::default(),
+            filters,
+            filter_game_version,
             filter_categories: FxHashSet::default(),
             show_categories: Arc::new(AtomicBool::new(false)),
             can_install_latest,
+            server_only,
             installed_mods_by_project,
             last_search: Arc::from(""),
             scroll_handle: UniformListScrollHandle::new(),
             search_error: None,
             image_cache: RetainAllImageCache::new(cx),
+            game_version_select_state,
+            sort_select_state,
+            _minecraft_versions_subscription,
+            _select_subscriptions,
         };
+        page.update_minecraft_versions(minecraft_versions, window, cx);
         page.load_more(cx);
         page
     }
 
+    fn update_minecraft_versions(&mut self, versions: Entity<FrontendMetadataState>, window: &mut Window, cx: &mut Context<Self>) {
+        let result: FrontendMetadataResult<MinecraftVersionManifest> = versions.read(cx).result();
+        let FrontendMetadataResult::Loaded(manifest) = result else {
+            return;
+        };
+
+        // Snapshots are only listed if they're the currently selected version
+        let mut versions: Vec<SharedString> = std::iter::once(SharedString::new_static(ANY_GAME_VERSION))
+            .chain(manifest.versions.iter()
+                .filter(|version| matches!(version.r#type, MinecraftVersionType::Release) || Some(version.id) == self.filter_game_version)
+                .map(|version| SharedString::new_static(version.id.as_str())))
+            .collect();
+
+        let selected = match self.filter_game_version {
+            Some(game_version) => {
+                let game_version = SharedString::new_static(game_version.as_str());
+                if !versions.contains(&game_version) {
+                    versions.insert(1, game_version.clone());
+                }
+                game_version
+            },
+            None => SharedString::new_static(ANY_GAME_VERSION),
+        };
+
+        self.game_version_select_state.update(cx, |select_state, cx| {
+            select_state.set_items(SearchableVec::new(versions), window, cx);
+            select_state.set_selected_value(&selected, window, cx);
+        });
+    }
+
+    fn on_game_version_selected(
+        &mut self,
+        _state: &Entity<SelectState<SearchableVec<SharedString>>>,
+        event: &SelectEvent<SearchableVec<SharedString>>,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let SelectEvent::Confirm(value) = event;
+        let game_version = value.as_ref()
+            .filter(|value| value.as_str() != ANY_GAME_VERSION)
+            .map(|value| Ustr::from(value.as_str()));
+
+        if self.filter_game_version == game_version {
+            return;
+        }
+        self.filter_game_version = game_version;
+        if self.install_for.is_none() {
+            self.filters.game_version = game_version;
+            self.save_filters(cx);
+        }
+        self.reload(cx);
+    }
+
+    fn on_sort_selected(
+        &mut self,
+        _state: &Entity<SelectState<Vec<SharedString>>>,
+        event: &SelectEvent<Vec<SharedString>>,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let SelectEvent::Confirm(value) = event;
+        let Some(sort) = value.as_ref().and_then(|value| SORT_OPTIONS.iter().find(|sort| sort.pretty_name() == value.as_str())) else {
+            return;
+        };
+
+        if self.filters.sort == *sort {
+            return;
+        }
+        self.filters.sort = *sort;
+        self.save_filters(cx);
+        self.reload(cx);
+    }
+
+    fn save_filters(&self, cx: &mut App) {
+        InterfaceConfig::get_mut(cx).modrinth_search_filters = self.filters.clone();
+    }
+
     fn on_search_input_event(
         &mut self,
         state: &Entity<InputState>,
@@ -152,11 +290,40 @@ impl ModrinthSearchPage {
         self.reload(cx);
     }
 
-    fn set_filter_loaders(&mut self, loaders: FxHashSet<Loader>, _window: &mut Window, cx: &mut Context<Self>) {
-        if self.filter_loaders == loaders {
+    fn set_filter_loaders(&mut self, loaders: Vec<Loader>, _window: &mut Window, cx: &mut Context<Self>) {
+        if self.filters.loaders == loaders {
             return;
         }
-        self.filter_loaders = loaders;
+        self.filters.loaders = loaders;
+        self.save_filters(cx);
+        self.reload(cx);
+    }
+
+    fn set_filter_environment(&mut self, client_side: bool, server_side: bool, _window: &mut Window, cx: &mut Context<Self>) {
+        if self.filters.client_side == client_side && self.filters.server_side == server_side {
+            return;
+        }
+        self.filters.client_side = client_side;
+        self.filters.server_side = server_side;
+        self.save_filters(cx);
+        self.reload(cx);
+    }
+
+    fn set_filter_open_source(&mut self, open_source: bool, _window: &mut Window, cx: &mut Context<Self>) {
+        if self.filters.open_source == open_source {
+            return;
+        }
+        self.filters.open_source = open_source;
+        self.save_filters(cx);
+        self.reload(cx);
+    }
+
+    fn set_hide_installed(&mut self, hide_installed: bool, _window: &mut Window, cx: &mut Context<Self>) {
+        if self.filters.hide_installed == hide_installed {
+            return;
+        }
+        self.filters.hide_installed = hide_installed;
+        self.save_filters(cx);
         self.reload(cx);
     }
 
@@ -178,6 +345,7 @@ impl ModrinthSearchPage {
                 if page.pending_clear {
                     page.pending_clear = false;
                     page.hits.clear();
+                    page.loaded_hits = 0;
                     page.total_hits = 1;
                     cx.notify();
                 }
@@ -206,51 +374,12 @@ impl ModrinthSearchPage {
             ModrinthProjectType::Shader => "shader",
         };
 
-        let offset = if self.pending_clear { 0 } else { self.hits.len() };
-
-        let mut facets = format!("[[\"project_type={}\"]", project_type);
-
-        let is_mod = self.filter_project_type == ModrinthProjectType::Mod || self.filter_project_type == ModrinthProjectType::Modpack;
-        if !self.filter_loaders.is_empty() && is_mod {
-            facets.push_str(",[");
-
-            let mut first = true;
-            for loader in &self.filter_loaders {
-                if first {
-                    first = false;
-                } else {
-                    facets.push(',');
-                }
-                facets.push_str("\"categories:");
-                facets.push_str(loader.as_modrinth_loader().id());
-                facets.push('"');
-            }
-            facets.push(']');
-        }
-
-        if !self.filter_categories.is_empty() {
-            facets.push_str(",[");
-
-            let mut first = true;
-            for category in &self.filter_categories {
-                if first {
-                    first = false;
-                } else {
-                    facets.push(',');
-                }
-                facets.push_str("\"categories:");
-                facets.push_str(*category);
-                facets.push('"');
-            }
-            facets.push(']');
-        }
-
-        facets.push(']');
+        let offset = if self.pending_clear { 0 } else { self.loaded_hits };
 
         let request = ModrinthSearchRequest {
             query,
-            facets: Some(facets.into()),
-            index: schema::modrinth::ModrinthSearchIndex::Relevance,
+            facets: self.build_facets(project_type),
+            index: self.filters.sort,
             offset,
             limit: 20,
         };
@@ -287,15 +416,47 @@ impl ModrinthSearchPage {
         }
     }
 
+    fn build_facets(&self, project_type: &str) -> Option<Arc<str>> {
+        let mut facets = ModrinthFacets::default();
+        facets.require("project_type", project_type);
+
+        let is_mod = self.filter_project_type == ModrinthProjectType::Mod || self.filter_project_type == ModrinthProjectType::Modpack;
+        if is_mod {
+            facets.any_of("categories", self.filters.loaders.iter().map(|loader| loader.as_modrinth_loader().id()));
+        }
+        facets.any_of("categories", self.filter_categories.iter());
+
+        if let Some(game_version) = self.filter_game_version {
+            facets.require("versions", game_version.as_str());
+        }
+        if self.filters.client_side {
+            facets.any_of("client_side", ["required", "optional"]);
+        }
+        if self.filters.server_side || self.server_only {
+            facets.any_of("server_side", ["required", "optional"]);
+        }
+        if self.filters.open_source {
+            facets.require("open_source", "true");
+        }
+
+        facets.build()
+    }
+
     fn apply_search_data(&mut self, search_result: &ModrinthSearchResult) {
         if self.pending_clear {
             self.pending_clear = false;
             self.hits.clear();
+            self.loaded_hits = 0;
             self.total_hits = 1;
             self._delayed_clear_task = Task::ready(());
         }
 
-        self.hits.extend(search_result.hits.iter().map(|hit| {
+        let hide_installed = self.filters.hide_installed && self.install_for.is_some();
+        let installed_mods_by_project = &self.installed_mods_by_project;
+        let is_installed = |project_id: &str| installed_mods_by_project.get(project_id).is_some_and(|installed| !installed.is_empty());
+
+        self.loaded_hits += search_result.hits.len();
+        self.hits.extend(search_result.hits.iter().filter(|hit| !hide_installed || !is_installed(&hit.project_id)).map(|hit| {
             let mut hit = hit.clone();
             if let Some(description) = hit.description {
                 hit.description = Some(description.replace("\n", " ").into());
@@ -475,17 +636,19 @@ impl ModrinthSearchPage {
                     )
                     .child(
                         Button::new(("open", index))
-                            .label("Open Page")
-                            .icon(IconName::Globe)
+                            .label("Details")
+                            .icon(IconName::Info)
                             .info()
                             .on_click({
-                                let project_type = hit.project_type.as_str();
                                 let project_id = hit.project_id.clone();
-                                move |_, _, cx| {
-                                    cx.open_url(&format!(
-                                        "https://modrinth.com/{}/{}",
-                                        project_type, project_id
-                                    ));
+                                let install_for = self.install_for;
+                                let path = self.page_path.pages();
+                                move |_, window, cx| {
+                                    let page = ui::PageType::ModrinthProject {
+                                        project: Ustr::from(&project_id),
+                                        installing_for: install_for,
+                                    };
+                                    crate::root::switch_page(page, &path, window, cx);
                                 }
                             }),
                     );
@@ -554,9 +717,14 @@ impl ModrinthSearchPage {
 
             let mut action = PrimaryAction::CheckForUpdates;
             for installed_mod in installed {
-                match installed_mod.status.load(std::sync::atomic::Ordering::Relaxed) {
+                let status = if installed_mod.pinned {
+                    ContentUpdateStatus::Pinned
+                } else {
+                    installed_mod.status.load(std::sync::atomic::Ordering::Relaxed)
+                };
+                match status {
                     ContentUpdateStatus::Unknown => {},
-                    ContentUpdateStatus::AlreadyUpToDate => {
+                    ContentUpdateStatus::AlreadyUpToDate | ContentUpdateStatus::NoVersionInChannel | ContentUpdateStatus::Pinned => {
                         if !matches!(action, PrimaryAction::Update(..)) {
                             action = PrimaryAction::UpToDate;
                         }
@@ -637,7 +805,7 @@ impl PrimaryAction {
 
 impl Render for ModrinthSearchPage {
     fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
-        let can_load_more = self.total_hits > self.hits.len();
+        let can_load_more = self.total_hits > self.loaded_hits;
         let scroll_handle = self.scroll_handle.clone();
 
         let item_count = self.hits.len() + if can_load_more || self.search_error.is_some() { 1 } else { 0 };
@@ -666,7 +834,15 @@ impl Render for ModrinthSearchPage {
         let mut top_bar = h_flex()
             .w_full()
             .gap_3()
-            .child(Input::new(&self.search_state));
+            .child(Input::new(&self.search_state))
+            .child(div().w_48().min_w_48().child(Select::new(&self.sort_select_state).title_prefix("Sort: ")))
+            .child(Button::new("collections").info().icon(IconName::Inbox).label("Collections").on_click({
+                let data = self.data.clone();
+                let install_for = self.install_for;
+                move |_, window, cx| {
+                    crate::modals::modrinth_collection::open(install_for, &data, window, cx);
+                }
+            }));
 
 
         if self.can_install_latest {
@@ -722,9 +898,9 @@ impl Render for ModrinthSearchPage {
                 .layout(Axis::Vertical)
                 .outline()
                 .multiple(true)
-                .child(Button::new("fabric").label("Fabric").selected(self.filter_loaders.contains(&Loader::Fabric)))
-                .child(Button::new("forge").label("Forge").selected(self.filter_loaders.contains(&Loader::Forge)))
-                .child(Button::new("neoforge").label("NeoForge").selected(self.filter_loaders.contains(&Loader::NeoForge)))
+                .child(Button::new("fabric").label("Fabric").selected(self.filters.loaders.contains(&Loader::Fabric)))
+                .child(Button::new("forge").label("Forge").selected(self.filters.loaders.contains(&Loader::Forge)))
+                .child(Button::new("neoforge").label("NeoForge").selected(self.filters.loaders.contains(&Loader::NeoForge)))
                 .on_click(cx.listener(|page, clicked: &Vec<usize>, window, cx| {
                     page.set_filter_loaders(clicked.iter().filter_map(|index| match index {
                         0 => Some(Loader::Fabric),
@@ -773,17 +949,48 @@ impl Render for ModrinthSearchPage {
             }).into_any_element()
         };
 
-        let parameters = v_flex().h_full().gap_3()
+        let environment_button_group = ButtonGroup::new("environment_group")
+            .layout(Axis::Vertical)
+            .outline()
+            .multiple(true)
+            .child(Button::new("client").label("Client").selected(self.filters.client_side))
+            .child(Button::new("server").label("Server").selected(self.filters.server_side || self.server_only).disabled(self.server_only))
+            .on_click(cx.listener(|page, clicked: &Vec<usize>, window, cx| {
+                page.set_filter_environment(clicked.contains(&0), clicked.contains(&1), window, cx);
+            }));
+
+        let open_source = Checkbox::new("open-source")
+            .label("Open source")
+            .checked(self.filters.open_source)
+            .on_click(cx.listener(|page, value: &bool, window, cx| {
+                page.set_filter_open_source(*value, window, cx);
+            }));
+
+        let hide_installed = self.install_for.is_some().then(|| {
+            Checkbox::new("hide-installed")
+                .label("Hide installed")
+                .checked(self.filters.hide_installed)
+                .on_click(cx.listener(|page, value: &bool, window, cx| {
+                    page.set_hide_installed(*value, window, cx);
+                }))
+        });
+
+        let parameters = v_flex().h_full().w_48().min_w_48().gap_3()
+            .child(Select::new(&self.game_version_select_state).title_prefix("Version: "))
             .child(type_button_group)
             .when_some(loader_button_group, |this, group| this.child(group))
-            .child(category);
+            .child(environment_button_group)
+            .child(open_source)
+            .when_some(hide_installed, |this, checkbox| this.child(checkbox))
+            .child(category)
+            .overflow_y_scrollbar();
 
         ui::page(cx, self.page_path.create_breadcrumb(&self.data, cx))
             .child(h_flex().size_full().p_3().gap_3().child(parameters).child(content))
     }
 }
 
-fn format_downloads(downloads: usize) -> String {
+pub fn format_downloads(downloads: usize) -> String {
     if downloads >= 1_000_000_000 {
         format!("{}B Downloads", (downloads / 10_000_000) as f64 / 100.0)
     } else if downloads >= 1_000_000 {
@@ -0,0 +1,530 @@
+use std::{cmp::Ordering, sync::Arc};
+
+use bridge::{instance::InstanceID, message::MessageToBackend, meta::{MetadataRequest, MetadataResult}};
+use gpui::{prelude::*, *};
+use gpui_component::{
+    ActiveTheme, Icon, IconName, Selectable, Sizable, button::{Button, ButtonGroup, ButtonVariants}, h_flex, scroll::ScrollableElement, select::{SearchableVec, Select, SelectEvent, SelectState}, skeleton::Skeleton, spinner::Spinner, text::TextView, v_flex, IndexPath
+};
+use rustc_hash::{FxHashMap, FxHashSet};
+use schema::{instance::ReleaseChannel, modrinth::{
+    ModrinthLoader, ModrinthProject, ModrinthProjectVersion, ModrinthProjectVersionsRequest, ModrinthProjectVersionsResult, ModrinthVersionStatus, ModrinthVersionType
+}};
+use ustr::Ustr;
+
+use crate::{
+    component::{error_alert::ErrorAlert, page_path::PagePath, release_channel_group::ReleaseChannelGroup}, entity::{
+        DataEntities, metadata::{AsMetadataResult, FrontendMetadata, FrontendMetadataResult, FrontendMetadataState}
+    }, pages::modrinth_page::format_downloads, png_render_cache, ts, ui
+};
+
+const ALL_GAME_VERSIONS: &str = "All game versions";
+const ALL_LOADERS: &str = "All loaders";
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ProjectTab {
+    Description,
+    Gallery,
+    Versions,
+}
+
+pub struct ModrinthProjectPage {
+    data: DataEntities,
+    page_path: PagePath,
+    project_id: Arc<str>,
+    install_for: Option<InstanceID>,
+    project: Entity<FrontendMetadataState>,
+    versions: Entity<FrontendMetadataState>,
+    tab: ProjectTab,
+    gallery_requested: bool,
+    gallery_images: FxHashMap<Arc<str>, Result<Arc<[u8]>, SharedString>>,
+    game_version_select_state: Option<Entity<SelectState<SearchableVec<SharedString>>>>,
+    loader_select_state: Option<Entity<SelectState<Vec<SharedString>>>>,
+    release_channel: ReleaseChannel,
+    expanded_changelogs: FxHashSet<Arc<str>>,
+    _gallery_tasks: Vec<Task<()>>,
+    _select_subscriptions: Vec<Subscription>,
+    _project_subscription: Subscription,
+    _versions_subscription: Subscription,
+}
+
+impl ModrinthProjectPage {
+    pub fn new(project: Ustr, install_for: Option<InstanceID>, page_path: PagePath, data: &DataEntities, _window: &mut Window, cx: &mut Context<Self>) -> Self {
+        let project_id: Arc<str> = project.as_str().into();
+
+        let project = FrontendMetadata::request(&data.metadata, MetadataRequest::ModrinthProject(project_id.clone()), cx);
+        let versions = FrontendMetadata::request(&data.metadata, MetadataRequest::ModrinthProjectVersions(ModrinthProjectVersionsRequest {
+            project_id: project_id.clone(),
+            game_versions: None,
+            loaders: None,
+        }), cx);
+
+        let _project_subscription = cx.observe(&project, |page, _, cx| {
+            page.load_gallery(cx);
+            cx.notify();
+        });
+        let _versions_subscription = cx.observe(&versions, |_, _, cx| cx.notify());
+
+        let release_channel = install_for
+            .and_then(|id| data.instances.read(cx).entries.get(&id))
+            .map(|instance| instance.read(cx).configuration.release_channel)
+            .unwrap_or_default();
+
+        let mut page = Self {
+            data: data.clone(),
+            page_path,
+            project_id,
+            install_for,
+            project,
+            versions,
+            tab: ProjectTab::Description,
+            gallery_requested: false,
+            gallery_images: FxHashMap::default(),
+            game_version_select_state: None,
+            loader_select_state: None,
+            release_channel,
+            expanded_changelogs: FxHashSet::default(),
+            _gallery_tasks: Vec::new(),
+            _select_subscriptions: Vec::new(),
+            _project_subscription,
+            _versions_subscription,
+        };
+        page.load_gallery(cx);
+        page
+    }
+
+    fn load_gallery(&mut self, cx: &mut Context<Self>) {
+        if self.gallery_requested {
+            return;
+        }
+        let FrontendMetadataResult::Loaded(project) = AsMetadataResult::<ModrinthProject>::result(self.project.read(cx)) else {
+            return;
+        };
+        self.gallery_requested = true;
+
+        let urls: Vec<Arc<str>> = project.gallery.iter().map(|image| image.url.clone()).collect();
+        for url in urls {
+            let (send, recv) = tokio::sync::oneshot::channel();
+            self.data.backend_handle.send(MessageToBackend::DownloadImage {
+                url: url.clone(),
+                max_width: 960,
+                channel: send,
+            });
+            self._gallery_tasks.push(cx.spawn(async move |page, cx| {
+                let result = recv.await.unwrap_or_else(|_| Err("Backend didn't respond".into()));
+                let _ = page.update(cx, move |page, cx| {
+                    page.gallery_images.insert(url, result.map_err(SharedString::from));
+                    cx.notify();
+                });
+            }));
+        }
+    }
+
+    fn create_version_filters(&mut self, versions: &[ModrinthProjectVersion], window: &mut Window, cx: &mut Context<Self>) {
+        let mut game_versions = FxHashSet::default();
+        let mut loaders = FxHashSet::default();
+        for version in versions {
+            game_versions.extend(version.game_versions.iter().flat_map(|versions| versions.iter().copied()));
+            loaders.extend(version.loaders.iter().flat_map(|loaders| loaders.iter().copied()));
+        }
+        loaders.remove(&ModrinthLoader::Unknown);
+
+        let mut game_versions: Vec<Ustr> = game_versions.into_iter().collect();
+        game_versions.sort_by(|a, b| {
+            let a_is_snapshot = a.contains("w") || a.contains("pre") || a.contains("rc");
+            let b_is_snapshot = b.contains("w") || b.contains("pre") || b.contains("rc");
+            if a_is_snapshot != b_is_snapshot {
+                if a_is_snapshot {
+                    Ordering::Greater
+                } else {
+                    Ordering::Less
+                }
+            } else {
+                lexical_sort::natural_lexical_cmp(a, b).reverse()
+            }
+        });
+
+        let mut loaders: Vec<ModrinthLoader> = loaders.into_iter().collect();
+        loaders.sort();
+
+        // Default to the game version of the instance we're installing for, if there are versions for it
+        let instance_version = self.install_for
+            .and_then(|id| self.data.instances.read(cx).entries.get(&id))
+            .map(|instance| instance.read(cx).configuration.minecraft_version);
+        let selected_game_version = instance_version
+            .and_then(|instance_version| game_versions.iter().position(|version| *version == instance_version))
+            .map(|index| index + 1)
+            .unwrap_or(0);
+
+        let game_version_items: Vec<SharedString> = std::iter::once(SharedString::new_static(ALL_GAME_VERSIONS))
+            .chain(game_versions.iter().map(|version| SharedString::new_static(version.as_str())))
+            .collect();
+        let loader_items: Vec<SharedString> = std::iter::once(SharedString::new_static(ALL_LOADERS))
+            .chain(loaders.iter().map(|loader| SharedString::new_static(loader.pretty_name())))
+            .collect();
+
+        let game_version_select_state = cx.new(|cx| {
+            let mut select_state = SelectState::new(SearchableVec::new(game_version_items), None, window, cx).searchable(true);
+            select_state.set_selected_index(Some(IndexPath::default().row(selected_game_version)), window, cx);
+            select_state
+        });
+        let loader_select_state = cx.new(|cx| {
+            let mut select_state = SelectState::new(loader_items, None, window, cx);
+            select_state.set_selected_index(Some(IndexPath::default()), window, cx);
+            select_state
+        });
+
+        self._select_subscriptions = vec![
+            cx.subscribe(&game_version_select_state, |_, _, _: &SelectEvent<_>, cx| cx.notify()),
+            cx.subscribe(&loader_select_state, |_, _, _: &SelectEvent<_>, cx| cx.notify()),
+        ];
+        self.game_version_select_state = Some(game_version_select_state);
+        self.loader_select_state = Some(loader_select_state);
+    }
+
+    fn render_header(&self, project: &ModrinthProject, cx: &mut Context<Self>) -> impl IntoElement {
+        let theme = cx.theme();
+
+        let image = if let Some(icon_url) = &project.icon_url
+            && !icon_url.is_empty()
+        {
+            gpui::img(SharedUri::from(icon_url))
+                .with_fallback(|| Skeleton::new().rounded_lg().size_16().into_any_element())
+        } else {
+            gpui::img(ImageSource::Resource(Resource::Embedded("images/default_mod.png".into())))
+        };
+
+        let install_button = Button::new("install")
+            .success()
+            .label("Install")
+            .icon(Icon::empty().path("icons/download.svg"))
+            .on_click({
+                let data = self.data.clone();
+                let name = SharedString::new(project.title.clone());
+                let project_id = self.project_id.clone();
+                let project_type = project.project_type;
+                let install_for = self.install_for;
+                move |_, window, cx| {
+                    crate::modals::modrinth_install::open(name.as_str(), project_id.clone(), project_type, install_for, &data, window, cx);
+                }
+            });
+
+        h_flex()
+            .gap_4()
+            .child(image.rounded_lg().size_16().min_w_16().min_h_16())
+            .child(v_flex()
+                .flex_grow()
+                .gap_1()
+                .child(div().text_xl().child(SharedString::new(project.title.clone())))
+                .child(div().text_color(theme.muted_foreground).child(SharedString::new(project.description.clone())))
+                .child(h_flex()
+                    .gap_3()
+                    .text_sm()
+                    .child(h_flex().gap_0p5().child(Icon::empty().path("icons/download.svg")).child(format_downloads(project.downloads)))
+                    .child(h_flex().gap_0p5().child(Icon::empty().path("icons/users.svg")).child(format!("{} Followers", project.followers)))))
+            .child(install_button)
+    }
+
+    fn render_sidebar(&self, project: &ModrinthProject, cx: &mut Context<Self>) -> impl IntoElement {
+        let theme = cx.theme();
+
+        let mut links = Vec::new();
+        let modrinth_url = format!("https://modrinth.com/{}/{}", project.project_type.as_str(), project.slug);
+        links.push((SharedString::new_static("Modrinth"), Arc::<str>::from(modrinth_url)));
+        if let Some(url) = &project.source_url {
+            links.push(("Source".into(), url.clone()));
+        }
+        if let Some(url) = &project.issues_url {
+            links.push(("Issues".into(), url.clone()));
+        }
+        if let Some(url) = &project.wiki_url {
+            links.push(("Wiki".into(), url.clone()));
+        }
+        if let Some(url) = &project.discord_url {
+            links.push(("Discord".into(), url.clone()));
+        }
+        for donation in project.donation_urls.iter() {
+            links.push((format!("Donate ({})", donation.platform).into(), donation.url.clone()));
+        }
+
+        let links = v_flex().gap_1().children(links.into_iter().enumerate().map(|(index, (label, url))| {
+            Button::new(("link", index))
+                .small()
+                .ghost()
+                .icon(IconName::Globe)
+                .label(label)
+                .on_click(move |_, _, cx| cx.open_url(&url))
+        }));
+
+        let license = project.license.as_ref().map(|license| {
+            let name = SharedString::new(license.name.clone().filter(|name| !name.is_empty()).unwrap_or(license.id.clone()));
+            if let Some(url) = license.url.clone() {
+                Button::new("license").small().ghost().label(name).on_click(move |_, _, cx| cx.open_url(&url)).into_any_element()
+            } else {
+                div().text_sm().child(name).into_any_element()
+            }
+        });
+
+        let categories = project.categories.iter().map(|category| ts!(category.as_str()).to_string()).collect::<Vec<_>>().join(", ");
+
+        v_flex()
+            .w_64()
+            .min_w_64()
+            .gap_3()
+            .child(v_flex().gap_1()
+                .child(div().text_sm().text_color(theme.muted_foreground).child("License"))
+                .child(license.unwrap_or_else(|| div().text_sm().child("Unknown").into_any_element())))
+            .when(!categories.is_empty(), |sidebar| {
+                sidebar.child(v_flex().gap_1()
+                    .child(div().text_sm().text_color(theme.muted_foreground).child("Categories"))
+                    .child(div().text_sm().child(categories)))
+            })
+            .child(v_flex().gap_1()
+                .child(div().text_sm().text_color(theme.muted_foreground).child("Links"))
+                .child(links))
+    }
+
+    fn render_gallery(&self, project: &ModrinthProject, cx: &mut Context<Self>) -> AnyElement {
+        if project.gallery.is_empty() {
+            return div().child("This project has no gallery images").into_any_element();
+        }
+
+        let mut images: Vec<_> = project.gallery.iter().collect();
+        images.sort_by_key(|image| (!image.featured, image.ordering));
+
+        let theme = cx.theme();
+        let muted_foreground = theme.muted_foreground;
+        let border = theme.border;
+
+        h_flex()
+            .flex_wrap()
+            .items_start()
+            .gap_3()
+            .children(images.into_iter().enumerate().map(|(index, image)| {
+                let preview = match self.gallery_images.get(&image.url) {
+                    Some(Ok(png)) => png_render_cache::render(png.clone(), cx).w_full().rounded_md().into_any_element(),
+                    Some(Err(error)) => ErrorAlert::new(("gallery_error", index), "Unable to load image".into(), error.clone()).into_any_element(),
+                    None => Skeleton::new().w_full().h(px(200.0)).rounded_md().into_any_element(),
+                };
+
+                v_flex()
+                    .w(px(360.0))
+                    .p_2()
+                    .gap_1()
+                    .rounded_lg()
+                    .border_1()
+                    .border_color(border)
+                    .child(preview)
+                    .when_some(image.title.clone(), |card, title| card.child(SharedString::new(title)))
+                    .when_some(image.description.clone(), |card, description| {
+                        card.child(div().text_sm().text_color(muted_foreground).child(SharedString::new(description)))
+                    })
+            }))
+            .into_any_element()
+    }
+
+    fn render_versions(&mut self, project: &ModrinthProject, window: &mut Window, cx: &mut Context<Self>) -> AnyElement {
+        let result: FrontendMetadataResult<ModrinthProjectVersionsResult> = self.versions.read(cx).result();
+        let versions = match result {
+            FrontendMetadataResult::Loading => {
+                return h_flex().gap_2().child("Loading versions...").child(Spinner::new()).into_any_element();
+            },
+            FrontendMetadataResult::Loaded(versions) => versions.0.clone(),
+            FrontendMetadataResult::Error(error) => {
+                return ErrorAlert::new("versions_error", "Error requesting from Modrinth".into(), error).into_any_element();
+            },
+        };
+
+        if self.game_version_select_state.is_none() {
+            self.create_version_filters(&versions, window, cx);
+        }
+
+        let selected_game_version = self.game_version_select_state.as_ref()
+            .and_then(|state| state.read(cx).selected_value().cloned())
+            .filter(|version| version.as_str() != ALL_GAME_VERSIONS);
+        let selected_loader = self.loader_select_state.as_ref()
+            .and_then(|state| state.read(cx).selected_value().cloned())
+            .filter(|loader| loader.as_str() != ALL_LOADERS)
+            .map(|loader| ModrinthLoader::from_name(loader.as_str()));
+
+        let channel_group = ReleaseChannelGroup::new("channel", self.release_channel)
+            .small()
+            .on_select(cx.listener(|this, release_channel: &ReleaseChannel, _, cx| {
+                this.release_channel = *release_channel;
+                cx.notify();
+            }));
+
+        let filters = h_flex()
+            .gap_2()
+            .child(div().w_64().child(Select::new(self.game_version_select_state.as_ref().unwrap()).title_prefix("Game Version: ")))
+            .child(div().w_48().child(Select::new(self.loader_select_state.as_ref().unwrap()).title_prefix("Loader: ")))
+            .child(channel_group);
+
+        let theme = cx.theme();
+        let muted_foreground = theme.muted_foreground;
+        let warning = theme.warning;
+        let border = theme.border;
+
+        let mut list = v_flex().gap_2();
+        let mut shown = 0;
+
+        for (index, version) in versions.iter().enumerate() {
+            if version.files.is_empty() {
+                continue;
+            }
+            if let Some(status) = version.status
+                && !matches!(status, ModrinthVersionStatus::Listed | ModrinthVersionStatus::Archived)
+            {
+                continue;
+            }
+            if !self.release_channel.allows(version.version_type) {
+                continue;
+            }
+            if let Some(selected_game_version) = &selected_game_version
+                && !version.game_versions.iter().flat_map(|versions| versions.iter()).any(|v| v.as_str() == selected_game_version.as_str())
+            {
+                continue;
+            }
+            if let Some(selected_loader) = selected_loader
+                && !version.loaders.iter().flat_map(|loaders| loaders.iter()).any(|loader| *loader == selected_loader)
+            {
+                continue;
+            }
+            shown += 1;
+
+            let name = version.version_number.clone().or(version.name.clone()).unwrap_or(version.id.clone());
+            let version_type = match version.version_type {
+                Some(ModrinthVersionType::Beta) => Some("Beta"),
+                Some(ModrinthVersionType::Alpha) => Some("Alpha"),
+                _ => None,
+            };
+
+            let game_versions = version.game_versions.as_ref().map(|versions| {
+                match versions.len() {
+                    0 => String::new(),
+                    1 => versions[0].to_string(),
+                    count => format!("{} – {}", versions[0], versions[count - 1]),
+                }
+            }).unwrap_or_default();
+            let loaders = version.loaders.iter()
+                .flat_map(|loaders| loaders.iter())
+                .filter(|loader| **loader != ModrinthLoader::Unknown)
+                .map(|loader| loader.pretty_name())
+                .collect::<Vec<_>>()
+                .join(", ");
+            let published = version.date_published.as_ref().map(|date| date.get(..10).unwrap_or(date).to_string()).unwrap_or_default();
+
+            let version_id = version.id.clone();
+            let expanded = self.expanded_changelogs.contains(&version_id);
+
+            let row = h_flex()
+                .gap_3()
+                .child(v_flex()
+                    .flex_grow()
+                    .child(h_flex()
+                        .gap_2()
+                        .child(SharedString::new(name))
+                        .when_some(version_type, |row, version_type| {
+                            row.child(div().text_xs().text_color(warning).child(version_type))
+                        }))
+                    .child(div().text_sm().text_color(muted_foreground).child(format!("{} · {}", loaders, game_versions))))
+                .child(div().w_24().text_sm().text_color(muted_foreground).child(published))
+                .child(div().w_32().text_sm().text_color(muted_foreground).child(format_downloads(version.downloads.unwrap_or(0))))
+                .when(version.changelog.as_ref().is_some_and(|changelog| !changelog.is_empty()), |row| {
+                    let version_id = version_id.clone();
+                    row.child(Button::new(("changelog", index)).compact().small().label(if expanded { "Hide changelog" } else { "Changelog" })
+                        .on_click(cx.listener(move |this, _, _, cx| {
+                            if !this.expanded_changelogs.remove(&version_id) {
+                                this.expanded_changelogs.insert(version_id.clone());
+                            }
+                            cx.notify();
+                        })))
+                })
+                .child(Button::new(("install", index)).success().small().label("Install").on_click({
+                    let data = self.data.clone();
+                    let name = SharedString::new(project.title.clone());
+                    let project_id = self.project_id.clone();
+                    let project_type = project.project_type;
+                    let install_for = self.install_for;
+                    move |_, window, cx| {
+                        crate::modals::modrinth_install::open_with_version(name.as_str(), project_id.clone(), project_type,
+                            install_for, Some(version_id.clone()), &data, window, cx);
+                    }
+                }));
+
+            let entry = v_flex()
+                .gap_1()
+                .p_2()
+                .rounded_md()
+                .border_1()
+                .border_color(border)
+                .child(row)
+                .when(expanded, |entry| {
+                    entry.when_some(version.changelog.clone(), |entry, changelog| {
+                        entry.child(div()
+                            .p_2()
+                            .text_sm()
+                            .child(TextView::markdown(("changelog_text", index), SharedString::new(changelog))))
+                    })
+                });
+
+            list = list.child(entry);
+        }
+
+        v_flex()
+            .gap_3()
+            .child(filters)
+            .when(shown == 0, |content| content.child("No versions match the selected filters"))
+            .child(list)
+            .into_any_element()
+    }
+}
+
+impl Render for ModrinthProjectPage {
+    fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let breadcrumb = self.page_path.create_breadcrumb(&self.data, cx);
+
+        let project = match self.project.read(cx) {
+            FrontendMetadataState::Loaded { result: Ok(MetadataResult::ModrinthProject(project)), .. } => Arc::clone(project),
+            state => {
+                let content = match AsMetadataResult::<ModrinthProject>::result(state) {
+                    FrontendMetadataResult::Error(error) => {
+                        ErrorAlert::new("project_error", "Error requesting from Modrinth".into(), error).into_any_element()
+                    },
+                    _ => h_flex().gap_2().child("Loading project...").child(Spinner::new()).into_any_element(),
+                };
+                return ui::page(cx, breadcrumb).child(div().p_3().child(content));
+            },
+        };
+
+        let tab_group = ButtonGroup::new("tab")
+            .outline()
+            .child(Button::new("description").label("Description").selected(self.tab == ProjectTab::Description))
+            .child(Button::new("gallery").label(format!("Gallery ({})", project.gallery.len())).selected(self.tab == ProjectTab::Gallery))
+            .child(Button::new("versions").label("Versions").selected(self.tab == ProjectTab::Versions))
+            .on_click(cx.listener(|page, clicked: &Vec<usize>, _, cx| {
+                page.tab = match clicked[0] {
+                    0 => ProjectTab::Description,
+                    1 => ProjectTab::Gallery,
+                    _ => ProjectTab::Versions,
+                };
+                cx.notify();
+            }));
+
+        let tab_content = match self.tab {
+            ProjectTab::Description => TextView::markdown("body", SharedString::new(project.body.clone())).into_any_element(),
+            ProjectTab::Gallery => self.render_gallery(&project, cx),
+            ProjectTab::Versions => self.render_versions(&project, window, cx),
+        };
+
+        let main = v_flex()
+            .flex_grow()
+            .gap_3()
+            .child(self.render_header(&project, cx))
+            .child(tab_group)
+            .child(tab_content)
+            .overflow_y_scrollbar();
+
+        ui::page(cx, breadcrumb)
+            .child(h_flex().size_full().items_start().p_3().gap_6().child(main).child(self.render_sidebar(&project, cx)))
+    }
+}
@@ -1,14 +1,13 @@
-use std::{collections::HashMap, sync::{Arc, atomic::AtomicBool}};
+use std::sync::{Arc, atomic::AtomicBool};
 
 use bridge::{instance::InstanceStatus, message::{BridgeNotificationType, MessageToFrontend}};
-use gpui::{px, size, AnyWindowHandle, App, AppContext, Entity, SharedString, TitlebarOptions, WindowDecorations, WindowHandle, WindowOptions};
+use gpui::{px, size, AnyWindowHandle, App, AppContext, SharedString, TitlebarOptions, WindowDecorations, WindowOptions};
 use gpui_component::{notification::{Notification, NotificationType}, Root, WindowExt};
 
-use crate::{entity::{DataEntities, account::AccountEntries, instance::InstanceEntries, metadata::FrontendMetadata}, game_output::{GameOutput, GameOutputRoot}, interface_config::InterfaceConfig};
+use crate::{entity::{DataEntities, account::AccountEntries, connectivity::ConnectivityState, instance::InstanceEntries, metadata::FrontendMetadata}, game_output::{GameOutput, GameOutputRoot, GameOutputWindows}, interface_config::InterfaceConfig};
 
 pub struct Processor {
     data: DataEntities,
-    game_output_windows: HashMap<usize, (WindowHandle<Root>, Entity<GameOutput>)>,
     main_window_handle: Option<AnyWindowHandle>,
     main_window_hidden: Arc<AtomicBool>,
 }
@@ -17,7 +16,6 @@ impl Processor {
     pub fn new(data: DataEntities, main_window_handle: AnyWindowHandle, main_window_hidden: Arc<AtomicBool>) -> Self {
         Self {
             data,
-            game_output_windows: HashMap::new(),
             main_window_handle: Some(main_window_handle),
             main_window_hidden,
         }
@@ -31,6 +29,12 @@ impl Processor {
             } => {
                 AccountEntries::set(&self.data.accounts, accounts, selected_account, cx);
             },
+            MessageToFrontend::ConnectivityUpdated { offline, unavailable } => {
+                ConnectivityState::set(&self.data.connectivity, offline, unavailable, cx);
+            },
+            MessageToFrontend::ProxyUpdated { proxy } => {
+                cx.set_http_client(crate::create_http_client(proxy.as_ref()));
+            },
             MessageToFrontend::InstanceAdded {
                 id,
                 name,
@@ -63,6 +67,7 @@ impl Processor {
                 dot_minecraft_folder,
                 configuration,
                 status,
+                sessions,
             } => {
                 if status == InstanceStatus::Running {
                     if InterfaceConfig::get(cx).hide_main_window_on_launch {
@@ -87,6 +92,7 @@ impl Processor {
                     dot_minecraft_folder,
                     configuration,
                     status,
+                    sessions,
                     cx,
                 );
             },
@@ -120,6 +126,14 @@ impl Processor {
                     window.push_notification(notification, cx);
                 });
             },
+            MessageToFrontend::AddBackgroundTask { error_title, modal_action } => {
+                let Some(handle) = self.main_window_handle else {
+                    return;
+                };
+                _ = handle.update(cx, |_, window, cx| {
+                    crate::modals::generic::show_notification(window, cx, SharedString::from(error_title), modal_action);
+                });
+            },
             MessageToFrontend::Refresh => {
                 let Some(handle) = self.main_window_handle else {
                     return;
@@ -136,12 +150,17 @@ impl Processor {
                     window.close_all_dialogs(cx);
                 });
             },
-            MessageToFrontend::CreateGameOutputWindow { id, keep_alive } => {
+            MessageToFrontend::CreateGameOutputWindow { id, keep_alive, console } => {
+                let title = if console.is_some() {
+                    SharedString::new_static("Minecraft Server Console")
+                } else {
+                    SharedString::new_static("Minecraft Game Output")
+                };
                 let options = WindowOptions {
                     app_id: Some("PandoraLauncher".into()),
                     window_min_size: Some(size(px(360.0), px(240.0))),
                     titlebar: Some(TitlebarOptions {
-                        title: Some(SharedString::new_static("Minecraft Game Output")),
+                        title: Some(title),
                         ..Default::default()
                     }),
                     window_decorations: Some(WindowDecorations::Server),
@@ -150,10 +169,10 @@ impl Processor {
                 _ = cx.open_window(options, |window, cx| {
                     let game_output = cx.new(|_| GameOutput::default());
                     let game_output_root = cx
-                        .new(|cx| GameOutputRoot::new(keep_alive, game_output.clone(), window, cx));
+                        .new(|cx| GameOutputRoot::new(keep_alive, game_output.clone(), console, window, cx));
                     window.activate_window();
                     let window_handle = window.window_handle().downcast::<Root>().unwrap();
-                    self.game_output_windows.insert(id, (window_handle, game_output.clone()));
+                    cx.default_global::<GameOutputWindows>().windows.insert(id, (window_handle, game_output.clone()));
                     cx.new(|cx| Root::new(game_output_root, window, cx))
                 });
             },
@@ -163,7 +182,7 @@ impl Processor {
                 level,
                 text,
             } => {
-                if let Some((window, game_output)) = self.game_output_windows.get(&id) {
+                if let Some((window, game_output)) = GameOutputWindows::get(id, cx) {
                     _ = window.update(cx, |_, window, cx| {
                         game_output.update(cx, |game_output, _| {
                             game_output.add(time, level, text);
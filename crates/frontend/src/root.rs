@@ -1,15 +1,17 @@
-use std::{path::Path, sync::Arc};
+use std::{path::{Path, PathBuf}, sync::Arc};
 
 use bridge::{
     handle::BackendHandle,
     install::ContentInstall,
     instance::{InstanceID, InstanceContentID},
-    message::{MessageToBackend, QuickPlayLaunch},
+    message::{MessageToBackend, QuickPlayLaunch, ServerModsTarget},
     modal_action::ModalAction,
 };
 use gpui::{prelude::*, *};
 use gpui_component::{breadcrumb::Breadcrumb, scroll::{ScrollableElement, ScrollbarAxis}, v_flex, Root, StyledExt};
 use parking_lot::RwLock;
+use schema::{backend_config::SecretStorageBackend, instance::ReleaseChannel};
+use uuid::Uuid;
 
 use crate::{entity::DataEntities, modals, ui::{LauncherUI, PageType}, CloseWindow, MAIN_FONT};
 
@@ -115,10 +117,32 @@ pub fn start_new_account_login(
     modals::generic::show_modal(window, cx, title, "Error adding account".into(), modal_action);
 }
 
+pub fn start_yggdrasil_account_login(
+    backend_handle: &BackendHandle,
+    server_url: Arc<str>,
+    username: Arc<str>,
+    password: Arc<str>,
+    window: &mut Window,
+    cx: &mut App,
+) {
+    let modal_action = ModalAction::default();
+
+    backend_handle.send(MessageToBackend::AddYggdrasilAccount {
+        server_url,
+        username,
+        password,
+        modal_action: modal_action.clone(),
+    });
+
+    let title = SharedString::new_static("Adding authlib-injector account");
+    modals::generic::show_modal(window, cx, title, "Error adding account".into(), modal_action);
+}
+
 pub fn start_instance(
     id: InstanceID,
     name: SharedString,
     quick_play: Option<QuickPlayLaunch>,
+    account: Option<Uuid>,
     backend_handle: &BackendHandle,
     window: &mut Window,
     cx: &mut App,
@@ -128,6 +152,7 @@ pub fn start_instance(
     backend_handle.send(MessageToBackend::StartInstance {
         id,
         quick_play,
+        account,
         modal_action: modal_action.clone(),
     });
 
@@ -186,6 +211,120 @@ pub fn update_single_mod(
     modals::generic::show_notification(window, cx, "Error downloading update".into(), modal_action);
 }
 
+pub fn start_update_contents(
+    instance: InstanceID,
+    content_ids: Vec<InstanceContentID>,
+    release_channel: ReleaseChannel,
+    backend_handle: &BackendHandle,
+    window: &mut Window,
+    cx: &mut App,
+) {
+    let modal_action = ModalAction::default();
+
+    backend_handle.send(MessageToBackend::UpdateContents {
+        instance,
+        content_ids,
+        release_channel,
+        modal_action: modal_action.clone(),
+    });
+
+    let title: SharedString = "Updating content".into();
+    modals::generic::show_modal(window, cx, title, "Error updating content".into(), modal_action);
+}
+
+pub fn start_import_curseforge_modpack(
+    path: PathBuf,
+    target: Option<InstanceID>,
+    backend_handle: &BackendHandle,
+    window: &mut Window,
+    cx: &mut App,
+) {
+    let modal_action = ModalAction::default();
+    let (send, recv) = tokio::sync::oneshot::channel();
+
+    backend_handle.send(MessageToBackend::ImportCurseForgeModpack {
+        path,
+        target,
+        modal_action: modal_action.clone(),
+        manual_downloads: send,
+    });
+
+    modals::generic::show_notification(window, cx, "Error importing modpack".into(), modal_action);
+
+    window.spawn(cx, async move |cx| {
+        let Ok(manual_downloads) = recv.await else {
+            return;
+        };
+        if manual_downloads.is_empty() {
+            return;
+        }
+        _ = cx.update(|window, cx| {
+            modals::manual_downloads::open_manual_downloads(manual_downloads, window, cx);
+        });
+    }).detach();
+}
+
+pub fn start_install_modrinth_projects(
+    instance: InstanceID,
+    project_ids: Arc<[Arc<str>]>,
+    backend_handle: &BackendHandle,
+    window: &mut Window,
+    cx: &mut App,
+) {
+    let modal_action = ModalAction::default();
+    let (send, recv) = tokio::sync::oneshot::channel();
+
+    backend_handle.send(MessageToBackend::InstallModrinthProjects {
+        instance,
+        project_ids,
+        modal_action: modal_action.clone(),
+        skipped: send,
+    });
+
+    modals::generic::show_notification(window, cx, "Error installing projects".into(), modal_action);
+
+    window.spawn(cx, async move |cx| {
+        let Ok(skipped) = recv.await else {
+            return;
+        };
+        if skipped.is_empty() {
+            return;
+        }
+        _ = cx.update(|window, cx| {
+            modals::modrinth_collection::open_skipped_projects(skipped, window, cx);
+        });
+    }).detach();
+}
+
+pub fn start_sync_server_mods(
+    client: InstanceID,
+    target: ServerModsTarget,
+    backend_handle: &BackendHandle,
+    window: &mut Window,
+    cx: &mut App,
+) {
+    let modal_action = ModalAction::default();
+    let (send, recv) = tokio::sync::oneshot::channel();
+
+    backend_handle.send(MessageToBackend::SyncServerMods {
+        client,
+        target,
+        modal_action: modal_action.clone(),
+        result: send,
+    });
+
+    modals::generic::show_notification(window, cx, "Error syncing mods to server".into(), modal_action);
+
+    window.spawn(cx, async move |cx| {
+        let Ok(result) = recv.await else {
+            return;
+        };
+        _ = cx.update(|window, cx| {
+            modals::sync_server_mods::open_sync_result(result, window, cx);
+        });
+    }).detach();
+}
+
 pub fn upload_log_file(
     path: Arc<Path>,
     backend_handle: &BackendHandle,
@@ -203,6 +342,74 @@ pub fn upload_log_file(
     modals::generic::show_modal(window, cx, title, "Error uploading log file".into(), modal_action);
 }
 
+pub fn start_cleanup_unused_files(
+    paths: Arc<[Arc<Path>]>,
+    backend_handle: &BackendHandle,
+    window: &mut Window,
+    cx: &mut App,
+) {
+    let modal_action = ModalAction::default();
+
+    backend_handle.send(MessageToBackend::CleanupUnusedFiles {
+        paths,
+        modal_action: modal_action.clone(),
+    });
+
+    let title: SharedString = "Cleaning up unused files".into();
+    modals::generic::show_modal(window, cx, title, "Error cleaning up unused files".into(), modal_action);
+}
+
+pub fn start_set_secret_storage_backend(
+    backend_handle: &BackendHandle,
+    backend: SecretStorageBackend,
+    window: &mut Window,
+    cx: &mut App,
+) {
+    let modal_action = ModalAction::default();
+
+    backend_handle.send(MessageToBackend::SetSecretStorageBackend {
+        backend,
+        modal_action: modal_action.clone(),
+    });
+
+    let title: SharedString = "Moving credentials".into();
+    modals::generic::show_modal(window, cx, title, "Error moving credentials".into(), modal_action);
+}
+
+pub fn start_verify_instance(
+    id: InstanceID,
+    backend_handle: &BackendHandle,
+    window: &mut Window,
+    cx: &mut App,
+) {
+    let modal_action = ModalAction::default();
+
+    backend_handle.send(MessageToBackend::VerifyInstance {
+        id,
+        modal_action: modal_action.clone(),
+    });
+
+    let title: SharedString = "Verifying instance".into();
+    modals::generic::show_modal(window, cx, title, "Error verifying instance".into(), modal_action);
+}
+
+pub fn start_prepare_instance(
+    id: InstanceID,
+    backend_handle: &BackendHandle,
+    window: &mut Window,
+    cx: &mut App,
+) {
+    let modal_action = ModalAction::default();
+
+    backend_handle.send(MessageToBackend::PrepareInstance {
+        id,
+        modal_action: modal_action.clone(),
+    });
+
+    let title: SharedString = "Preparing instance".into();
+    modals::generic::show_modal(window, cx, title, "Error preparing instance".into(), modal_action);
+}
+
 pub fn switch_page(
     page: PageType,
     breadcrumbs: &[PageType],
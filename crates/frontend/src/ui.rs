@@ -12,9 +12,9 @@ use gpui_component::{
     sidebar::{Sidebar, SidebarFooter, SidebarGroup, SidebarMenu, SidebarMenuItem},
     v_flex,
 };
-use rand::Rng;
 use schema::modrinth::ModrinthProjectType;
 use serde::{Deserialize, Serialize};
+use ustr::Ustr;
 use uuid::Uuid;
 
 use crate::{
@@ -31,6 +31,7 @@ use crate::{
         instance::instance_page::{InstancePage, InstanceSubpageType},
         instances_page::InstancesPage,
         modrinth_page::ModrinthSearchPage,
+        modrinth_project_page::ModrinthProjectPage,
         syncing_page::SyncingPage,
     },
     png_render_cache, root,
@@ -45,6 +46,7 @@ pub struct LauncherUI {
     _instance_modified_subscription: Subscription,
     _instance_removed_subscription: Subscription,
     _instance_moved_to_top_subscription: Subscription,
+    _connectivity_subscription: Subscription,
 }
 
 #[derive(Copy, Clone, PartialEq, Eq)]
@@ -55,6 +57,10 @@ pub enum PageType {
         installing_for: Option<InstanceID>,
         project_type: Option<ModrinthProjectType>,
     },
+    ModrinthProject {
+        project: Ustr,
+        installing_for: Option<InstanceID>,
+    },
     InstancePage(InstanceID, InstanceSubpageType),
 }
 
@@ -63,7 +69,7 @@ impl PageType {
         match self {
             PageType::Instances => SerializedPageType::Instances,
             PageType::Syncing => SerializedPageType::Syncing,
-            PageType::Modrinth { installing_for, .. } => {
+            PageType::Modrinth { installing_for, .. } | PageType::ModrinthProject { installing_for, .. } => {
                 if let Some(installing_for) = installing_for {
                     if let Some(name) = InstanceEntries::find_name_by_id(&data.instances, *installing_for, cx) {
                         return SerializedPageType::Modrinth {
@@ -132,6 +138,11 @@ pub enum LauncherPage {
         installing_for: Option<InstanceID>,
         page: Entity<ModrinthSearchPage>,
     },
+    ModrinthProject {
+        project: Ustr,
+        installing_for: Option<InstanceID>,
+        page: Entity<ModrinthProjectPage>,
+    },
     InstancePage(InstanceID, InstanceSubpageType, Entity<InstancePage>),
 }
 
@@ -141,6 +152,7 @@ impl LauncherPage {
             LauncherPage::Instances(entity) => entity.into_any_element(),
             LauncherPage::Syncing(entity) => entity.into_any_element(),
             LauncherPage::Modrinth { page, .. } => page.into_any_element(),
+            LauncherPage::ModrinthProject { page, .. } => page.into_any_element(),
             LauncherPage::InstancePage(_, _, entity) => entity.into_any_element(),
         }
     }
@@ -153,6 +165,10 @@ impl LauncherPage {
                 installing_for: *installing_for,
                 project_type: None,
             },
+            LauncherPage::ModrinthProject { project, installing_for, .. } => PageType::ModrinthProject {
+                project: *project,
+                installing_for: *installing_for,
+            },
             LauncherPage::InstancePage(id, subpage, _) => PageType::InstancePage(*id, *subpage),
         }
     }
@@ -207,6 +223,8 @@ impl LauncherUI {
                 cx.notify();
             });
 
+        let _connectivity_subscription = cx.observe(&data.connectivity, |_, _, cx| cx.notify());
+
         let config = InterfaceConfig::get(cx);
         let page_type = PageType::from_serialized(&config.main_page, data, cx);
         let page_path: Vec<PageType> =
@@ -221,6 +239,7 @@ impl LauncherUI {
             _instance_modified_subscription,
             _instance_removed_subscription,
             _instance_moved_to_top_subscription,
+            _connectivity_subscription,
         }
     }
 
@@ -242,6 +261,10 @@ impl LauncherUI {
                 let page = cx.new(|cx| ModrinthSearchPage::new(installing_for, project_type, path, data, window, cx));
                 LauncherPage::Modrinth { installing_for, page }
             },
+            PageType::ModrinthProject { project, installing_for } => {
+                let page = cx.new(|cx| ModrinthProjectPage::new(project, installing_for, path, data, window, cx));
+                LauncherPage::ModrinthProject { project, installing_for, page }
+            },
             PageType::InstancePage(id, subpage) => LauncherPage::InstancePage(
                 id,
                 subpage,
@@ -344,6 +367,7 @@ impl Render for LauncherUI {
         }
 
         let accounts = self.data.accounts.read(cx);
+        let needs_relogin = accounts.selected_account.as_ref().is_some_and(|account| account.needs_relogin);
         let (account_head, account_name) = if let Some(account) = &accounts.selected_account {
             let account_name = SharedString::new(account.username.clone());
             let head = if let Some(head) = &account.head {
@@ -371,7 +395,8 @@ impl Render for LauncherUI {
                     .justify_center()
                     .text_size(rems(0.9375))
                     .child(account_head.size_8().min_w_8().min_h_8())
-                    .child(account_name),
+                    .child(account_name)
+                    .when(needs_relogin, |this| this.child(Icon::new(IconName::Info).text_color(cx.theme().warning))),
             )
             .on_click({
                 let accounts = self.data.accounts.clone();
@@ -413,6 +438,9 @@ impl Render for LauncherUI {
                                         .h_10()
                                         .child(head.size_8().min_w_8().min_h_8())
                                         .child(account_name.clone())
+                                        .when(account.needs_relogin, |this| {
+                                            this.child(div().text_sm().text_color(cx.theme().warning).child("Log in again"))
+                                        })
                                         .when(!selected, |this| {
                                             this.on_click({
                                                 let backend_handle = backend_handle.clone();
@@ -466,7 +494,7 @@ impl Render for LauncherUI {
                                             move |_, window, cx| {
                                                 let name_input = cx.new(|cx| InputState::new(window, cx));
                                                 let uuid_input =
-                                                    cx.new(|cx| InputState::new(window, cx).placeholder("Random"));
+                                                    cx.new(|cx| InputState::new(window, cx).placeholder("Derived from the name"));
                                                 let backend_handle = backend_handle.clone();
                                                 window.open_dialog(cx, move |dialog, _, cx| {
                                                     let username = name_input.read(cx).value();
@@ -485,18 +513,9 @@ impl Render for LauncherUI {
                                                         .on_click(move |_, window, cx| {
                                                             window.close_all_dialogs(cx);
 
-                                                            let uuid = if let Ok(uuid) = Uuid::try_parse(&uuid) {
-                                                                uuid
-                                                            } else {
-                                                                let uuid: u128 = rand::thread_rng().r#gen();
-                                                                let uuid = (uuid & !0xF0000000000000000000)
-                                                                    | 0x30000000000000000000; // set version to 3
-                                                                Uuid::from_u128(uuid)
-                                                            };
-
                                                             backend_handle.send(MessageToBackend::AddOfflineAccount {
                                                                 name: username.clone().into(),
-                                                                uuid,
+                                                                uuid: Uuid::try_parse(&uuid).ok(),
                                                             });
                                                         });
 
@@ -515,6 +534,71 @@ impl Render for LauncherUI {
                                             }
                                         }),
                                 )
+                                .child(
+                                    Button::new("add-yggdrasil")
+                                        .h_10()
+                                        .success()
+                                        .icon(IconName::Plus)
+                                        .label("Add authlib-injector account")
+                                        .on_click({
+                                            let backend_handle = backend_handle.clone();
+                                            move |_, window, cx| {
+                                                let server_input = cx.new(|cx| InputState::new(window, cx).placeholder("https://example.com/api/yggdrasil"));
+                                                let username_input = cx.new(|cx| InputState::new(window, cx));
+                                                let password_input = cx.new(|cx| InputState::new(window, cx).masked(true));
+                                                let backend_handle = backend_handle.clone();
+                                                window.open_dialog(cx, move |dialog, _, cx| {
+                                                    let server_url = server_input.read(cx).value();
+                                                    let username = username_input.read(cx).value();
+                                                    let password = password_input.read(cx).value();
+
+                                                    let valid = !server_url.trim().is_empty() && !username.trim().is_empty() && !password.is_empty();
+
+                                                    let backend_handle = backend_handle.clone();
+                                                    let mut add_button = Button::new("add")
+                                                        .label("Log in")
+                                                        .disabled(!valid)
+                                                        .on_click(move |_, window, cx| {
+                                                            window.close_all_dialogs(cx);
+
+                                                            crate::root::start_yggdrasil_account_login(
+                                                                &backend_handle,
+                                                                server_url.trim().into(),
+                                                                username.trim().into(),
+                                                                password.clone().into(),
+                                                                window,
+                                                                cx,
+                                                            );
+                                                        });
+
+                                                    if valid {
+                                                        add_button = add_button.success();
+                                                    }
+
+                                                    dialog.title("Add authlib-injector account").child(
+                                                        v_flex()
+                                                            .gap_2()
+                                                            .child(crate::labelled("Auth server", Input::new(&server_input)))
+                                                            .child(crate::labelled("Username or email", Input::new(&username_input)))
+                                                            .child(crate::labelled("Password", Input::new(&password_input)))
+                                                            .child(add_button),
+                                                    )
+                                                });
+                                            }
+                                        }),
+                                )
+                                .child(
+                                    Button::new("manage-skins")
+                                        .h_10()
+                                        .icon(IconName::Palette)
+                                        .label("Manage skins")
+                                        .on_click({
+                                            let backend_handle = backend_handle.clone();
+                                            move |_, window, cx| {
+                                                crate::modals::skins::open(&backend_handle, window, cx);
+                                            }
+                                        }),
+                                )
                                 .children(items),
                         )
                     });
@@ -536,6 +620,26 @@ impl Render for LauncherUI {
                 }
             });
 
+        let offline_indicator = self.data.connectivity.read(cx).offline.then(|| {
+            div()
+                .id("offline-indicator")
+                .flex()
+                .items_center()
+                .gap_1()
+                .p_2()
+                .rounded(cx.theme().radius)
+                .text_color(cx.theme().warning)
+                .hover(|this| this.bg(cx.theme().sidebar_accent))
+                .child(IconName::CircleX)
+                .child("Offline")
+                .on_click({
+                    let data = self.data.clone();
+                    move |_, window, cx| {
+                        modals::offline::open_offline_status(&data, window, cx);
+                    }
+                })
+        });
+
         let sidebar = Sidebar::left()
             .w(relative(1.))
             .border_0()
@@ -549,7 +653,7 @@ impl Render for LauncherUI {
                     .child(pandora_icon.size_8().min_w_8().min_h_8())
                     .child("Pandora"),
             )
-            .footer(h_flex().flex_wrap().justify_center().w_full().child(settings_button).child(footer))
+            .footer(h_flex().flex_wrap().justify_center().w_full().children(offline_indicator).child(settings_button).child(footer))
             .children(groups);
 
         h_resizable("container")
@@ -7,7 +7,9 @@ use std::sync::Arc;
 use std::fmt::Write;
 use std::time::SystemTime;
 
-use bridge::message::MessageToFrontend;
+use bridge::account::Account;
+use bridge::instance::InstanceID;
+use bridge::message::{MessageToBackend, MessageToFrontend};
 use bridge::modal_action::{ModalAction, ProgressTrackerFinishType};
 use clap::Parser;
 use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
@@ -20,6 +22,15 @@ struct Args {
     /// Instance to launch, instead of opening the launcher
     #[arg(long)]
     run_instance: Option<String>,
+    /// Account to launch the instance with, either a username or a UUID
+    #[arg(long, requires = "run_instance")]
+    account: Option<String>,
+    /// Instance to verify and repair the files of, instead of opening the launcher
+    #[arg(long, conflicts_with = "run_instance")]
+    verify_instance: Option<String>,
+    /// Instance to download and install everything needed to launch, without starting the game
+    #[arg(long, conflicts_with_all = ["run_instance", "verify_instance"])]
+    prepare_instance: Option<String>,
 }
 
 pub mod panic;
@@ -49,34 +60,70 @@ fn main() {
     panic::install_logging_hook();
 
     if let Some(run_instance) = args.run_instance {
-        let (backend_recv, backend_handle, mut frontend_recv, frontend_handle) = bridge::handle::create_pair();
-
-        backend::start(launcher_dir.clone(), frontend_handle, backend_handle.clone(), backend_recv);
-
-        while let Some(message) = frontend_recv.try_recv() {
-            if let MessageToFrontend::InstanceAdded { id, name, .. } = message {
-                if name.as_str() == run_instance.as_str() {
-                    println!("Starting instance {}", run_instance);
-                    let modal_action = ModalAction::default();
-                    backend_handle.send(bridge::message::MessageToBackend::StartInstance {
-                        id,
-                        quick_play: None,
-                        modal_action: modal_action.clone()
-                    });
-                    run_modal_action(modal_action);
-                    std::thread::sleep(std::time::Duration::from_millis(100));
-                    return;
-                }
+        run_headless(launcher_dir, &run_instance, |id, accounts, modal_action| {
+            let account = args.account.map(|account| {
+                let found = accounts.iter().find(|candidate| {
+                    &*candidate.username == account.as_str() || candidate.uuid.to_string().eq_ignore_ascii_case(&account)
+                });
+                let Some(found) = found else {
+                    show_error(format!("Unable to find account {}", account));
+                    std::process::exit(1);
+                };
+                found.uuid
+            });
+            println!("Starting instance {}", run_instance);
+            MessageToBackend::StartInstance {
+                id,
+                quick_play: None,
+                account,
+                modal_action,
             }
-        }
-
-        show_error(format!("Unable to find instance {}", run_instance));
-        std::process::exit(1);
+        });
+    } else if let Some(verify_instance) = args.verify_instance {
+        run_headless(launcher_dir, &verify_instance, |id, _, modal_action| {
+            println!("Verifying instance {}", verify_instance);
+            MessageToBackend::VerifyInstance {
+                id,
+                modal_action,
+            }
+        });
+    } else if let Some(prepare_instance) = args.prepare_instance {
+        run_headless(launcher_dir, &prepare_instance, |id, _, modal_action| {
+            println!("Preparing instance {}", prepare_instance);
+            MessageToBackend::PrepareInstance {
+                id,
+                modal_action,
+            }
+        });
     } else {
         run_gui(launcher_dir);
     }
 }
 
+fn run_headless(launcher_dir: PathBuf, instance_name: &str, create_message: impl FnOnce(InstanceID, &[Account], ModalAction) -> MessageToBackend) {
+    let (backend_recv, backend_handle, mut frontend_recv, frontend_handle) = bridge::handle::create_pair();
+
+    backend::start(launcher_dir, frontend_handle, backend_handle.clone(), backend_recv);
+
+    let mut accounts: Arc<[Account]> = Arc::from([]);
+    while let Some(message) = frontend_recv.try_recv() {
+        if let MessageToFrontend::AccountsUpdated { accounts: updated, .. } = message {
+            accounts = updated;
+        } else if let MessageToFrontend::InstanceAdded { id, name, .. } = message {
+            if name.as_str() == instance_name {
+                let modal_action = ModalAction::default();
+                backend_handle.send(create_message(id, &accounts, modal_action.clone()));
+                run_modal_action(modal_action);
+                std::thread::sleep(std::time::Duration::from_millis(100));
+                return;
+            }
+        }
+    }
+
+    show_error(format!("Unable to find instance {}", instance_name));
+    std::process::exit(1);
+}
+
 fn show_error(error: String) {
     log::error!("{}", error);
     _ = DialogBuilder::message()
@@ -116,6 +163,7 @@ fn run_modal_action(modal_action: ModalAction) {
 
         if let Some(visit_url) = &*modal_action.visit_url.write().unwrap() {
             if opened.insert(visit_url.url.clone()) {
+                _ = m.println(&*visit_url.message);
                 _ = m.println(format!("Open this URL in your browser to continue: {}", visit_url.url));
                 let open = DialogBuilder::message()
                     .set_title("Open URL")
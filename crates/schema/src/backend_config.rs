@@ -1,3 +1,5 @@
+use std::{collections::BTreeMap, path::Path, sync::Arc};
+
 use enumset::{EnumSet, EnumSetType};
 use serde::{Deserialize, Serialize};
 
@@ -6,6 +8,126 @@ pub struct BackendConfig {
     pub sync_targets: EnumSet<SyncTarget>,
     #[serde(default = "default_true", skip_serializing_if = "skip_if_true")]
     pub open_game_output_when_launching: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub curseforge_api_url: Option<Arc<str>>,
+    #[serde(default, skip_serializing_if = "EndpointConfig::is_empty")]
+    pub endpoints: EndpointConfig,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<ProxyConfig>,
+    /// PEM files containing additional root certificates to trust, e.g. for networks that intercept TLS
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extra_root_certificates: Vec<Arc<Path>>,
+    /// Maximum number of files downloaded at once, lowering it lets downloads that already started finish first
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_concurrent_downloads: Option<usize>,
+    /// Combined download speed limit in KiB/s
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub download_speed_limit_kib: Option<u32>,
+    /// Log in to Microsoft accounts by entering a code on another device, instead of through a local redirect server
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub device_code_login: bool,
+    /// Where account credentials and other secrets are stored
+    #[serde(default, deserialize_with = "crate::try_deserialize", skip_serializing_if = "SecretStorageBackend::is_auto")]
+    pub secret_storage: SecretStorageBackend,
+    /// Key for the encrypted secret file instead of a passphrase, should be kept outside the launcher directory, e.g. a mounted secret
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secret_key_file: Option<Arc<Path>>,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SecretStorageBackend {
+    /// Use the platform keyring, falling back to the encrypted file when no keyring is available
+    #[default]
+    Auto,
+    Keyring,
+    /// File in the launcher directory, encrypted with a passphrase or the configured key file
+    EncryptedFile,
+}
+
+impl SecretStorageBackend {
+    pub fn is_auto(&self) -> bool {
+        *self == Self::Auto
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ProxyConfig {
+    /// Proxy url including the scheme, one of http://, https://, socks5:// or socks5h://
+    pub url: Arc<str>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub username: Option<Arc<str>>,
+    /// Comma-separated hosts that bypass the proxy, in the same format as the NO_PROXY variable
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub no_proxy: Option<Arc<str>>,
+}
+
+/// Replacement base URLs for remote services, e.g. an internal caching proxy or a mirror
+/// such as BMCLAPI. Requests for urls of the origin are sent to the replacement first and fall back to the origin,
+/// urls the launcher builds itself point at the replacement directly
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(transparent)]
+pub struct EndpointConfig(pub BTreeMap<EndpointService, Arc<str>>);
+
+impl EndpointConfig {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Url of a file of the service, on the configured replacement if there is one
+    pub fn url(&self, service: EndpointService, path: &str) -> String {
+        match self.0.get(&service) {
+            Some(base) => format!("{}/{}", base.trim_end_matches('/'), path),
+            None => format!("{}{}", service.origins()[0], path),
+        }
+    }
+
+    /// Returns the mirrored url if it belongs to a service with a configured replacement
+    pub fn rewrite(&self, url: &str) -> Option<String> {
+        for (service, base) in &self.0 {
+            for origin in service.origins() {
+                if let Some(rest) = url.strip_prefix(origin) {
+                    return Some(format!("{}/{}", base.trim_end_matches('/'), rest));
+                }
+            }
+        }
+        None
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, strum::EnumIter)]
+#[serde(rename_all = "snake_case")]
+pub enum EndpointService {
+    MojangMeta,
+    MojangData,
+    Libraries,
+    Assets,
+    FabricMeta,
+    FabricMaven,
+    ForgeMaven,
+    NeoforgeMaven,
+    ModrinthApi,
+    ModrinthCdn,
+    AuthlibInjector,
+}
+
+impl EndpointService {
+    /// Base urls of the service, the first one is used when building urls
+    pub fn origins(self) -> &'static [&'static str] {
+        match self {
+            EndpointService::MojangMeta => &["https://piston-meta.mojang.com/", "https://launchermeta.mojang.com/"],
+            EndpointService::MojangData => &["https://piston-data.mojang.com/"],
+            EndpointService::Libraries => &["https://libraries.minecraft.net/"],
+            EndpointService::Assets => &["https://resources.download.minecraft.net/"],
+            EndpointService::FabricMeta => &["https://meta.fabricmc.net/"],
+            EndpointService::FabricMaven => &["https://maven.fabricmc.net/"],
+            EndpointService::ForgeMaven => &["https://maven.minecraftforge.net/", "https://files.minecraftforge.net/maven/"],
+            EndpointService::NeoforgeMaven => &["https://maven.neoforged.net/releases/"],
+            EndpointService::ModrinthApi => &["https://api.modrinth.com/"],
+            EndpointService::ModrinthCdn => &["https://cdn.modrinth.com/"],
+            EndpointService::AuthlibInjector => &["https://authlib-injector.yushi.moe/"],
+        }
+    }
 }
 
 #[derive(Debug, enum_map::Enum, EnumSetType, strum::EnumIter)]
@@ -56,3 +178,92 @@ fn default_true() -> bool {
 fn skip_if_true(value: &bool) -> bool {
     *value
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rewrite_replaces_origin() {
+        let mut config = EndpointConfig::default();
+        config.0.insert(EndpointService::Libraries, "https://mirror.example.com/maven/".into());
+
+        assert_eq!(
+            config.rewrite("https://libraries.minecraft.net/com/mojang/brigadier/1.0.18/brigadier-1.0.18.jar").as_deref(),
+            Some("https://mirror.example.com/maven/com/mojang/brigadier/1.0.18/brigadier-1.0.18.jar")
+        );
+    }
+
+    #[test]
+    fn test_rewrite_modrinth_api_and_cdn_separately() {
+        let mut config = EndpointConfig::default();
+        config.0.insert(EndpointService::ModrinthApi, "https://mirror.example.com/modrinth".into());
+
+        assert_eq!(
+            config.rewrite("https://api.modrinth.com/v2/project/sodium").as_deref(),
+            Some("https://mirror.example.com/modrinth/v2/project/sodium")
+        );
+        assert_eq!(config.rewrite("https://cdn.modrinth.com/data/AANobbMI/versions/abc/sodium.jar"), None);
+
+        config.0.insert(EndpointService::ModrinthCdn, "https://cdn.example.com/".into());
+        assert_eq!(
+            config.rewrite("https://cdn.modrinth.com/data/AANobbMI/versions/abc/sodium.jar").as_deref(),
+            Some("https://cdn.example.com/data/AANobbMI/versions/abc/sodium.jar")
+        );
+    }
+
+    #[test]
+    fn test_rewrite_mojang_meta_and_data_separately() {
+        let mut config = EndpointConfig::default();
+        config.0.insert(EndpointService::MojangMeta, "https://mirror.example.com/meta".into());
+
+        assert_eq!(
+            config.rewrite("https://piston-meta.mojang.com/mc/game/version_manifest_v2.json").as_deref(),
+            Some("https://mirror.example.com/meta/mc/game/version_manifest_v2.json")
+        );
+        assert_eq!(config.rewrite("https://piston-data.mojang.com/v1/objects/abc/client.jar"), None);
+
+        config.0.insert(EndpointService::MojangData, "https://mirror.example.com/data".into());
+        assert_eq!(
+            config.rewrite("https://piston-data.mojang.com/v1/objects/abc/client.jar").as_deref(),
+            Some("https://mirror.example.com/data/v1/objects/abc/client.jar")
+        );
+    }
+
+    #[test]
+    fn test_url_uses_configured_base() {
+        let mut config = EndpointConfig::default();
+        let path = "net/fabricmc/fabric-loader/0.16.10/fabric-loader-0.16.10.jar";
+        assert_eq!(config.url(EndpointService::FabricMaven, path), format!("https://maven.fabricmc.net/{path}"));
+
+        config.0.insert(EndpointService::FabricMaven, "https://mirror.example.com/fabric/".into());
+        assert_eq!(config.url(EndpointService::FabricMaven, path), format!("https://mirror.example.com/fabric/{path}"));
+        assert_eq!(
+            config.url(EndpointService::NeoforgeMaven, "net/neoforged/neoforge/21.1.1/neoforge-21.1.1-installer.jar"),
+            "https://maven.neoforged.net/releases/net/neoforged/neoforge/21.1.1/neoforge-21.1.1-installer.jar"
+        );
+    }
+
+    #[test]
+    fn test_rewrite_authlib_injector() {
+        let mut config = EndpointConfig::default();
+        config.0.insert(EndpointService::AuthlibInjector, "https://mirror.example.com/authlib-injector".into());
+
+        assert_eq!(
+            config.rewrite("https://authlib-injector.yushi.moe/artifact/latest.json").as_deref(),
+            Some("https://mirror.example.com/authlib-injector/artifact/latest.json")
+        );
+    }
+
+    #[test]
+    fn test_rewrite_ignores_unconfigured_services() {
+        let mut config = EndpointConfig::default();
+        config.0.insert(EndpointService::Assets, "https://mirror.example.com/assets".into());
+
+        assert_eq!(config.rewrite("https://libraries.minecraft.net/a/b.jar"), None);
+        assert_eq!(
+            config.rewrite("https://resources.download.minecraft.net/ab/abcdef").as_deref(),
+            Some("https://mirror.example.com/assets/ab/abcdef")
+        );
+    }
+}
@@ -2,6 +2,8 @@ use std::sync::Arc;
 
 use serde::{Deserialize, Serialize};
 
+use crate::loader::Loader;
+
 #[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum ContentSource {
@@ -10,5 +12,59 @@ pub enum ContentSource {
     ModrinthUnknown,
     ModrinthProject {
         project: Arc<str>
+    },
+    CurseForgeProject {
+        project: u32
+    },
+}
+
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ContentProviderKind {
+    Modrinth,
+    CurseForge,
+}
+
+impl ContentProviderKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            ContentProviderKind::Modrinth => "Modrinth",
+            ContentProviderKind::CurseForge => "CurseForge",
+        }
     }
 }
+
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ContentSearchType {
+    Mod,
+    Modpack,
+    ResourcePack,
+    Shader,
+}
+
+/// Search that works the same regardless of which provider handles it
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ContentSearchRequest {
+    pub provider: ContentProviderKind,
+    pub query: Option<Arc<str>>,
+    pub content_type: ContentSearchType,
+    pub game_version: Option<Arc<str>>,
+    pub loader: Option<Loader>,
+    pub offset: usize,
+    pub limit: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct ContentSearchResult {
+    pub hits: Arc<[ContentSearchHit]>,
+    pub total_hits: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct ContentSearchHit {
+    pub project_id: Arc<str>,
+    pub name: Arc<str>,
+    pub summary: Option<Arc<str>>,
+    pub author: Option<Arc<str>>,
+    pub downloads: u64,
+    pub icon_url: Option<Arc<str>>,
+}
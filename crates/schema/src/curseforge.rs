@@ -0,0 +1,382 @@
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use ustr::Ustr;
+
+use crate::{loader::Loader, modrinth::ModrinthVersionType};
+
+pub const CURSEFORGE_API_URL: &str = "https://api.curseforge.com";
+pub const CURSEFORGE_MINECRAFT_GAME_ID: u32 = 432;
+
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CurseForgeSearchRequest {
+    pub class_id: Option<CurseForgeClass>,
+    pub search_filter: Option<Arc<str>>,
+    pub game_version: Option<Arc<str>>,
+    pub mod_loader_type: Option<CurseForgeModLoaderType>,
+    pub sort_field: CurseForgeSortField,
+    pub index: usize,
+    pub page_size: usize,
+}
+
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CurseForgeModFilesRequest {
+    #[serde(skip)]
+    pub mod_id: u32,
+    pub game_version: Option<Arc<str>>,
+    pub mod_loader_type: Option<CurseForgeModLoaderType>,
+}
+
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(into = "u32")]
+pub enum CurseForgeClass {
+    Mod,
+    Modpack,
+    Resourcepack,
+    Shader,
+}
+
+impl CurseForgeClass {
+    pub fn from_id(id: u32) -> Option<Self> {
+        match id {
+            6 => Some(Self::Mod),
+            4471 => Some(Self::Modpack),
+            12 => Some(Self::Resourcepack),
+            6552 => Some(Self::Shader),
+            _ => None,
+        }
+    }
+
+    pub fn install_directory(self) -> &'static str {
+        match self {
+            CurseForgeClass::Mod | CurseForgeClass::Modpack => "mods",
+            CurseForgeClass::Resourcepack => "resourcepacks",
+            CurseForgeClass::Shader => "shaderpacks",
+        }
+    }
+}
+
+impl From<CurseForgeClass> for u32 {
+    fn from(value: CurseForgeClass) -> Self {
+        match value {
+            CurseForgeClass::Mod => 6,
+            CurseForgeClass::Modpack => 4471,
+            CurseForgeClass::Resourcepack => 12,
+            CurseForgeClass::Shader => 6552,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(into = "u8")]
+pub enum CurseForgeModLoaderType {
+    Forge,
+    Fabric,
+    Quilt,
+    NeoForge,
+}
+
+impl CurseForgeModLoaderType {
+    pub fn from_loader(loader: Loader) -> Option<Self> {
+        match loader {
+            Loader::Fabric => Some(Self::Fabric),
+            Loader::Forge => Some(Self::Forge),
+            Loader::NeoForge => Some(Self::NeoForge),
+            _ => None,
+        }
+    }
+}
+
+impl From<CurseForgeModLoaderType> for u8 {
+    fn from(value: CurseForgeModLoaderType) -> Self {
+        match value {
+            CurseForgeModLoaderType::Forge => 1,
+            CurseForgeModLoaderType::Fabric => 4,
+            CurseForgeModLoaderType::Quilt => 5,
+            CurseForgeModLoaderType::NeoForge => 6,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(into = "u8")]
+pub enum CurseForgeSortField {
+    Featured,
+    Popularity,
+    LastUpdated,
+    Name,
+    TotalDownloads,
+}
+
+impl From<CurseForgeSortField> for u8 {
+    fn from(value: CurseForgeSortField) -> Self {
+        match value {
+            CurseForgeSortField::Featured => 1,
+            CurseForgeSortField::Popularity => 2,
+            CurseForgeSortField::LastUpdated => 3,
+            CurseForgeSortField::Name => 4,
+            CurseForgeSortField::TotalDownloads => 6,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CurseForgeResponse<T> {
+    pub data: T,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CurseForgePagination {
+    pub index: usize,
+    pub page_size: usize,
+    pub result_count: usize,
+    pub total_count: usize,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CurseForgeSearchResult {
+    pub data: Arc<[CurseForgeMod]>,
+    pub pagination: CurseForgePagination,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CurseForgeModFilesResult {
+    pub data: Arc<[CurseForgeFile]>,
+    pub pagination: CurseForgePagination,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CurseForgeMod {
+    pub id: u32,
+    pub name: Arc<str>,
+    pub slug: Arc<str>,
+    pub summary: Option<Arc<str>>,
+    pub download_count: f64,
+    pub class_id: Option<u32>,
+    pub authors: Arc<[CurseForgeAuthor]>,
+    pub logo: Option<CurseForgeAsset>,
+    pub links: Option<CurseForgeLinks>,
+    pub allow_mod_distribution: Option<bool>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CurseForgeAuthor {
+    pub name: Arc<str>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CurseForgeAsset {
+    pub thumbnail_url: Option<Arc<str>>,
+    pub url: Option<Arc<str>>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CurseForgeLinks {
+    pub website_url: Option<Arc<str>>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CurseForgeFile {
+    pub id: u32,
+    pub mod_id: u32,
+    pub display_name: Arc<str>,
+    pub file_name: Arc<str>,
+    pub release_type: CurseForgeReleaseType,
+    pub hashes: Arc<[CurseForgeFileHash]>,
+    pub file_length: usize,
+    pub download_url: Option<Arc<str>>,
+    pub game_versions: Arc<[Ustr]>,
+    #[serde(default)]
+    pub dependencies: Arc<[CurseForgeDependency]>,
+    pub file_fingerprint: u32,
+}
+
+impl CurseForgeFile {
+    pub fn sha1(&self) -> Option<&Arc<str>> {
+        self.hashes.iter().find(|hash| hash.algo == CurseForgeHashAlgo::Sha1).map(|hash| &hash.value)
+    }
+
+    /// CurseForge lists loaders alongside game versions, e.g. ["1.20.1", "Fabric", "Client"]
+    pub fn supports_loader(&self, loader: CurseForgeModLoaderType) -> bool {
+        let name = match loader {
+            CurseForgeModLoaderType::Forge => "Forge",
+            CurseForgeModLoaderType::Fabric => "Fabric",
+            CurseForgeModLoaderType::Quilt => "Quilt",
+            CurseForgeModLoaderType::NeoForge => "NeoForge",
+        };
+        self.game_versions.iter().any(|version| version.as_str() == name)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(from = "u8")]
+pub enum CurseForgeReleaseType {
+    Release,
+    Beta,
+    Alpha,
+    Unknown,
+}
+
+impl From<u8> for CurseForgeReleaseType {
+    fn from(value: u8) -> Self {
+        match value {
+            1 => Self::Release,
+            2 => Self::Beta,
+            3 => Self::Alpha,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+impl CurseForgeReleaseType {
+    pub fn as_version_type(self) -> ModrinthVersionType {
+        match self {
+            CurseForgeReleaseType::Release => ModrinthVersionType::Release,
+            CurseForgeReleaseType::Beta => ModrinthVersionType::Beta,
+            CurseForgeReleaseType::Alpha => ModrinthVersionType::Alpha,
+            CurseForgeReleaseType::Unknown => ModrinthVersionType::Other,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CurseForgeFileHash {
+    pub value: Arc<str>,
+    pub algo: CurseForgeHashAlgo,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(from = "u8")]
+pub enum CurseForgeHashAlgo {
+    Sha1,
+    Md5,
+    Unknown,
+}
+
+impl From<u8> for CurseForgeHashAlgo {
+    fn from(value: u8) -> Self {
+        match value {
+            1 => Self::Sha1,
+            2 => Self::Md5,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CurseForgeDependency {
+    pub mod_id: u32,
+    pub relation_type: CurseForgeRelationType,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(from = "u8")]
+pub enum CurseForgeRelationType {
+    EmbeddedLibrary,
+    OptionalDependency,
+    RequiredDependency,
+    Tool,
+    Incompatible,
+    Include,
+    Unknown,
+}
+
+impl From<u8> for CurseForgeRelationType {
+    fn from(value: u8) -> Self {
+        match value {
+            1 => Self::EmbeddedLibrary,
+            2 => Self::OptionalDependency,
+            3 => Self::RequiredDependency,
+            4 => Self::Tool,
+            5 => Self::Incompatible,
+            6 => Self::Include,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CurseForgeFingerprintsRequest<'a> {
+    pub fingerprints: &'a [u32],
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CurseForgeFingerprintMatchesResult {
+    pub exact_matches: Arc<[CurseForgeFingerprintMatch]>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CurseForgeFingerprintMatch {
+    pub id: u32,
+    pub file: CurseForgeFile,
+}
+
+/// Fingerprint used by CurseForge to identify files: murmur2 with seed 1, ignoring whitespace bytes
+pub fn curseforge_fingerprint(bytes: &[u8]) -> u32 {
+    const M: u32 = 0x5bd1e995;
+
+    let is_whitespace = |b: &u8| matches!(*b, 9 | 10 | 13 | 32);
+
+    let length = bytes.iter().filter(|b| !is_whitespace(b)).count() as u32;
+    let mut hash = 1 ^ length;
+
+    let mut chunk = 0u32;
+    let mut chunk_len = 0;
+
+    for &byte in bytes.iter().filter(|b| !is_whitespace(b)) {
+        chunk |= (byte as u32) << (chunk_len * 8);
+        chunk_len += 1;
+
+        if chunk_len == 4 {
+            let mut k = chunk.wrapping_mul(M);
+            k ^= k >> 24;
+            k = k.wrapping_mul(M);
+
+            hash = hash.wrapping_mul(M) ^ k;
+
+            chunk = 0;
+            chunk_len = 0;
+        }
+    }
+
+    if chunk_len > 0 {
+        hash ^= chunk;
+        hash = hash.wrapping_mul(M);
+    }
+
+    hash ^= hash >> 13;
+    hash = hash.wrapping_mul(M);
+    hash ^= hash >> 15;
+
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::curseforge_fingerprint;
+
+    #[test]
+    fn test_fingerprint_known_answers() {
+        assert_eq!(curseforge_fingerprint(b""), 1540447798);
+        assert_eq!(curseforge_fingerprint(b"a"), 626045324);
+        assert_eq!(curseforge_fingerprint(b"abcd"), 3376380438);
+        assert_eq!(curseforge_fingerprint(b"Hello, World!"), 1961219979);
+        assert_eq!(curseforge_fingerprint(b"The quick brown fox jumps over the lazy dog"), 3751777527);
+    }
+
+    #[test]
+    fn test_fingerprint_ignores_whitespace() {
+        assert_eq!(curseforge_fingerprint(b"Hello,\tWorld!\r\n"), curseforge_fingerprint(b"Hello, World!"));
+        assert_eq!(curseforge_fingerprint(b" \t\r\n"), curseforge_fingerprint(b""));
+    }
+}
@@ -0,0 +1,68 @@
+use std::sync::Arc;
+
+use serde::Deserialize;
+use ustr::Ustr;
+
+use crate::loader::Loader;
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CurseForgeManifestJson {
+    pub minecraft: CurseForgeManifestMinecraft,
+    pub manifest_type: Arc<str>,
+    pub name: Arc<str>,
+    #[serde(default)]
+    pub version: Option<Arc<str>>,
+    #[serde(default)]
+    pub author: Option<Arc<str>>,
+    pub files: Arc<[CurseForgeManifestFile]>,
+    #[serde(default)]
+    pub overrides: Option<Arc<str>>,
+}
+
+impl CurseForgeManifestJson {
+    /// Returns the loader and loader version of the primary mod loader, e.g. "forge-47.2.0"
+    pub fn loader(&self) -> (Loader, Option<Ustr>) {
+        let mod_loader = self.minecraft.mod_loaders.iter()
+            .find(|mod_loader| mod_loader.primary)
+            .or(self.minecraft.mod_loaders.first());
+
+        let Some(mod_loader) = mod_loader else {
+            return (Loader::Vanilla, None);
+        };
+
+        match mod_loader.id.split_once('-') {
+            Some((name, version)) => (Loader::from_name(name), Some(Ustr::from(version))),
+            None => (Loader::from_name(&mod_loader.id), None),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CurseForgeManifestMinecraft {
+    pub version: Ustr,
+    #[serde(default)]
+    pub mod_loaders: Arc<[CurseForgeManifestModLoader]>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct CurseForgeManifestModLoader {
+    pub id: Arc<str>,
+    #[serde(default)]
+    pub primary: bool,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct CurseForgeManifestFile {
+    #[serde(rename = "projectID")]
+    pub project_id: u32,
+    #[serde(rename = "fileID")]
+    pub file_id: u32,
+    #[serde(default = "default_required")]
+    pub required: bool,
+}
+
+fn default_required() -> bool {
+    true
+}
@@ -11,6 +11,8 @@ pub struct FabricModJson {
     // pub description: Option<Arc<str>>,
     pub authors: Option<Vec<Person>>,
     pub icon: Option<Icon>,
+    #[serde(default, deserialize_with = "crate::try_deserialize")]
+    pub environment: FabricEnvironment,
     // #[serde(alias = "requires")]
     // pub depends: Option<HashMap<Arc<str>, Dependency>>,
     // pub breaks: Option<HashMap<Arc<str>, Dependency>>,
@@ -23,6 +25,17 @@ pub struct FabricModJson {
 //     Multiple(Vec<Arc<str>>)
 // }
 
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum FabricEnvironment {
+    #[default]
+    #[serde(rename = "*")]
+    Any,
+    #[serde(rename = "client")]
+    Client,
+    #[serde(rename = "server")]
+    Server,
+}
+
 #[derive(Deserialize, Debug)]
 #[serde(untagged)]
 pub enum Icon {
@@ -45,3 +58,27 @@ impl Person {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{FabricEnvironment, FabricModJson};
+
+    fn parse_environment(environment: &str) -> FabricEnvironment {
+        let json = format!(r#"{{"id":"example","version":"1.0.0"{environment}}}"#);
+        serde_json::from_str::<FabricModJson>(&json).unwrap().environment
+    }
+
+    #[test]
+    fn test_parse_environment() {
+        assert_eq!(parse_environment(r#","environment":"client""#), FabricEnvironment::Client);
+        assert_eq!(parse_environment(r#","environment":"server""#), FabricEnvironment::Server);
+        assert_eq!(parse_environment(r#","environment":"*""#), FabricEnvironment::Any);
+    }
+
+    #[test]
+    fn test_parse_missing_or_invalid_environment() {
+        assert_eq!(parse_environment(""), FabricEnvironment::Any);
+        assert_eq!(parse_environment(r#","environment":"both""#), FabricEnvironment::Any);
+        assert_eq!(parse_environment(r#","environment":["client"]"#), FabricEnvironment::Any);
+    }
+}
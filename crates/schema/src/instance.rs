@@ -2,11 +2,14 @@ use std::{path::Path, sync::Arc};
 
 use serde::{Deserialize, Serialize};
 use ustr::Ustr;
+use uuid::Uuid;
 
-use crate::loader::Loader;
+use crate::{forge::ForgeSide, loader::Loader, modrinth::{ModrinthSideRequirement, ModrinthVersionType}};
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct InstanceConfiguration {
+    #[serde(default, deserialize_with = "crate::try_deserialize", skip_serializing_if = "is_default_instance_kind")]
+    pub kind: InstanceKind,
     pub minecraft_version: Ustr,
     pub loader: Loader,
     #[serde(default)]
@@ -17,6 +20,74 @@ pub struct InstanceConfiguration {
     pub jvm_flags: Option<InstanceJvmFlagsConfiguration>,
     #[serde(default, deserialize_with = "crate::try_deserialize", skip_serializing_if = "is_default_jvm_binary_configuration")]
     pub jvm_binary: Option<InstanceJvmBinaryConfiguration>,
+    #[serde(default, deserialize_with = "crate::try_deserialize", skip_serializing_if = "is_default_release_channel")]
+    pub release_channel: ReleaseChannel,
+    /// Account to launch with instead of the selected account
+    #[serde(default, deserialize_with = "crate::try_deserialize", skip_serializing_if = "Option::is_none")]
+    pub account: Option<Uuid>,
+}
+
+/// Whether an instance runs the game or a dedicated server
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum InstanceKind {
+    #[default]
+    Client,
+    Server,
+}
+
+impl InstanceKind {
+    pub fn forge_side(self) -> ForgeSide {
+        match self {
+            InstanceKind::Client => ForgeSide::Client,
+            InstanceKind::Server => ForgeSide::Server,
+        }
+    }
+
+    /// Whether content with the given client and server requirements can be installed on this side
+    pub fn supports(self, client: ModrinthSideRequirement, server: ModrinthSideRequirement) -> bool {
+        match self {
+            InstanceKind::Client => client != ModrinthSideRequirement::Unsupported,
+            InstanceKind::Server => server != ModrinthSideRequirement::Unsupported,
+        }
+    }
+}
+
+fn is_default_instance_kind(kind: &InstanceKind) -> bool {
+    *kind == InstanceKind::default()
+}
+
+/// The least stable kind of content version that may be picked automatically. Defaults to alpha so instances
+/// created before channels existed keep installing and updating content that only has beta or alpha versions
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ReleaseChannel {
+    Release,
+    Beta,
+    #[default]
+    Alpha,
+}
+
+impl ReleaseChannel {
+    pub fn allows(self, version_type: Option<ModrinthVersionType>) -> bool {
+        match version_type {
+            None | Some(ModrinthVersionType::Release) => true,
+            Some(ModrinthVersionType::Beta) => self != ReleaseChannel::Release,
+            Some(ModrinthVersionType::Alpha) | Some(ModrinthVersionType::Other) => self == ReleaseChannel::Alpha,
+        }
+    }
+
+    pub fn version_types(self) -> &'static [ModrinthVersionType] {
+        match self {
+            ReleaseChannel::Release => &[ModrinthVersionType::Release],
+            ReleaseChannel::Beta => &[ModrinthVersionType::Release, ModrinthVersionType::Beta],
+            ReleaseChannel::Alpha => &[ModrinthVersionType::Release, ModrinthVersionType::Beta, ModrinthVersionType::Alpha],
+        }
+    }
+}
+
+fn is_default_release_channel(channel: &ReleaseChannel) -> bool {
+    *channel == ReleaseChannel::default()
 }
 
 #[derive(Serialize, Deserialize, Debug, Copy, Clone)]
@@ -78,3 +149,24 @@ fn is_default_jvm_binary_configuration(config: &Option<InstanceJvmBinaryConfigur
         true
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{InstanceConfiguration, ReleaseChannel};
+
+    #[test]
+    fn test_old_configuration_allows_every_channel() {
+        let configuration: InstanceConfiguration = serde_json::from_str(r#"{"minecraft_version":"1.20.1","loader":"fabric"}"#).unwrap();
+        assert_eq!(configuration.release_channel, ReleaseChannel::Alpha);
+    }
+
+    #[test]
+    fn test_release_channel_roundtrip() {
+        let mut configuration: InstanceConfiguration = serde_json::from_str(r#"{"minecraft_version":"1.20.1","loader":"fabric"}"#).unwrap();
+        configuration.release_channel = ReleaseChannel::Release;
+
+        let json = serde_json::to_string(&configuration).unwrap();
+        let configuration: InstanceConfiguration = serde_json::from_str(&json).unwrap();
+        assert_eq!(configuration.release_channel, ReleaseChannel::Release);
+    }
+}
@@ -3,6 +3,8 @@ use serde::Deserialize;
 pub mod assets_index;
 pub mod backend_config;
 pub mod content;
+pub mod curseforge;
+pub mod curseforge_manifest;
 pub mod fabric_launch;
 pub mod fabric_loader_manifest;
 pub mod fabric_mod;
@@ -17,6 +19,7 @@ pub mod modification;
 pub mod modrinth;
 pub mod mrpack;
 pub mod resourcepack;
+pub mod server_properties;
 pub mod version;
 pub mod version_manifest;
 
@@ -17,4 +17,6 @@ pub struct ModrinthModpackFileDownload {
 #[derive(Debug, Clone, Copy, Deserialize)]
 pub struct ModrinthEnv {
     pub client: ModrinthSideRequirement,
+    #[serde(default)]
+    pub server: ModrinthSideRequirement,
 }
@@ -21,9 +21,10 @@ pub struct ModrinthProjectVersionsRequest {
     pub loaders: Option<Arc<[ModrinthLoader]>>,
 }
 
-#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[derive(Default, Debug, Copy, Clone, Hash, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum ModrinthSearchIndex {
+    #[default]
     Relevance,
     Downloads,
     Follows,
@@ -31,6 +32,48 @@ pub enum ModrinthSearchIndex {
     Updated,
 }
 
+impl ModrinthSearchIndex {
+    pub fn pretty_name(self) -> &'static str {
+        match self {
+            ModrinthSearchIndex::Relevance => "Relevance",
+            ModrinthSearchIndex::Downloads => "Downloads",
+            ModrinthSearchIndex::Follows => "Follows",
+            ModrinthSearchIndex::Newest => "Newest",
+            ModrinthSearchIndex::Updated => "Updated",
+        }
+    }
+}
+
+/// Builder for the `facets` parameter of a search request
+///
+/// Values inside a group are OR'd together, while the groups themselves are AND'd
+#[derive(Default, Debug, Clone)]
+pub struct ModrinthFacets {
+    groups: Vec<Vec<String>>,
+}
+
+impl ModrinthFacets {
+    /// Requires at least one of the values to match, groups without any values are ignored
+    pub fn any_of<V: AsRef<str>>(&mut self, key: &str, values: impl IntoIterator<Item = V>) -> &mut Self {
+        let group: Vec<String> = values.into_iter().map(|value| format!("{}:{}", key, value.as_ref())).collect();
+        if !group.is_empty() {
+            self.groups.push(group);
+        }
+        self
+    }
+
+    pub fn require(&mut self, key: &str, value: &str) -> &mut Self {
+        self.any_of(key, [value])
+    }
+
+    pub fn build(&self) -> Option<Arc<str>> {
+        if self.groups.is_empty() {
+            return None;
+        }
+        serde_json::to_string(&self.groups).ok().map(Arc::from)
+    }
+}
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct ModrinthSearchResult {
     pub hits: Arc<[ModrinthHit]>,
@@ -66,13 +109,77 @@ pub struct ModrinthHit {
     // pub featured_gallery: Option<Arc<str>>,
 }
 
-#[derive(PartialEq, Eq, Debug, Copy, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
+pub struct ModrinthProject {
+    pub id: Arc<str>,
+    pub slug: Arc<str>,
+    pub project_type: ModrinthProjectType,
+    pub title: Arc<str>,
+    pub description: Arc<str>,
+    pub body: Arc<str>,
+    pub categories: Arc<[Ustr]>,
+    pub client_side: Option<ModrinthSideRequirement>,
+    pub server_side: Option<ModrinthSideRequirement>,
+    pub downloads: usize,
+    pub followers: usize,
+    pub icon_url: Option<Arc<str>>,
+    pub issues_url: Option<Arc<str>>,
+    pub source_url: Option<Arc<str>>,
+    pub wiki_url: Option<Arc<str>>,
+    pub discord_url: Option<Arc<str>>,
+    #[serde(default)]
+    pub donation_urls: Arc<[ModrinthDonationUrl]>,
+    pub license: Option<ModrinthLicense>,
+    #[serde(default)]
+    pub gallery: Arc<[ModrinthGalleryImage]>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ModrinthCollection {
+    pub id: Arc<str>,
+    pub name: Arc<str>,
+    pub description: Option<Arc<str>>,
+    pub icon_url: Option<Arc<str>>,
+    #[serde(default)]
+    pub projects: Arc<[Arc<str>]>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ModrinthUser {
+    pub id: Arc<str>,
+    pub username: Arc<str>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ModrinthDonationUrl {
+    pub platform: Arc<str>,
+    pub url: Arc<str>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ModrinthLicense {
+    pub id: Arc<str>,
+    pub name: Option<Arc<str>>,
+    pub url: Option<Arc<str>>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ModrinthGalleryImage {
+    pub url: Arc<str>,
+    pub featured: bool,
+    pub title: Option<Arc<str>>,
+    pub description: Option<Arc<str>>,
+    pub ordering: i64,
+}
+
+#[derive(PartialEq, Eq, Debug, Copy, Clone, Default, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum ModrinthSideRequirement {
     Required,
     Optional,
     Unsupported,
     #[serde(other)]
+    #[default]
     Unknown,
 }
 
@@ -111,9 +218,12 @@ pub struct ModrinthProjectVersion {
     pub project_id: Arc<str>,
     pub name: Option<Arc<str>>,
     pub version_number: Option<Arc<str>>,
+    pub changelog: Option<Arc<str>>,
     pub dependencies: Option<Vec<ModrinthDependency>>,
     pub version_type: Option<ModrinthVersionType>,
     pub status: Option<ModrinthVersionStatus>,
+    pub date_published: Option<Arc<str>>,
+    pub downloads: Option<usize>,
     pub files: Arc<[ModrinthFile]>,
 }
 
@@ -203,7 +313,7 @@ impl ModrinthLoader {
     }
 }
 
-#[derive(Debug, Copy, Clone, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Copy, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "lowercase")]
 pub enum ModrinthVersionType {
     Release,
@@ -241,3 +351,39 @@ pub struct ModrinthHashes {
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct ModrinthVersionFileUpdateResult(pub ModrinthProjectVersion);
+
+#[cfg(test)]
+mod tests {
+    use super::ModrinthFacets;
+
+    #[test]
+    fn test_empty_facets() {
+        let mut facets = ModrinthFacets::default();
+        assert_eq!(facets.build(), None);
+
+        facets.any_of("categories", Vec::<&str>::new());
+        assert_eq!(facets.build(), None);
+    }
+
+    #[test]
+    fn test_facet_groups() {
+        let mut facets = ModrinthFacets::default();
+        facets
+            .require("project_type", "mod")
+            .any_of("categories", ["fabric", "forge"])
+            .any_of("client_side", Vec::<&str>::new())
+            .require("versions", "1.20.1");
+
+        assert_eq!(
+            facets.build().as_deref(),
+            Some(r#"[["project_type:mod"],["categories:fabric","categories:forge"],["versions:1.20.1"]]"#)
+        );
+    }
+
+    #[test]
+    fn test_facet_escaping() {
+        let mut facets = ModrinthFacets::default();
+        facets.require("categories", "a\"b");
+        assert_eq!(facets.build().as_deref(), Some(r#"[["categories:a\"b"]]"#));
+    }
+}
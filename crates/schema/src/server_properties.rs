@@ -0,0 +1,188 @@
+use std::{fmt::Write, sync::Arc};
+
+/// A dedicated server's server.properties. Lines are kept as they were read so that comments,
+/// ordering and unmodified values are written back unchanged
+#[derive(Debug, Clone, Default)]
+pub struct ServerProperties {
+    lines: Vec<ServerPropertiesLine>,
+}
+
+#[derive(Debug, Clone)]
+enum ServerPropertiesLine {
+    Property {
+        key: Arc<str>,
+        value: Arc<str>,
+        raw: Option<Arc<str>>,
+    },
+    Other(Arc<str>),
+}
+
+impl ServerProperties {
+    pub fn parse(text: &str) -> Self {
+        let lines = text.lines().map(|line| {
+            let trimmed = line.trim_start();
+            if trimmed.is_empty() || trimmed.starts_with('#') || trimmed.starts_with('!') {
+                return ServerPropertiesLine::Other(line.into());
+            }
+
+            let (key, value) = split_property(trimmed);
+            ServerPropertiesLine::Property {
+                key: unescape(key).into(),
+                value: unescape(value).into(),
+                raw: Some(line.into()),
+            }
+        }).collect();
+
+        Self { lines }
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.properties().find(|(k, _)| &***k == key).map(|(_, value)| &**value)
+    }
+
+    /// Replaces the value of an existing property, or appends it to the end of the file
+    pub fn set(&mut self, key: &str, value: &str) {
+        for line in &mut self.lines {
+            if let ServerPropertiesLine::Property { key: k, value: v, raw } = line && &**k == key {
+                if &**v != value {
+                    *v = value.into();
+                    *raw = None;
+                }
+                return;
+            }
+        }
+
+        self.lines.push(ServerPropertiesLine::Property {
+            key: key.into(),
+            value: value.into(),
+            raw: None,
+        });
+    }
+
+    pub fn properties(&self) -> impl Iterator<Item = (&Arc<str>, &Arc<str>)> {
+        self.lines.iter().filter_map(|line| match line {
+            ServerPropertiesLine::Property { key, value, .. } => Some((key, value)),
+            ServerPropertiesLine::Other(_) => None,
+        })
+    }
+
+    pub fn write(&self) -> String {
+        let mut output = String::new();
+        for line in &self.lines {
+            match line {
+                ServerPropertiesLine::Property { raw: Some(raw), .. } | ServerPropertiesLine::Other(raw) => {
+                    output.push_str(raw);
+                },
+                ServerPropertiesLine::Property { key, value, raw: None } => {
+                    escape(&mut output, key, true);
+                    output.push('=');
+                    escape(&mut output, value, false);
+                },
+            }
+            output.push('\n');
+        }
+        output
+    }
+}
+
+/// Splits at the first unescaped separator, which is one of '=', ':' or whitespace
+fn split_property(line: &str) -> (&str, &str) {
+    let mut escaped = false;
+    for (index, c) in line.char_indices() {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        } else if c == '=' || c == ':' || c.is_whitespace() {
+            let key = &line[..index];
+            let mut rest = line[index..].trim_start();
+            if c.is_whitespace() && let Some(stripped) = rest.strip_prefix(['=', ':']) {
+                rest = stripped.trim_start();
+            } else if !c.is_whitespace() {
+                rest = line[index+1..].trim_start();
+            }
+            return (key, rest);
+        }
+    }
+    (line, "")
+}
+
+fn unescape(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            output.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => output.push('\n'),
+            Some('t') => output.push('\t'),
+            Some('r') => output.push('\r'),
+            Some('f') => output.push('\x0c'),
+            Some('u') => {
+                let hex: String = chars.by_ref().take(4).collect();
+                match u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32) {
+                    Some(c) => output.push(c),
+                    None => {
+                        output.push_str("\\u");
+                        output.push_str(&hex);
+                    },
+                }
+            },
+            Some(c) => output.push(c),
+            None => {},
+        }
+    }
+    output
+}
+
+fn escape(output: &mut String, text: &str, is_key: bool) {
+    for (index, c) in text.chars().enumerate() {
+        match c {
+            '\\' => output.push_str("\\\\"),
+            '=' | ':' | '#' | '!' => {
+                output.push('\\');
+                output.push(c);
+            },
+            ' ' if is_key || index == 0 => output.push_str("\\ "),
+            '\n' => output.push_str("\\n"),
+            '\t' => output.push_str("\\t"),
+            '\r' => output.push_str("\\r"),
+            '\x0c' => output.push_str("\\f"),
+            c if (c as u32) < 0x20 || (c as u32) > 0x7e => {
+                let mut units = [0_u16; 2];
+                for unit in c.encode_utf16(&mut units) {
+                    _ = write!(output, "\\u{:04X}", unit);
+                }
+            },
+            c => output.push(c),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unmodified_lines_are_preserved() {
+        let text = "#Minecraft server properties\n#Sat Jan 01 00:00:00 UTC 2000\nmotd=A Minecraft Server\nlevel-seed=\nserver-ip = 127.0.0.1\n";
+        let properties = ServerProperties::parse(text);
+
+        assert_eq!(properties.get("motd"), Some("A Minecraft Server"));
+        assert_eq!(properties.get("level-seed"), Some(""));
+        assert_eq!(properties.get("server-ip"), Some("127.0.0.1"));
+        assert_eq!(properties.write(), text);
+    }
+
+    #[test]
+    fn test_set_escapes_values() {
+        let mut properties = ServerProperties::parse("motd=A Minecraft Server\nmax-players=20\n");
+        properties.set("motd", "Testing: \u{a7}aMods");
+        properties.set("white-list", "true");
+
+        assert_eq!(properties.write(), "motd=Testing\\: \\u00A7aMods\nmax-players=20\nwhite-list=true\n");
+        assert_eq!(ServerProperties::parse(&properties.write()).get("motd"), Some("Testing: \u{a7}aMods"));
+    }
+}